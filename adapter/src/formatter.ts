@@ -1,11 +1,13 @@
-import type { AnalysisResponse, CoupledFile, RiskLevel, FormattedCoupledFile, TestInfo } from "./types.js";
+import type { AnalysisResponse, CoupledFile, RiskLevel, RiskThresholds, FormattedCoupledFile, TestInfo } from "./types.js";
 
 const DISPLAY_LIMIT = 5;
 
-export function classifyRisk(score: number): RiskLevel {
-  if (score >= 0.8) return "Critical";
-  if (score >= 0.6) return "High";
-  if (score >= 0.3) return "Medium";
+const FIXED_THRESHOLDS: RiskThresholds = { critical: 0.8, high: 0.6, medium: 0.3 };
+
+export function classifyRisk(score: number, thresholds: RiskThresholds = FIXED_THRESHOLDS): RiskLevel {
+  if (score >= thresholds.critical) return "Critical";
+  if (score >= thresholds.high) return "High";
+  if (score >= thresholds.medium) return "Medium";
   return "Low";
 }
 
@@ -90,7 +92,7 @@ export function formatAnalysisResponse(response: AnalysisResponse): string {
   const formattedFiles: FormattedCoupledFile[] = displayFiles.map((f) => {
     const formatted: FormattedCoupledFile = {
       path: f.path,
-      risk_level: classifyRisk(f.risk_score),
+      risk_level: classifyRisk(f.risk_score, response.risk_thresholds),
       risk_score: f.risk_score,
       description: describeFile(f, response.commit_count),
     };
@@ -98,16 +98,19 @@ export function createMcpServer(): McpServer {
       inputSchema: {
         query: z.string().optional().describe("Search query to match against note content and file paths"),
         file_path: z.string().optional().describe("Filter notes for a specific file path"),
+        author: z.string().optional().describe("Filter notes added by this git user name or email"),
+        offset: z.number().int().min(0).optional().describe("Skip this many notes; pass a previous response's next_offset to read the next page"),
+        limit: z.number().int().min(1).optional().describe("Return at most this many notes"),
         repo_root: z.string().describe("Absolute path to the git repository root"),
       },
     },
-    async ({ query, file_path, repo_root }) => {
+    async ({ query, file_path, author, offset, limit, repo_root }) => {
       try {
         let response;
         if (query) {
-          response = await searchNotes({ query, repo_root });
+          response = await searchNotes({ query, repo_root, offset, limit });
         } else {
-          response = await listNotes({ repo_root, file_path });
+          response = await listNotes({ repo_root, file_path, author, offset, limit });
         }
         return {
           content: [
@@ -140,11 +143,19 @@ export function createMcpServer(): McpServer {
         "Get usage statistics for this repository including how many analyses have been performed, risk distributions, notes created, and performance metrics. Useful for understanding your usage patterns and the health of the codebase analysis.",
       inputSchema: {
         repo_root: z.string().describe("Absolute path to the git repository root"),
+        group_by: z
+          .enum(["day", "week"])
+          .optional()
+          .describe("Also return a series of summaries per day or week, to spot trends"),
+        file_path: z
+          .string()
+          .optional()
+          .describe("Also return how often this file (relative to repo root) was analyzed and its typical risk profile"),
       },
     },
-    async ({ repo_root }) => {
+    async ({ repo_root, group_by, file_path }) => {
       try {
-        const response = await getMetrics({ repo_root });
+        const response = await getMetrics({ repo_root, group_by, file_path });
         return {
           content: [
             {
@@ -14,6 +14,8 @@ import type {
   ListNotesResponse,
   GetMetricsRequest,
   MetricsResponse,
+  GetStatusRequest,
+  StatusResponse,
   ProcessResult,
 } from "./types.js";
 
@@ -120,13 +122,73 @@ export function runCore(args: string[]): Promise<ProcessResult> {
 export async function analyze(
   request: AnalysisRequest
 ): Promise<AnalysisResponse> {
-  const result = await runCore([
+  const args = [
     "analyze",
     "--file",
     request.file_path,
     "--repo-root",
     request.repo_root,
-  ]);
+  ];
+
+  if (request.scope) {
+    args.push("--scope", request.scope);
+  }
+
+  if (request.rev) {
+    args.push("--rev", request.rev);
+  }
+
+  if (request.coupling_window_hours !== undefined) {
+    args.push("--coupling-window", String(request.coupling_window_hours));
+  }
+
+  if (request.max_p_value !== undefined) {
+    args.push("--max-p-value", String(request.max_p_value));
+  }
+
+  if (request.half_life_days !== undefined) {
+    args.push("--half-life", String(request.half_life_days));
+  }
+
+  if (request.depth !== undefined) {
+    args.push("--depth", String(request.depth));
+  }
+
+  if (request.regions) {
+    args.push("--regions");
+  }
+
+  if (request.all) {
+    args.push("--all");
+  } else if (request.limit !== undefined) {
+    args.push("--limit", String(request.limit));
+  }
+
+  if (request.offset) {
+    args.push("--offset", String(request.offset));
+  }
+
+  for (const glob of request.include ?? []) {
+    args.push("--include", glob);
+  }
+
+  for (const glob of request.exclude ?? []) {
+    args.push("--exclude", glob);
+  }
+
+  if (request.min_coupling !== undefined) {
+    args.push("--min-coupling", String(request.min_coupling));
+  }
+
+  if (request.min_risk !== undefined) {
+    args.push("--min-risk", String(request.min_risk));
+  }
+
+  if (request.cross_package) {
+    args.push("--cross-package");
+  }
+
+  const result = await runCore(args);
 
   if (result.exitCode !== 0) {
     throw new Error(
@@ -180,13 +242,23 @@ export async function addNote(
 export async function searchNotes(
   request: SearchNotesRequest
 ): Promise<SearchNotesResponse> {
-  const result = await runCore([
+  const args = [
     "search-notes",
     "--query",
     request.query,
     "--repo-root",
     request.repo_root,
-  ]);
+  ];
+
+  if (request.offset) {
+    args.push("--offset", String(request.offset));
+  }
+
+  if (request.limit !== undefined) {
+    args.push("--limit", String(request.limit));
+  }
+
+  const result = await runCore(args);
 
   if (result.exitCode !== 0) {
     throw new Error(
@@ -216,6 +288,18 @@ export async function listNotes(
     args.push("--file", request.file_path);
   }
 
+  if (request.author) {
+    args.push("--author", request.author);
+  }
+
+  if (request.offset) {
+    args.push("--offset", String(request.offset));
+  }
+
+  if (request.limit !== undefined) {
+    args.push("--limit", String(request.limit));
+  }
+
   const result = await runCore(args);
 
   if (result.exitCode !== 0) {
@@ -236,11 +320,14 @@ export async function listNotes(
 export async function getMetrics(
   request: GetMetricsRequest
 ): Promise<MetricsResponse> {
-  const result = await runCore([
-    "get-metrics",
-    "--repo-root",
-    request.repo_root,
-  ]);
+  const args = ["get-metrics", "--repo-root", request.repo_root];
+  if (request.group_by) {
+    args.push("--group-by", request.group_by);
+  }
+  if (request.file_path) {
+    args.push("--file", request.file_path);
+  }
+  const result = await runCore(args);
 
   if (result.exitCode !== 0) {
     throw new Error(
@@ -256,3 +343,23 @@ export async function getMetrics(
     );
   }
 }
+
+export async function getStatus(
+  request: GetStatusRequest
+): Promise<StatusResponse> {
+  const result = await runCore(["status", "--repo-root", request.repo_root]);
+
+  if (result.exitCode !== 0) {
+    throw new Error(
+      `engram-core exited with code ${result.exitCode}: ${result.stderr}`
+    );
+  }
+
+  try {
+    return JSON.parse(result.stdout) as StatusResponse;
+  } catch {
+    throw new Error(
+      `Failed to parse engram-core output: ${result.stdout.slice(0, 200)}`
+    );
+  }
+}
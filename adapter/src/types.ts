@@ -4,10 +4,26 @@ export interface Memory {
   symbol_name?: string;
   content: string;
   created_at: string;
+  author_name?: string;
+  author_email?: string;
+  head_commit?: string;
+  blob_oid?: string;
+  /** How far the file has moved on since the note was added. */
+  drift?: NoteDrift;
+  /** A user-level note; `file_path` is the glob it applies to. */
+  global?: boolean;
+}
+
+export interface NoteDrift {
+  commits_since: number;
+  file_changed: boolean;
 }
 
 export interface TestIntent {
   title: string;
+  cases?: number;
+  covers_symbols?: string[];
+  failing?: boolean;
 }
 
 export interface CoupledFile {
@@ -17,38 +33,183 @@ export interface CoupledFile {
   risk_score: number;
   memories?: Memory[];
   test_intents?: TestIntent[];
+  emerging?: boolean;
+  /** Whether the pair co-changes more or less often lately than before. */
+  trend?: "increasing" | "stable" | "decreasing";
+  lossy_path?: boolean;
+  /** Upstream commits since the branch point that changed this file. */
+  upstream_commits?: number;
+  recently_failing?: boolean;
+  /** Co-changes by conventional-commit type; `other` for untyped commits. */
+  co_change_types?: Record<string, number>;
+  confidence?: number;
+  reverse_confidence?: number;
+  lift?: number;
+  /** For a two-hop coupling, the directly coupled file it was reached through. */
+  via?: string;
+  /** Most recent commits shared with the analyzed file (or `via`), newest first. */
+  shared_commits?: SharedCommit[];
+  /** When this file last changed with the analyzed file (or `via`), ISO 8601 UTC. */
+  last_co_change_at?: string;
+  /** Line regions of the analyzed file that co-change with this file. */
+  regions?: LineRegion[];
+  /** Whether either file imports the other; "none" is hidden coupling. */
+  static_link?: "imports" | "imported_by" | "mutual" | "none";
+  /** Whether tests for this file were found; false flags a file without test coverage. */
+  has_tests?: boolean;
+  /** Whether this file is in another package (Cargo.toml, package.json, go.mod, ...) than the analyzed file. */
+  cross_package?: boolean;
+  /** What `risk_score` is weighed from, for re-ranking by your own policy. */
+  risk_components?: RiskComponents;
+  /** `risk_score` was capped below Critical for weak coupling. */
+  gated?: boolean;
+}
+
+/** Each in [0, 1]; risk = 0.5 * coupling + 0.3 * churn + 0.2 * recency, before the gate. */
+export interface RiskComponents {
+  coupling: number;
+  churn: number;
+  recency: number;
+}
+
+export interface LineRegion {
+  start_line: number;
+  end_line: number;
+  commits: string[];
+}
+
+export interface SharedCommit {
+  hash: string;
+  timestamp: number;
 }
 
 export interface DiscoveredTestFile {
   path: string;
   test_intents: TestIntent[];
   test_count: number;
+  recently_failing?: boolean;
+  /** Commits to the analyzed file since this test file last changed. */
+  source_commits_since?: number;
+  /** The analyzed file changed repeatedly without this test changing. */
+  stale?: boolean;
 }
 
 export interface TestInfo {
   test_files: DiscoveredTestFile[];
   coverage_hint?: string;
+  partial?: boolean;
 }
 
 export interface IndexingStatus {
   strategy: string;
   commits_indexed: number;
   is_complete: boolean;
+  complete_to_depth?: boolean;
+}
+
+export interface WarmupPlan {
+  estimated_cycles_remaining: number;
+  estimated_commits_remaining?: number;
+  suggested_requery_ms: number;
 }
 
 export interface AnalysisResponse {
+  /** Present with `--output-format json-v2` or `ndjson`. */
+  schema_version?: number;
   file_path: string;
   repo_root: string;
   coupled_files: CoupledFile[];
   commit_count: number;
+  /** Indexing and scoring time (timings.index_ms + timings.query_ms); the whole analysis is timings.total_ms. */
   analysis_time_ms: number;
+  /** Risk level thresholds calibrated to this repo's scores; absent, the fixed ones apply. */
+  risk_thresholds?: RiskThresholds;
+  /** Where the analysis time went, phase by phase. */
+  timings?: PhaseTimings;
   test_info?: TestInfo;
   indexing_status?: IndexingStatus;
+  warmup_plan?: WarmupPlan;
+  enrichment_omitted?: EnrichmentOmitted;
+  extensions?: Record<string, unknown>;
+  /** Files changed under the same tickets, often in separate commits. */
+  ticket_coupled_files?: TicketCoupledFile[];
+  /** Offset of the next page of coupled files, when the limit cut the list short. */
+  next_offset?: number;
+  /** How far the branch's upstream moved on since the branch point. */
+  upstream_drift?: UpstreamDrift;
+}
+
+/** Lowest risk score at each level above Low. */
+export interface RiskThresholds {
+  critical: number;
+  high: number;
+  medium: number;
+}
+
+export interface PhaseTimings {
+  index_ms: number;
+  query_ms: number;
+  enrich_memories_ms: number;
+  enrich_tests_ms: number;
+  /** Config, static import links, upstream drift, blame, enrichers and budget trimming. */
+  other_ms: number;
+  /** The whole analysis; the phases add up to it. */
+  total_ms: number;
+}
+
+export interface UpstreamDrift {
+  /** The upstream ref compared against (e.g. "origin/main"). */
+  upstream: string;
+  branch_point: string;
+  commits: number;
+  /** Upstream commits that changed the analyzed file. */
+  file_commits: number;
+}
+
+export interface TicketCoupledFile {
+  path: string;
+  shared_tickets: number;
+}
+
+export interface EnrichmentOmitted {
+  memories: number;
+  test_intents: number;
+  coupled_files?: number;
 }
 
 export interface AnalysisRequest {
   file_path: string;
   repo_root: string;
+  /** Restrict indexing and coupling to this subtree (monorepos). */
+  scope?: string;
+  /** Analyze as of this revision (e.g. "origin/main") instead of HEAD. */
+  rev?: string;
+  /** Also couple files the same author changed within this many hours. */
+  coupling_window_hours?: number;
+  /** Drop couplings at least this likely to be chance (e.g. 0.05). */
+  max_p_value?: number;
+  /** Halve a co-change's weight in risk scores every this many days. */
+  half_life_days?: number;
+  /** 2 also reports files coupled with the top coupled files. */
+  depth?: number;
+  /** Blame the file to find the line regions each coupled file co-changes with. */
+  regions?: boolean;
+  /** Report at most this many coupled files (default 10). */
+  limit?: number;
+  /** Report every coupled file, however many. */
+  all?: boolean;
+  /** Skip this many of the highest-risk coupled files (see `next_offset`). */
+  offset?: number;
+  /** Only report coupled files matching one of these globs (e.g. "src/**"). */
+  include?: string[];
+  /** Never report coupled files matching these globs (e.g. "docs/**"). */
+  exclude?: string[];
+  /** Only report coupled files with at least this coupling score (e.g. 0.3). */
+  min_coupling?: number;
+  /** Only report coupled files with at least this risk score (e.g. 0.5). */
+  min_risk?: number;
+  /** Only report coupled files in another package than the analyzed file. */
+  cross_package?: boolean;
 }
 
 export interface ProcessResult {
@@ -89,21 +250,35 @@ export interface AddNoteResponse {
 export interface SearchNotesRequest {
   query: string;
   repo_root: string;
+  /** Skip this many notes, newest first. */
+  offset?: number;
+  /** Return at most this many notes. */
+  limit?: number;
 }
 
 export interface SearchNotesResponse {
   query: string;
   memories: Memory[];
+  /** Offset of the next page, when the limit cut the list short. */
+  next_offset?: number;
 }
 
 export interface ListNotesRequest {
   repo_root: string;
   file_path?: string;
+  /** Only notes added by this git user name or email. */
+  author?: string;
+  /** Skip this many notes, newest first. */
+  offset?: number;
+  /** Return at most this many notes. */
+  limit?: number;
 }
 
 export interface ListNotesResponse {
   file_path?: string;
   memories: Memory[];
+  /** Offset of the next page, when the limit cut the list short. */
+  next_offset?: number;
 }
 
 export interface MetricsSummary {
@@ -119,13 +294,70 @@ export interface MetricsSummary {
   test_files_found: number;
   test_intents_extracted: number;
   avg_analysis_time_ms: number;
+  avg_response_bytes: number;
+  max_response_bytes: number;
+  avg_coupled_files_bytes: number;
+  avg_memories_bytes: number;
+  avg_test_intents_bytes: number;
+  indexing_runs: number;
+  background_indexing_runs: number;
+  avg_indexing_time_ms: number;
+  avg_commits_per_sec: number;
+}
+
+export interface MetricsPeriodSummary extends MetricsSummary {
+  period_start: string;
 }
 
 export interface MetricsResponse {
   repo_root: string;
   summary: MetricsSummary;
+  group_by?: "day" | "week";
+  series?: MetricsPeriodSummary[];
+  file?: FileMetrics;
+}
+
+export interface FileMetrics {
+  file_path: string;
+  times_analyzed: number;
+  avg_coupled_files: number;
+  avg_critical_count: number;
+  avg_high_count: number;
+  avg_medium_count: number;
+  avg_low_count: number;
+  avg_analysis_time_ms: number;
+  last_analyzed_at?: string;
 }
 
 export interface GetMetricsRequest {
   repo_root: string;
+  group_by?: "day" | "week";
+  file_path?: string;
+}
+
+export interface IndexStateInfo {
+  strategy: string;
+  commits_indexed: number;
+  is_complete: boolean;
+  last_updated: number;
+  target_path?: string;
+  head_commit: string;
+  total_commits: number | null;
+  shallow?: boolean;
+  percent_complete: number | null;
+}
+
+export interface StatusResponse {
+  repo_root: string;
+  indexing: IndexStateInfo | null;
+  reindex_in_progress: boolean;
+  background_queue_len: number;
+  db_bytes: number;
+  temporal_index_rows: number;
+  indexed_commits: number;
+  indexed_files: number;
+}
+
+export interface GetStatusRequest {
+  repo_root: string;
 }
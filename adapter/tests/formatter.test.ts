@@ -23,6 +23,13 @@ describe("classifyRisk", () => {
     expect(classifyRisk(0.29)).toBe("Low");
     expect(classifyRisk(0.0)).toBe("Low");
   });
+
+  it("should use calibrated thresholds when given", () => {
+    const thresholds = { critical: 0.95, high: 0.9, medium: 0.85 };
+    expect(classifyRisk(0.96, thresholds)).toBe("Critical");
+    expect(classifyRisk(0.92, thresholds)).toBe("High");
+    expect(classifyRisk(0.8, thresholds)).toBe("Low");
+  });
 });
 
 describe("describeFile", () => {
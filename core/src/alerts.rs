@@ -0,0 +1,300 @@
+use std::collections::HashMap;
+
+use crate::persistence::{CoChangeSplit, Database};
+use crate::types::{AlertsResponse, CoupledFile, CouplingAlert, CouplingTrend};
+
+/// Default length of the "recent" window, in days.
+pub const DEFAULT_WINDOW_DAYS: u32 = 30;
+
+/// Wilson lower bound a pair's co-change rate must reach to count as coupled.
+pub const SIGNIFICANCE_THRESHOLD: f64 = 0.3;
+
+/// z-score for a 95% confidence interval.
+const WILSON_Z: f64 = 1.96;
+
+/// Pairs with fewer co-changes than this are never reported.
+const MIN_CO_CHANGES: u32 = 3;
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// How many times higher (or lower) a pair's co-change rate in the recent
+/// window must be than before it to count as a trend.
+const TREND_RATIO: f64 = 1.5;
+
+/// Lower bound of the Wilson score interval for `successes / trials`.
+/// Penalizes small samples, so two co-changes out of two commits does not
+/// outrank forty out of fifty.
+pub(crate) fn wilson_lower_bound(successes: u32, trials: u32) -> f64 {
+    if trials == 0 {
+        return 0.0;
+    }
+    let n = trials as f64;
+    let p = successes as f64 / n;
+    let z2 = WILSON_Z * WILSON_Z;
+    let centre = p + z2 / (2.0 * n);
+    let margin = WILSON_Z * ((p * (1.0 - p) + z2 / (4.0 * n)) / n).sqrt();
+    ((centre - margin) / (1.0 + z2 / n)).max(0.0)
+}
+
+/// Co-change history of one file pair, split at the start of the recent window.
+struct PairCounts {
+    co_total: u32,
+    co_before: u32,
+    a_total: u32,
+    a_before: u32,
+    b_total: u32,
+    b_before: u32,
+}
+
+impl PairCounts {
+    /// Score the pair now and as of the window start. The trial count is the
+    /// number of commits touching either file, so the score is symmetric.
+    fn scores(&self) -> (f64, f64) {
+        let union_total = self.a_total + self.b_total - self.co_total;
+        let union_before = self.a_before + self.b_before - self.co_before;
+        (
+            wilson_lower_bound(self.co_total, union_total),
+            wilson_lower_bound(self.co_before, union_before),
+        )
+    }
+
+    /// A pair is emerging when it is significant now but was not before the window.
+    fn is_emerging(&self) -> bool {
+        let (now, before) = self.scores();
+        self.co_total >= MIN_CO_CHANGES
+            && now >= SIGNIFICANCE_THRESHOLD
+            && before < SIGNIFICANCE_THRESHOLD
+    }
+
+    /// Compare the pair's co-change rate (co-changes per commit touching
+    /// either file) within the window with its rate before it. Pairs with
+    /// too few co-changes to tell, or where neither file changed within
+    /// the window, are stable.
+    fn trend(&self) -> CouplingTrend {
+        let rate = |co: u32, a: u32, b: u32| {
+            let union = a + b - co;
+            (union > 0).then(|| co as f64 / union as f64)
+        };
+        let recent = rate(
+            self.co_total - self.co_before,
+            self.a_total - self.a_before,
+            self.b_total - self.b_before,
+        );
+        let before = rate(self.co_before, self.a_before, self.b_before);
+        match (recent, before) {
+            _ if self.co_total < MIN_CO_CHANGES => CouplingTrend::Stable,
+            (Some(recent), Some(before)) if recent > before * TREND_RATIO => CouplingTrend::Increasing,
+            (Some(recent), Some(before)) if recent * TREND_RATIO < before => CouplingTrend::Decreasing,
+            (Some(recent), None) if recent > 0.0 => CouplingTrend::Increasing,
+            _ => CouplingTrend::Stable,
+        }
+    }
+}
+
+/// Start of the recent window, measured back from the newest indexed commit.
+/// None when the indexed history does not reach back past the window: with
+/// no "before" to compare against, every coupling would look new.
+fn window_start(db: &Database, window_days: u32) -> Result<Option<i64>, rusqlite::Error> {
+    let (oldest_ts, newest_ts) = db.commit_time_range()?;
+    let since = newest_ts - window_days as i64 * SECONDS_PER_DAY;
+    Ok((oldest_ts < since).then_some(since))
+}
+
+/// Report file pairs whose coupling crossed the significance threshold
+/// within the last `window_days` of indexed history.
+pub fn coupling_alerts(
+    db: &Database,
+    repo_root: &str,
+    window_days: u32,
+) -> Result<AlertsResponse, Box<dyn std::error::Error>> {
+    let mut alerts: Vec<CouplingAlert> = Vec::new();
+    let Some(since) = window_start(db, window_days)? else {
+        return Ok(AlertsResponse {
+            repo_root: repo_root.to_string(),
+            window_days,
+            threshold: SIGNIFICANCE_THRESHOLD,
+            alerts,
+        });
+    };
+    let file_counts = db.file_commit_counts_split(since)?;
+
+    for (file_a, file_b, co_total, co_before) in db.recent_pair_counts(since)? {
+        let (Some(&(a_total, a_before)), Some(&(b_total, b_before))) =
+            (file_counts.get(&file_a), file_counts.get(&file_b))
+        else {
+            continue;
+        };
+        let counts = PairCounts { co_total, co_before, a_total, a_before, b_total, b_before };
+        if !counts.is_emerging() {
+            continue;
+        }
+
+        let (score, previous_score) = counts.scores();
+        alerts.push(CouplingAlert {
+            file_a,
+            file_b,
+            co_change_count: co_total,
+            recent_co_change_count: co_total - co_before,
+            score,
+            previous_score,
+        });
+    }
+
+    alerts.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.file_a.cmp(&b.file_a))
+            .then_with(|| a.file_b.cmp(&b.file_b))
+    });
+
+    Ok(AlertsResponse {
+        repo_root: repo_root.to_string(),
+        window_days,
+        threshold: SIGNIFICANCE_THRESHOLD,
+        alerts,
+    })
+}
+
+/// Set `emerging` on coupled files whose coupling with `file_path` formed
+/// within the default recent window, and `trend` on every directly coupled
+/// file.
+pub fn mark_trends(
+    db: &Database,
+    file_path: &str,
+    coupled_files: &mut [CoupledFile],
+) -> Result<(), rusqlite::Error> {
+    if coupled_files.is_empty() {
+        return Ok(());
+    }
+
+    let Some(since) = window_start(db, DEFAULT_WINDOW_DAYS)? else {
+        return Ok(());
+    };
+    let a_total = db.commit_count(file_path)?;
+    let a_before = db.commit_count_before(file_path, since)?;
+    let paths: Vec<&str> = coupled_files.iter().map(|f| f.path.as_str()).collect();
+    let splits: HashMap<String, CoChangeSplit> = db
+        .co_change_splits(file_path, &paths, since)?
+        .into_iter()
+        .map(|split| (split.path.clone(), split))
+        .collect();
+
+    for cf in coupled_files.iter_mut() {
+        let Some(split) = splits.get(&cf.path) else {
+            continue;
+        };
+        let counts = PairCounts {
+            co_total: split.co_total,
+            co_before: split.co_before,
+            a_total,
+            a_before,
+            b_total: split.commits_total,
+            b_before: split.commits_before,
+        };
+        cf.emerging = counts.is_emerging();
+        cf.trend = Some(counts.trend());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = SECONDS_PER_DAY;
+
+    #[test]
+    fn test_wilson_lower_bound_penalizes_small_samples() {
+        assert_eq!(wilson_lower_bound(0, 0), 0.0);
+        let small = wilson_lower_bound(2, 2);
+        let large = wilson_lower_bound(40, 50);
+        assert!(small < large, "small={small} large={large}");
+        assert!(large < 0.8);
+    }
+
+    /// A/B were independent for a year, then started changing together;
+    /// C/D have always changed together.
+    fn seed(db: &Database) {
+        let now = 400 * DAY;
+        for i in 0..10 {
+            let ts = i * 30 * DAY;
+            db.insert_commit(&format!("a{i}"), &["src/A.ts"], ts).unwrap();
+            db.insert_commit(&format!("b{i}"), &["src/B.ts"], ts).unwrap();
+            db.insert_commit(&format!("cd{i}"), &["src/C.ts", "src/D.ts"], ts).unwrap();
+        }
+        for i in 0..20 {
+            db.insert_commit(&format!("ab{i}"), &["src/A.ts", "src/B.ts"], now - i).unwrap();
+        }
+        db.insert_commit("cd_recent", &["src/C.ts", "src/D.ts"], now).unwrap();
+    }
+
+    #[test]
+    fn test_alerts_report_only_newly_formed_couplings() {
+        let db = Database::in_memory().unwrap();
+        seed(&db);
+
+        let resp = coupling_alerts(&db, "/repo", DEFAULT_WINDOW_DAYS).unwrap();
+
+        assert_eq!(resp.alerts.len(), 1);
+        let alert = &resp.alerts[0];
+        assert_eq!(alert.file_a, "src/A.ts");
+        assert_eq!(alert.file_b, "src/B.ts");
+        assert_eq!(alert.co_change_count, 20);
+        assert_eq!(alert.recent_co_change_count, 20);
+        assert!(alert.score >= SIGNIFICANCE_THRESHOLD);
+        assert_eq!(alert.previous_score, 0.0);
+    }
+
+    #[test]
+    fn test_no_alerts_when_history_fits_in_window() {
+        let db = Database::in_memory().unwrap();
+        for i in 0..5 {
+            db.insert_commit(&format!("ab{i}"), &["src/A.ts", "src/B.ts"], i * DAY).unwrap();
+        }
+
+        let resp = coupling_alerts(&db, "/repo", DEFAULT_WINDOW_DAYS).unwrap();
+        assert!(resp.alerts.is_empty());
+    }
+
+    #[test]
+    fn test_mark_emerging_flags_coupled_files() {
+        let db = Database::in_memory().unwrap();
+        seed(&db);
+
+        let mut files = vec![CoupledFile {
+            path: "src/B.ts".to_string(),
+            ..Default::default()
+        }];
+        mark_trends(&db, "src/A.ts", &mut files).unwrap();
+        assert!(files[0].emerging);
+        assert_eq!(files[0].trend, Some(CouplingTrend::Increasing));
+
+        let mut files = vec![CoupledFile {
+            path: "src/D.ts".to_string(),
+            ..Default::default()
+        }];
+        mark_trends(&db, "src/C.ts", &mut files).unwrap();
+        assert!(!files[0].emerging);
+        assert_eq!(files[0].trend, Some(CouplingTrend::Stable));
+    }
+
+    #[test]
+    fn test_mark_trends_flags_fading_couplings() {
+        let db = Database::in_memory().unwrap();
+        seed(&db);
+        // E/F changed together for a year; lately E changes alone
+        for i in 0..10 {
+            db.insert_commit(&format!("ef{i}"), &["src/E.ts", "src/F.ts"], i * 30 * DAY).unwrap();
+        }
+        for i in 0..5 {
+            db.insert_commit(&format!("e{i}"), &["src/E.ts"], 400 * DAY - i).unwrap();
+        }
+
+        let mut files = vec![CoupledFile { path: "src/F.ts".to_string(), ..Default::default() }];
+        mark_trends(&db, "src/E.ts", &mut files).unwrap();
+        assert!(!files[0].emerging);
+        assert_eq!(files[0].trend, Some(CouplingTrend::Decreasing));
+    }
+}
@@ -0,0 +1,452 @@
+use std::collections::HashMap;
+
+use crate::persistence::Database;
+use crate::timestamps::iso_timestamp;
+use crate::types::{
+    AuthorInfo, BusFactorResponse, CoEditEdge, CollabGraphResponse, CoupledFile, Expert, ExpertFile,
+    ModuleContribution, OwnershipEntry,
+};
+
+/// Files with fewer indexed commits than this are too young to judge ownership.
+const MIN_COMMITS: u32 = 3;
+
+/// Reviewers `experts` suggests by default.
+pub const DEFAULT_EXPERTS: usize = 3;
+
+/// Top coupled files whose authors count toward expertise in a file.
+const EXPERT_COUPLED_FILES: usize = 5;
+
+/// Expertise in a file halves for every this many days since the author
+/// last changed it.
+const EXPERT_HALF_LIFE_DAYS: f64 = 180.0;
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// Per-author commit tally for a single file or directory.
+struct AuthorTally {
+    commits: HashMap<String, (String, u32)>, // email -> (display name, commits)
+}
+
+impl AuthorTally {
+    fn new() -> Self {
+        Self { commits: HashMap::new() }
+    }
+
+    fn add(&mut self, email: &str, name: &str, count: u32) {
+        let entry = self
+            .commits
+            .entry(email.to_string())
+            .or_insert_with(|| (name.to_string(), 0));
+        entry.1 += count;
+    }
+
+    /// Summarize into an ownership entry. Bus factor is the smallest number
+    /// of authors who together account for more than half of the commits.
+    fn summarize(&self, path: &str) -> OwnershipEntry {
+        let mut authors: Vec<(&String, &(String, u32))> = self.commits.iter().collect();
+        authors.sort_by(|a, b| b.1.1.cmp(&a.1.1).then_with(|| a.0.cmp(b.0)));
+
+        let total: u32 = authors.iter().map(|(_, (_, c))| c).sum();
+        let mut bus_factor = 0u32;
+        let mut covered = 0u32;
+        for (_, (_, count)) in &authors {
+            bus_factor += 1;
+            covered += count;
+            if covered * 2 > total {
+                break;
+            }
+        }
+
+        let (top_email, (top_name, top_count)) = authors[0];
+        OwnershipEntry {
+            path: path.to_string(),
+            bus_factor,
+            dominant_author: top_name.clone(),
+            dominant_author_email: top_email.clone(),
+            dominant_share: *top_count as f64 / total.max(1) as f64,
+            author_count: authors.len() as u32,
+            commit_count: total,
+        }
+    }
+}
+
+/// Record authors for indexed commits that have none, reading them from
+/// git. Indexes completed before authors were recorded are never walked
+/// again, so the author reports fill them in on first use. Commits git no
+/// longer has are skipped. Returns the number of authors recorded.
+pub fn backfill_authors(db: &Database, repo: &git2::Repository) -> Result<u32, Box<dyn std::error::Error>> {
+    let missing = db.commits_without_author()?;
+    if missing.is_empty() {
+        return Ok(0);
+    }
+
+    let mut recorded = 0;
+    db.begin_transaction()?;
+    for hash in &missing {
+        let Some(commit) = git2::Oid::from_str(hash).ok().and_then(|oid| repo.find_commit(oid).ok()) else {
+            continue;
+        };
+        let author = commit.author();
+        db.insert_commit_author(
+            hash,
+            author.name().unwrap_or(""),
+            author.email().unwrap_or(""),
+            commit.time().seconds(),
+        )?;
+        recorded += 1;
+    }
+    db.commit_transaction()?;
+    Ok(recorded)
+}
+
+/// Report files and directories under `path_prefix` whose history is
+/// dominated by a single author (bus factor of 1).
+///
+/// Directory tallies are edit-weighted: a commit touching three files in
+/// the same directory counts three times toward its author.
+pub fn bus_factor(
+    db: &Database,
+    repo_root: &str,
+    path_prefix: Option<&str>,
+) -> Result<BusFactorResponse, Box<dyn std::error::Error>> {
+    let rows = db.file_author_counts(path_prefix.unwrap_or(""))?;
+
+    let mut files: HashMap<String, AuthorTally> = HashMap::new();
+    let mut dirs: HashMap<String, AuthorTally> = HashMap::new();
+    for (path, email, name, count) in &rows {
+        files
+            .entry(path.clone())
+            .or_insert_with(AuthorTally::new)
+            .add(email, name, *count);
+
+        let dir = match path.rfind('/') {
+            Some(idx) => &path[..idx],
+            None => ".",
+        };
+        dirs.entry(dir.to_string())
+            .or_insert_with(AuthorTally::new)
+            .add(email, name, *count);
+    }
+
+    Ok(BusFactorResponse {
+        repo_root: repo_root.to_string(),
+        path_prefix: path_prefix.map(|s| s.to_string()),
+        files: single_owner_entries(&files),
+        directories: single_owner_entries(&dirs),
+    })
+}
+
+/// Ownership summary for a single file, or None if no indexed commit
+/// touching it has a recorded author.
+pub fn file_ownership(
+    db: &Database,
+    file_path: &str,
+) -> Result<Option<OwnershipEntry>, rusqlite::Error> {
+    let mut tally = AuthorTally::new();
+    for (path, email, name, count) in db.file_author_counts(file_path)? {
+        if path == file_path {
+            tally.add(&email, &name, count);
+        }
+    }
+    Ok((!tally.commits.is_empty()).then(|| tally.summarize(file_path)))
+}
+
+/// The first `depth` directory components of `path` (`.` for root files).
+fn module_of(path: &str, depth: u32) -> String {
+    let dirs: Vec<&str> = path.split('/').collect();
+    let dirs = &dirs[..dirs.len() - 1];
+    if dirs.is_empty() {
+        return ".".to_string();
+    }
+    dirs[..dirs.len().min(depth.max(1) as usize)].join("/")
+}
+
+/// Who works on what: an author x module contribution matrix and
+/// author-author co-editing weights, from the authors in the temporal index.
+///
+/// Modules are the first `module_depth` directory levels. Contributions are
+/// edit-weighted like `bus_factor` directories. Two authors co-edit a file
+/// when both have commits touching it.
+pub fn collab_graph(
+    db: &Database,
+    repo_root: &str,
+    module_depth: u32,
+) -> Result<CollabGraphResponse, rusqlite::Error> {
+    let rows = db.file_author_counts("")?;
+
+    let mut names: HashMap<String, String> = HashMap::new();
+    let mut matrix: HashMap<(String, String), u32> = HashMap::new();
+    let mut by_file: HashMap<&str, Vec<(&str, u32)>> = HashMap::new();
+    for (path, email, name, count) in &rows {
+        names.entry(email.clone()).or_insert_with(|| name.clone());
+        *matrix.entry((email.clone(), module_of(path, module_depth))).or_default() += count;
+        by_file.entry(path).or_default().push((email, *count));
+    }
+
+    let mut pairs: HashMap<(&str, &str), (u32, u32)> = HashMap::new();
+    for editors in by_file.values() {
+        for (i, (a, count_a)) in editors.iter().enumerate() {
+            for (b, count_b) in &editors[i + 1..] {
+                let key = if a < b { (*a, *b) } else { (*b, *a) };
+                let entry = pairs.entry(key).or_default();
+                entry.0 += 1;
+                entry.1 += count_a.min(count_b);
+            }
+        }
+    }
+
+    let mut authors: Vec<AuthorInfo> = names
+        .into_iter()
+        .map(|(email, name)| AuthorInfo { name, email })
+        .collect();
+    authors.sort_by(|a, b| a.email.cmp(&b.email));
+
+    let mut contributions: Vec<ModuleContribution> = matrix
+        .into_iter()
+        .map(|((author_email, module), commits)| ModuleContribution { author_email, module, commits })
+        .collect();
+    contributions.sort_by(|a, b| {
+        a.module
+            .cmp(&b.module)
+            .then_with(|| b.commits.cmp(&a.commits))
+            .then_with(|| a.author_email.cmp(&b.author_email))
+    });
+
+    let mut co_editing: Vec<CoEditEdge> = pairs
+        .into_iter()
+        .map(|((a, b), (shared_files, weight))| CoEditEdge {
+            author_a: a.to_string(),
+            author_b: b.to_string(),
+            shared_files,
+            weight,
+        })
+        .collect();
+    co_editing.sort_by(|a, b| {
+        b.weight
+            .cmp(&a.weight)
+            .then_with(|| a.author_a.cmp(&b.author_a))
+            .then_with(|| a.author_b.cmp(&b.author_b))
+    });
+
+    Ok(CollabGraphResponse {
+        repo_root: repo_root.to_string(),
+        module_depth,
+        authors,
+        contributions,
+        co_editing,
+    })
+}
+
+/// Suggest up to `limit` reviewers for a change to `file_path`: the authors
+/// who know it and its top `coupled_files` best. Each file adds the
+/// author's share of its commits, weighted by the file's coupling score
+/// (1 for `file_path` itself) and halved for every `EXPERT_HALF_LIFE_DAYS`
+/// between the author's last change to it and the newest indexed commit.
+pub fn experts(
+    db: &Database,
+    file_path: &str,
+    coupled_files: &[CoupledFile],
+    limit: usize,
+) -> Result<Vec<Expert>, rusqlite::Error> {
+    let newest_ts = db.commit_time_range()?.1;
+    let files = std::iter::once((file_path, 1.0)).chain(
+        coupled_files
+            .iter()
+            .filter(|f| f.via.is_none())
+            .take(EXPERT_COUPLED_FILES)
+            .map(|f| (f.path.as_str(), f.coupling_score)),
+    );
+
+    // email -> (expert, last commit timestamp)
+    let mut tallies: HashMap<String, (Expert, i64)> = HashMap::new();
+    for (path, weight) in files {
+        let authors = db.file_authors(path)?;
+        let total: u32 = authors.iter().map(|(_, _, commits, _)| commits).sum();
+        for (email, name, commits, last_ts) in authors {
+            let age_days = (newest_ts - last_ts).max(0) as f64 / SECONDS_PER_DAY;
+            let recency = 0.5f64.powf(age_days / EXPERT_HALF_LIFE_DAYS);
+            let (expert, last) = tallies.entry(email.clone()).or_insert_with(|| {
+                let expert = Expert {
+                    name,
+                    email,
+                    score: 0.0,
+                    commits: 0,
+                    last_commit_at: String::new(),
+                    files: Vec::new(),
+                };
+                (expert, i64::MIN)
+            });
+            expert.score += weight * commits as f64 / total.max(1) as f64 * recency;
+            expert.commits += commits;
+            expert.files.push(ExpertFile {
+                path: path.to_string(),
+                commits,
+                last_commit_at: iso_timestamp(last_ts),
+            });
+            *last = (*last).max(last_ts);
+        }
+    }
+
+    let mut experts: Vec<Expert> = tallies
+        .into_values()
+        .map(|(mut expert, last)| {
+            expert.last_commit_at = iso_timestamp(last);
+            expert.files.sort_by(|a, b| b.commits.cmp(&a.commits).then_with(|| a.path.cmp(&b.path)));
+            expert
+        })
+        .collect();
+    experts.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.email.cmp(&b.email)));
+    experts.truncate(limit);
+    Ok(experts)
+}
+
+fn single_owner_entries(tallies: &HashMap<String, AuthorTally>) -> Vec<OwnershipEntry> {
+    let mut entries: Vec<OwnershipEntry> = tallies
+        .iter()
+        .map(|(path, tally)| tally.summarize(path))
+        .filter(|e| e.bus_factor == 1 && e.commit_count >= MIN_COMMITS)
+        .collect();
+    entries.sort_by(|a, b| b.commit_count.cmp(&a.commit_count).then_with(|| a.path.cmp(&b.path)));
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(db: &Database, hash: &str, files: &[&str], author: &str) {
+        db.insert_commit(hash, files, 0).unwrap();
+        db.insert_commit_author(hash, author, &format!("{author}@example.com"), 0)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_reports_single_owner_files() {
+        let db = Database::in_memory().unwrap();
+        for i in 0..4 {
+            commit(&db, &format!("a{i}"), &["src/owned.ts"], "alice");
+        }
+        commit(&db, "b0", &["src/owned.ts", "src/shared.ts"], "bob");
+        commit(&db, "a4", &["src/shared.ts"], "alice");
+        commit(&db, "c0", &["src/shared.ts"], "carol");
+
+        let resp = bus_factor(&db, "/repo", None).unwrap();
+
+        assert_eq!(resp.files.len(), 1);
+        let owned = &resp.files[0];
+        assert_eq!(owned.path, "src/owned.ts");
+        assert_eq!(owned.bus_factor, 1);
+        assert_eq!(owned.dominant_author, "alice");
+        assert_eq!(owned.commit_count, 5);
+        assert!((owned.dominant_share - 0.8).abs() < 1e-9);
+
+        // src/: alice 5, bob 2, carol 1 edits -> still dominated by alice
+        assert_eq!(resp.directories.len(), 1);
+        assert_eq!(resp.directories[0].path, "src");
+        assert_eq!(resp.directories[0].author_count, 3);
+    }
+
+    #[test]
+    fn test_skips_young_files_and_respects_prefix() {
+        let db = Database::in_memory().unwrap();
+        commit(&db, "a0", &["src/new.ts"], "alice");
+        for i in 0..3 {
+            commit(&db, &format!("b{i}"), &["lib/old.ts"], "bob");
+        }
+
+        let resp = bus_factor(&db, "/repo", None).unwrap();
+        assert_eq!(resp.files.len(), 1);
+        assert_eq!(resp.files[0].path, "lib/old.ts");
+
+        let scoped = bus_factor(&db, "/repo", Some("src/")).unwrap();
+        assert!(scoped.files.is_empty());
+        assert_eq!(scoped.path_prefix.as_deref(), Some("src/"));
+    }
+
+    #[test]
+    fn test_collab_graph() {
+        let db = Database::in_memory().unwrap();
+        commit(&db, "a0", &["api/auth.ts", "api/user.ts"], "alice");
+        commit(&db, "a1", &["api/auth.ts"], "alice");
+        commit(&db, "b0", &["api/auth.ts", "web/app.ts"], "bob");
+        commit(&db, "c0", &["README.md"], "carol");
+
+        let resp = collab_graph(&db, "/repo", 1).unwrap();
+
+        assert_eq!(resp.authors.len(), 3);
+        let contribution = |email: &str, module: &str| {
+            resp.contributions
+                .iter()
+                .find(|c| c.author_email == email && c.module == module)
+                .map(|c| c.commits)
+        };
+        assert_eq!(contribution("alice@example.com", "api"), Some(3));
+        assert_eq!(contribution("bob@example.com", "api"), Some(1));
+        assert_eq!(contribution("bob@example.com", "web"), Some(1));
+        assert_eq!(contribution("carol@example.com", "."), Some(1));
+
+        assert_eq!(resp.co_editing.len(), 1);
+        let edge = &resp.co_editing[0];
+        assert_eq!((edge.author_a.as_str(), edge.author_b.as_str()), ("alice@example.com", "bob@example.com"));
+        assert_eq!(edge.shared_files, 1);
+        assert_eq!(edge.weight, 1);
+    }
+
+    #[test]
+    fn test_experts_weigh_coupling_and_recency() {
+        let db = Database::in_memory().unwrap();
+        let day = SECONDS_PER_DAY as i64;
+        let commit_at = |hash: &str, files: &[&str], author: &str, ts: i64| {
+            db.insert_commit(hash, files, ts).unwrap();
+            db.insert_commit_author(hash, author, &format!("{author}@example.com"), ts).unwrap();
+        };
+        // alice wrote auth.ts years ago; bob and carol work on it now,
+        // and carol also owns the session code it is coupled with
+        for i in 0..4 {
+            commit_at(&format!("a{i}"), &["src/auth.ts"], "alice", i);
+        }
+        commit_at("b0", &["src/auth.ts"], "bob", 1000 * day);
+        commit_at("b1", &["src/auth.ts"], "bob", 1000 * day);
+        commit_at("c0", &["src/auth.ts", "src/session.ts"], "carol", 1000 * day);
+        commit_at("c1", &["src/session.ts"], "carol", 1000 * day);
+
+        let session = CoupledFile { path: "src/session.ts".to_string(), coupling_score: 0.5, ..Default::default() };
+        let experts = experts(&db, "src/auth.ts", &[session], 2).unwrap();
+
+        let emails: Vec<&str> = experts.iter().map(|e| e.email.as_str()).collect();
+        assert_eq!(emails, vec!["carol@example.com", "bob@example.com"]);
+        let carol = &experts[0];
+        assert_eq!(carol.commits, 3);
+        assert_eq!(carol.files[0].path, "src/session.ts");
+        assert_eq!(carol.files[0].commits, 2);
+        assert_eq!(carol.last_commit_at, iso_timestamp(1000 * day));
+    }
+
+    #[test]
+    fn test_module_of() {
+        assert_eq!(module_of("README.md", 1), ".");
+        assert_eq!(module_of("src/a/b.rs", 1), "src");
+        assert_eq!(module_of("src/a/b.rs", 2), "src/a");
+        assert_eq!(module_of("src/a/b.rs", 5), "src/a");
+    }
+
+    #[test]
+    fn test_backfill_authors_for_older_index() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let repo = git2::Repository::init(dir.path()).unwrap();
+        let sig = git2::Signature::new("Ada", "ada@example.com", &git2::Time::new(1_700_000_000, 0)).unwrap();
+        let tree = repo.find_tree(repo.index().unwrap().write_tree().unwrap()).unwrap();
+        let oid = repo.commit(Some("HEAD"), &sig, &sig, "init", &tree, &[]).unwrap();
+
+        // Indexed before authors were recorded, plus a commit git no longer has
+        let db = Database::in_memory().unwrap();
+        db.insert_commit(&oid.to_string(), &["src/a.ts"], 1_700_000_000).unwrap();
+        db.insert_commit("0123456789012345678901234567890123456789", &["src/a.ts"], 0).unwrap();
+
+        assert_eq!(backfill_authors(&db, &repo).unwrap(), 1);
+        let owner = file_ownership(&db, "src/a.ts").unwrap().unwrap();
+        assert_eq!(owner.dominant_author, "Ada");
+        assert_eq!(owner.commit_count, 1);
+        assert_eq!(backfill_authors(&db, &repo).unwrap(), 0);
+    }
+}
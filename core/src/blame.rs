@@ -0,0 +1,126 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use git2::{BlameOptions, Repository};
+
+use crate::persistence::Database;
+use crate::types::{AnalysisResponse, LineRegion};
+
+/// Lines from other commits up to this many apart are bridged, so a block
+/// broken up by a later one-line fix still reads as one region.
+const MERGE_GAP: u32 = 3;
+/// Regions reported per coupled file, largest first.
+const MAX_REGIONS: usize = 5;
+
+/// Mark, for each directly coupled file, the line regions of the analyzed
+/// file that were last changed by a commit shared with it: the part of the
+/// file that actually co-changes.
+///
+/// Blames the file as of `rev` (HEAD when `None`). Blame credits each line
+/// to its latest commit only, so lines a shared commit touched but a later
+/// commit rewrote are not attributed to the coupling.
+pub fn annotate_regions(
+    repo_root: &Path,
+    db: &Database,
+    rev: Option<&str>,
+    response: &mut AnalysisResponse,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let repo = Repository::open(repo_root)?;
+    let mut opts = BlameOptions::new();
+    if let Some(rev) = rev {
+        opts.newest_commit(repo.revparse_single(rev)?.peel_to_commit()?.id());
+    }
+    let blame = repo.blame_file(Path::new(&response.file_path), Some(&mut opts))?;
+    // (commit, first line, last line), in file order
+    let hunks: Vec<(String, u32, u32)> = blame
+        .iter()
+        .filter(|hunk| hunk.lines_in_hunk() > 0)
+        .map(|hunk| {
+            let start = hunk.final_start_line() as u32;
+            (hunk.final_commit_id().to_string(), start, start + hunk.lines_in_hunk() as u32 - 1)
+        })
+        .collect();
+
+    for file in response.coupled_files.iter_mut().filter(|f| f.via.is_none()) {
+        let shared: HashSet<String> = db
+            .shared_commits(&response.file_path, &file.path)?
+            .into_iter()
+            .map(|(hash, _, _)| hash)
+            .collect();
+        file.regions = line_regions(&hunks, &shared);
+    }
+    Ok(())
+}
+
+/// Merge the hunks last changed by a `shared` commit into regions, keep the
+/// largest, and return them in file order.
+fn line_regions(hunks: &[(String, u32, u32)], shared: &HashSet<String>) -> Vec<LineRegion> {
+    let mut regions: Vec<LineRegion> = Vec::new();
+    for (commit, start, end) in hunks.iter().filter(|(commit, _, _)| shared.contains(commit)) {
+        match regions.last_mut() {
+            Some(last) if *start <= last.end_line + MERGE_GAP + 1 => {
+                last.end_line = last.end_line.max(*end);
+                last.commits.insert(commit.clone());
+            }
+            _ => regions.push(LineRegion {
+                start_line: *start,
+                end_line: *end,
+                commits: [commit.clone()].into(),
+            }),
+        }
+    }
+
+    regions.sort_by(|a, b| {
+        (b.end_line - b.start_line)
+            .cmp(&(a.end_line - a.start_line))
+            .then_with(|| a.start_line.cmp(&b.start_line))
+    });
+    regions.truncate(MAX_REGIONS);
+    regions.sort_by_key(|r| r.start_line);
+    regions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::commit_files;
+    use crate::indexing::IndexOptions;
+    use crate::temporal;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_regions_cover_lines_from_shared_commits() {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let lines = |tail: &str| format!("a\nb\nc\nd\ne\nf\ng\n{tail}");
+        commit_files(&repo, "alice", &[("src/auth.rs", &lines("h\n"))], "Add auth");
+        commit_files(&repo, "alice", &[("src/auth.rs", &lines("h\ni\nj\n")), ("src/session.rs", "1")], "Sessions");
+        commit_files(&repo, "alice", &[("src/auth.rs", &lines("h\ni\nj\nk\n")), ("src/session.rs", "2")], "More");
+
+        let db = Database::in_memory().unwrap();
+        let (mut response, _) =
+            temporal::analyze(dir.path(), "src/auth.rs", &db, &IndexOptions::default()).unwrap();
+        annotate_regions(dir.path(), &db, None, &mut response).unwrap();
+
+        let session = response.coupled_files.iter().find(|f| f.path == "src/session.rs").unwrap();
+        assert_eq!(session.regions.len(), 1);
+        assert_eq!((session.regions[0].start_line, session.regions[0].end_line), (9, 11));
+        assert_eq!(session.regions[0].commits.len(), 2);
+    }
+
+    #[test]
+    fn test_line_regions_bridge_small_gaps_and_keep_largest() {
+        let hunk = |c: &str, s, e| (c.to_string(), s, e);
+        let hunks = vec![
+            hunk("x", 1, 10),
+            hunk("y", 11, 11),
+            hunk("x", 12, 20),
+            hunk("y", 21, 40),
+            hunk("x", 41, 41),
+        ];
+        let shared: HashSet<String> = ["x".to_string()].into();
+        let regions = line_regions(&hunks, &shared);
+        let spans: Vec<(u32, u32)> = regions.iter().map(|r| (r.start_line, r.end_line)).collect();
+        assert_eq!(spans, vec![(1, 20), (41, 41)]);
+    }
+}
@@ -0,0 +1,309 @@
+use crate::config::Config;
+use crate::types::{AnalysisResponse, EnrichmentOmitted};
+
+/// Rough bytes per token of serialized JSON, for token budgets.
+const BYTES_PER_TOKEN: usize = 4;
+
+/// Size limit for an analysis response handed to an agent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextBudget {
+    /// Serialized JSON bytes
+    Bytes(usize),
+    /// Approximate tokens, estimated as one per `BYTES_PER_TOKEN` bytes
+    Tokens(usize),
+}
+
+impl ContextBudget {
+    fn bytes(self) -> usize {
+        match self {
+            ContextBudget::Bytes(bytes) => bytes,
+            ContextBudget::Tokens(tokens) => tokens.saturating_mul(BYTES_PER_TOKEN),
+        }
+    }
+}
+
+/// Split `cap` items across files that each want `demands[i]`, in proportion
+/// to `weights[i]`. No file gets more than it asked for; capacity a file
+/// cannot use is redistributed to the others. Leftover single items go to
+/// earlier (higher-ranked) files first.
+fn fair_shares(demands: &[usize], weights: &[f64], cap: usize) -> Vec<usize> {
+    if demands.iter().sum::<usize>() <= cap {
+        return demands.to_vec();
+    }
+
+    let mut shares = vec![0usize; demands.len()];
+    let mut remaining = cap;
+    while remaining > 0 {
+        let active: Vec<usize> = (0..demands.len()).filter(|&i| shares[i] < demands[i]).collect();
+        if active.is_empty() {
+            break;
+        }
+
+        let weight_sum: f64 = active.iter().map(|&i| weights[i].max(0.0)).sum();
+        let mut given = 0;
+        for &i in &active {
+            let weight = if weight_sum > 0.0 {
+                weights[i].max(0.0) / weight_sum
+            } else {
+                1.0 / active.len() as f64
+            };
+            let quota = (remaining as f64 * weight).floor() as usize;
+            let add = quota.min(demands[i] - shares[i]);
+            shares[i] += add;
+            given += add;
+        }
+
+        if given == 0 {
+            // Quotas all rounded down to zero: hand out one at a time by rank.
+            for &i in active.iter().take(remaining) {
+                shares[i] += 1;
+                given += 1;
+            }
+        }
+        remaining -= given;
+    }
+
+    shares
+}
+
+/// Enforce the response-wide caps on memories and test intents. Coupled
+/// files are already sorted by risk, so higher-risk files keep more of
+/// their enrichment. Dropped counts are reported in `enrichment_omitted`.
+pub fn apply_enrichment_caps(response: &mut AnalysisResponse, config: &Config) {
+    let files = &mut response.coupled_files;
+    let weights: Vec<f64> = files.iter().map(|f| f.risk_score).collect();
+
+    let memory_demand: Vec<usize> = files.iter().map(|f| f.memories.len()).collect();
+    let memory_shares = fair_shares(&memory_demand, &weights, config.max_memories);
+
+    let intent_demand: Vec<usize> = files.iter().map(|f| f.test_intents.len()).collect();
+    let intent_shares = fair_shares(&intent_demand, &weights, config.max_test_intents);
+
+    let mut omitted = EnrichmentOmitted::default();
+    for (i, file) in files.iter_mut().enumerate() {
+        omitted.memories += (memory_demand[i] - memory_shares[i]) as u32;
+        omitted.test_intents += (intent_demand[i] - intent_shares[i]) as u32;
+        file.memories.truncate(memory_shares[i]);
+        file.test_intents.truncate(intent_shares[i]);
+    }
+
+    response.enrichment_omitted =
+        (omitted.memories > 0 || omitted.test_intents > 0).then_some(omitted);
+}
+
+/// One unit of content `prioritize_context` can drop.
+enum Trim {
+    Extensions,
+    CoupledIntent(usize),
+    TestInfoIntent(usize),
+    Memory(usize),
+    CoupledFile,
+    TestInfo,
+}
+
+/// Trim `response` until its serialized JSON fits `budget`, dropping the
+/// least valuable content first:
+///
+/// 1. enricher `extensions`
+/// 2. test intents on coupled files, lowest-risk file first
+/// 3. test intents of the analyzed file's own tests, last file first
+/// 4. memories, lowest-risk file first
+/// 5. coupled files, lowest risk first
+/// 6. `test_info`
+///
+/// Within a file, later entries go before earlier ones. Dropped memories,
+/// intents, and files are added to `enrichment_omitted`. The result is
+/// deterministic for a given response and budget; if even the bare
+/// response does not fit, the bare response is returned.
+pub fn prioritize_context(response: &AnalysisResponse, budget: ContextBudget) -> AnalysisResponse {
+    let limit = budget.bytes();
+    let mut out = response.clone();
+
+    while serialized_len(&out) > limit {
+        let Some(step) = next_trim(&out) else {
+            break;
+        };
+        let omitted = out.enrichment_omitted.get_or_insert_with(EnrichmentOmitted::default);
+        match step {
+            Trim::Extensions => out.extensions = None,
+            Trim::CoupledIntent(i) => {
+                out.coupled_files[i].test_intents.pop();
+                omitted.test_intents += 1;
+            }
+            Trim::TestInfoIntent(i) => {
+                if let Some(info) = out.test_info.as_mut() {
+                    info.test_files[i].test_intents.pop();
+                }
+                omitted.test_intents += 1;
+            }
+            Trim::Memory(i) => {
+                out.coupled_files[i].memories.pop();
+                omitted.memories += 1;
+            }
+            Trim::CoupledFile => {
+                out.coupled_files.pop();
+                omitted.coupled_files += 1;
+            }
+            Trim::TestInfo => out.test_info = None,
+        }
+    }
+
+    out
+}
+
+/// Next thing to drop. Coupled files are sorted by risk, so the lowest-risk
+/// file with something left to trim is the last one.
+fn next_trim(response: &AnalysisResponse) -> Option<Trim> {
+    let files = &response.coupled_files;
+    if response.extensions.is_some() {
+        return Some(Trim::Extensions);
+    }
+    if let Some(i) = files.iter().rposition(|f| !f.test_intents.is_empty()) {
+        return Some(Trim::CoupledIntent(i));
+    }
+    if let Some(info) = &response.test_info
+        && let Some(i) = info.test_files.iter().rposition(|f| !f.test_intents.is_empty())
+    {
+        return Some(Trim::TestInfoIntent(i));
+    }
+    if let Some(i) = files.iter().rposition(|f| !f.memories.is_empty()) {
+        return Some(Trim::Memory(i));
+    }
+    if !files.is_empty() {
+        return Some(Trim::CoupledFile);
+    }
+    response.test_info.is_some().then_some(Trim::TestInfo)
+}
+
+fn serialized_len(response: &AnalysisResponse) -> usize {
+    serde_json::to_vec(response).map_or(0, |json| json.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{CoupledFile, Memory, TestIntent};
+
+    fn memory(i: usize) -> Memory {
+        Memory {
+            id: i as i64,
+            file_path: String::new(),
+            symbol_name: None,
+            content: format!("note {i}"),
+            created_at: String::new(),
+            author_name: None,
+            author_email: None,
+            head_commit: None,
+            blob_oid: None,
+            drift: None,
+            global: false,
+        }
+    }
+
+    #[test]
+    fn test_fair_shares_redistributes_unused_capacity() {
+        // Under the cap: untouched
+        assert_eq!(fair_shares(&[2, 3], &[0.9, 0.1], 10), vec![2, 3]);
+        // The low-risk file only wants 1, so the rest flows to the others
+        assert_eq!(fair_shares(&[20, 1, 20], &[0.5, 0.3, 0.2], 10), vec![7, 1, 2]);
+        // Zero weights fall back to an even split, rank order for leftovers
+        assert_eq!(fair_shares(&[5, 5, 5], &[0.0, 0.0, 0.0], 4), vec![2, 1, 1]);
+    }
+
+    fn annotated_response() -> AnalysisResponse {
+        AnalysisResponse {
+            file_path: "src/A.ts".to_string(),
+            repo_root: "/repo".to_string(),
+            coupled_files: (0..4)
+                .map(|i| CoupledFile {
+                    path: format!("src/F{i}.ts"),
+                    risk_score: 0.9 - i as f64 * 0.2,
+                    memories: (0..2).map(memory).collect(),
+                    test_intents: (0..2)
+                        .map(|j| TestIntent { title: format!("intent {i}.{j}"), ..Default::default() })
+                        .collect(),
+                    ..Default::default()
+                })
+                .collect(),
+            commit_count: 10,
+            analysis_time_ms: 1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_prioritize_context_drops_low_value_content_first() {
+        let full = annotated_response();
+        let full_len = serialized_len(&full);
+
+        // Fits: unchanged
+        let same = prioritize_context(&full, ContextBudget::Bytes(full_len));
+        assert_eq!(serialized_len(&same), full_len);
+        assert!(same.enrichment_omitted.is_none());
+
+        // Slightly over: intents of the lowest-risk file go first
+        let trimmed = prioritize_context(&full, ContextBudget::Bytes(full_len - 10));
+        assert!(serialized_len(&trimmed) <= full_len - 10);
+        assert_eq!(trimmed.coupled_files.len(), 4);
+        assert!(trimmed.coupled_files[3].test_intents.len() < 2);
+        assert_eq!(trimmed.coupled_files[0].test_intents.len(), 2);
+        assert_eq!(trimmed.coupled_files[3].memories.len(), 2);
+
+        // Tight: enrichment gone, then low-risk files dropped
+        let tight = prioritize_context(&full, ContextBudget::Tokens(120));
+        assert!(serialized_len(&tight) <= 480);
+        assert!(tight.coupled_files.len() < 4);
+        assert_eq!(tight.coupled_files[0].path, "src/F0.ts");
+        assert!(tight.coupled_files.iter().all(|f| f.test_intents.is_empty()));
+        let omitted = tight.enrichment_omitted.as_ref().unwrap();
+        assert_eq!(omitted.test_intents, 8);
+        assert_eq!(omitted.coupled_files, 4 - tight.coupled_files.len() as u32);
+
+        // Deterministic
+        let again = prioritize_context(&full, ContextBudget::Tokens(120));
+        assert_eq!(serde_json::to_string(&again).unwrap(), serde_json::to_string(&tight).unwrap());
+
+        // Impossible budget: bare response
+        let bare = prioritize_context(&full, ContextBudget::Bytes(1));
+        assert!(bare.coupled_files.is_empty());
+    }
+
+    #[test]
+    fn test_caps_heavily_annotated_file() {
+        let mut response = AnalysisResponse {
+            file_path: "src/A.ts".to_string(),
+            repo_root: "/repo".to_string(),
+            coupled_files: vec![
+                CoupledFile {
+                    path: "src/Hot.ts".to_string(),
+                    risk_score: 0.6,
+                    memories: (0..30).map(memory).collect(),
+                    ..Default::default()
+                },
+                CoupledFile {
+                    path: "src/Other.ts".to_string(),
+                    risk_score: 0.4,
+                    memories: (0..3).map(memory).collect(),
+                    test_intents: vec![TestIntent { title: "works".to_string(), ..Default::default() }],
+                    ..Default::default()
+                },
+            ],
+            commit_count: 10,
+            analysis_time_ms: 1,
+            ..Default::default()
+        };
+        let config = Config {
+            max_memories: 10,
+            ..Config::default()
+        };
+
+        apply_enrichment_caps(&mut response, &config);
+
+        assert_eq!(response.coupled_files[0].memories.len(), 7);
+        assert_eq!(response.coupled_files[1].memories.len(), 3);
+        assert_eq!(response.coupled_files[1].test_intents.len(), 1);
+        let omitted = response.enrichment_omitted.unwrap();
+        assert_eq!(omitted.memories, 23);
+        assert_eq!(omitted.test_intents, 0);
+    }
+}
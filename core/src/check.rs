@@ -0,0 +1,192 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use git2::Repository;
+
+use crate::indexing::IndexOptions;
+use crate::persistence::Database;
+use crate::risk::risk_level;
+use crate::temporal;
+use crate::types::{CheckFinding, CheckResponse};
+
+/// Risk score at which an omitted coupled file is reported by default
+/// (`Critical`).
+pub const DEFAULT_MIN_RISK_SCORE: f64 = 0.8;
+
+/// Lowest risk level that fails a check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailOn {
+    Critical,
+    High,
+    Medium,
+}
+
+impl FailOn {
+    /// Lowest score at this level, matching `risk::risk_level`.
+    pub fn min_score(self) -> f64 {
+        match self {
+            FailOn::Critical => 0.8,
+            FailOn::High => 0.6,
+            FailOn::Medium => 0.3,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CheckOptions {
+    /// Check the index rather than every uncommitted change
+    pub staged: bool,
+    /// Report omitted coupled files at or above this risk score
+    pub min_risk_score: f64,
+    /// Ignore couplings seen in fewer commits than this
+    pub min_co_changes: u32,
+    /// Fail only on findings at or above this level. `None` fails on any
+    /// finding.
+    pub fail_on: Option<FailOn>,
+}
+
+impl Default for CheckOptions {
+    fn default() -> Self {
+        Self { staged: false, min_risk_score: DEFAULT_MIN_RISK_SCORE, min_co_changes: 0, fail_on: None }
+    }
+}
+
+/// Files in the pending commit: the index against HEAD with `staged`, else
+/// every uncommitted change in the working tree. Deletions count, so a
+/// file removed alongside its partner is not reported as missing.
+pub fn changed_files(repo: &Repository, staged: bool) -> Result<Vec<String>, git2::Error> {
+    let head_tree = match repo.head() {
+        Ok(head) => Some(head.peel_to_tree()?),
+        Err(_) => None, // Unborn branch: everything is new
+    };
+    let diff = if staged {
+        repo.diff_tree_to_index(head_tree.as_ref(), None, None)?
+    } else {
+        let mut opts = git2::DiffOptions::new();
+        opts.include_untracked(true).recurse_untracked_dirs(true);
+        repo.diff_tree_to_workdir_with_index(head_tree.as_ref(), Some(&mut opts))?
+    };
+
+    let mut files = BTreeSet::new();
+    for delta in diff.deltas() {
+        for file in [delta.old_file(), delta.new_file()] {
+            if let Some(path) = file.path().and_then(Path::to_str) {
+                files.insert(path.to_string());
+            }
+        }
+    }
+    Ok(files.into_iter().collect())
+}
+
+/// Check the pending commit's blast radius: analyze each changed file and
+/// report the strongly coupled files the commit leaves out. A file coupled
+/// to several changed files is reported once, at its highest risk.
+///
+/// Findings at or above `fail_on` (every finding without it) are marked
+/// `blocking`; the check passes when none are.
+pub fn check(
+    repo_root: &Path,
+    db: &Database,
+    opts: &IndexOptions,
+    options: &CheckOptions,
+) -> Result<CheckResponse, Box<dyn std::error::Error>> {
+    let repo = Repository::open(repo_root)?;
+    let changed = changed_files(&repo, options.staged)?;
+    // Everything that can fail the check is reported, whatever the threshold
+    let min_score = match options.fail_on {
+        Some(level) => level.min_score().min(options.min_risk_score),
+        None => options.min_risk_score,
+    };
+    let in_commit: BTreeSet<&str> = changed.iter().map(String::as_str).collect();
+
+    let mut findings: BTreeMap<String, CheckFinding> = BTreeMap::new();
+    for file in &changed {
+        let (response, _) = temporal::analyze(repo_root, file, db, opts)?;
+        for coupled in response.coupled_files {
+            if coupled.risk_score < min_score
+                || coupled.co_change_count < options.min_co_changes
+                || in_commit.contains(coupled.path.as_str())
+            {
+                continue;
+            }
+            let finding = findings.entry(coupled.path.clone()).or_insert_with(|| CheckFinding {
+                path: coupled.path.clone(),
+                risk_level: String::new(),
+                risk_score: 0.0,
+                co_change_count: 0,
+                coupled_to: Vec::new(),
+                blocking: false,
+            });
+            if coupled.risk_score > finding.risk_score {
+                finding.risk_level = risk_level(coupled.risk_score).to_string();
+                finding.risk_score = coupled.risk_score;
+                finding.co_change_count = coupled.co_change_count;
+                finding.blocking = options.fail_on.is_none_or(|level| coupled.risk_score >= level.min_score());
+            }
+            finding.coupled_to.push(file.clone());
+        }
+    }
+
+    let mut findings: Vec<CheckFinding> = findings.into_values().collect();
+    findings.sort_by(|a, b| b.risk_score.total_cmp(&a.risk_score).then_with(|| a.path.cmp(&b.path)));
+    Ok(CheckResponse {
+        repo_root: repo_root.to_string_lossy().to_string(),
+        staged: options.staged,
+        changed_files: changed,
+        passed: !findings.iter().any(|f| f.blocking),
+        findings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::commit;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_check_reports_omitted_coupled_files() {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        for i in 0..5 {
+            commit(&repo, &["api.ts", "client.ts"], &format!("c{i}"));
+        }
+        commit(&repo, &["unrelated.ts"], "unrelated");
+
+        // Stage api.ts only; client.ts is edited but not staged
+        std::fs::write(dir.path().join("api.ts"), "v5").unwrap();
+        std::fs::write(dir.path().join("client.ts"), "v5").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("api.ts")).unwrap();
+        index.write().unwrap();
+
+        let db = Database::in_memory().unwrap();
+        let opts = IndexOptions::default();
+        let staged = CheckOptions { staged: true, ..Default::default() };
+        let resp = check(dir.path(), &db, &opts, &staged).unwrap();
+        assert_eq!(resp.changed_files, ["api.ts"]);
+        assert!(!resp.passed);
+        assert_eq!(resp.findings.len(), 1);
+        assert_eq!(resp.findings[0].path, "client.ts");
+        assert_eq!(resp.findings[0].risk_level, "Critical");
+        assert_eq!(resp.findings[0].coupled_to, ["api.ts"]);
+        assert!(resp.findings[0].blocking);
+
+        // Too few co-changes to count
+        let strict = CheckOptions { min_co_changes: 6, ..staged.clone() };
+        assert!(check(dir.path(), &db, &opts, &strict).unwrap().findings.is_empty());
+
+        // The working tree includes client.ts, so nothing is missing
+        let resp = check(dir.path(), &db, &opts, &CheckOptions::default()).unwrap();
+        assert_eq!(resp.changed_files, ["api.ts", "client.ts"]);
+        assert!(resp.passed);
+    }
+
+    #[test]
+    fn test_fail_on_matches_risk_levels() {
+        for (level, name) in [(FailOn::Critical, "Critical"), (FailOn::High, "High"), (FailOn::Medium, "Medium")] {
+            assert_eq!(risk_level(level.min_score()), name);
+            assert_ne!(risk_level(level.min_score() - 0.01), name);
+        }
+    }
+}
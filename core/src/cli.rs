@@ -1,10 +1,90 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+
+pub use crate::schema::JsonFormat;
 
 #[derive(Parser, Debug)]
 #[command(name = "engram-core", about = "Blast radius detector for AI agents")]
 pub struct Cli {
     #[command(subcommand)]
     pub command: Command,
+
+    /// Shape of JSON output: `json` (the default) is the original
+    /// unversioned shape, also available as `json-v1` to pin it; `json-v2`
+    /// adds `schema_version`; `ndjson` is json-v2 with lists (notes, coupled
+    /// files, tests, ...) split into one record per line. Implies --json
+    #[arg(long, global = true, value_enum)]
+    pub output_format: Option<JsonFormat>,
+
+    /// Print JSON even when stdout is a terminal. Without it, analyze,
+    /// list-notes, search-notes and tests-to-run print a readable summary
+    /// to a terminal and JSON everywhere else
+    #[arg(long, global = true)]
+    pub json: bool,
+}
+
+/// Output format for analysis results.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Json,
+    /// Markdown section suitable for a PR comment
+    Markdown,
+}
+
+/// Output format for `check`.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CheckFormat {
+    Json,
+    /// Markdown section suitable for a PR comment
+    Markdown,
+    /// GitHub Actions workflow commands (`::error file=...`), shown as
+    /// annotations on the PR diff
+    Github,
+}
+
+/// Output format for the dashboard.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DashboardFormat {
+    Json,
+    /// Self-contained static HTML page
+    Html,
+}
+
+/// Format of imported test results.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TestResultsFormat {
+    /// JUnit XML, as written by most test runners
+    Junit,
+}
+
+/// Lowest risk level of an omitted coupled file that fails `check`.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FailOnLevel {
+    Critical,
+    High,
+    Medium,
+}
+
+/// Period for grouping metrics into a series.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MetricsGroupBy {
+    Day,
+    /// Weeks starting on Monday
+    Week,
+}
+
+/// Output format for the coupling graph.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphFormat {
+    Json,
+    Dot,
+}
+
+/// File format of an exported co-change matrix.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatrixFormat {
+    Csv,
+    /// Needs a build with the `parquet` feature
+    Parquet,
 }
 
 #[derive(Subcommand, Debug)]
@@ -15,6 +95,120 @@ pub enum Command {
         #[arg(long)]
         file: String,
 
+        /// Current content of the file, overriding what is on disk.
+        /// Pass `-` to read it from stdin (e.g. an unsaved editor buffer).
+        #[arg(long)]
+        content: Option<String>,
+
+        /// Never read the working tree during enrichment (coupling still
+        /// comes from git history). Also settable as `no_fs` in
+        /// `.engram/config.json`
+        #[arg(long)]
+        no_fs: bool,
+
+        /// Do not record this analysis in the local usage metrics. Also
+        /// settable as `no_metrics` in `.engram/config.json`
+        #[arg(long)]
+        no_metrics: bool,
+
+        /// Trim the response to roughly this many tokens, dropping the
+        /// lowest-value content first
+        #[arg(long)]
+        max_tokens: Option<usize>,
+
+        /// Restrict indexing and coupling to this subtree (e.g.
+        /// `packages/payments/`), for monorepos. Also settable as `scope`
+        /// in `.engram/config.json`; changing it rebuilds the index
+        #[arg(long)]
+        scope: Option<String>,
+
+        /// Analyze as of this revision (e.g. `origin/main`, a tag or a
+        /// commit) instead of HEAD; switching revisions rebuilds the index
+        #[arg(long)]
+        rev: Option<String>,
+
+        /// Also count files the same author changed within this many hours
+        /// of the file as co-changed, for histories where one change spans
+        /// many small commits. Also settable as `coupling_window_hours` in
+        /// `.engram/config.json`
+        #[arg(long, value_name = "HOURS")]
+        coupling_window: Option<u64>,
+
+        /// Drop coupled files whose co-changes are this likely to be chance
+        /// given both files' churn (e.g. 0.05), so one-off couplings in a
+        /// short history stop showing up. Also settable as `max_p_value`
+        /// in `.engram/config.json`
+        #[arg(long, value_name = "P")]
+        max_p_value: Option<f64>,
+
+        /// Weigh co-changes by age in risk scores, halving every this many
+        /// days, so pairs that stopped changing together rank below current
+        /// ones. Also settable as `coupling_half_life_days` in
+        /// `.engram/config.json`
+        #[arg(long, value_name = "DAYS")]
+        half_life: Option<u64>,
+
+        /// Coupling hops to follow: 2 also reports files strongly coupled
+        /// with the top coupled files, with attenuated scores and the file
+        /// they were reached through
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..=2))]
+        depth: u32,
+
+        /// Blame the file and report, per coupled file, the line regions
+        /// that co-change with it
+        #[arg(long)]
+        regions: bool,
+
+        /// Report at most this many coupled files, highest risk first
+        #[arg(
+            long,
+            default_value_t = crate::risk::DEFAULT_LIMIT as u32,
+            value_parser = clap::value_parser!(u32).range(1..)
+        )]
+        limit: u32,
+
+        /// Report every coupled file, however many
+        #[arg(long, conflicts_with = "limit")]
+        all: bool,
+
+        /// Skip this many of the highest-risk coupled files, to page
+        /// through the rest; the response's `next_offset` points at the
+        /// next page
+        #[arg(long, default_value_t = 0)]
+        offset: usize,
+
+        /// Only report coupled files matching this glob (e.g. `src/**`);
+        /// repeatable. Filtered-out files do not count toward --limit
+        #[arg(long, value_name = "GLOB")]
+        include: Vec<String>,
+
+        /// Never report coupled files matching this glob (e.g. `docs/**`);
+        /// repeatable
+        #[arg(long, value_name = "GLOB")]
+        exclude: Vec<String>,
+
+        /// Only report coupled files changing with the file at least this
+        /// often (coupling score, e.g. 0.3). Weaker files do not count
+        /// toward --limit
+        #[arg(long, value_name = "SCORE")]
+        min_coupling: Option<f64>,
+
+        /// Only report coupled files with at least this risk score (e.g.
+        /// 0.5). Weaker files do not count toward --limit
+        #[arg(long, value_name = "SCORE")]
+        min_risk: Option<f64>,
+
+        /// Only report coupled files in another package (directory with a
+        /// Cargo.toml, package.json, go.mod, ...) than the file. Others do
+        /// not count toward --limit
+        #[arg(long)]
+        cross_package: bool,
+
+        /// Output format (default: a readable summary on a terminal, JSON
+        /// otherwise)
+        #[arg(long, value_enum)]
+        format: Option<OutputFormat>,
+
         /// Path to the git repository root
         #[arg(long)]
         repo_root: String,
@@ -34,6 +228,17 @@ pub enum Command {
         #[arg(long)]
         content: String,
 
+        /// Do not record this note in the local usage metrics. Also
+        /// settable as `no_metrics` in `.engram/config.json`
+        #[arg(long)]
+        no_metrics: bool,
+
+        /// Save to the user-level notes database instead (`ENGRAM_GLOBAL_DB`,
+        /// default ~/.local/share/engram/notes.db), where --file is a glob
+        /// (e.g. `**/*.proto`) matched against files in every repository
+        #[arg(long)]
+        global: bool,
+
         /// Path to the git repository root
         #[arg(long)]
         repo_root: String,
@@ -45,6 +250,15 @@ pub enum Command {
         #[arg(long)]
         query: String,
 
+        /// Skip this many notes (newest first), to page through the rest
+        #[arg(long, default_value_t = 0)]
+        offset: usize,
+
+        /// Return at most this many notes; the response's `next_offset`
+        /// points at the next page
+        #[arg(long)]
+        limit: Option<usize>,
+
         /// Path to the git repository root
         #[arg(long)]
         repo_root: String,
@@ -56,6 +270,23 @@ pub enum Command {
         #[arg(long)]
         file: Option<String>,
 
+        /// Only notes added by this author (git user name or email)
+        #[arg(long)]
+        author: Option<String>,
+
+        /// List the user-level notes instead
+        #[arg(long, conflicts_with_all = ["file", "author"])]
+        global: bool,
+
+        /// Skip this many notes (newest first), to page through the rest
+        #[arg(long, default_value_t = 0)]
+        offset: usize,
+
+        /// Return at most this many notes; the response's `next_offset`
+        /// points at the next page
+        #[arg(long)]
+        limit: Option<usize>,
+
         /// Path to the git repository root
         #[arg(long)]
         repo_root: String,
@@ -63,6 +294,337 @@ pub enum Command {
 
     /// Get usage metrics for the repository
     GetMetrics {
+        /// Also report a series of summaries per day or week
+        #[arg(long, value_enum)]
+        group_by: Option<MetricsGroupBy>,
+
+        /// Also report how often this file was analyzed and its typical
+        /// risk profile (relative to repo root)
+        #[arg(long)]
+        file: Option<String>,
+
+        /// Path to the git repository root
+        #[arg(long)]
+        repo_root: String,
+    },
+
+    /// Report files and directories dominated by a single author
+    BusFactor {
+        /// Optional path prefix to restrict the report to
+        #[arg(long)]
+        path: Option<String>,
+
+        /// Path to the git repository root
+        #[arg(long)]
+        repo_root: String,
+    },
+
+    /// Export who collaborates on what: author x module contributions and
+    /// author co-editing weights
+    CollabGraph {
+        /// Directory levels that make up a module
+        #[arg(long, default_value_t = 1)]
+        module_depth: u32,
+
+        /// Path to the git repository root
+        #[arg(long)]
+        repo_root: String,
+    },
+
+    /// Suggest moving files whose strongest couplings live in another directory
+    SuggestMoves {
+        /// Optional path prefix to restrict suggestions to
+        #[arg(long)]
+        path: Option<String>,
+
+        /// Path to the git repository root
+        #[arg(long)]
+        repo_root: String,
+    },
+
+    /// Export pairwise co-change counts of indexed files for offline analysis
+    ExportMatrix {
+        /// Optional path prefix both files of a pair must be under
+        #[arg(long)]
+        path: Option<String>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = MatrixFormat::Csv)]
+        format: MatrixFormat,
+
+        /// Write the matrix to this file and print a summary; without it,
+        /// CSV goes to stdout
+        #[arg(long)]
+        output: Option<String>,
+
+        /// Path to the git repository root
+        #[arg(long)]
+        repo_root: String,
+    },
+
+    /// Group files into implicit modules (communities of the co-change
+    /// graph) and flag groups that span declared package boundaries
+    Clusters {
+        /// Optional path prefix to restrict clustering to
+        #[arg(long)]
+        path: Option<String>,
+
+        /// Path to the git repository root
+        #[arg(long)]
+        repo_root: String,
+    },
+
+    /// Report couplings that formed recently (newly significant co-change)
+    Alerts {
+        /// Length of the recent window, in days
+        #[arg(long, default_value_t = crate::alerts::DEFAULT_WINDOW_DAYS)]
+        days: u32,
+
+        /// Path to the git repository root
+        #[arg(long)]
+        repo_root: String,
+    },
+
+    /// Export the coupling graph around a file
+    Graph {
+        /// Path to the root file (relative to repo root)
+        #[arg(long)]
+        file: String,
+
+        /// Number of coupling hops to follow from the root file
+        #[arg(long, default_value_t = 1)]
+        depth: u32,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = GraphFormat::Json)]
+        format: GraphFormat,
+
+        /// Path to the git repository root
+        #[arg(long)]
+        repo_root: String,
+    },
+
+    /// Rebuild the temporal index with the current indexing config
+    Reindex {
+        /// Drop all indexed history and rebuild it from scratch (an
+        /// interrupted rebuild is resumed instead)
+        #[arg(long)]
+        full: bool,
+
+        /// Rebuild from this commit instead of HEAD; later commits are
+        /// indexed incrementally by the next analysis
+        #[arg(long, value_name = "OID", conflicts_with = "full")]
+        from: Option<String>,
+
+        /// Stop after this many minutes; rerun to resume from the checkpoint
+        #[arg(long)]
+        max_minutes: Option<u64>,
+
+        /// Path to the git repository root
+        #[arg(long)]
+        repo_root: String,
+    },
+
+    /// Shrink the engram database (vacuum, analyze, WAL checkpoint) and
+    /// report its size before and after
+    Compact {
+        /// Path to the git repository root
+        #[arg(long)]
+        repo_root: String,
+    },
+
+    /// Check a pending commit for strongly coupled files it leaves out (a
+    /// pre-commit blast-radius check)
+    Check {
+        /// Check the staged files only, rather than every uncommitted change
+        #[arg(long)]
+        staged: bool,
+
+        /// Exit with status 1 when any coupled file is missing
+        #[arg(long)]
+        fail: bool,
+
+        /// Exit with status 1 when a missing coupled file is at or above
+        /// this risk level (lower-risk ones are reported too)
+        #[arg(long, value_enum, conflicts_with = "fail")]
+        fail_on: Option<FailOnLevel>,
+
+        /// Report missing coupled files at or above this risk score
+        /// (0.0-1.0; default 0.8, the Critical level)
+        #[arg(long, default_value_t = crate::check::DEFAULT_MIN_RISK_SCORE)]
+        min_risk_score: f64,
+
+        /// Ignore couplings seen in fewer commits than this
+        #[arg(long, default_value_t = 0)]
+        min_co_changes: u32,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = CheckFormat::Json)]
+        format: CheckFormat,
+
+        /// Path to the git repository root
+        #[arg(long)]
+        repo_root: String,
+    },
+
+    /// List the tests to run for a file or the pending commit, with a
+    /// runnable command per test framework
+    TestsToRun {
+        /// Path to the changed file (relative to repo root); defaults to
+        /// the files in the pending commit
+        #[arg(long)]
+        file: Option<String>,
+
+        /// Without --file, use the staged files only, rather than every
+        /// uncommitted change
+        #[arg(long, conflicts_with = "file")]
+        staged: bool,
+
+        /// Path to the git repository root
+        #[arg(long)]
+        repo_root: String,
+    },
+
+    /// Index the commits that landed since the last run (what the git
+    /// hooks run)
+    Update {
+        /// Path to the git repository root
+        #[arg(long)]
+        repo_root: String,
+    },
+
+    /// Install post-commit, post-merge and post-rewrite git hooks that run
+    /// `update` in the background
+    InstallHooks {
+        /// Path to the git repository root
+        #[arg(long)]
+        repo_root: String,
+    },
+
+    /// Keep the index current: index new commits as they land, printing a
+    /// JSON line after each catch-up. Runs until interrupted
+    Watch {
+        /// Quiet period after a ref change before indexing, in milliseconds
+        #[arg(long, default_value_t = 500)]
+        debounce_ms: u64,
+
+        /// Also serve a read-only dashboard (HTML at `/`, JSON under
+        /// `/api/`) on this address, e.g. `127.0.0.1:7878`
+        #[arg(long)]
+        dashboard: Option<std::net::SocketAddr>,
+
+        /// Path to the git repository root
+        #[arg(long)]
+        repo_root: String,
+    },
+
+    /// Show indexing progress and database stats
+    Status {
+        /// Path to the git repository root
+        #[arg(long)]
+        repo_root: String,
+    },
+
+    /// Check the engram database and index state, and suggest fixes
+    Doctor {
+        /// Path to the git repository root
+        #[arg(long)]
+        repo_root: String,
+    },
+
+    /// Import test run results so analyses can flag recently failing tests
+    ImportTestResults {
+        /// Format of the result files
+        #[arg(long, value_enum, default_value_t = TestResultsFormat::Junit)]
+        format: TestResultsFormat,
+
+        /// Result files to import
+        #[arg(required = true)]
+        files: Vec<String>,
+
+        /// Path to the git repository root
+        #[arg(long)]
+        repo_root: String,
+    },
+
+    /// Summarize a file for a first read: owners, age, top coupled files,
+    /// notes, and recent commits
+    Orient {
+        /// Path to the file (relative to repo root)
+        #[arg(long)]
+        file: String,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Json)]
+        format: OutputFormat,
+
+        /// Path to the git repository root
+        #[arg(long)]
+        repo_root: String,
+    },
+
+    /// List the shared commits behind a coupling score
+    Why {
+        /// Path to the file (relative to repo root)
+        #[arg(long)]
+        file: String,
+
+        /// Path to the coupled file (relative to repo root)
+        #[arg(long)]
+        coupled: String,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Json)]
+        format: OutputFormat,
+
+        /// Path to the git repository root
+        #[arg(long)]
+        repo_root: String,
+    },
+
+    /// Report couplings of a file that appeared, strengthened, weakened or
+    /// vanished between two refs, e.g. to check a decoupling refactor
+    Compare {
+        /// Path to the file (relative to repo root)
+        #[arg(long)]
+        file: String,
+
+        /// Ref marking the end of the "before" period (e.g. the refactor's tag)
+        #[arg(long)]
+        from: String,
+
+        /// Ref marking the end of the "after" period
+        #[arg(long, default_value = "HEAD")]
+        to: String,
+
+        /// Path to the git repository root
+        #[arg(long)]
+        repo_root: String,
+    },
+
+    /// Suggest reviewers for a change to a file: the authors who know it
+    /// and its top coupled files best, with their commits and last change
+    Experts {
+        /// Path to the file (relative to repo root)
+        #[arg(long)]
+        file: String,
+
+        /// Reviewers to suggest
+        #[arg(long, default_value_t = crate::authors::DEFAULT_EXPERTS)]
+        limit: usize,
+
+        /// Path to the git repository root
+        #[arg(long)]
+        repo_root: String,
+    },
+
+    /// Print a read-only dashboard of index status, hotspots, recent
+    /// analyses, and note activity (`watch --dashboard` serves it live)
+    Dashboard {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = DashboardFormat::Html)]
+        format: DashboardFormat,
+
         /// Path to the git repository root
         #[arg(long)]
         repo_root: String,
@@ -0,0 +1,259 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::packages;
+use crate::persistence::Database;
+use crate::types::{ClustersResponse, CouplingCluster};
+
+/// Pairs that co-changed fewer times than this are not edges.
+const MIN_CO_CHANGES: u32 = 3;
+/// Edges weaker than this (Jaccard: co-changes over commits touching
+/// either file) are left out, so incidental co-changes don't bridge modules.
+const MIN_EDGE_WEIGHT: f64 = 0.2;
+/// Local-moving passes per level before giving up on convergence.
+const MAX_PASSES: usize = 100;
+
+/// Group files under `path_prefix` into communities of the co-change graph
+/// (Louvain modularity maximisation), each an implicit module. Clusters whose
+/// files belong to more than one of the declared packages in
+/// `package_roots` are flagged as spanning package boundaries.
+pub fn coupling_clusters(
+    db: &Database,
+    repo_root: &str,
+    path_prefix: Option<&str>,
+    package_roots: &[String],
+) -> Result<ClustersResponse, rusqlite::Error> {
+    let prefix = path_prefix.unwrap_or("");
+    let commit_counts = db.file_commit_counts_split(0)?;
+
+    let mut index: BTreeMap<String, usize> = BTreeMap::new();
+    let mut pairs: Vec<(String, String, f64)> = Vec::new();
+    for (a, b, co_changes) in db.pair_counts(MIN_CO_CHANGES)? {
+        if !a.starts_with(prefix) || !b.starts_with(prefix) {
+            continue;
+        }
+        let commits_a = commit_counts.get(&a).map_or(0, |c| c.0);
+        let commits_b = commit_counts.get(&b).map_or(0, |c| c.0);
+        let union = (commits_a + commits_b).saturating_sub(co_changes).max(co_changes);
+        let weight = co_changes as f64 / union as f64;
+        if weight >= MIN_EDGE_WEIGHT {
+            index.insert(a.clone(), 0);
+            index.insert(b.clone(), 0);
+            pairs.push((a, b, weight));
+        }
+    }
+    let paths: Vec<String> = index.keys().cloned().collect();
+    for (i, path) in paths.iter().enumerate() {
+        index.insert(path.clone(), i);
+    }
+    let edges: Vec<(usize, usize, f64)> = pairs
+        .iter()
+        .map(|(a, b, weight)| (index[a], index[b], *weight))
+        .collect();
+
+    let membership = louvain(paths.len(), &edges);
+    let modularity = modularity(&membership, &edges);
+
+    // community -> (members, internal weight, total weight)
+    let mut groups: HashMap<usize, (Vec<usize>, f64, f64)> = HashMap::new();
+    for (node, &community) in membership.iter().enumerate() {
+        groups.entry(community).or_default().0.push(node);
+    }
+    for &(a, b, weight) in &edges {
+        for node in [a, b] {
+            groups.get_mut(&membership[node]).unwrap().2 += weight;
+        }
+        if membership[a] == membership[b] {
+            groups.get_mut(&membership[a]).unwrap().1 += 2.0 * weight;
+        }
+    }
+
+    let mut clusters: Vec<CouplingCluster> = groups
+        .into_values()
+        .filter(|(members, _, _)| members.len() >= 2)
+        .map(|(members, internal, total)| {
+            let files: Vec<String> = members.iter().map(|&node| paths[node].clone()).collect();
+            let packages: BTreeSet<&str> = files
+                .iter()
+                .filter_map(|file| packages::package_of(file, package_roots))
+                .map(|root| if root.is_empty() { "." } else { root })
+                .collect();
+            CouplingCluster {
+                files,
+                cohesion: internal / total,
+                spans_packages: packages.len() > 1,
+                packages: packages.into_iter().map(String::from).collect(),
+            }
+        })
+        .collect();
+
+    clusters.sort_by(|a, b| {
+        b.files
+            .len()
+            .cmp(&a.files.len())
+            .then_with(|| b.cohesion.total_cmp(&a.cohesion))
+            .then_with(|| a.files.cmp(&b.files))
+    });
+
+    Ok(ClustersResponse {
+        repo_root: repo_root.to_string(),
+        modularity,
+        clusters,
+    })
+}
+
+/// Louvain community detection over an undirected weighted graph of `n`
+/// nodes: move nodes between communities while modularity improves, then
+/// collapse each community into a node and repeat until nothing moves.
+/// Returns each node's community.
+fn louvain(n: usize, edges: &[(usize, usize, f64)]) -> Vec<usize> {
+    let mut membership: Vec<usize> = (0..n).collect();
+    let mut level_edges = edges.to_vec();
+    let mut level_size = n;
+
+    loop {
+        let communities = local_moving(level_size, &level_edges);
+        let count = communities.iter().max().map_or(0, |&c| c + 1);
+        if count == level_size {
+            return membership;
+        }
+        for community in membership.iter_mut() {
+            *community = communities[*community];
+        }
+
+        let mut collapsed: BTreeMap<(usize, usize), f64> = BTreeMap::new();
+        for &(a, b, weight) in &level_edges {
+            let (a, b) = (communities[a], communities[b]);
+            *collapsed.entry((a.min(b), a.max(b))).or_default() += weight;
+        }
+        level_edges = collapsed.into_iter().map(|((a, b), weight)| (a, b, weight)).collect();
+        level_size = count;
+    }
+}
+
+/// One Louvain level: each node greedily joins the neighbouring community
+/// with the best modularity gain. Communities are numbered densely in order
+/// of their first node.
+fn local_moving(n: usize, edges: &[(usize, usize, f64)]) -> Vec<usize> {
+    let mut adjacency: Vec<Vec<(usize, f64)>> = vec![Vec::new(); n];
+    let mut degree = vec![0.0; n];
+    for &(a, b, weight) in edges {
+        if a != b {
+            adjacency[a].push((b, weight));
+            adjacency[b].push((a, weight));
+        }
+        degree[a] += weight;
+        degree[b] += weight;
+    }
+    let total_weight: f64 = degree.iter().sum();
+
+    let mut community: Vec<usize> = (0..n).collect();
+    if total_weight > 0.0 {
+        let mut community_degree = degree.clone();
+        for _ in 0..MAX_PASSES {
+            let mut moved = false;
+            for node in 0..n {
+                let current = community[node];
+                let mut links: BTreeMap<usize, f64> = BTreeMap::new();
+                for &(neighbor, weight) in &adjacency[node] {
+                    *links.entry(community[neighbor]).or_default() += weight;
+                }
+
+                community_degree[current] -= degree[node];
+                let gain = |c: usize, link: f64| link - community_degree[c] * degree[node] / total_weight;
+                let mut best = current;
+                let mut best_gain = gain(current, links.get(&current).copied().unwrap_or(0.0));
+                for (&c, &link) in &links {
+                    let g = gain(c, link);
+                    if g > best_gain + 1e-12 {
+                        best = c;
+                        best_gain = g;
+                    }
+                }
+                community_degree[best] += degree[node];
+                community[node] = best;
+                moved |= best != current;
+            }
+            if !moved {
+                break;
+            }
+        }
+    }
+
+    let mut renumbered: HashMap<usize, usize> = HashMap::new();
+    community
+        .into_iter()
+        .map(|c| {
+            let next = renumbered.len();
+            *renumbered.entry(c).or_insert(next)
+        })
+        .collect()
+}
+
+/// Newman modularity of a partition: the share of edge weight inside
+/// communities minus the share expected if edges were placed at random.
+fn modularity(membership: &[usize], edges: &[(usize, usize, f64)]) -> f64 {
+    let total_weight: f64 = edges.iter().map(|e| e.2).sum::<f64>() * 2.0;
+    if total_weight == 0.0 {
+        return 0.0;
+    }
+    let mut internal: HashMap<usize, f64> = HashMap::new();
+    let mut degree: HashMap<usize, f64> = HashMap::new();
+    for &(a, b, weight) in edges {
+        if membership[a] == membership[b] {
+            *internal.entry(membership[a]).or_default() += 2.0 * weight;
+        }
+        *degree.entry(membership[a]).or_default() += weight;
+        *degree.entry(membership[b]).or_default() += weight;
+    }
+    degree
+        .iter()
+        .map(|(c, d)| internal.get(c).copied().unwrap_or(0.0) / total_weight - (d / total_weight).powi(2))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commits(db: &Database, prefix: &str, n: usize, files: &[&str]) {
+        for i in 0..n {
+            db.insert_commit(&format!("{prefix}{i}"), files, i as i64).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_clusters_follow_co_change_not_directories() {
+        let db = Database::in_memory().unwrap();
+        commits(&db, "a", 5, &["api/handler.rs", "api/routes.rs", "web/form.ts"]);
+        commits(&db, "b", 5, &["web/app.ts", "web/store.ts", "web/view.ts"]);
+        // A single weak bridge does not merge the two groups
+        commits(&db, "c", 1, &["web/form.ts", "web/app.ts"]);
+
+        let roots = vec!["api".to_string(), "web".to_string()];
+        let resp = coupling_clusters(&db, "/repo", None, &roots).unwrap();
+
+        assert_eq!(resp.clusters.len(), 2);
+        assert!(resp.modularity > 0.4);
+        let api = resp.clusters.iter().find(|c| c.files.contains(&"api/handler.rs".to_string())).unwrap();
+        assert_eq!(api.files, vec!["api/handler.rs", "api/routes.rs", "web/form.ts"]);
+        assert_eq!(api.packages, vec!["api", "web"]);
+        assert!(api.spans_packages);
+        assert!((api.cohesion - 1.0).abs() < 1e-9);
+
+        let web = resp.clusters.iter().find(|c| c.files.contains(&"web/app.ts".to_string())).unwrap();
+        assert_eq!(web.packages, vec!["web"]);
+        assert!(!web.spans_packages);
+    }
+
+    #[test]
+    fn test_path_prefix_restricts_clusters() {
+        let db = Database::in_memory().unwrap();
+        commits(&db, "a", 4, &["api/a.rs", "api/b.rs"]);
+        commits(&db, "b", 4, &["web/a.ts", "web/b.ts"]);
+
+        let resp = coupling_clusters(&db, "/repo", Some("web/"), &[]).unwrap();
+        assert_eq!(resp.clusters.len(), 1);
+        assert_eq!(resp.clusters[0].files, vec!["web/a.ts", "web/b.ts"]);
+        assert!(resp.clusters[0].packages.is_empty());
+    }
+}
@@ -0,0 +1,308 @@
+use git2::{Oid, Repository};
+
+/// Oid size of the SHA-1 graphs this reader understands.
+const HASH_LEN: usize = 20;
+/// Header: signature, version, hash version, chunk count, base graphs.
+const HEADER_LEN: usize = 8;
+/// A chunk table entry: chunk id and file offset.
+const CHUNK_ENTRY_LEN: usize = 12;
+/// `OIDF`: commits with first oid byte <= i, for each byte value.
+const FANOUT_LEN: usize = 256 * 4;
+/// A `CDAT` entry: root tree, two parent positions, generation and time.
+const CDAT_ENTRY_LEN: usize = HASH_LEN + 16;
+/// Parent position meaning "no parent".
+const NO_PARENT: u32 = 0x7000_0000;
+
+/// Reader for git's commit-graph file (`.git/objects/info/commit-graph`),
+/// written by `git gc`, `git fetch` and `git commit-graph write`.
+///
+/// The graph stores each commit's root tree and parents in fixed-size
+/// records, so a walk can skip inflating and parsing commit objects, which
+/// dominates first-parent walks over huge histories. The file is read into
+/// memory once (tens of megabytes for the Linux kernel, which a deep walk
+/// touches most of anyway), and its header and chunk table are checked
+/// against its length, so lookups are plain slice reads.
+///
+/// Only a single-file SHA-1 graph is used; split graph chains, other hash
+/// functions and shallow clones (whose graph may name parents that are not
+/// there) fall back to parsing commits.
+pub struct CommitGraph {
+    data: Vec<u8>,
+    commits: u32,
+    fanout: usize,
+    oid_lookup: usize,
+    commit_data: usize,
+}
+
+/// A commit as recorded in the graph.
+pub struct GraphCommit {
+    pub oid: Oid,
+    pub tree: Oid,
+    /// Position of the first parent, if any.
+    pub first_parent: Option<u32>,
+}
+
+impl CommitGraph {
+    /// Open the repo's commit-graph, or `None` if it has none that can be
+    /// used.
+    pub fn open(repo: &Repository) -> Option<Self> {
+        if repo.is_shallow() {
+            return None;
+        }
+        let path = repo.commondir().join("objects").join("info").join("commit-graph");
+        Self::parse(std::fs::read(path).ok()?)
+    }
+
+    /// Check the header and locate the chunks, or `None` for a graph this
+    /// reader does not understand or whose chunks do not fit in the file.
+    fn parse(data: Vec<u8>) -> Option<Self> {
+        let header = data.get(..HEADER_LEN)?;
+        // Signature, version 1, SHA-1, no base graphs
+        if &header[..4] != b"CGPH" || header[4] != 1 || header[5] != 1 || header[7] != 0 {
+            return None;
+        }
+
+        let chunk_count = header[6] as usize;
+        // The table ends with a terminating entry holding the end offset
+        let table = data.get(HEADER_LEN..HEADER_LEN + (chunk_count + 1) * CHUNK_ENTRY_LEN)?;
+        let entries: Vec<(&[u8], usize)> = table
+            .chunks_exact(CHUNK_ENTRY_LEN)
+            .map(|entry| (&entry[..4], u64::from_be_bytes(entry[4..].try_into().unwrap()) as usize))
+            .collect();
+        if entries.windows(2).any(|pair| pair[0].1 > pair[1].1) || entries[chunk_count].1 > data.len() {
+            return None;
+        }
+        let chunk = |id: &[u8]| {
+            let i = entries[..chunk_count].iter().position(|(chunk_id, _)| *chunk_id == id)?;
+            Some((entries[i].1, entries[i + 1].1 - entries[i].1))
+        };
+
+        let (fanout, fanout_len) = chunk(b"OIDF")?;
+        let (oid_lookup, oid_lookup_len) = chunk(b"OIDL")?;
+        let (commit_data, commit_data_len) = chunk(b"CDAT")?;
+        if fanout_len < FANOUT_LEN {
+            return None;
+        }
+        let mut graph = Self { data, commits: 0, fanout, oid_lookup, commit_data };
+        let commits = graph.fanout_entry(255);
+        if oid_lookup_len < commits as usize * HASH_LEN || commit_data_len < commits as usize * CDAT_ENTRY_LEN {
+            return None;
+        }
+        graph.commits = commits;
+        Some(graph)
+    }
+
+    /// Position of `oid` in the graph, or `None` if the graph predates it.
+    pub fn position(&self, oid: Oid) -> Option<u32> {
+        let first = oid.as_bytes()[0];
+        let mut low = if first == 0 { 0 } else { self.fanout_entry(first - 1) };
+        let mut high = self.fanout_entry(first).min(self.commits);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match self.oid_bytes(mid).cmp(oid.as_bytes()) {
+                std::cmp::Ordering::Equal => return Some(mid),
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Greater => high = mid,
+            }
+        }
+        None
+    }
+
+    /// The commit at `position`, or `None` if the graph names a position
+    /// it does not have.
+    pub fn commit(&self, position: u32) -> Option<GraphCommit> {
+        if position >= self.commits {
+            return None;
+        }
+        let start = self.commit_data + position as usize * CDAT_ENTRY_LEN;
+        let entry = &self.data[start..start + CDAT_ENTRY_LEN];
+        let parent = u32::from_be_bytes(entry[20..24].try_into().unwrap());
+        if parent != NO_PARENT && parent >= self.commits {
+            return None;
+        }
+        Some(GraphCommit {
+            oid: oid_from(self.oid_bytes(position)),
+            tree: oid_from(&entry[..20]),
+            first_parent: (parent != NO_PARENT).then_some(parent),
+        })
+    }
+
+    fn fanout_entry(&self, byte: u8) -> u32 {
+        let start = self.fanout + byte as usize * 4;
+        u32::from_be_bytes(self.data[start..start + 4].try_into().unwrap())
+    }
+
+    fn oid_bytes(&self, position: u32) -> &[u8] {
+        let start = self.oid_lookup + position as usize * HASH_LEN;
+        &self.data[start..start + HASH_LEN]
+    }
+}
+
+fn oid_from(bytes: &[u8]) -> Oid {
+    Oid::from_bytes(bytes).expect("20-byte oid")
+}
+
+/// Walk of the first-parent chain from a commit, newest first, yielding
+/// each commit with its root tree. Commits newer than the commit-graph are
+/// parsed; once the walk reaches one the graph covers, it continues from
+/// the graph alone.
+pub struct FirstParentWalk<'r> {
+    repo: &'r Repository,
+    graph: Option<CommitGraph>,
+    next: Next,
+}
+
+enum Next {
+    Commit(Oid),
+    Graph(u32),
+    Done,
+}
+
+impl<'r> FirstParentWalk<'r> {
+    pub fn new(repo: &'r Repository, tip: Oid) -> Self {
+        Self { repo, graph: CommitGraph::open(repo), next: Next::Commit(tip) }
+    }
+
+    fn step(&mut self) -> Result<Option<(Oid, Oid)>, git2::Error> {
+        match std::mem::replace(&mut self.next, Next::Done) {
+            Next::Done => Ok(None),
+            Next::Commit(oid) => {
+                let commit = self.repo.find_commit(oid)?;
+                if let Ok(parent) = commit.parent_id(0) {
+                    self.next = match self.graph.as_ref().and_then(|g| g.position(parent)) {
+                        Some(position) => Next::Graph(position),
+                        None => Next::Commit(parent),
+                    };
+                }
+                Ok(Some((oid, commit.tree_id())))
+            }
+            Next::Graph(position) => {
+                let graph = self.graph.as_ref().expect("graph positions come from the graph");
+                let commit = graph
+                    .commit(position)
+                    .ok_or_else(|| git2::Error::from_str("commit-graph: position out of range"))?;
+                if let Some(parent) = commit.first_parent {
+                    self.next = Next::Graph(parent);
+                }
+                Ok(Some((commit.oid, commit.tree)))
+            }
+        }
+    }
+}
+
+impl Iterator for FirstParentWalk<'_> {
+    /// A commit and its root tree.
+    type Item = Result<(Oid, Oid), git2::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.step().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::commit;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    /// A two-commit graph: `[0x10; 20]` (tree `[0xaa; 20]`, a root) and its
+    /// child `[0x20; 20]` (tree `[0xbb; 20]`).
+    fn graph_bytes() -> Vec<u8> {
+        let commits: [([u8; 20], [u8; 20], u32); 2] =
+            [([0x10; 20], [0xaa; 20], NO_PARENT), ([0x20; 20], [0xbb; 20], 0)];
+        let fanout: Vec<u8> = (0..=255u8)
+            .flat_map(|byte| (commits.iter().filter(|c| c.0[0] <= byte).count() as u32).to_be_bytes())
+            .collect();
+        let oids: Vec<u8> = commits.iter().flat_map(|c| c.0).collect();
+        let data: Vec<u8> = commits
+            .iter()
+            .flat_map(|c| [&c.1[..], &c.2.to_be_bytes(), &NO_PARENT.to_be_bytes(), &[0; 8]].concat())
+            .collect();
+
+        let mut bytes = b"CGPH\x01\x01\x03\x00".to_vec();
+        let mut offset = (HEADER_LEN + 4 * CHUNK_ENTRY_LEN) as u64;
+        for (id, chunk) in [(b"OIDF", &fanout), (b"OIDL", &oids), (b"CDAT", &data)] {
+            bytes.extend_from_slice(id);
+            bytes.extend_from_slice(&offset.to_be_bytes());
+            offset += chunk.len() as u64;
+        }
+        bytes.extend_from_slice(&[0; 4]);
+        bytes.extend_from_slice(&offset.to_be_bytes());
+        for chunk in [fanout, oids, data] {
+            bytes.extend_from_slice(&chunk);
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_reads_graph_and_rejects_malformed_ones() {
+        let graph = CommitGraph::parse(graph_bytes()).unwrap();
+        assert_eq!(graph.commits, 2);
+        let child = graph.commit(graph.position(Oid::from_bytes(&[0x20; 20]).unwrap()).unwrap()).unwrap();
+        assert_eq!(child.tree, Oid::from_bytes(&[0xbb; 20]).unwrap());
+        let root = graph.commit(child.first_parent.unwrap()).unwrap();
+        assert_eq!(root.oid, Oid::from_bytes(&[0x10; 20]).unwrap());
+        assert_eq!(root.first_parent, None);
+        assert!(graph.position(Oid::from_bytes(&[0x30; 20]).unwrap()).is_none());
+        assert!(graph.commit(2).is_none());
+
+        // Cut short: the chunk table points past the end
+        let mut truncated = graph_bytes();
+        truncated.pop();
+        assert!(CommitGraph::parse(truncated).is_none());
+        assert!(CommitGraph::parse(graph_bytes()[..HEADER_LEN].to_vec()).is_none());
+
+        // Fanout claiming more commits than the chunks hold
+        let mut overcounted = graph_bytes();
+        let last_fanout = HEADER_LEN + 4 * CHUNK_ENTRY_LEN + 255 * 4;
+        overcounted[last_fanout..last_fanout + 4].copy_from_slice(&3u32.to_be_bytes());
+        assert!(CommitGraph::parse(overcounted).is_none());
+
+        // A parent position the graph does not have
+        let mut bad_parent = graph_bytes();
+        let len = bad_parent.len();
+        bad_parent[len - 16..len - 12].copy_from_slice(&5u32.to_be_bytes());
+        assert!(CommitGraph::parse(bad_parent).unwrap().commit(1).is_none());
+    }
+
+    #[test]
+    fn test_walk_continues_from_graph_past_newer_commits() {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        for n in 0..5 {
+            commit(&repo, &["a.rs"], &format!("c{n}"));
+        }
+        let Ok(written) = Command::new("git")
+            .args(["commit-graph", "write", "--reachable"])
+            .current_dir(dir.path())
+            .status()
+        else {
+            eprintln!("skipping test_walk_continues_from_graph_past_newer_commits: no git CLI to write a graph");
+            return;
+        };
+        assert!(written.success());
+        // Newer than the graph
+        commit(&repo, &["a.rs"], "c5");
+        let tip = commit(&repo, &["a.rs"], "c6");
+
+        let graph = CommitGraph::open(&repo).unwrap();
+        assert_eq!(graph.commits, 5);
+        assert_eq!(graph.position(tip), None);
+
+        let walk = FirstParentWalk::new(&repo, tip);
+        assert!(walk.graph.is_some());
+        let walked: Vec<(Oid, Oid)> = walk.map(Result::unwrap).collect();
+
+        let mut revwalk = repo.revwalk().unwrap();
+        revwalk.push(tip).unwrap();
+        let expected: Vec<(Oid, Oid)> = revwalk
+            .map(|oid| {
+                let oid = oid.unwrap();
+                (oid, repo.find_commit(oid).unwrap().tree_id())
+            })
+            .collect();
+        assert_eq!(walked, expected);
+    }
+}
+
@@ -0,0 +1,146 @@
+use std::collections::{HashMap, HashSet};
+
+use git2::{Oid, Repository};
+
+use crate::indexing::IndexOptions;
+use crate::persistence::Database;
+use crate::types::{CompareResponse, CouplingChange, CouplingDelta};
+
+/// How many times stronger (or weaker) a coupling must be after `from`
+/// than before it to count as strengthened (or weakened).
+const CHANGE_RATIO: f64 = 1.5;
+
+/// How the couplings of `file_path` changed between two points in history:
+/// its indexed commits reachable from `from` ("before") against those
+/// reachable from `to` but not `from` ("after"), as when checking whether
+/// a decoupling refactor at `from` worked.
+///
+/// Coupling in each period is the share of the file's commits in it that
+/// also changed the coupled file. Only indexed commits are counted, so
+/// `to` should be within the indexed history (HEAD's, by default). Files
+/// whose coupling held steady, or that `opts` ignores or scopes out, are
+/// left out; the rest come strongest change first.
+pub fn compare(
+    repo: &Repository,
+    db: &Database,
+    opts: &IndexOptions,
+    repo_root: &str,
+    file_path: &str,
+    from: &str,
+    to: &str,
+) -> Result<CompareResponse, Box<dyn std::error::Error>> {
+    let from_oid = repo.revparse_single(from)?.peel_to_commit()?.id();
+    let to_oid = repo.revparse_single(to)?.peel_to_commit()?.id();
+    let before_set = reachable(repo, from_oid, None)?;
+    let after_set = reachable(repo, to_oid, Some(from_oid))?;
+
+    let mut before_commits = 0u32;
+    let mut after_commits = 0u32;
+    // path -> (co-changes before, co-changes after)
+    let mut co_changes: HashMap<String, (u32, u32)> = HashMap::new();
+    for (hash, files) in db.commits_with_files(file_path)? {
+        let Ok(oid) = Oid::from_str(&hash) else {
+            continue;
+        };
+        let before = if before_set.contains(&oid) {
+            true
+        } else if after_set.contains(&oid) {
+            false
+        } else {
+            continue;
+        };
+        if before {
+            before_commits += 1;
+        } else {
+            after_commits += 1;
+        }
+        for path in files.into_iter().filter(|p| !opts.ignore.is_ignored(p) && opts.in_scope(p)) {
+            let counts = co_changes.entry(path).or_default();
+            if before {
+                counts.0 += 1;
+            } else {
+                counts.1 += 1;
+            }
+        }
+    }
+
+    let share = |co: u32, commits: u32| if commits > 0 { co as f64 / commits as f64 } else { 0.0 };
+    let mut changes: Vec<CouplingDelta> = co_changes
+        .into_iter()
+        .filter_map(|(path, (before_co_changes, after_co_changes))| {
+            let before = share(before_co_changes, before_commits);
+            let after = share(after_co_changes, after_commits);
+            let change = match (before_co_changes, after_co_changes) {
+                // Without commits on both sides there is nothing to compare
+                _ if before_commits == 0 || after_commits == 0 => return None,
+                (0, _) => CouplingChange::Appeared,
+                (_, 0) => CouplingChange::Vanished,
+                _ if after > before * CHANGE_RATIO => CouplingChange::Strengthened,
+                _ if after * CHANGE_RATIO < before => CouplingChange::Weakened,
+                _ => return None,
+            };
+            Some(CouplingDelta { path, change, before, after, before_co_changes, after_co_changes })
+        })
+        .collect();
+    changes.sort_by(|a, b| {
+        (b.after - b.before)
+            .abs()
+            .total_cmp(&(a.after - a.before).abs())
+            .then_with(|| a.path.cmp(&b.path))
+    });
+
+    Ok(CompareResponse {
+        file_path: file_path.to_string(),
+        repo_root: repo_root.to_string(),
+        from: from.to_string(),
+        to: to.to_string(),
+        before_commits,
+        after_commits,
+        changes,
+    })
+}
+
+/// Commits reachable from `tip`, less those reachable from `hide`.
+fn reachable(repo: &Repository, tip: Oid, hide: Option<Oid>) -> Result<HashSet<Oid>, git2::Error> {
+    let mut walk = repo.revwalk()?;
+    walk.push(tip)?;
+    if let Some(hide) = hide {
+        walk.hide(hide)?;
+    }
+    walk.collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::commit;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_compare_reports_changed_couplings() {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        // Before the refactor, api.rs always changed with db.rs
+        for i in 0..4 {
+            commit(&repo, &["api.rs", "db.rs", "log.rs"], &format!("before {i}"));
+        }
+        let refactor = commit(&repo, &["api.rs", "store.rs"], "refactor");
+        repo.tag_lightweight("v1", &repo.find_object(refactor, None).unwrap(), false).unwrap();
+        // After it, api.rs changes with store.rs instead
+        for i in 0..3 {
+            commit(&repo, &["api.rs", "store.rs", "log.rs"], &format!("after {i}"));
+        }
+
+        let db = Database::in_memory().unwrap();
+        let opts = IndexOptions::default();
+        crate::temporal::analyze(dir.path(), "api.rs", &db, &opts).unwrap();
+        let resp = compare(&repo, &db, &opts, "/repo", "api.rs", "v1", "HEAD").unwrap();
+
+        assert_eq!((resp.before_commits, resp.after_commits), (5, 3));
+        let change = |path: &str| resp.changes.iter().find(|c| c.path == path).map(|c| c.change);
+        assert_eq!(change("db.rs"), Some(CouplingChange::Vanished));
+        assert_eq!(change("store.rs"), Some(CouplingChange::Strengthened));
+        assert_eq!(change("log.rs"), None);
+        assert_eq!(resp.changes[0].path, "db.rs");
+    }
+}
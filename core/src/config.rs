@@ -0,0 +1,277 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::Deserialize;
+
+/// Per-repository settings, read from `.engram/config.json`.
+/// Every field is optional; missing fields fall back to the defaults below.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Total memories allowed across all coupled files in one response.
+    pub max_memories: usize,
+    /// Total test intents allowed across all coupled files in one response.
+    pub max_test_intents: usize,
+    /// Test intents extracted from any one test file.
+    pub max_intents_per_file: usize,
+    /// Which intents a test file with more than `max_intents_per_file`
+    /// tests contributes.
+    pub intent_selection: IntentSelection,
+    /// Never read the working tree during enrichment (same as `--no-fs`).
+    pub no_fs: bool,
+    /// Never write usage metrics events (same as `--no-metrics`).
+    pub no_metrics: bool,
+    /// Diff merge commits against their first parent when indexing
+    /// (legacy behaviour; attributes a whole merged branch to the merge).
+    pub diff_merges: bool,
+    /// Merges whose branch brought in more commits than this are left out
+    /// of path-filtered indexes rather than walked commit by commit; 0 for
+    /// no cap. Ignored with `diff_merges`.
+    pub max_merged_branch_commits: usize,
+    /// Commits changing more indexed files than this (formatting sweeps,
+    /// vendoring, license-header updates) are left out of the coupling
+    /// index; 0 for no cap.
+    pub max_commit_files: usize,
+    /// A complete index last brought up to date longer ago than this many
+    /// hours is caught up in the background on the next analysis, even if
+    /// HEAD has not moved; 0 never does.
+    pub max_index_age_hours: u64,
+    /// Count files the same author changed within this many hours of each
+    /// other as co-changed, not just files changed in the same commit.
+    /// For teams that split one logical change over many small commits.
+    /// Same as `--coupling-window`.
+    pub coupling_window_hours: Option<u64>,
+    /// Drop coupled files whose co-changes with the analyzed file are this
+    /// likely (or more) to be chance, given how often each changes: 0.05
+    /// keeps only couplings significant at 5%. Same as `--max-p-value`.
+    pub max_p_value: Option<f64>,
+    /// Weigh co-changes by age in risk scores, halving every this many
+    /// days, so couplings that have gone quiet rank below current ones.
+    /// Same as `--half-life`.
+    pub coupling_half_life_days: Option<u64>,
+    /// How co-changes are turned into a coupling score.
+    pub coupling_metric: CouplingMetric,
+    /// Restrict indexing and coupling to this subtree (`packages/payments/`),
+    /// for monorepos. Same as `--scope`.
+    pub scope: Option<String>,
+    /// External commands that add fields to analysis responses.
+    pub enrichers: Vec<EnricherConfig>,
+    /// Test conventions added to the built-in ones.
+    pub test_patterns: TestPatternsConfig,
+    /// How tests for the analyzed file are found.
+    pub test_discovery: TestDiscovery,
+    /// A test of the analyzed file that has not changed across this many
+    /// changes to the file is marked stale; 0 marks none.
+    pub stale_test_commits: u32,
+    /// Where the Critical/High/Medium/Low lines between risk scores fall.
+    pub risk_levels: RiskLevels,
+}
+
+/// How `test_info` finds the analyzed file's tests.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestDiscovery {
+    /// Naming conventions only (`auth.ts` -> `auth.test.ts`).
+    #[default]
+    Naming,
+    /// Naming conventions, plus any test file in the working tree whose
+    /// imports refer to the analyzed file. Reads every test file, so it is
+    /// slower on large repos.
+    Imports,
+}
+
+/// Coupling score of a file that changed in some of the analyzed file's
+/// commits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CouplingMetric {
+    /// Share of the analyzed file's commits the file changed in. Not
+    /// symmetric: a small helper changed only alongside a big file is fully
+    /// coupled to it, however rarely the big file needs the helper.
+    #[default]
+    Confidence,
+    /// Shared commits over the commits either file changed in.
+    Jaccard,
+    /// Shared commits over the geometric mean of each file's commits.
+    Cosine,
+}
+
+/// How risk scores map to risk levels.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskLevels {
+    /// The same score lines in every repo (0.8, 0.6, 0.3).
+    #[default]
+    Fixed,
+    /// Lines drawn from this repo's own scores, so that in a repo where
+    /// everything scores high only its top tenth is Critical.
+    Percentile,
+}
+
+/// How a test file's intents are chosen when it has more tests than the
+/// per-file cap.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IntentSelection {
+    /// The first tests in the file.
+    #[default]
+    First,
+    /// Tests whose titles name a symbol of the analyzed file, then the
+    /// first of the rest.
+    Symbols,
+}
+
+/// Extra test conventions for frameworks engram does not know, merged
+/// with the built-ins.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct TestPatternsConfig {
+    /// Globs matched against repo-relative paths (`spec/**/*_spec.lua`);
+    /// matching files are treated as tests.
+    pub globs: Vec<String>,
+    /// Title regexes keyed by file extension (`"lua"`). Each match is one
+    /// test; the first capture group (or the whole match) is its title.
+    pub titles: BTreeMap<String, Vec<String>>,
+}
+
+/// An external enricher: `command` is run in the repo root with the
+/// analysis response JSON on stdin and must print a JSON value, which is
+/// added to the response under `extensions.<name>`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EnricherConfig {
+    pub name: String,
+    /// Program and arguments (not run through a shell).
+    pub command: Vec<String>,
+    #[serde(default = "default_enricher_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+fn default_enricher_timeout_ms() -> u64 {
+    2000
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_memories: 15,
+            max_test_intents: 15,
+            max_intents_per_file: 5,
+            intent_selection: IntentSelection::First,
+            no_fs: false,
+            no_metrics: false,
+            diff_merges: false,
+            max_merged_branch_commits: 1000,
+            max_commit_files: 1000,
+            max_index_age_hours: 24,
+            coupling_window_hours: None,
+            max_p_value: None,
+            coupling_half_life_days: None,
+            coupling_metric: CouplingMetric::Confidence,
+            scope: None,
+            enrichers: Vec::new(),
+            test_patterns: TestPatternsConfig::default(),
+            test_discovery: TestDiscovery::Naming,
+            stale_test_commits: 5,
+            risk_levels: RiskLevels::Fixed,
+        }
+    }
+}
+
+impl Config {
+    /// Load the repo's config. A missing file yields the defaults; a file
+    /// that exists but does not parse is an error rather than silently ignored.
+    pub fn load(repo_root: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let path = repo_root.join(".engram").join("config.json");
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.into()),
+        };
+        serde_json::from_str(&text)
+            .map_err(|e| format!("invalid config {}: {e}", path.display()).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_missing_config_uses_defaults() {
+        let dir = TempDir::new().unwrap();
+        let config = Config::load(dir.path()).unwrap();
+        assert_eq!(config.max_memories, 15);
+        assert_eq!(config.max_commit_files, 1000);
+        assert!(!config.no_fs);
+    }
+
+    #[test]
+    fn test_partial_config_overrides_fields() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join(".engram")).unwrap();
+        fs::write(dir.path().join(".engram/config.json"), r#"{"max_memories": 4}"#).unwrap();
+
+        let config = Config::load(dir.path()).unwrap();
+        assert_eq!(config.max_memories, 4);
+        assert_eq!(config.max_test_intents, 15);
+        assert_eq!(config.max_intents_per_file, 5);
+        assert_eq!(config.intent_selection, IntentSelection::First);
+    }
+
+    #[test]
+    fn test_intent_selection_config() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join(".engram")).unwrap();
+        fs::write(
+            dir.path().join(".engram/config.json"),
+            r#"{"max_intents_per_file": 8, "intent_selection": "symbols"}"#,
+        )
+        .unwrap();
+
+        let config = Config::load(dir.path()).unwrap();
+        assert_eq!(config.max_intents_per_file, 8);
+        assert_eq!(config.intent_selection, IntentSelection::Symbols);
+    }
+
+    #[test]
+    fn test_enricher_config() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join(".engram")).unwrap();
+        fs::write(
+            dir.path().join(".engram/config.json"),
+            r#"{"enrichers": [{"name": "slo", "command": ["./slo-owner", "--json"]}]}"#,
+        )
+        .unwrap();
+
+        let config = Config::load(dir.path()).unwrap();
+        assert_eq!(config.enrichers.len(), 1);
+        assert_eq!(config.enrichers[0].command, vec!["./slo-owner", "--json"]);
+        assert_eq!(config.enrichers[0].timeout_ms, 2000);
+    }
+
+    #[test]
+    fn test_test_patterns_config() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join(".engram")).unwrap();
+        fs::write(
+            dir.path().join(".engram/config.json"),
+            r#"{"test_patterns": {"globs": ["t/**/*.t"], "titles": {"t": ["ok\\(.*'([^']*)'\\)"]}}}"#,
+        )
+        .unwrap();
+
+        let config = Config::load(dir.path()).unwrap();
+        assert_eq!(config.test_patterns.globs, vec!["t/**/*.t"]);
+        assert_eq!(config.test_patterns.titles["t"], vec![r"ok\(.*'([^']*)'\)"]);
+    }
+
+    #[test]
+    fn test_malformed_config_is_an_error() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join(".engram")).unwrap();
+        fs::write(dir.path().join(".engram/config.json"), "{not json").unwrap();
+
+        assert!(Config::load(dir.path()).is_err());
+    }
+}
@@ -0,0 +1,315 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::time::Duration;
+
+use git2::Repository;
+
+use crate::persistence::{Database, Page};
+use crate::types::{DashboardSnapshot, Hotspot, IndexingStatus};
+
+const HOTSPOT_LIMIT: usize = 15;
+const RECENT_LIMIT: usize = 20;
+
+/// Paths `serve` answers; every other one is a 404.
+const ROUTES: [&str; 6] = ["/", "/api/snapshot", "/api/status", "/api/hotspots", "/api/analyses", "/api/notes"];
+/// Longest request head (request line and headers) `serve` reads.
+const MAX_REQUEST_BYTES: u64 = 16 * 1024;
+/// How long `serve` waits for a client to send its request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Collect the dashboard data: index status, the most frequently changed
+/// files, recent analyses, and recently added notes. Reads only.
+pub fn snapshot(db: &Database, repo_root: &Path) -> Result<DashboardSnapshot, rusqlite::Error> {
+    let shallow = Repository::open(repo_root).is_ok_and(|repo| repo.is_shallow());
+    let indexing_status = db.get_indexing_state()?.map(|state| IndexingStatus {
+        strategy: state.strategy,
+        commits_indexed: state.commits_indexed,
+        complete_to_depth: state.is_complete && shallow,
+        is_complete: state.is_complete,
+    });
+
+    let hotspots = db
+        .hotspots(HOTSPOT_LIMIT)?
+        .into_iter()
+        .map(|(path, commit_count)| Hotspot { path, commit_count })
+        .collect();
+
+    Ok(DashboardSnapshot {
+        repo_root: repo_root.to_string_lossy().into_owned(),
+        indexing_status,
+        hotspots,
+        recent_analyses: db.recent_analyses(RECENT_LIMIT)?,
+        recent_notes: db.list_memories(None, None, Page { offset: 0, limit: Some(RECENT_LIMIT) })?,
+    })
+}
+
+/// Serve the dashboard of `repo_root` over HTTP on `listener`, one request
+/// at a time, until accepting a connection fails. `GET /` is the HTML page;
+/// `/api/snapshot` is the whole snapshot as JSON, and `/api/status`,
+/// `/api/hotspots`, `/api/analyses` and `/api/notes` its parts. Each
+/// request opens the database at `db_path` afresh, read-only.
+pub fn serve(listener: &TcpListener, repo_root: &Path, db_path: &Path) -> std::io::Result<()> {
+    for stream in listener.incoming() {
+        if let Err(e) = respond(stream?, repo_root, db_path) {
+            eprintln!("Warning: dashboard request failed: {e}");
+        }
+    }
+    Ok(())
+}
+
+fn respond(mut stream: TcpStream, repo_root: &Path, db_path: &Path) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut reader = BufReader::new((&stream).take(MAX_REQUEST_BYTES));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Drain the headers, so closing the connection does not reset it
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && header.trim_end() != "" {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let path = parts.next().unwrap_or_default().split('?').next().unwrap_or_default();
+    let (status, content_type, body) = if method != "GET" {
+        ("405 Method Not Allowed", "text/plain", "Only GET is supported\n".to_string())
+    } else {
+        route(path, repo_root, db_path)
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}; charset=utf-8\r\nContent-Length: {}\r\n\
+         Cache-Control: no-store\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
+
+/// Status, content type and body for a GET of `path`.
+fn route(path: &str, repo_root: &Path, db_path: &Path) -> (&'static str, &'static str, String) {
+    if !ROUTES.contains(&path) {
+        return ("404 Not Found", "text/plain", format!("No such page: {path}\n"));
+    }
+    let snap = match Database::open_read_only(db_path).and_then(|db| snapshot(&db, repo_root)) {
+        Ok(snap) => snap,
+        Err(e) => {
+            let body = format!("Could not read {}: {e}\n", db_path.display());
+            return ("503 Service Unavailable", "text/plain", body);
+        }
+    };
+    let json = match path {
+        "/" => return ("200 OK", "text/html", render_html(&snap)),
+        "/api/status" => serde_json::to_string(&snap.indexing_status),
+        "/api/hotspots" => serde_json::to_string(&snap.hotspots),
+        "/api/analyses" => serde_json::to_string(&snap.recent_analyses),
+        "/api/notes" => serde_json::to_string(&snap.recent_notes),
+        _ => serde_json::to_string(&snap),
+    };
+    match json {
+        Ok(body) => ("200 OK", "application/json", body),
+        Err(e) => ("500 Internal Server Error", "text/plain", format!("{e}\n")),
+    }
+}
+
+/// Render a snapshot as a self-contained HTML page (no scripts, no external
+/// assets), suitable for opening locally or publishing as a CI artifact.
+pub fn render_html(snapshot: &DashboardSnapshot) -> String {
+    let mut out = String::from(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>engram dashboard</title>\n\
+         <style>body{font-family:system-ui,sans-serif;margin:2rem;max-width:60rem}\
+         table{border-collapse:collapse;width:100%;margin-bottom:2rem}\
+         th,td{text-align:left;padding:.25rem .5rem;border-bottom:1px solid #ddd}\
+         td.n{text-align:right}</style>\n</head>\n<body>\n",
+    );
+    out.push_str(&format!("<h1>engram: {}</h1>\n", escape(&snapshot.repo_root)));
+
+    out.push_str("<h2>Index</h2>\n<p>");
+    match &snapshot.indexing_status {
+        Some(status) => out.push_str(&format!(
+            "{} commits indexed ({} strategy, {}).",
+            status.commits_indexed,
+            escape(&status.strategy),
+            if status.is_complete { "complete" } else { "in progress" }
+        )),
+        None => out.push_str("Not indexed yet."),
+    }
+    out.push_str("</p>\n");
+
+    out.push_str("<h2>Hotspots</h2>\n");
+    table(
+        &mut out,
+        &["File", "Commits"],
+        snapshot
+            .hotspots
+            .iter()
+            .map(|h| vec![code(&h.path), number(h.commit_count)]),
+    );
+
+    out.push_str("<h2>Recent analyses</h2>\n");
+    table(
+        &mut out,
+        &["When (UTC)", "File", "Coupled", "Critical", "High", "Time (ms)"],
+        snapshot.recent_analyses.iter().map(|a| {
+            vec![
+                escape(&a.timestamp),
+                code(&a.file_path),
+                number(a.coupled_files_count),
+                number(a.critical_count),
+                number(a.high_count),
+                number(a.analysis_time_ms),
+            ]
+        }),
+    );
+
+    out.push_str("<h2>Recent notes</h2>\n");
+    table(
+        &mut out,
+        &["When (UTC)", "File", "Note"],
+        snapshot
+            .recent_notes
+            .iter()
+            .map(|m| vec![escape(&m.created_at), code(&m.file_path), escape(&m.content)]),
+    );
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn table(out: &mut String, headers: &[&str], rows: impl Iterator<Item = Vec<String>>) {
+    let rows: Vec<Vec<String>> = rows.collect();
+    if rows.is_empty() {
+        out.push_str("<p>None.</p>\n");
+        return;
+    }
+    out.push_str("<table>\n<tr>");
+    for header in headers {
+        out.push_str(&format!("<th>{header}</th>"));
+    }
+    out.push_str("</tr>\n");
+    for row in rows {
+        out.push_str("<tr>");
+        for cell in row {
+            out.push_str(&cell);
+        }
+        out.push_str("</tr>\n");
+    }
+    out.push_str("</table>\n");
+}
+
+fn code(text: &str) -> String {
+    format!("<td><code>{}</code></td>", escape(text))
+}
+
+fn number(n: impl std::fmt::Display) -> String {
+    format!("<td class=\"n\">{n}</td>")
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::{IndexingState, NoteOrigin};
+    use crate::test_support::commit;
+
+    #[test]
+    fn test_snapshot_and_html() {
+        let db = Database::in_memory().unwrap();
+        for i in 0..3 {
+            db.insert_commit(&format!("c{i}"), &["src/hot.rs", "src/b.rs"], i).unwrap();
+        }
+        db.insert_commit("c3", &["src/hot.rs"], 3).unwrap();
+        db.add_memory("src/hot.rs", None, "Don't <b>touch</b> this", &NoteOrigin::default()).unwrap();
+
+        let snap = snapshot(&db, Path::new("/repo")).unwrap();
+        assert!(snap.indexing_status.is_none());
+        assert_eq!(snap.hotspots[0].path, "src/hot.rs");
+        assert_eq!(snap.hotspots[0].commit_count, 4);
+        assert_eq!(snap.recent_notes.len(), 1);
+        assert!(snap.recent_analyses.is_empty());
+
+        let html = render_html(&snap);
+        assert!(html.contains("Not indexed yet."));
+        assert!(html.contains("<td><code>src/hot.rs</code></td><td class=\"n\">4</td>"));
+        assert!(html.contains("Don&#39;t &lt;b&gt;touch&lt;/b&gt; this"));
+        assert!(!html.contains("<script"));
+    }
+
+    #[test]
+    fn test_snapshot_marks_shallow_index_complete_to_depth() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let root = commit(&repo, &["a.rs"], "c0");
+        commit(&repo, &["a.rs"], "c1");
+        let db = Database::in_memory().unwrap();
+        db.set_indexing_state(&IndexingState {
+            head_commit: repo.head().unwrap().target().unwrap().to_string(),
+            resume_oid: None,
+            commits_indexed: 2,
+            strategy: "full".to_string(),
+            is_complete: true,
+            last_updated: 1,
+            target_path: None,
+        })
+        .unwrap();
+        let status = |db: &Database| snapshot(db, dir.path()).unwrap().indexing_status.unwrap();
+        assert!(!status(&db).complete_to_depth);
+
+        std::fs::write(repo.path().join("shallow"), format!("{root}\n")).unwrap();
+        assert!(status(&db).complete_to_depth);
+    }
+
+    #[test]
+    fn test_serve_answers_html_and_json() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db_path = dir.path().join("engram.db");
+        {
+            let db = Database::open(&db_path).unwrap();
+            db.insert_commit("c0", &["src/hot.rs", "src/b.rs"], 0).unwrap();
+            db.add_memory("src/hot.rs", None, "Fragile", &NoteOrigin::default()).unwrap();
+        }
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let root = dir.path().to_path_buf();
+        std::thread::spawn(move || serve(&listener, &root, &root.join("engram.db")));
+
+        let get = |request: &str| {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            write!(stream, "{request}\r\nHost: localhost\r\n\r\n").unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            let (head, body) = response.split_once("\r\n\r\n").unwrap();
+            (head.lines().next().unwrap().to_string(), body.to_string())
+        };
+
+        let (status, body) = get("GET / HTTP/1.1");
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert!(body.contains("<code>src/hot.rs</code>"));
+
+        let (status, body) = get("GET /api/notes?x=1 HTTP/1.1");
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        let notes: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(notes[0]["content"], "Fragile");
+
+        let (_, body) = get("GET /api/snapshot HTTP/1.1");
+        let snap: DashboardSnapshot = serde_json::from_str(&body).unwrap();
+        assert_eq!(snap.hotspots.len(), 2);
+
+        assert_eq!(get("GET /api/missing HTTP/1.1").0, "HTTP/1.1 404 Not Found");
+        assert_eq!(get("POST /api/notes HTTP/1.1").0, "HTTP/1.1 405 Method Not Allowed");
+    }
+}
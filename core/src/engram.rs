@@ -0,0 +1,113 @@
+use std::path::{Path, PathBuf};
+
+use crate::persistence::{Database, Page};
+use crate::types::{AddNoteResponse, ListNotesResponse, MetricsResponse, SearchNotesResponse};
+use crate::{AnalyzeOptions, AnalyzeResult, NoteOptions, knowledge, metrics};
+
+/// A repository opened for in-process use by other Rust tools.
+///
+/// Where the crate-level functions open `.engram/engram.db` on every call,
+/// an `Engram` opens it once and keeps it open until dropped. Results are
+/// the same types the CLI serializes.
+///
+/// ```no_run
+/// let engram = engram_core::Engram::open("/path/to/repo")?;
+/// let result = engram.analyze("src/auth.ts", &Default::default())?;
+/// for file in &result.response.coupled_files {
+///     println!("{} {:.2}", file.path, file.risk_score);
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct Engram {
+    repo_root: PathBuf,
+    db: Database,
+}
+
+impl Engram {
+    /// Open (creating if needed) the engram database of the repository at
+    /// `repo_root`.
+    pub fn open(repo_root: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        let repo_root = repo_root.as_ref().to_path_buf();
+        let db = crate::open_db(&repo_root)?;
+        Ok(Engram { repo_root, db })
+    }
+
+    pub fn repo_root(&self) -> &Path {
+        &self.repo_root
+    }
+
+    /// Analyze the blast radius of changing `file_path`. When the result's
+    /// `needs_background` is set, history is only partly indexed; run
+    /// `indexing::background_index` to finish it.
+    pub fn analyze(
+        &self,
+        file_path: &str,
+        options: &AnalyzeOptions,
+    ) -> Result<AnalyzeResult, Box<dyn std::error::Error>> {
+        crate::analyze_in(&self.db, &self.repo_root, file_path, options)
+    }
+
+    /// Add a note about `file_path`, or about `symbol_name` within it.
+    pub fn add_note(
+        &self,
+        file_path: &str,
+        symbol_name: Option<&str>,
+        content: &str,
+        options: &NoteOptions,
+    ) -> Result<AddNoteResponse, Box<dyn std::error::Error>> {
+        crate::add_note_in(&self.db, &self.repo_root, file_path, symbol_name, content, options)
+    }
+
+    /// A page of the notes whose content or file path matches `query`.
+    pub fn search_notes(&self, query: &str, page: Page) -> Result<SearchNotesResponse, Box<dyn std::error::Error>> {
+        knowledge::search_notes(&self.db, query, page)
+    }
+
+    /// A page of notes, optionally only those on `file_path` and those
+    /// added by `author` (git user name or email).
+    pub fn list_notes(
+        &self,
+        file_path: Option<&str>,
+        author: Option<&str>,
+        page: Page,
+    ) -> Result<ListNotesResponse, Box<dyn std::error::Error>> {
+        knowledge::list_notes(&self.db, file_path, author, page)
+    }
+
+    /// Usage metrics for the repository.
+    pub fn metrics(&self, options: &metrics::MetricsOptions) -> Result<MetricsResponse, Box<dyn std::error::Error>> {
+        metrics::get_metrics_with_options(&self.db, &self.repo_root.to_string_lossy(), options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::commit_by;
+    use git2::Repository;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_handle_serves_repeated_calls() {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Ada").unwrap();
+        config.set_str("user.email", "ada@example.com").unwrap();
+        for i in 0..3 {
+            commit_by(&repo, "Ada", &["a.rs", "b.rs"], &format!("change {i}"));
+        }
+
+        let engram = Engram::open(dir.path()).unwrap();
+        engram.add_note("b.rs", None, "Mirror of a.rs", &NoteOptions::default()).unwrap();
+        let result = engram.analyze("a.rs", &AnalyzeOptions::default()).unwrap();
+        let coupled = &result.response.coupled_files;
+        assert_eq!(coupled[0].path, "b.rs");
+        assert_eq!(coupled[0].memories[0].content, "Mirror of a.rs");
+
+        assert_eq!(engram.search_notes("Mirror", Page::default()).unwrap().memories.len(), 1);
+        assert_eq!(engram.list_notes(Some("b.rs"), Some("ada"), Page::default()).unwrap().memories.len(), 1);
+        let summary = engram.metrics(&Default::default()).unwrap().summary;
+        assert_eq!((summary.total_analyses, summary.notes_created), (1, 1));
+    }
+}
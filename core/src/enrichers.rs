@@ -0,0 +1,146 @@
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::config::EnricherConfig;
+use crate::types::AnalysisResponse;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Run the configured external enrichers and attach their output under
+/// `response.extensions`. An enricher that fails, times out, or prints
+/// something other than JSON is reported on stderr and left out; it never
+/// fails the analysis.
+pub fn run_enrichers(repo_root: &Path, enrichers: &[EnricherConfig], response: &mut AnalysisResponse) {
+    if enrichers.is_empty() {
+        return;
+    }
+    let input = match serde_json::to_vec(&*response) {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!("Warning: could not serialize response for enrichers: {e}");
+            return;
+        }
+    };
+
+    let mut extensions = BTreeMap::new();
+    for enricher in enrichers {
+        match run_one(repo_root, enricher, &input) {
+            Ok(value) => {
+                extensions.insert(enricher.name.clone(), value);
+            }
+            Err(e) => eprintln!("Warning: enricher '{}' skipped: {e}", enricher.name),
+        }
+    }
+    if !extensions.is_empty() {
+        response.extensions = Some(extensions);
+    }
+}
+
+fn run_one(
+    repo_root: &Path,
+    enricher: &EnricherConfig,
+    input: &[u8],
+) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    let (program, args) = enricher.command.split_first().ok_or("empty command")?;
+    let mut child = Command::new(program)
+        .args(args)
+        .current_dir(repo_root)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()?;
+
+    // Feed stdin and drain stdout on threads so a chatty or non-reading
+    // enricher cannot block us past the timeout.
+    let mut stdin = child.stdin.take().ok_or("no stdin")?;
+    let input = input.to_vec();
+    std::thread::spawn(move || {
+        let _ = stdin.write_all(&input);
+    });
+    let mut stdout = child.stdout.take().ok_or("no stdout")?;
+    let reader = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        stdout.read_to_end(&mut buf).map(|_| buf)
+    });
+
+    let deadline = Instant::now() + Duration::from_millis(enricher.timeout_ms);
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!("timed out after {}ms", enricher.timeout_ms).into());
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    };
+    if !status.success() {
+        return Err(format!("exited with {status}").into());
+    }
+
+    let output = reader.join().map_err(|_| "stdout reader panicked")??;
+    Ok(serde_json::from_slice(&output)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn enricher(name: &str, script: &str, timeout_ms: u64) -> EnricherConfig {
+        EnricherConfig {
+            name: name.to_string(),
+            command: vec!["sh".to_string(), "-c".to_string(), script.to_string()],
+            timeout_ms,
+        }
+    }
+
+    fn response() -> AnalysisResponse {
+        AnalysisResponse {
+            file_path: "src/Auth.ts".to_string(),
+            repo_root: "/repo".to_string(),
+            commit_count: 3,
+            analysis_time_ms: 1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_merges_output_under_enricher_name() {
+        let dir = TempDir::new().unwrap();
+        let mut resp = response();
+        let enrichers = [
+            // Echoes back a field of the response it was given
+            enricher("echo", r#"sed -n 's/.*"file_path":"\([^"]*\)".*/{"seen":"\1"}/p'"#, 5000),
+            enricher("slo", r#"cat >/dev/null; echo '{"owner":"payments"}'"#, 5000),
+        ];
+
+        run_enrichers(dir.path(), &enrichers, &mut resp);
+
+        let ext = resp.extensions.unwrap();
+        assert_eq!(ext["echo"]["seen"], "src/Auth.ts");
+        assert_eq!(ext["slo"]["owner"], "payments");
+    }
+
+    #[test]
+    fn test_failing_slow_and_invalid_enrichers_are_skipped() {
+        let dir = TempDir::new().unwrap();
+        let mut resp = response();
+        let enrichers = [
+            enricher("fails", "exit 3", 5000),
+            enricher("slow", "sleep 5", 100),
+            enricher("garbage", "echo not json", 5000),
+            EnricherConfig { name: "empty".to_string(), command: vec![], timeout_ms: 100 },
+        ];
+
+        let start = Instant::now();
+        run_enrichers(dir.path(), &enrichers, &mut resp);
+
+        assert!(start.elapsed() < Duration::from_secs(4));
+        assert!(resp.extensions.is_none());
+    }
+}
@@ -0,0 +1,103 @@
+use std::path::Path;
+
+use git2::{Oid, Repository};
+
+use crate::indexing::IndexOptions;
+use crate::persistence::Database;
+use crate::risk;
+use crate::temporal;
+use crate::types::{CouplingEvidence, RecentCommit};
+
+/// The commits behind the coupling between `file_path` and `coupled_path`:
+/// every indexed commit that changed both, newest first, with its subject.
+///
+/// Indexes the same way `analyze` does, and carries the pair's risk score
+/// when `coupled_path` is among the files `analyze` reports. Returns
+/// (evidence, needs_background_indexing).
+pub fn why(
+    repo_root: &Path,
+    file_path: &str,
+    coupled_path: &str,
+    db: &Database,
+    opts: &IndexOptions,
+) -> Result<(CouplingEvidence, bool), Box<dyn std::error::Error>> {
+    let (analysis, needs_background) = temporal::analyze(repo_root, file_path, db, opts)?;
+    let repo = Repository::open(repo_root)?;
+
+    let shared = db.shared_commits(file_path, coupled_path)?;
+    let co_change_count = shared.len() as u32;
+    let coupling_score = risk::coupling_score(
+        opts.coupling_metric,
+        co_change_count,
+        analysis.commit_count,
+        db.commit_count(coupled_path)?,
+    );
+
+    // Subjects come from git, not the index; a commit that no longer exists
+    // (rewritten history not yet reindexed) keeps an empty summary so the
+    // count still matches the evidence listed.
+    let commits = shared
+        .into_iter()
+        .map(|(hash, timestamp, author)| {
+            let summary = Oid::from_str(&hash)
+                .ok()
+                .and_then(|oid| repo.find_commit(oid).ok())
+                .map(|commit| commit.summary().unwrap_or("").to_string())
+                .unwrap_or_default();
+            RecentCommit {
+                hash,
+                summary,
+                author,
+                timestamp,
+            }
+        })
+        .collect();
+
+    let evidence = CouplingEvidence {
+        file_path: file_path.to_string(),
+        coupled_path: coupled_path.to_string(),
+        repo_root: repo_root.to_string_lossy().to_string(),
+        commit_count: analysis.commit_count,
+        co_change_count,
+        coupling_score,
+        risk_score: analysis
+            .coupled_files
+            .iter()
+            .find(|f| f.path == coupled_path)
+            .map(|f| f.risk_score),
+        commits,
+    };
+
+    Ok((evidence, needs_background))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::commit;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_why_lists_shared_commits() {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        commit(&repo, &["src/auth.rs", "src/session.rs"], "Add auth");
+        commit(&repo, &["src/auth.rs"], "Tweak auth");
+        commit(&repo, &["src/auth.rs", "src/session.rs"], "Refresh tokens\n\nBody");
+        commit(&repo, &["src/session.rs"], "Tweak session");
+
+        let db = Database::in_memory().unwrap();
+        let (evidence, _) =
+            why(dir.path(), "src/auth.rs", "src/session.rs", &db, &IndexOptions::default()).unwrap();
+
+        assert_eq!(evidence.commit_count, 3);
+        assert_eq!(evidence.co_change_count, 2);
+        assert!((evidence.coupling_score - 2.0 / 3.0).abs() < 1e-9);
+        assert!(evidence.risk_score.is_some());
+        let summaries: Vec<&str> = evidence.commits.iter().map(|c| c.summary.as_str()).collect();
+        assert_eq!(summaries.len(), 2);
+        assert!(summaries.contains(&"Add auth"));
+        assert!(summaries.contains(&"Refresh tokens"));
+        assert_eq!(evidence.commits[0].author.as_deref(), Some("alice"));
+    }
+}
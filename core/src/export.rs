@@ -0,0 +1,155 @@
+use std::io::Write;
+
+use crate::persistence::CoChangePair;
+
+/// File format of an exported co-change matrix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatrixFormat {
+    /// One header row, then one row per pair
+    Csv,
+    /// A single row group; needs the `parquet` feature
+    Parquet,
+}
+
+impl MatrixFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MatrixFormat::Csv => "csv",
+            MatrixFormat::Parquet => "parquet",
+        }
+    }
+}
+
+const COLUMNS: [&str; 5] = ["file_a", "file_b", "co_changes", "commits_a", "commits_b"];
+
+/// Write `pairs` to `out` as `format`, one record per pair.
+pub fn write_matrix(
+    pairs: &[CoChangePair],
+    format: MatrixFormat,
+    out: impl Write + Send,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match format {
+        MatrixFormat::Csv => Ok(write_csv(pairs, out)?),
+        MatrixFormat::Parquet => write_parquet(pairs, out),
+    }
+}
+
+fn write_csv(pairs: &[CoChangePair], mut out: impl Write) -> std::io::Result<()> {
+    writeln!(out, "{}", COLUMNS.join(","))?;
+    for pair in pairs {
+        writeln!(
+            out,
+            "{},{},{},{},{}",
+            csv_field(&pair.file_a),
+            csv_field(&pair.file_b),
+            pair.co_changes,
+            pair.commits_a,
+            pair.commits_b,
+        )?;
+    }
+    out.flush()
+}
+
+/// `value` quoted as RFC 4180 requires when it holds a delimiter, quote or
+/// line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(feature = "parquet")]
+fn write_parquet(pairs: &[CoChangePair], out: impl Write + Send) -> Result<(), Box<dyn std::error::Error>> {
+    use parquet::data_type::{ByteArray, ByteArrayType, Int64Type};
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::writer::SerializedFileWriter;
+    use parquet::schema::parser::parse_message_type;
+    use std::sync::Arc;
+
+    let schema = parse_message_type(
+        "message co_change_matrix {
+            REQUIRED BYTE_ARRAY file_a (UTF8);
+            REQUIRED BYTE_ARRAY file_b (UTF8);
+            REQUIRED INT64 co_changes;
+            REQUIRED INT64 commits_a;
+            REQUIRED INT64 commits_b;
+        }",
+    )?;
+    let mut writer = SerializedFileWriter::new(out, Arc::new(schema), Arc::new(WriterProperties::builder().build()))?;
+    let mut row_group = writer.next_row_group()?;
+    let mut index = 0;
+    while let Some(mut column) = row_group.next_column()? {
+        let paths = |path: fn(&CoChangePair) -> &str| -> Vec<ByteArray> {
+            pairs.iter().map(|pair| ByteArray::from(path(pair))).collect()
+        };
+        let counts = |count: fn(&CoChangePair) -> u32| -> Vec<i64> {
+            pairs.iter().map(|pair| count(pair) as i64).collect()
+        };
+        match index {
+            0 => column.typed::<ByteArrayType>().write_batch(&paths(|p| &p.file_a), None, None)?,
+            1 => column.typed::<ByteArrayType>().write_batch(&paths(|p| &p.file_b), None, None)?,
+            2 => column.typed::<Int64Type>().write_batch(&counts(|p| p.co_changes), None, None)?,
+            3 => column.typed::<Int64Type>().write_batch(&counts(|p| p.commits_a), None, None)?,
+            _ => column.typed::<Int64Type>().write_batch(&counts(|p| p.commits_b), None, None)?,
+        };
+        column.close()?;
+        index += 1;
+    }
+    row_group.close()?;
+    writer.close()?;
+    Ok(())
+}
+
+#[cfg(not(feature = "parquet"))]
+fn write_parquet(_pairs: &[CoChangePair], _out: impl Write + Send) -> Result<(), Box<dyn std::error::Error>> {
+    Err("Parquet export needs engram-core built with the `parquet` feature".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::Database;
+
+    #[test]
+    fn test_csv_matrix_of_pairs_under_prefix() {
+        let db = Database::in_memory().unwrap();
+        db.insert_commit("c1", &["src/a.rs", "src/b,c.rs", "docs/x.md"], 1000).unwrap();
+        db.insert_commit("c2", &["src/a.rs", "src/b,c.rs"], 2000).unwrap();
+        db.insert_commit("c3", &["src/a.rs"], 3000).unwrap();
+
+        let pairs = db.co_change_matrix("src/").unwrap();
+        assert_eq!(pairs.len(), 1);
+        let mut out = Vec::new();
+        write_matrix(&pairs, MatrixFormat::Csv, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "file_a,file_b,co_changes,commits_a,commits_b\nsrc/a.rs,\"src/b,c.rs\",2,3,2\n"
+        );
+        assert_eq!(db.co_change_matrix("").unwrap().len(), 3);
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_parquet_matrix_round_trips() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        let pair = CoChangePair {
+            file_a: "a.rs".to_string(),
+            file_b: "b.rs".to_string(),
+            co_changes: 2,
+            commits_a: 3,
+            commits_b: 2,
+        };
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("matrix.parquet");
+        write_matrix(&[pair.clone(), pair], MatrixFormat::Parquet, std::fs::File::create(&path).unwrap()).unwrap();
+
+        let reader = SerializedFileReader::new(std::fs::File::open(&path).unwrap()).unwrap();
+        let metadata = reader.metadata().file_metadata();
+        assert_eq!(metadata.num_rows(), 2);
+        let columns: Vec<&str> = metadata.schema_descr().columns().iter().map(|c| c.name()).collect();
+        assert_eq!(columns, COLUMNS);
+    }
+}
@@ -0,0 +1,219 @@
+use std::path::{Path, PathBuf};
+
+use git2::{ObjectType, Oid, Repository};
+
+use crate::persistence::Database;
+
+/// `origin` URL reduced to host and path, so the forms one remote is
+/// cloned by (`git@host:org/repo.git`, `https://user@host/org/repo/`)
+/// compare equal.
+fn normalize_url(url: &str) -> String {
+    let url = url.trim().trim_end_matches('/');
+    let url = url.strip_suffix(".git").unwrap_or(url);
+    let rest = match url.split_once("://") {
+        Some((_, rest)) => rest.to_string(),
+        // scp-like syntax: [user@]host:path
+        None => url.replacen(':', "/", 1),
+    };
+    let (authority, path) = rest.split_once('/').unwrap_or((&rest, ""));
+    let host = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+    format!("{}/{path}", host.to_lowercase())
+}
+
+fn hash(text: &str) -> Result<String, git2::Error> {
+    Ok(Oid::hash_object(ObjectType::Blob, text.as_bytes())?.to_string())
+}
+
+/// Hashes of the `origin` URL, normalized and as written (which databases
+/// stamped before normalization hold); empty when there is no origin.
+fn origin_hashes(repo: &Repository) -> Result<(String, String), git2::Error> {
+    let url = match repo.find_remote("origin") {
+        Ok(remote) => remote.url().unwrap_or("").to_string(),
+        Err(_) => return Ok((String::new(), String::new())),
+    };
+    if url.is_empty() {
+        return Ok((String::new(), String::new()));
+    }
+    Ok((hash(&normalize_url(&url))?, hash(&url)?))
+}
+
+fn has_commit(repo: &Repository, hash: &str) -> bool {
+    Oid::from_str(hash).ok().and_then(|oid| repo.find_commit(oid).ok()).is_some()
+}
+
+/// Check the database against the repository it is being used for.
+///
+/// The database is keyed on a commit of the history it indexed: a root
+/// commit once an index has reached one (recorded by the indexer, so no
+/// walk is needed here), until then the oldest commit indexed so far.
+/// Returns false if `repo` does not have that commit, i.e. the database
+/// was built from a different repository (a `.engram` directory copied
+/// between projects). A shallow clone may lack it without being another
+/// repository, so there it only counts as foreign when the origins differ
+/// too. Unshallowing (`git fetch --unshallow`) keeps the commit, which
+/// merely gains parents, so it still matches.
+///
+/// A changed origin alone is only a warning, since remotes get renamed
+/// and moved; the new origin is recorded.
+pub fn verify(repo: &Repository, db: &Database) -> Result<bool, Box<dyn std::error::Error>> {
+    let (stored_origin, mut anchor) = db.get_fingerprint()?.unwrap_or_default();
+    let (origin, origin_as_written) = origin_hashes(repo)?;
+    let origin_changed = !origin.is_empty()
+        && !stored_origin.is_empty()
+        && stored_origin != origin
+        && stored_origin != origin_as_written;
+
+    if anchor.is_empty() {
+        anchor = db.oldest_indexed_commit()?.unwrap_or_default();
+    }
+    if !anchor.is_empty() && !has_commit(repo, &anchor) && (!repo.is_shallow() || origin_changed) {
+        return Ok(false);
+    }
+
+    if origin_changed {
+        eprintln!("Warning: the origin remote changed since this repository was indexed; keeping the index");
+    }
+    if db.get_fingerprint()? != Some((origin.clone(), anchor.clone())) {
+        db.set_fingerprint(&origin, &anchor)?;
+    }
+    Ok(true)
+}
+
+/// Move a foreign database (and its WAL/SHM files) aside so a fresh one can
+/// be created in its place. Returns the path it was moved to.
+pub fn set_aside(db_path: &Path) -> std::io::Result<PathBuf> {
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let target = db_path.with_extension(format!("db.foreign-{stamp}"));
+    std::fs::rename(db_path, &target)?;
+    for suffix in ["-wal", "-shm"] {
+        let side = PathBuf::from(format!("{}{suffix}", db_path.display()));
+        if side.exists() {
+            std::fs::rename(&side, format!("{}{suffix}", target.display()))?;
+        }
+    }
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::commit;
+    use tempfile::TempDir;
+
+    fn init_repo(origin: Option<&str>, message: &str) -> TempDir {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        commit(&repo, &["a.rs"], message);
+        commit(&repo, &["a.rs"], "second");
+        if let Some(url) = origin {
+            repo.remote("origin", url).unwrap();
+        }
+        dir
+    }
+
+    /// The root and second commits of a repo from `init_repo`.
+    fn commits(repo: &Repository) -> (String, String) {
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        (head.parent_id(0).unwrap().to_string(), head.id().to_string())
+    }
+
+    #[test]
+    fn test_normalize_url() {
+        let forms = [
+            "https://github.com/org/repo.git",
+            "https://user@GitHub.com/org/repo/",
+            "git@github.com:org/repo.git",
+            "ssh://git@github.com/org/repo",
+        ];
+        for url in forms {
+            assert_eq!(normalize_url(url), "github.com/org/repo", "{url}");
+        }
+        assert_ne!(normalize_url("git@github.com:org/other.git"), "github.com/org/repo");
+    }
+
+    #[test]
+    fn test_verify_keys_on_recorded_root() {
+        let dir = init_repo(Some("https://example.com/a.git"), "root a");
+        let repo = Repository::open(dir.path()).unwrap();
+        let (root, _) = commits(&repo);
+        let db = Database::in_memory().unwrap();
+
+        // Nothing indexed yet: nothing to check, only the origin is stamped
+        assert!(verify(&repo, &db).unwrap());
+        let (origin, anchor) = db.get_fingerprint().unwrap().unwrap();
+        assert!(!origin.is_empty());
+        assert!(anchor.is_empty());
+
+        db.set_root_commit(&root).unwrap();
+        assert!(verify(&repo, &db).unwrap());
+        assert_eq!(db.get_fingerprint().unwrap().unwrap().1, root);
+    }
+
+    #[test]
+    fn test_verify_rejects_other_repo() {
+        let a = init_repo(Some("https://example.com/a.git"), "root a");
+        let b = init_repo(Some("https://example.com/b.git"), "root b");
+        let repo_a = Repository::open(a.path()).unwrap();
+        let repo_b = Repository::open(b.path()).unwrap();
+
+        // Before an index reaches the root, its oldest commit stands in
+        let (root, second) = commits(&repo_a);
+        let partial = Database::in_memory().unwrap();
+        partial.insert_commit(&second, &["a.rs"], 2).unwrap();
+        assert!(verify(&repo_a, &partial).unwrap());
+        assert_eq!(partial.get_fingerprint().unwrap().unwrap().1, second);
+        assert!(!verify(&repo_b, &partial).unwrap());
+
+        let complete = Database::in_memory().unwrap();
+        complete.set_root_commit(&root).unwrap();
+        assert!(verify(&repo_a, &complete).unwrap());
+        assert!(!verify(&repo_b, &complete).unwrap());
+    }
+
+    #[test]
+    fn test_verify_accepts_changed_origin() {
+        let a = init_repo(Some("https://example.com/a.git"), "root a");
+        let repo = Repository::open(a.path()).unwrap();
+        let db = Database::in_memory().unwrap();
+        db.set_root_commit(&commits(&repo).0).unwrap();
+        assert!(verify(&repo, &db).unwrap());
+        let (before, _) = db.get_fingerprint().unwrap().unwrap();
+
+        // Another way of writing the same remote is not a change
+        repo.remote_set_url("origin", "git@example.com:a.git").unwrap();
+        assert!(verify(&repo, &db).unwrap());
+        assert_eq!(db.get_fingerprint().unwrap().unwrap().0, before);
+
+        // A moved remote is a warning, and the new origin is recorded
+        repo.remote_set_url("origin", "https://example.com/other.git").unwrap();
+        assert!(verify(&repo, &db).unwrap());
+        assert_ne!(db.get_fingerprint().unwrap().unwrap().0, before);
+    }
+
+    #[test]
+    fn test_verify_across_shallow_clones() {
+        let a = init_repo(Some("https://example.com/a.git"), "root a");
+        let repo = Repository::open(a.path()).unwrap();
+        let (root, second) = commits(&repo);
+
+        // Stamped in a shallow clone, then unshallowed: the recorded
+        // boundary commit now has parents but is still there
+        let db = Database::in_memory().unwrap();
+        db.set_root_commit(&second).unwrap();
+        assert!(verify(&repo, &db).unwrap());
+
+        // A shallow clone may lack the recorded root; only a different
+        // origin makes it foreign then
+        std::fs::write(repo.path().join("shallow"), format!("{root}\n")).unwrap();
+        let repo = Repository::open(a.path()).unwrap();
+        assert!(repo.is_shallow());
+        let db = Database::in_memory().unwrap();
+        db.set_root_commit("0123456789012345678901234567890123456789").unwrap();
+        assert!(verify(&repo, &db).unwrap());
+        repo.remote_set_url("origin", "https://example.com/b.git").unwrap();
+        assert!(!verify(&repo, &db).unwrap());
+    }
+}
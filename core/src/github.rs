@@ -0,0 +1,84 @@
+use crate::types::CheckResponse;
+
+/// Render a check as GitHub Actions workflow commands, so findings show up
+/// as annotations on the PR diff.
+///
+/// An omitted file is not in the diff, so each finding is annotated on the
+/// changed files it is coupled to. Blocking findings are errors, the rest
+/// warnings.
+pub fn render_check_annotations(response: &CheckResponse) -> String {
+    let mut out = String::new();
+    for finding in &response.findings {
+        let command = if finding.blocking { "error" } else { "warning" };
+        for changed in &finding.coupled_to {
+            let message = format!(
+                "{} usually changes with {changed} ({} risk, score {:.2}, {} co-changes) \
+                 but is not part of this change.",
+                finding.path, finding.risk_level, finding.risk_score, finding.co_change_count,
+            );
+            out.push_str(&format!(
+                "::{command} file={},line=1,title={}::{}\n",
+                escape_property(changed),
+                escape_property(&format!("Coupled file not changed: {}", finding.path)),
+                escape_data(&message),
+            ));
+        }
+    }
+    if !response.findings.is_empty() {
+        out.push_str(&format!(
+            "::notice title=engram::{} coupled file(s) missing from {} changed file(s)\n",
+            response.findings.len(),
+            response.changed_files.len(),
+        ));
+    }
+    out
+}
+
+/// Escape a workflow command message.
+fn escape_data(text: &str) -> String {
+    text.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+/// Escape a workflow command property value (`file=`, `title=`).
+fn escape_property(text: &str) -> String {
+    escape_data(text).replace(':', "%3A").replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::CheckFinding;
+
+    #[test]
+    fn test_renders_annotations_on_changed_files() {
+        let finding = |path: &str, blocking: bool| CheckFinding {
+            path: path.to_string(),
+            risk_level: if blocking { "Critical" } else { "High" }.to_string(),
+            risk_score: if blocking { 0.9 } else { 0.65 },
+            co_change_count: 7,
+            coupled_to: vec!["src/api.ts".to_string(), "src/a,b.ts".to_string()],
+            blocking,
+        };
+        let response = CheckResponse {
+            repo_root: "/repo".to_string(),
+            staged: true,
+            changed_files: vec!["src/api.ts".to_string(), "src/a,b.ts".to_string()],
+            findings: vec![finding("src/client.ts", true), finding("docs/100%.md", false)],
+            passed: false,
+        };
+
+        let out = render_check_annotations(&response);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].starts_with(
+            "::error file=src/api.ts,line=1,title=Coupled file not changed%3A src/client.ts::src/client.ts usually"
+        ));
+        assert!(lines[1].starts_with("::error file=src/a%2Cb.ts,"));
+        assert!(lines[2].starts_with("::warning file=src/api.ts,"));
+        assert!(lines[2].contains("::docs/100%25.md usually changes with src/api.ts (High risk, score 0.65"));
+        assert_eq!(lines[4], "::notice title=engram::2 coupled file(s) missing from 2 changed file(s)");
+
+        let clean = CheckResponse { findings: vec![], passed: true, ..response };
+        assert!(render_check_annotations(&clean).is_empty());
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::persistence::Database;
+use crate::types::{CouplingGraph, GraphEdge, GraphNode};
+
+/// Strongest couplings followed from each node, so a depth-2 walk from a
+/// hub file stays readable.
+const MAX_NEIGHBORS: usize = 10;
+
+/// Walk the temporal index outward from `file_path`, up to `depth` hops,
+/// following each node's strongest couplings.
+///
+/// Edges are undirected; an edge's weight is the larger of the two
+/// directional coupling scores (co-changes / commits of the source file).
+pub fn coupling_graph(
+    db: &Database,
+    file_path: &str,
+    depth: u32,
+) -> Result<CouplingGraph, rusqlite::Error> {
+    let mut nodes: Vec<GraphNode> = Vec::new();
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut edges: HashMap<(String, String), GraphEdge> = HashMap::new();
+    let mut queue: VecDeque<(String, u32)> = VecDeque::new();
+
+    seen.insert(file_path.to_string());
+    queue.push_back((file_path.to_string(), 0));
+
+    while let Some((path, distance)) = queue.pop_front() {
+        let commit_count = db.commit_count(&path)?;
+        nodes.push(GraphNode {
+            path: path.clone(),
+            distance,
+            commit_count,
+        });
+        if distance >= depth || commit_count == 0 {
+            continue;
+        }
+
+        for (neighbor, co_change_count) in db.coupled_files(&path)?.into_iter().take(MAX_NEIGHBORS) {
+            let weight = co_change_count as f64 / commit_count as f64;
+            let key = if path < neighbor {
+                (path.clone(), neighbor.clone())
+            } else {
+                (neighbor.clone(), path.clone())
+            };
+            let edge = edges.entry(key.clone()).or_insert_with(|| GraphEdge {
+                source: key.0,
+                target: key.1,
+                co_change_count,
+                coupling_score: 0.0,
+            });
+            edge.coupling_score = edge.coupling_score.max(weight);
+
+            if seen.insert(neighbor.clone()) {
+                queue.push_back((neighbor, distance + 1));
+            }
+        }
+    }
+
+    let mut edges: Vec<GraphEdge> = edges.into_values().collect();
+    edges.sort_by(|a, b| {
+        b.coupling_score
+            .partial_cmp(&a.coupling_score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.source.cmp(&b.source))
+            .then_with(|| a.target.cmp(&b.target))
+    });
+
+    Ok(CouplingGraph {
+        root: file_path.to_string(),
+        depth,
+        nodes,
+        edges,
+    })
+}
+
+/// Render a coupling graph as Graphviz DOT. Edge pen width and label
+/// reflect the coupling score; the root node is highlighted.
+pub fn to_dot(graph: &CouplingGraph) -> String {
+    let mut out = String::from("graph engram {\n");
+    out.push_str("  node [shape=box, fontname=\"Helvetica\"];\n");
+
+    for node in &graph.nodes {
+        let style = if node.distance == 0 {
+            ", style=filled, fillcolor=\"#f4cccc\""
+        } else {
+            ""
+        };
+        out.push_str(&format!(
+            "  \"{}\" [label=\"{}\\n{} commits\"{style}];\n",
+            escape(&node.path),
+            escape(&node.path),
+            node.commit_count,
+        ));
+    }
+
+    for edge in &graph.edges {
+        out.push_str(&format!(
+            "  \"{}\" -- \"{}\" [label=\"{:.2}\", weight={}, penwidth={:.1}];\n",
+            escape(&edge.source),
+            escape(&edge.target),
+            edge.coupling_score,
+            edge.co_change_count,
+            1.0 + edge.coupling_score * 4.0,
+        ));
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seed() -> Database {
+        let db = Database::in_memory().unwrap();
+        for i in 0..4 {
+            db.insert_commit(&format!("ab{i}"), &["src/A.ts", "src/B.ts"], i).unwrap();
+        }
+        db.insert_commit("bc0", &["src/B.ts", "src/C.ts"], 10).unwrap();
+        db.insert_commit("cd0", &["src/C.ts", "src/D.ts"], 11).unwrap();
+        db
+    }
+
+    #[test]
+    fn test_graph_respects_depth() {
+        let db = seed();
+
+        let graph = coupling_graph(&db, "src/A.ts", 1).unwrap();
+        let paths: Vec<&str> = graph.nodes.iter().map(|n| n.path.as_str()).collect();
+        assert_eq!(paths, vec!["src/A.ts", "src/B.ts"]);
+        assert_eq!(graph.edges.len(), 1);
+        assert!((graph.edges[0].coupling_score - 1.0).abs() < 1e-9);
+
+        let graph = coupling_graph(&db, "src/A.ts", 2).unwrap();
+        assert_eq!(graph.nodes.len(), 3);
+        assert_eq!(graph.nodes[2].path, "src/C.ts");
+        assert_eq!(graph.nodes[2].distance, 2);
+        assert_eq!(graph.edges.len(), 2);
+    }
+
+    #[test]
+    fn test_to_dot_renders_nodes_and_edges() {
+        let db = seed();
+        let dot = to_dot(&coupling_graph(&db, "src/A.ts", 2).unwrap());
+
+        assert!(dot.starts_with("graph engram {"));
+        assert!(dot.contains("\"src/A.ts\" [label=\"src/A.ts\\n4 commits\", style=filled"));
+        assert!(dot.contains("\"src/A.ts\" -- \"src/B.ts\" [label=\"1.00\""));
+        assert!(dot.contains("\"src/B.ts\" -- \"src/C.ts\""));
+        assert!(!dot.contains("src/D.ts"));
+    }
+}
@@ -0,0 +1,210 @@
+use std::path::{Path, PathBuf};
+
+use git2::Repository;
+
+use crate::types::{HookInstall, InstallHooksResponse};
+
+/// Hooks that run after HEAD gains commits: commits, pulls and merges, and
+/// rebases or amends.
+pub const HOOKS: [&str; 3] = ["post-commit", "post-merge", "post-rewrite"];
+
+const BEGIN_MARKER: &str = "# >>> engram >>>";
+const END_MARKER: &str = "# <<< engram <<<";
+
+/// Interpreters that run the sh block; other hooks are left alone.
+const SH_INTERPRETERS: [&str; 5] = ["sh", "bash", "dash", "ksh", "zsh"];
+
+/// Install git hooks that index new commits in the background after they
+/// land, using the `engram-core` binary at `binary`.
+///
+/// Hooks are written to `core.hooksPath` if set, else the repo's hooks
+/// directory. An existing hook keeps its content: the engram block is
+/// replaced if a previous install left one, and otherwise appended, or
+/// inserted before a final `exit` or `exec` so it still runs. Hooks in
+/// another language than sh are skipped and reported.
+pub fn install_hooks(repo_root: &Path, binary: &Path) -> Result<InstallHooksResponse, Box<dyn std::error::Error>> {
+    let repo = Repository::open(repo_root)?;
+    let hooks_dir = hooks_dir(&repo)?;
+    std::fs::create_dir_all(&hooks_dir)?;
+
+    let block = hook_block(binary);
+    let mut hooks = Vec::new();
+    for name in HOOKS {
+        let path = hooks_dir.join(name);
+        let mut reason = None;
+        let (content, action) = match std::fs::read_to_string(&path) {
+            Ok(existing) => match replace_block(&existing, &block) {
+                Some(updated) if updated == existing => (existing, "unchanged"),
+                Some(updated) => (updated, "updated"),
+                None => match interpreter(&existing) {
+                    Some(other) if !SH_INTERPRETERS.contains(&other) => {
+                        reason = Some(format!("runs {other}, not sh; add `engram-core update` to it by hand"));
+                        (existing, "skipped")
+                    }
+                    _ => add_block(&existing, &block),
+                },
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => (format!("#!/bin/sh\n{block}"), "created"),
+            Err(e) => return Err(e.into()),
+        };
+        if !matches!(action, "unchanged" | "skipped") {
+            std::fs::write(&path, content)?;
+            make_executable(&path)?;
+        }
+        hooks.push(HookInstall {
+            name: name.to_string(),
+            path: path.to_string_lossy().to_string(),
+            action: action.to_string(),
+            reason,
+        });
+    }
+
+    Ok(InstallHooksResponse {
+        repo_root: repo_root.to_string_lossy().to_string(),
+        hooks_dir: hooks_dir.to_string_lossy().to_string(),
+        hooks,
+    })
+}
+
+fn hooks_dir(repo: &Repository) -> Result<PathBuf, git2::Error> {
+    match repo.config()?.get_path("core.hooksPath") {
+        Ok(path) if path.is_absolute() => Ok(path),
+        // Relative to the working tree, as git resolves it for hooks
+        Ok(path) => Ok(repo.workdir().unwrap_or(repo.path()).join(path)),
+        Err(_) => Ok(repo.commondir().join("hooks")),
+    }
+}
+
+/// The marked block appended to each hook. It detaches so the commit
+/// returns immediately, and never fails the hook.
+fn hook_block(binary: &Path) -> String {
+    let binary = binary.to_string_lossy().replace('\'', r"'\''");
+    format!(
+        "{BEGIN_MARKER}\n\
+         # Installed by `engram-core install-hooks`: index new commits in the background.\n\
+         if [ -x '{binary}' ]; then\n  \
+           ('{binary}' update --repo-root \"$(git rev-parse --show-toplevel)\" >/dev/null 2>&1 &)\n\
+         fi\n\
+         {END_MARKER}\n"
+    )
+}
+
+/// The interpreter named by the shebang of `content` (`bash` for both
+/// `#!/bin/bash` and `#!/usr/bin/env bash`), or `None` without one, in
+/// which case git runs the hook with sh.
+fn interpreter(content: &str) -> Option<&str> {
+    let shebang = content.lines().next()?.strip_prefix("#!")?;
+    let mut words = shebang.split_whitespace();
+    let program = words.next()?.rsplit('/').next()?;
+    if program != "env" {
+        return Some(program);
+    }
+    words.find(|word| !word.starts_with('-'))?.rsplit('/').next()
+}
+
+/// `content` with `block` added where it runs: before a final `exit` or
+/// `exec` line, which would end the hook first, or else at the end.
+fn add_block(content: &str, block: &str) -> (String, &'static str) {
+    let content = content.trim_end();
+    let (head, last) = match content.rsplit_once('\n') {
+        Some((head, last)) => (format!("{head}\n"), last),
+        None => (String::new(), content),
+    };
+    let last_word = last.split_whitespace().next().unwrap_or_default();
+    if matches!(last_word, "exit" | "exec") {
+        return (format!("{head}{block}{last}\n"), "inserted");
+    }
+    (format!("{content}\n{block}"), "appended")
+}
+
+/// `content` with its engram block swapped for `block`, or `None` if it
+/// has none.
+fn replace_block(content: &str, block: &str) -> Option<String> {
+    let start = content.find(BEGIN_MARKER)?;
+    let end = content[start..].find(END_MARKER)? + start + END_MARKER.len();
+    let end = if content[end..].starts_with('\n') { end + 1 } else { end };
+    Some(format!("{}{block}{}", &content[..start], &content[end..]))
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut permissions = std::fs::metadata(path)?.permissions();
+    permissions.set_mode(permissions.mode() | 0o755);
+    std::fs::set_permissions(path, permissions)
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_install_hooks_is_idempotent_and_keeps_existing_hooks() {
+        let dir = TempDir::new().unwrap();
+        Repository::init(dir.path()).unwrap();
+        let hooks = dir.path().join(".git").join("hooks");
+        std::fs::create_dir_all(&hooks).unwrap();
+        std::fs::write(hooks.join("post-merge"), "#!/bin/sh\nnpm install\n").unwrap();
+
+        let resp = install_hooks(dir.path(), Path::new("/opt/engram/engram-core")).unwrap();
+        let actions: Vec<&str> = resp.hooks.iter().map(|h| h.action.as_str()).collect();
+        assert_eq!(actions, ["created", "appended", "created"]);
+
+        let merge = std::fs::read_to_string(hooks.join("post-merge")).unwrap();
+        assert!(merge.starts_with("#!/bin/sh\nnpm install\n"));
+        assert!(merge.contains("'/opt/engram/engram-core' update --repo-root"));
+
+        // Reinstalling with a new binary replaces the block in place
+        let resp = install_hooks(dir.path(), Path::new("/usr/bin/engram-core")).unwrap();
+        assert!(resp.hooks.iter().all(|h| h.action == "updated"));
+        let merge = std::fs::read_to_string(hooks.join("post-merge")).unwrap();
+        assert_eq!(merge.matches(BEGIN_MARKER).count(), 1);
+        assert!(!merge.contains("/opt/engram"));
+        assert!(merge.contains("npm install"));
+
+        let resp = install_hooks(dir.path(), Path::new("/usr/bin/engram-core")).unwrap();
+        assert!(resp.hooks.iter().all(|h| h.action == "unchanged"));
+    }
+
+    #[test]
+    fn test_install_hooks_respects_interpreter_and_final_exit() {
+        let dir = TempDir::new().unwrap();
+        Repository::init(dir.path()).unwrap();
+        let hooks = dir.path().join(".git").join("hooks");
+        std::fs::create_dir_all(&hooks).unwrap();
+        let python = "#!/usr/bin/env python3\nprint('hi')\n";
+        std::fs::write(hooks.join("post-commit"), python).unwrap();
+        std::fs::write(hooks.join("post-merge"), "#!/usr/bin/env bash\nnpm install\nexec lefthook run post-merge\n")
+            .unwrap();
+        std::fs::write(hooks.join("post-rewrite"), "exit 0\n").unwrap();
+
+        let resp = install_hooks(dir.path(), Path::new("/opt/engram/engram-core")).unwrap();
+        let actions: Vec<&str> = resp.hooks.iter().map(|h| h.action.as_str()).collect();
+        assert_eq!(actions, ["skipped", "inserted", "inserted"]);
+        assert!(resp.hooks[0].reason.as_deref().unwrap().contains("python3"));
+        assert_eq!(std::fs::read_to_string(hooks.join("post-commit")).unwrap(), python);
+
+        let merge = std::fs::read_to_string(hooks.join("post-merge")).unwrap();
+        assert!(merge.starts_with("#!/usr/bin/env bash\nnpm install\n# >>> engram >>>"));
+        assert!(merge.ends_with("# <<< engram <<<\nexec lefthook run post-merge\n"));
+
+        // Without a shebang git runs the hook with sh
+        let rewrite = std::fs::read_to_string(hooks.join("post-rewrite")).unwrap();
+        assert!(rewrite.starts_with("# >>> engram >>>"));
+        assert!(rewrite.ends_with("# <<< engram <<<\nexit 0\n"));
+    }
+
+    #[test]
+    fn test_interpreter() {
+        assert_eq!(interpreter("#!/bin/sh\n"), Some("sh"));
+        assert_eq!(interpreter("#! /usr/bin/env -S bash -e\n"), Some("bash"));
+        assert_eq!(interpreter("#!/usr/bin/python3\n"), Some("python3"));
+        assert_eq!(interpreter("npm test\n"), None);
+    }
+}
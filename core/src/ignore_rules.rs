@@ -0,0 +1,128 @@
+use std::path::Path;
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+/// Name of the per-repo ignore file, in gitignore syntax.
+pub const IGNORE_FILE: &str = ".engramignore";
+
+/// Paths excluded from indexing and results by the repo's `.engramignore`.
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreRules {
+    matcher: Option<Gitignore>,
+}
+
+impl IgnoreRules {
+    /// Load `.engramignore` from the repo root. A missing file means nothing
+    /// is ignored; malformed lines are reported on stderr and skipped.
+    pub fn load(repo_root: &Path) -> Self {
+        let path = repo_root.join(IGNORE_FILE);
+        if !path.is_file() {
+            return Self::default();
+        }
+
+        let mut builder = GitignoreBuilder::new(repo_root);
+        if let Some(e) = builder.add(&path) {
+            eprintln!("Warning: {IGNORE_FILE}: {e}");
+        }
+        match builder.build() {
+            Ok(matcher) => Self { matcher: Some(matcher) },
+            Err(e) => {
+                eprintln!("Warning: ignoring {IGNORE_FILE}: {e}");
+                Self::default()
+            }
+        }
+    }
+
+    /// True if the repo-relative `path` (or one of its parent directories) is ignored.
+    pub fn is_ignored(&self, path: &str) -> bool {
+        self.matcher
+            .as_ref()
+            .is_some_and(|m| m.matched_path_or_any_parents(path, false).is_ignore())
+    }
+}
+
+/// Globs narrowing which coupled files an analysis reports (`--include`,
+/// `--exclude`). Unlike `.engramignore` they leave the index alone: a path
+/// is reported if it matches some include glob (or there are none) and no
+/// exclude glob.
+#[derive(Debug, Clone, Default)]
+pub struct PathFilter {
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+}
+
+impl PathFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self, globset::Error> {
+        Ok(Self { include: glob_set(include)?, exclude: glob_set(exclude)? })
+    }
+
+    /// True if the filter lets every path through.
+    pub fn is_empty(&self) -> bool {
+        self.include.is_none() && self.exclude.is_none()
+    }
+
+    pub fn allows(&self, path: &str) -> bool {
+        self.include.as_ref().is_none_or(|globs| globs.is_match(path))
+            && !self.exclude.as_ref().is_some_and(|globs| globs.is_match(path))
+    }
+}
+
+/// `None` for no globs. `*` stops at `/`, as in gitignore.
+fn glob_set(globs: &[String]) -> Result<Option<GlobSet>, globset::Error> {
+    if globs.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        builder.add(GlobBuilder::new(glob).literal_separator(true).build()?);
+    }
+    Ok(Some(builder.build()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_missing_file_ignores_nothing() {
+        let dir = TempDir::new().unwrap();
+        assert!(!IgnoreRules::load(dir.path()).is_ignored("src/a.rs"));
+    }
+
+    #[test]
+    fn test_gitignore_syntax() {
+        let dir = TempDir::new().unwrap();
+        fs::write(
+            dir.path().join(IGNORE_FILE),
+            "# generated code\ngenerated/\n*.pb.go\nfixtures/**\n!fixtures/keep.json\n/root-only.ts\n",
+        )
+        .unwrap();
+        let rules = IgnoreRules::load(dir.path());
+
+        assert!(rules.is_ignored("generated/api.ts"));
+        assert!(rules.is_ignored("src/generated/deep/api.ts"));
+        assert!(rules.is_ignored("proto/user.pb.go"));
+        assert!(rules.is_ignored("fixtures/big.json"));
+        assert!(!rules.is_ignored("fixtures/keep.json"));
+        assert!(rules.is_ignored("root-only.ts"));
+        assert!(!rules.is_ignored("src/root-only.ts"));
+        assert!(!rules.is_ignored("src/main.go"));
+    }
+
+    #[test]
+    fn test_path_filter() {
+        let globs = |globs: &[&str]| globs.iter().map(|g| g.to_string()).collect::<Vec<_>>();
+        let filter = PathFilter::new(&globs(&["src/**", "*.toml"]), &globs(&["**/*_test.go"])).unwrap();
+
+        assert!(filter.allows("src/api/handler.go"));
+        assert!(!filter.allows("src/api/handler_test.go"));
+        assert!(filter.allows("Cargo.toml"));
+        assert!(!filter.allows("crates/x/Cargo.toml"));
+        assert!(!filter.allows("docs/guide.md"));
+        assert!(PathFilter::default().allows("docs/guide.md"));
+        assert!(PathFilter::new(&globs(&["src/[a"]), &[]).is_err());
+    }
+}
@@ -0,0 +1,335 @@
+use std::path::Path;
+use std::sync::LazyLock;
+use std::time::Instant;
+
+use regex::Regex;
+
+use crate::sandbox;
+use crate::test_intents::TestPatterns;
+use crate::types::{CoupledFile, StaticLink};
+
+/// Test files read per scan; beyond this the scan stops early.
+const MAX_SCANNED_TEST_FILES: usize = 2000;
+/// Larger test files are skipped (generated fixtures, bundles).
+const MAX_TEST_FILE_BYTES: u64 = 512 * 1024;
+
+const JS_EXTENSIONS: [&str; 6] = ["ts", "tsx", "js", "jsx", "mjs", "cjs"];
+
+/// `import ... from "x"`, `export ... from "x"`, `import "x"`,
+/// `require("x")` and `import("x")`: group 1 is the specifier.
+static JS_IMPORT_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?:\b(?:import|export)\b[^'"`;]*?\bfrom\s*|\bimport\s*|\b(?:require|import)\s*\(\s*)['"]([^'"]+)['"]"#,
+    )
+    .unwrap()
+});
+
+/// `from pkg.mod import a, b` (groups 1-2) or `import pkg.mod` (group 3).
+static PYTHON_IMPORT_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?m)^\s*(?:from\s+(\.*[\w.]*)\s+import\s+\(?([\w, \t]+)|import\s+([\w.]+))").unwrap()
+});
+
+/// `require_relative "x"` (group 1) or `require "x"` (group 2).
+static RUBY_IMPORT_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"\brequire_relative\s*\(?\s*['"]([^'"]+)['"]|\brequire\s*\(?\s*['"]([^'"]+)['"]"#).unwrap()
+});
+
+/// JVM `import a.b.C` and PHP `use A\B\C`.
+static QUALIFIED_IMPORT_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?m)^\s*(?:import\s+(?:static\s+)?([\w.]+)|use\s+\\?([\w\\]+))").unwrap()
+});
+
+/// Rust `use some_crate::a::b`.
+static RUST_USE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\buse\s+(\w+(?:::\w+)+)").unwrap());
+
+/// C/C++ `#include "x.h"`.
+static INCLUDE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"#\s*include\s*"([^"]+)""#).unwrap());
+
+/// Test files in the working tree whose import, require, or use statements
+/// refer to `source_path`. Walks the tree honouring `.gitignore`, reading
+/// at most `MAX_SCANNED_TEST_FILES` test files and stopping at `deadline`.
+/// Returns (test files, whether the whole tree was scanned).
+pub fn tests_importing(
+    repo_root: &Path,
+    source_path: &str,
+    patterns: &TestPatterns,
+    deadline: Instant,
+) -> (Vec<String>, bool) {
+    let mut found = Vec::new();
+    let mut scanned = 0;
+    let mut complete = true;
+    let walker = ignore::WalkBuilder::new(repo_root).build();
+
+    for entry in walker.flatten() {
+        if Instant::now() >= deadline {
+            complete = false;
+            break;
+        }
+        if !entry.file_type().is_some_and(|t| t.is_file())
+            || entry.metadata().is_ok_and(|m| m.len() > MAX_TEST_FILE_BYTES)
+        {
+            continue;
+        }
+        let Some(rel) = entry
+            .path()
+            .strip_prefix(repo_root)
+            .ok()
+            .and_then(|p| p.to_str())
+            .map(|p| p.replace('\\', "/"))
+        else {
+            continue;
+        };
+        if rel == source_path || !patterns.is_test_file(&rel) {
+            continue;
+        }
+
+        scanned += 1;
+        if scanned > MAX_SCANNED_TEST_FILES {
+            complete = false;
+            break;
+        }
+        if let Some(content) = sandbox::read_repo_file(repo_root, &rel)
+            && imports_source(&rel, &content, source_path)
+        {
+            found.push(rel);
+        }
+    }
+
+    found.sort();
+    (found, complete)
+}
+
+/// Mark each coupled file with the static link between it and `file_path`,
+/// from their import statements in the working tree (`source` is the
+/// analyzed file's content, if already read). Files that cannot be read are
+/// left unmarked.
+pub fn mark_static_links(
+    repo_root: &Path,
+    file_path: &str,
+    source: Option<&str>,
+    coupled_files: &mut [CoupledFile],
+) {
+    let Some(source) = source.map(str::to_string).or_else(|| sandbox::read_repo_file(repo_root, file_path)) else {
+        return;
+    };
+    for file in coupled_files.iter_mut() {
+        let Some(content) = sandbox::read_repo_file(repo_root, &file.path) else {
+            continue;
+        };
+        let imports = imports_source(file_path, &source, &file.path);
+        let imported_by = imports_source(&file.path, &content, file_path);
+        file.static_link = Some(match (imports, imported_by) {
+            (true, true) => StaticLink::Mutual,
+            (true, false) => StaticLink::Imports,
+            (false, true) => StaticLink::ImportedBy,
+            (false, false) => StaticLink::None,
+        });
+    }
+}
+
+/// Whether the file at `test_path` (usually a test) imports `source_path`.
+pub fn imports_source(test_path: &str, content: &str, source_path: &str) -> bool {
+    let (source_base, _) = split_ext(source_path);
+    let test_dir = test_path.rsplit_once('/').map_or("", |(dir, _)| dir);
+    let (_, test_ext) = split_ext(test_path);
+
+    match test_ext {
+        ext if JS_EXTENSIONS.contains(&ext) => JS_IMPORT_RE.captures_iter(content).any(|cap| {
+            let spec = &cap[1];
+            if !spec.starts_with('.') {
+                return false;
+            }
+            let target = join(test_dir, spec);
+            let (target_base, target_ext) = split_ext(&target);
+            let target_base = if JS_EXTENSIONS.contains(&target_ext) { target_base } else { target.as_str() };
+            source_base == target_base || source_base == format!("{target_base}/index")
+        }),
+        "py" => PYTHON_IMPORT_RE.captures_iter(content).any(|cap| {
+            let modules: Vec<String> = match (cap.get(1), cap.get(3)) {
+                (Some(from), _) => {
+                    let names = cap.get(2).map_or("", |m| m.as_str());
+                    std::iter::once(from.as_str().to_string())
+                        .chain(names.split(',').map(|n| n.trim()).filter(|n| !n.is_empty()).map(|n| {
+                            let sep = if from.as_str().ends_with('.') { "" } else { "." };
+                            format!("{}{sep}{n}", from.as_str())
+                        }))
+                        .collect()
+                }
+                (None, Some(import)) => vec![import.as_str().to_string()],
+                _ => Vec::new(),
+            };
+            modules.iter().any(|module| {
+                let path = python_module_path(test_dir, module);
+                path.is_some_and(|(path, relative)| {
+                    let matches = |base: &str| if relative { base == path } else { ends_with_path(base, &path) };
+                    matches(source_base) || source_base.strip_suffix("/__init__").is_some_and(matches)
+                })
+            })
+        }),
+        "rb" => RUBY_IMPORT_RE.captures_iter(content).any(|cap| match (cap.get(1), cap.get(2)) {
+            (Some(relative), _) => split_ext(&join(test_dir, relative.as_str())).0 == source_base,
+            (None, Some(feature)) => ends_with_path(source_base, split_ext(feature.as_str()).0),
+            _ => false,
+        }),
+        "java" | "kt" | "scala" | "php" => QUALIFIED_IMPORT_RE.captures_iter(content).any(|cap| {
+            let path = match (cap.get(1), cap.get(2)) {
+                (Some(jvm), _) => jvm.as_str().replace('.', "/"),
+                // PHP: the first namespace segment is the PSR-4 vendor prefix
+                (None, Some(php)) => {
+                    let php = php.as_str();
+                    php.split_once('\\').map_or(php, |(_, rest)| rest).replace('\\', "/")
+                }
+                _ => return false,
+            };
+            ends_with_path(source_base, &path)
+        }),
+        "rs" => RUST_USE_RE.captures_iter(content).any(|cap| {
+            let segments: Vec<&str> = cap[1].split("::").skip(1).collect();
+            (1..=segments.len()).any(|n| {
+                let module = format!("src/{}", segments[..n].join("/"));
+                ends_with_path(source_base, &module) || ends_with_path(source_base, &format!("{module}/mod"))
+            })
+        }),
+        "c" | "cc" | "cpp" | "cxx" | "h" | "hpp" => INCLUDE_RE.captures_iter(content).any(|cap| {
+            let (include_base, _) = split_ext(&cap[1]);
+            source_base == split_ext(&join(test_dir, &cap[1])).0 || ends_with_path(source_base, include_base)
+        }),
+        _ => false,
+    }
+}
+
+/// Repo-relative path for a Python module, and whether it was a relative
+/// import (resolved against the test's directory) rather than absolute.
+fn python_module_path(test_dir: &str, module: &str) -> Option<(String, bool)> {
+    let dots = module.len() - module.trim_start_matches('.').len();
+    let rest = module[dots..].replace('.', "/");
+    if dots == 0 {
+        return (!rest.is_empty()).then_some((rest, false));
+    }
+    let mut dir = test_dir.to_string();
+    for _ in 1..dots {
+        dir = dir.rsplit_once('/').map_or(String::new(), |(parent, _)| parent.to_string());
+    }
+    Some((join(&dir, &rest), true))
+}
+
+/// `path` is `suffix`, or ends with `/suffix`.
+fn ends_with_path(path: &str, suffix: &str) -> bool {
+    !suffix.is_empty()
+        && path
+            .strip_suffix(suffix)
+            .is_some_and(|head| head.is_empty() || head.ends_with('/'))
+}
+
+/// Split off the extension of the last path component.
+fn split_ext(path: &str) -> (&str, &str) {
+    let name_start = path.rfind('/').map_or(0, |i| i + 1);
+    match path[name_start..].rfind('.') {
+        Some(dot) if dot > 0 => (&path[..name_start + dot], &path[name_start + dot + 1..]),
+        _ => (path, ""),
+    }
+}
+
+/// Join a relative specifier onto `dir`, resolving `.` and `..`.
+fn join(dir: &str, rel: &str) -> String {
+    let mut parts: Vec<&str> = dir.split('/').filter(|p| !p.is_empty()).collect();
+    for part in rel.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_imports_source_by_language() {
+        let ts = "import { login } from '../../src/auth';\nconst x = require('./helpers');\n";
+        assert!(imports_source("test/flows/auth_flow_spec.ts", ts, "src/auth.ts"));
+        assert!(imports_source("test/flows/auth_flow_spec.ts", ts, "test/flows/helpers/index.js"));
+        assert!(!imports_source("test/flows/auth_flow_spec.ts", ts, "src/session.ts"));
+        assert!(imports_source("src/a.test.ts", "import './b.js'", "src/b.ts"));
+
+        let py = "from myapp.billing import tax, invoice\nimport myapp.auth\nfrom . import conftest_helpers\n";
+        assert!(imports_source("tests/test_flow.py", py, "src/myapp/billing/tax.py"));
+        assert!(imports_source("tests/test_flow.py", py, "myapp/auth.py"));
+        assert!(imports_source("tests/test_flow.py", py, "myapp/billing/__init__.py"));
+        assert!(imports_source("tests/test_flow.py", py, "tests/conftest_helpers.py"));
+        assert!(!imports_source("tests/test_flow.py", py, "myapp/shipping.py"));
+
+        let rb = "require_relative '../app/models/cart'\nrequire 'checkout/payment'\n";
+        assert!(imports_source("spec/flow_spec.rb", rb, "app/models/cart.rb"));
+        assert!(imports_source("spec/flow_spec.rb", rb, "lib/checkout/payment.rb"));
+
+        let java = "package shop;\nimport com.shop.billing.Invoice;\nimport static org.junit.Assert.*;\n";
+        assert!(imports_source("src/test/java/shop/FlowTest.java", java, "src/main/java/com/shop/billing/Invoice.java"));
+
+        let php = "<?php\nuse App\\Billing\\Invoice;\n";
+        assert!(imports_source("tests/FlowTest.php", php, "src/Billing/Invoice.php"));
+
+        let rust = "use mycrate::billing::invoice::Invoice;\n";
+        assert!(imports_source("tests/flow.rs", rust, "src/billing/invoice.rs"));
+        assert!(imports_source("tests/flow.rs", rust, "src/billing/mod.rs"));
+        assert!(!imports_source("tests/flow.rs", rust, "src/auth.rs"));
+
+        let cpp = "#include \"billing/invoice.h\"\n#include <vector>\n";
+        assert!(imports_source("tests/flow_test.cc", cpp, "src/billing/invoice.cc"));
+    }
+
+    #[test]
+    fn test_tests_importing_scans_working_tree() {
+        let tmp = TempDir::new().unwrap();
+        fs::create_dir_all(tmp.path().join("src")).unwrap();
+        fs::create_dir_all(tmp.path().join("test/flows")).unwrap();
+        fs::create_dir_all(tmp.path().join("node_modules/x")).unwrap();
+        fs::write(tmp.path().join(".gitignore"), "node_modules/\n").unwrap();
+        fs::write(tmp.path().join("src/auth.ts"), "export function login() {}").unwrap();
+        fs::write(tmp.path().join("test/flows/auth_flow.spec.ts"), "import { login } from '../../src/auth';").unwrap();
+        fs::write(tmp.path().join("test/flows/other.spec.ts"), "import { x } from '../../src/other';").unwrap();
+        fs::write(tmp.path().join("node_modules/x/a.test.js"), "require('../../src/auth')").unwrap();
+        // Not a test file, even though it imports the source
+        fs::write(tmp.path().join("src/index.ts"), "export * from './auth';").unwrap();
+
+        // `ignore` only honours .gitignore inside a git repo
+        git2::Repository::init(tmp.path()).unwrap();
+
+        let deadline = Instant::now() + std::time::Duration::from_secs(10);
+        let (found, complete) = tests_importing(tmp.path(), "src/auth.ts", &TestPatterns::default(), deadline);
+        assert_eq!(found, vec!["test/flows/auth_flow.spec.ts"]);
+        assert!(complete);
+
+        // Out of time: nothing scanned, reported as incomplete
+        let (found, complete) = tests_importing(tmp.path(), "src/auth.ts", &TestPatterns::default(), Instant::now());
+        assert!(found.is_empty());
+        assert!(!complete);
+    }
+
+    #[test]
+    fn test_mark_static_links() {
+        let tmp = TempDir::new().unwrap();
+        fs::create_dir_all(tmp.path().join("src")).unwrap();
+        fs::write(tmp.path().join("src/auth.ts"), "import { s } from './session';").unwrap();
+        fs::write(tmp.path().join("src/session.ts"), "export const s = 1;").unwrap();
+        fs::write(tmp.path().join("src/login.ts"), "import { login } from './auth';").unwrap();
+        fs::write(tmp.path().join("src/styles.css"), "body {}").unwrap();
+
+        let file = |path: &str| CoupledFile { path: path.to_string(), ..Default::default() };
+        let mut coupled = vec![file("src/session.ts"), file("src/login.ts"), file("src/styles.css"), file("gone.ts")];
+        mark_static_links(tmp.path(), "src/auth.ts", None, &mut coupled);
+
+        let links: Vec<Option<StaticLink>> = coupled.iter().map(|f| f.static_link).collect();
+        assert_eq!(
+            links,
+            vec![Some(StaticLink::Imports), Some(StaticLink::ImportedBy), Some(StaticLink::None), None]
+        );
+    }
+}
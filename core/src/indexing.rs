@@ -1,22 +1,252 @@
 use git2::{Oid, Repository};
-use std::path::Path;
+use regex::Regex;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, mpsc};
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use crate::persistence::{Database, IndexingState};
+use crate::commit_graph::FirstParentWalk;
+use crate::config::{Config, CouplingMetric, RiskLevels};
+use crate::ignore_rules::{IgnoreRules, PathFilter};
+use crate::metrics;
+use crate::persistence::{CatchUp, Database, IndexTarget, IndexingState};
+use crate::risk;
 use crate::temporal::should_index_file;
+use crate::types::{CoupledFile, WarmupPlan};
 
 const DEFAULT_COMMIT_LIMIT: usize = 1000;
 const SCOPE_BUDGET_MS: u64 = 500;
 const FOREGROUND_BATCH_SIZE: usize = 100;
 const BACKGROUND_BATCH_SIZE: usize = 50;
 
+/// Time budget for one background indexing cycle, run after each analyze
+/// call that reports `needs_background`.
+pub const BACKGROUND_BUDGET: Duration = Duration::from_secs(5);
+
 /// Safety margin before starting a `diff_tree_to_tree`.
-/// `path_filtered_index` uses `simplify_first_parent()` so diffs are against
+/// `path_filtered_index` walks first parents only, so diffs are against
 /// first-parent only — typically 10-50ms on the Linux kernel. A 200ms margin
 /// covers even large first-parent diffs while ensuring subsequent calls
 /// (150ms budget < 200ms) never attempt diffs.
 const DIFF_SAFETY_MARGIN_MS: u128 = 200;
 
+/// Background queue limits: at most this many files wait for PathFiltered
+/// walks, each gets at most this many background cycles, and no cycle
+/// slice is shorter than `MIN_JOB_SLICE`.
+const MAX_QUEUE_LEN: usize = 32;
+const MAX_JOB_ATTEMPTS: u32 = 20;
+const MIN_JOB_SLICE: Duration = Duration::from_secs(2);
+
+/// Most files a commit may add (or delete) for its renames to be detected;
+/// git's default `diff.renameLimit`.
+const RENAME_LIMIT: usize = 1000;
+
+/// Upper bound on diff worker threads in a global index.
+const MAX_INDEX_WORKERS: usize = 8;
+
+/// A commit whose tree or diff fails to load this many times (odb
+/// corruption, objects missing from a partial clone) is quarantined and
+/// skipped by later walks.
+pub(crate) const MAX_COMMIT_FAILURES: u32 = 3;
+
+/// Indexing policy, derived from the repo config and `.engramignore`.
+#[derive(Debug, Clone, Default)]
+pub struct IndexOptions {
+    /// Diff merge commits against their first parent, attributing every
+    /// file changed on the merged branch to the merge (legacy behaviour;
+    /// inflates coupling). When false, merges are skipped in global walks
+    /// and attributed to the branch's own commits in path-filtered walks.
+    pub diff_merges: bool,
+    /// Merged branches with more commits than this are not attributed in
+    /// path-filtered walks (neither the merge nor its branch commits are
+    /// indexed); `None` for no cap.
+    pub max_merged_branch: Option<usize>,
+    /// Commits changing more indexed files than this record none of them:
+    /// they couple everything with everything. `None` for no cap.
+    pub max_commit_files: Option<usize>,
+    /// A complete index last updated longer ago than this is caught up in
+    /// the background; `None` trusts it until HEAD moves.
+    pub max_index_age: Option<Duration>,
+    /// Paths never recorded in the index or reported as coupled.
+    pub ignore: IgnoreRules,
+    /// Repo root to record indexing runs under in the usage metrics;
+    /// `None` records nothing (`no_metrics`).
+    pub metrics_repo_root: Option<String>,
+    /// Subtree (`packages/payments/`, always with a trailing slash) that
+    /// indexing and coupling are restricted to; `None` for the whole repo.
+    pub scope: Option<String>,
+    /// Revision (`origin/main`, a tag, an oid) whose history is indexed
+    /// instead of HEAD's.
+    pub rev: Option<String>,
+    /// Coupling counts a file changed by the same author within this long
+    /// of a change to the analyzed file; `None` for same-commit coupling.
+    /// Applied when querying, so changing it needs no reindex.
+    pub coupling_window: Option<Duration>,
+    /// Coupled files whose co-changes are at least this likely to be
+    /// chance (`risk::co_change_p_value`) are not reported.
+    pub max_p_value: Option<f64>,
+    /// Half-life of a co-change's weight in risk scores (`risk::Decay`);
+    /// `None` weighs every co-change alike.
+    pub half_life: Option<Duration>,
+    /// How co-changes are turned into coupling scores.
+    pub coupling_metric: CouplingMetric,
+    /// Coupling hops to follow: 2 adds files coupled with the top coupled
+    /// files; anything less reports direct coupling only.
+    pub depth: u32,
+    /// Coupled files reported, highest risk first; `None` reports all.
+    pub limit: Option<usize>,
+    /// Which coupled files are reported; applied before `limit`, so
+    /// filtered-out files do not take up its slots.
+    pub paths: PathFilter,
+    /// Coupled files with a lower coupling score are not reported; like
+    /// `paths`, applied before `limit`.
+    pub min_coupling: Option<f64>,
+    /// Coupled files with a lower risk score are not reported; like
+    /// `paths`, applied before `limit`.
+    pub min_risk: Option<f64>,
+    /// Only coupled files in another package than the analyzed file are
+    /// reported; like `paths`, applied before `limit`.
+    pub cross_package_only: bool,
+    /// Draw risk level thresholds from the repo's own scores once the
+    /// index is complete (`risk_levels = "percentile"`).
+    pub calibrate_risk: bool,
+}
+
+impl IndexOptions {
+    pub fn load(repo_root: &Path, config: &Config) -> Self {
+        Self {
+            diff_merges: config.diff_merges,
+            max_merged_branch: (config.max_merged_branch_commits > 0).then_some(config.max_merged_branch_commits),
+            max_commit_files: (config.max_commit_files > 0).then_some(config.max_commit_files),
+            max_index_age: (config.max_index_age_hours > 0)
+                .then(|| Duration::from_secs(config.max_index_age_hours * 3600)),
+            ignore: IgnoreRules::load(repo_root),
+            metrics_repo_root: (!config.no_metrics).then(|| repo_root.to_string_lossy().to_string()),
+            scope: config.scope.as_deref().and_then(normalize_scope),
+            rev: None,
+            coupling_window: config.coupling_window_hours.map(|hours| Duration::from_secs(hours * 3600)),
+            max_p_value: config.max_p_value,
+            half_life: config.coupling_half_life_days.map(|days| Duration::from_secs(days * 86400)),
+            coupling_metric: config.coupling_metric,
+            depth: 1,
+            limit: Some(risk::DEFAULT_LIMIT),
+            paths: PathFilter::default(),
+            min_coupling: None,
+            min_risk: None,
+            cross_package_only: false,
+            calibrate_risk: config.risk_levels == RiskLevels::Percentile,
+        }
+    }
+
+    /// Restrict to `scope` instead of the configured one.
+    pub fn with_scope(mut self, scope: &str) -> Self {
+        self.scope = normalize_scope(scope);
+        self
+    }
+
+    /// Index the history of `rev` instead of HEAD.
+    pub fn with_rev(mut self, rev: &str) -> Self {
+        self.rev = Some(rev.to_string());
+        self
+    }
+
+    /// Keep up the index that is there, whatever it was built for, rather
+    /// than rebuilding it for these options. What background and
+    /// hook-driven runs use.
+    pub fn with_target(mut self, target: IndexTarget) -> Self {
+        self.scope = target.scope;
+        self.rev = target.rev;
+        self
+    }
+
+    pub fn target(&self) -> IndexTarget {
+        IndexTarget { scope: self.scope.clone(), rev: self.rev.clone() }
+    }
+
+    /// The commit whose history is indexed: `rev`, or HEAD.
+    pub fn tip(&self, repo: &Repository) -> Result<Oid, git2::Error> {
+        match &self.rev {
+            Some(rev) => Ok(repo.revparse_single(rev)?.peel_to_commit()?.id()),
+            None => Ok(repo.head()?.peel_to_commit()?.id()),
+        }
+    }
+
+    /// True if `path` lies in the scope (always, without one).
+    pub fn in_scope(&self, path: &str) -> bool {
+        self.scope.as_ref().is_none_or(|scope| path.starts_with(scope.as_str()))
+    }
+
+    /// True if a scored coupled file clears `min_coupling` and `min_risk`.
+    pub fn meets_thresholds(&self, file: &CoupledFile) -> bool {
+        self.min_coupling.is_none_or(|min| file.coupling_score >= min)
+            && self.min_risk.is_none_or(|min| file.risk_score >= min)
+    }
+
+    /// True if `state` was last brought up to date longer ago than
+    /// `max_index_age`.
+    pub fn is_outdated(&self, state: &IndexingState) -> bool {
+        self.max_index_age
+            .is_some_and(|age| unix_now().saturating_sub(state.last_updated) > age.as_secs() as i64)
+    }
+
+    /// Record an indexing run in the usage metrics, unless disabled.
+    /// Failures are logged; they never fail the indexing.
+    fn record_run(&self, db: &Database, result: &SmartIndexResult, start: Instant, budget: Duration, background: bool) {
+        let Some(repo_root) = &self.metrics_repo_root else {
+            return;
+        };
+        if let Err(e) = metrics::record_indexing_event(db, result, start.elapsed(), budget, background, repo_root) {
+            eprintln!("Warning: Failed to record indexing metrics: {e}");
+        }
+    }
+
+    /// True if `path` should be recorded: an indexable file type that the
+    /// repo's `.engramignore` does not exclude.
+    pub fn indexes(&self, path: &str) -> bool {
+        should_index_file(path) && self.in_scope(path) && !self.ignore.is_ignored(path)
+    }
+}
+
+/// `./packages/payments` -> `packages/payments/`. A scope naming the repo
+/// root is no scope.
+pub fn normalize_scope(scope: &str) -> Option<String> {
+    let scope = scope.replace('\\', "/");
+    let scope = scope.trim_start_matches("./").trim_matches('/');
+    (!scope.is_empty() && scope != ".").then(|| format!("{scope}/"))
+}
+
+/// Make the index match the scope and revision of `opts`. An index built
+/// for another scope holds the wrong files, and one built for another
+/// revision commits that are not in its history, so it is cleared for a
+/// fresh walk (notes are kept). Returns true if it was cleared.
+pub fn ensure_target(db: &Database, opts: &IndexOptions) -> Result<bool, rusqlite::Error> {
+    let indexed = db.get_index_target()?;
+    let target = opts.target();
+    if indexed == target {
+        return Ok(false);
+    }
+    let cleared = db.get_indexing_state()?.is_some();
+    if cleared {
+        eprintln!(
+            "Warning: index was built for {}; rebuilding for {}",
+            describe_target(&indexed),
+            describe_target(&target),
+        );
+        db.clear_index()?;
+    }
+    db.set_index_target(&target)?;
+    Ok(cleared)
+}
+
+fn describe_target(target: &IndexTarget) -> String {
+    format!(
+        "{} at {}",
+        target.scope.as_deref().unwrap_or("the whole repo"),
+        target.rev.as_deref().unwrap_or("HEAD"),
+    )
+}
+
 /// The strategy chosen after the scoping phase.
 #[derive(Debug, Clone, PartialEq)]
 pub enum Strategy {
@@ -40,6 +270,7 @@ impl Strategy {
         }
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Self {
         match s {
             "complete" => Strategy::Complete,
@@ -57,6 +288,8 @@ pub struct SmartIndexResult {
     pub commits_indexed: u32,
     pub is_complete: bool,
     pub needs_background: bool,
+    /// Commits indexed per second during this call, if any walking happened.
+    pub commits_per_sec: Option<f64>,
 }
 
 /// Pure function: decide strategy based on scoping results.
@@ -106,111 +339,496 @@ pub fn file_changed_in_commit(
     commit_blob != parent_blob
 }
 
-/// Time-bounded global indexing. Processes commits from HEAD (or resume_oid),
-/// inserting changed files into the DB.
-///
-/// Returns (commits_indexed, last_oid_processed, hit_end_of_history).
-pub fn budgeted_global_index(
+/// Whether a parentless commit is the cut-off point of a shallow clone
+/// rather than a true root. Its parents exist but were not fetched, so
+/// diffing it against the empty tree would record every file in the repo
+/// as changed together. Only called for parentless commits, so reading
+/// `.git/shallow` each time is cheap.
+pub fn is_shallow_boundary(repo: &Repository, commit: &git2::Commit) -> bool {
+    if commit.parent_count() > 0 || !repo.is_shallow() {
+        return false;
+    }
+    let id = commit.id().to_string();
+    std::fs::read_to_string(repo.path().join("shallow")).is_ok_and(|shallow| shallow.lines().any(|l| l.trim() == id))
+}
+
+/// Diff a commit against `parent_tree` (None for a root commit) and record
+/// the indexable files it touched, along with the commit's author.
+fn index_commit(
     repo: &Repository,
     db: &Database,
-    budget: Duration,
-    commit_limit: usize,
-    resume_from: Option<&str>,
-    batch_size: usize,
-) -> Result<(u32, Option<String>, bool), Box<dyn std::error::Error>> {
-    let start = Instant::now();
-    let mut revwalk = repo.revwalk()?;
-    revwalk.set_sorting(git2::Sort::TIME)?;
+    opts: &IndexOptions,
+    commit: &git2::Commit,
+    parent_tree: Option<&git2::Tree>,
+    tree: &git2::Tree,
+) -> Result<CommitChanges, Box<dyn std::error::Error>> {
+    let changes = diff_commit(repo, opts, commit, parent_tree, tree)?;
+    record_commit(db, &changes)?;
+    Ok(changes)
+}
 
-    revwalk.push_head()?;
+/// What a commit contributes to the index: the indexable files it touched
+/// and its author. Computed without the database, so diffs can run on
+/// worker threads.
+struct CommitChanges {
+    hash: String,
+    timestamp: i64,
+    author_name: String,
+    author_email: String,
+    files: Vec<String>,
+    /// Paths in `files` that are not valid UTF-8, with their raw bytes
+    lossy: Vec<(String, Vec<u8>)>,
+    /// Files the commit renamed, as (old_path, new_path)
+    renames: Vec<(String, String)>,
+    /// Whether the message reads as a bug fix
+    is_fix: bool,
+    /// Conventional-commit type of the message
+    commit_type: Option<&'static str>,
+    /// Issue IDs the message refers to
+    tickets: Vec<String>,
+    /// Whether this is a root commit of the history
+    is_root: bool,
+}
 
-    if let Some(oid_str) = resume_from {
-        let resume_oid = Oid::from_str(oid_str)?;
-        // Skip commits until we pass the resume point
-        loop {
-            match revwalk.next() {
-                Some(Ok(oid)) if oid == resume_oid => break,
-                Some(Ok(_)) => continue,
-                _ => return Ok((0, None, true)),
+/// Words in a commit summary that mark a bug fix (`fix:`, `Fixed crash`,
+/// `hotfix`), or an issue closed anywhere in the message (`Closes #12`,
+/// `resolves PAY-431`).
+static FIX_MESSAGE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r"(?i)\A[^\n]*\b(?:fix(?:e[sd])?|bugs?|bugfix|hotfix)\b",
+        r"|\b(?:close[sd]?|resolve[sd]?)\s+(?:#\d+|[a-z][a-z0-9]+-\d+)\b",
+    ))
+    .unwrap()
+});
+
+/// Conventional-commit summary prefix: `feat:`, `fix(auth):`, `refactor!:`.
+static CONVENTIONAL_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\A([a-z]+)(?:\([^)\n]*\))?!?:\s").unwrap());
+
+/// Types from the Conventional Commits spec and its common presets.
+const CONVENTIONAL_TYPES: [&str; 11] =
+    ["feat", "fix", "refactor", "chore", "docs", "test", "perf", "build", "ci", "style", "revert"];
+
+/// The conventional-commit type of a message, if it has one.
+pub fn conventional_type(message: &str) -> Option<&'static str> {
+    let prefix = CONVENTIONAL_RE.captures(message)?.get(1)?.as_str().to_ascii_lowercase();
+    CONVENTIONAL_TYPES.into_iter().find(|t| *t == prefix)
+}
+
+/// Issue references: tracker keys (`PAY-431`) and GitHub-style `#567`.
+static TICKET_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b([A-Z][A-Z0-9]+-[0-9]+)\b|(?:^|[^\w&])(#[0-9]+)\b").unwrap());
+
+/// Prefixes shaped like tracker keys that name standards instead.
+const NOT_TICKET_PREFIXES: [&str; 10] = ["UTF", "SHA", "ISO", "RFC", "PEP", "ECMA", "HTTP", "TLS", "AES", "RSA"];
+
+/// The issue IDs a commit message refers to, each once.
+pub fn ticket_ids(message: &str) -> Vec<String> {
+    let mut tickets: Vec<String> = Vec::new();
+    for captures in TICKET_RE.captures_iter(message) {
+        let Some(ticket) = captures.get(1).or(captures.get(2)).map(|m| m.as_str()) else {
+            continue;
+        };
+        let prefix = ticket.split('-').next().unwrap_or_default();
+        if NOT_TICKET_PREFIXES.contains(&prefix) || tickets.iter().any(|t| t == ticket) {
+            continue;
+        }
+        tickets.push(ticket.to_string());
+    }
+    tickets
+}
+
+/// Whether a commit message describes a bug fix. Files that break together
+/// weigh more in risk scores than files refactored together.
+pub fn is_fix_message(message: &str) -> bool {
+    FIX_MESSAGE_RE.is_match(message)
+}
+
+fn diff_commit(
+    repo: &Repository,
+    opts: &IndexOptions,
+    commit: &git2::Commit,
+    parent_tree: Option<&git2::Tree>,
+    tree: &git2::Tree,
+) -> Result<CommitChanges, git2::Error> {
+    let mut diff_opts = git2::DiffOptions::new();
+    diff_opts.skip_binary_check(true);
+    if let Some(scope) = &opts.scope {
+        // Skips whole out-of-scope trees, so a release commit touching
+        // every package costs only the scoped part of its diff
+        diff_opts.pathspec(scope);
+    }
+
+    let mut diff = repo.diff_tree_to_tree(parent_tree, Some(tree), Some(&mut diff_opts))?;
+    let renames = find_renames(&mut diff, opts)?;
+
+    // Paths that are not valid UTF-8 are stored lossily (U+FFFD for bad
+    // bytes) and their raw bytes kept in `lossy_paths`, rather than dropped.
+    let mut files_in_commit: Vec<String> = Vec::new();
+    let mut lossy: Vec<(String, Vec<u8>)> = Vec::new();
+    diff.foreach(
+        &mut |delta, _| {
+            if let Some(raw) = delta.new_file().path_bytes() {
+                let path_str = String::from_utf8_lossy(raw);
+                if opts.indexes(&path_str) {
+                    if let std::borrow::Cow::Owned(_) = path_str {
+                        lossy.push((path_str.to_string(), raw.to_vec()));
+                    }
+                    files_in_commit.push(path_str.into_owned());
+                }
             }
+            true
+        },
+        None,
+        None,
+        None,
+    )?;
+    if opts.max_commit_files.is_some_and(|cap| files_in_commit.len() > cap) {
+        // Renames are still recorded, so history before them stays joined up
+        files_in_commit.clear();
+        lossy.clear();
+    }
+
+    let author = commit.author();
+    let message = String::from_utf8_lossy(commit.message_bytes());
+    Ok(CommitChanges {
+        hash: commit.id().to_string(),
+        timestamp: commit.time().seconds(),
+        author_name: author.name().unwrap_or("").to_string(),
+        author_email: author.email().unwrap_or("").to_string(),
+        files: files_in_commit,
+        lossy,
+        renames,
+        is_fix: is_fix_message(&message),
+        commit_type: conventional_type(&message),
+        tickets: ticket_ids(&message),
+        is_root: commit.parent_count() == 0 && !is_shallow_boundary(repo, commit),
+    })
+}
+
+/// Turn the delete+add pairs of `diff` into renames and return them as
+/// (old_path, new_path). The similarity pass is quadratic in the files
+/// added and deleted, so it is skipped for commits (mass moves, vendoring)
+/// with more than `RENAME_LIMIT` of either, which then stay delete+add.
+fn find_renames(diff: &mut git2::Diff, opts: &IndexOptions) -> Result<Vec<(String, String)>, git2::Error> {
+    let (mut added, mut deleted) = (0usize, 0usize);
+    for delta in diff.deltas() {
+        match delta.status() {
+            git2::Delta::Added => added += 1,
+            git2::Delta::Deleted => deleted += 1,
+            _ => {}
         }
     }
+    if added == 0 || deleted == 0 || added > RENAME_LIMIT || deleted > RENAME_LIMIT {
+        return Ok(Vec::new());
+    }
 
-    let mut indexed = 0u32;
-    let mut last_oid: Option<String> = None;
-    let mut hit_end = true;
-    let mut batch_count = 0usize;
+    let mut find_opts = git2::DiffFindOptions::new();
+    find_opts.renames(true).rename_limit(RENAME_LIMIT);
+    diff.find_similar(Some(&mut find_opts))?;
+
+    let path = |file: git2::DiffFile| file.path_bytes().map(|raw| String::from_utf8_lossy(raw).into_owned());
+    Ok(diff
+        .deltas()
+        .filter(|delta| delta.status() == git2::Delta::Renamed)
+        .filter_map(|delta| Some((path(delta.old_file())?, path(delta.new_file())?)))
+        .filter(|(old, new)| opts.indexes(old) && opts.indexes(new))
+        .collect())
+}
 
-    db.begin_transaction()?;
+/// Record a commit's files under their current names: walks run newest
+/// first, so renames seen so far (recorded by newer commits) apply to it.
+/// Its own renames are recorded for the older commits still to come, and
+/// applied to older history a previous walk already indexed.
+fn record_commit(db: &Database, changes: &CommitChanges) -> Result<(), rusqlite::Error> {
+    for (path, raw) in &changes.lossy {
+        db.insert_lossy_path(path, raw)?;
+    }
 
-    for oid_result in revwalk {
-        if start.elapsed() >= budget || indexed as usize >= commit_limit {
-            hit_end = false; // Stopped early (time or limit), not end of history
-            break;
+    if !changes.files.is_empty() {
+        let files = changes
+            .files
+            .iter()
+            .map(|path| db.current_path(path, changes.timestamp))
+            .collect::<Result<Vec<String>, _>>()?;
+        let file_refs: Vec<&str> = files.iter().map(|s| s.as_str()).collect();
+        db.insert_commit(&changes.hash, &file_refs, changes.timestamp)?;
+        db.insert_commit_author(&changes.hash, &changes.author_name, &changes.author_email, changes.timestamp)?;
+        if changes.is_fix {
+            db.mark_fix_commit(&changes.hash)?;
+        }
+        if let Some(commit_type) = changes.commit_type {
+            db.set_commit_type(&changes.hash, commit_type)?;
+        }
+        for ticket in &changes.tickets {
+            db.insert_commit_ticket(&changes.hash, ticket)?;
         }
+    }
+    if changes.is_root {
+        db.set_root_commit(&changes.hash)?;
+    }
+    for (old_path, new_path) in &changes.renames {
+        db.insert_rename(old_path, new_path, changes.timestamp)?;
+        db.rename_indexed_path(old_path, new_path, changes.timestamp)?;
+    }
 
-        let oid = oid_result?;
-        let hash = oid.to_string();
-        let commit = repo.find_commit(oid)?;
-        let timestamp = commit.time().seconds();
-        let tree = commit.tree()?;
+    Ok(())
+}
 
-        let parent_tree = if commit.parent_count() > 0 {
-            Some(commit.parent(0)?.tree()?)
-        } else {
-            None
+/// Commits skipped because they repeatedly failed to load.
+struct Quarantine {
+    skipped: HashSet<Oid>,
+}
+
+impl Quarantine {
+    fn load(db: &Database) -> Result<Self, rusqlite::Error> {
+        let skipped = db
+            .quarantined_commits(MAX_COMMIT_FAILURES)?
+            .iter()
+            .filter_map(|hash| Oid::from_str(hash).ok())
+            .collect();
+        Ok(Self { skipped })
+    }
+
+    /// Run `f`, the per-commit work for `oid`. Returns None (without calling
+    /// `f`) for a quarantined commit.
+    ///
+    /// A git error is counted against the commit. Below the threshold it
+    /// is returned so a transient failure is retried on the next walk; at
+    /// the threshold the commit is quarantined and None is returned so the
+    /// walk moves past it. Must run inside a transaction, which is committed
+    /// (keeping the failure count and the walk's progress) before an error
+    /// is returned, and reopened otherwise.
+    fn run<T>(
+        &mut self,
+        db: &Database,
+        oid: Oid,
+        f: impl FnOnce() -> Result<T, Box<dyn std::error::Error>>,
+    ) -> Result<Option<T>, Box<dyn std::error::Error>> {
+        if self.skipped.contains(&oid) {
+            return Ok(None);
+        }
+        let e = match f() {
+            Ok(value) => return Ok(Some(value)),
+            Err(e) => e,
+        };
+        let Some(git_err) = e.downcast_ref::<git2::Error>() else {
+            return Err(e);
         };
 
-        let mut diff_opts = git2::DiffOptions::new();
-        diff_opts.skip_binary_check(true);
+        let failures = db.record_commit_failure(&oid.to_string(), &git_err.to_string(), unix_now())?;
+        db.commit_transaction()?;
+        if failures < MAX_COMMIT_FAILURES {
+            return Err(e);
+        }
+        db.begin_transaction()?;
+        eprintln!("Warning: quarantined commit {oid} after {failures} failed attempts: {git_err}");
+        self.skipped.insert(oid);
+        Ok(None)
+    }
+}
 
-        let diff = repo.diff_tree_to_tree(
-            parent_tree.as_ref(),
-            Some(&tree),
-            Some(&mut diff_opts),
-        )?;
+/// Time-bounded global indexing. Processes commits from the tip (HEAD, or
+/// `opts.rev`) or resume_oid, inserting changed files into the DB.
+///
+/// Returns (commits_indexed, last_oid_processed, hit_end_of_history).
+pub fn budgeted_global_index(
+    repo: &Repository,
+    db: &Database,
+    opts: &IndexOptions,
+    budget: Duration,
+    commit_limit: usize,
+    resume_from: Option<&str>,
+    batch_size: usize,
+) -> Result<(u32, Option<String>, bool), Box<dyn std::error::Error>> {
+    let tip = opts.tip(repo)?;
+    budgeted_index_from(repo, db, opts, tip, None, budget, commit_limit, resume_from, batch_size)
+}
+
+/// How the walker thread of a global index ended.
+enum WalkEnd {
+    /// Every commit was handed out
+    History,
+    /// The writer stopped taking results
+    Stopped,
+    /// The resume point is not in the history (rewritten?)
+    ResumeNotFound,
+}
+
+/// `budgeted_global_index` over the history of `tip` instead of HEAD,
+/// leaving out the history of `stop_at` (already indexed) if given.
+///
+/// Commits are independent, so their diffs are computed by `index_workers`
+/// threads, each with its own repository handle, while a walker thread
+/// hands out commits and this thread writes the results. Results are
+/// written in walk order, so the resume point never skips a commit.
+#[allow(clippy::too_many_arguments)]
+pub fn budgeted_index_from(
+    repo: &Repository,
+    db: &Database,
+    opts: &IndexOptions,
+    tip: Oid,
+    stop_at: Option<Oid>,
+    budget: Duration,
+    commit_limit: usize,
+    resume_from: Option<&str>,
+    batch_size: usize,
+) -> Result<(u32, Option<String>, bool), Box<dyn std::error::Error>> {
+    let start = Instant::now();
+    let resume_oid = resume_from.map(Oid::from_str).transpose()?;
+    let git_dir = repo.path();
+    let workers = index_workers();
+
+    let mut quarantine = Quarantine::load(db)?;
+    db.begin_transaction()?;
 
-        let mut files_in_commit: Vec<String> = Vec::new();
-        diff.foreach(
-            &mut |delta, _| {
-                if let Some(path) = delta.new_file().path() {
-                    if let Some(path_str) = path.to_str() {
-                        if should_index_file(path_str) {
-                            files_in_commit.push(path_str.to_string());
-                        }
+    let stop = AtomicBool::new(false);
+    std::thread::scope(|s| {
+        let (job_tx, job_rx) = mpsc::sync_channel::<(usize, Oid)>(workers * 4);
+        let (result_tx, result_rx) = mpsc::sync_channel(workers * 4);
+        // Shared so the walker sees the channel close once every worker quits
+        let job_rx = Arc::new(Mutex::new(job_rx));
+
+        let walker = s.spawn(move || -> Result<WalkEnd, git2::Error> {
+            let repo = Repository::open(git_dir)?;
+            let mut revwalk = repo.revwalk()?;
+            revwalk.set_sorting(git2::Sort::TIME)?;
+            revwalk.push(tip)?;
+            if let Some(stop_at) = stop_at {
+                revwalk.hide(stop_at)?;
+            }
+            if let Some(resume_oid) = resume_oid {
+                // Skip commits until we pass the resume point
+                loop {
+                    match revwalk.next() {
+                        Some(Ok(oid)) if oid == resume_oid => break,
+                        Some(Ok(_)) => continue,
+                        _ => return Ok(WalkEnd::ResumeNotFound),
                     }
                 }
-                true
-            },
-            None,
-            None,
-            None,
-        )?;
+            }
+            for (seq, oid) in revwalk.enumerate() {
+                if job_tx.send((seq, oid?)).is_err() {
+                    return Ok(WalkEnd::Stopped);
+                }
+            }
+            Ok(WalkEnd::History)
+        });
 
-        if !files_in_commit.is_empty() {
-            let file_refs: Vec<&str> = files_in_commit.iter().map(|s| s.as_str()).collect();
-            db.insert_commit(&hash, &file_refs, timestamp)?;
+        for _ in 0..workers {
+            let (result_tx, job_rx, stop) = (result_tx.clone(), Arc::clone(&job_rx), &stop);
+            s.spawn(move || {
+                let repo = Repository::open(git_dir);
+                loop {
+                    let Ok((seq, oid)) = job_rx.lock().unwrap().recv() else { break };
+                    if stop.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    let changes = match &repo {
+                        Ok(repo) => diff_for_global_walk(repo, opts, oid),
+                        Err(e) => Err(git2::Error::from_str(&e.to_string())),
+                    };
+                    if result_tx.send((seq, oid, changes)).is_err() {
+                        break;
+                    }
+                }
+            });
         }
+        drop((result_tx, job_rx));
+
+        let mut indexed = 0u32;
+        let mut last_oid: Option<String> = None;
+        let mut hit_end = true;
+        let mut batch_count = 0usize;
+        let mut pending = BTreeMap::new();
+        let mut next_seq = 0usize;
+        let written = 'write: loop {
+            let Ok((seq, oid, changes)) = result_rx.recv() else {
+                break Ok(()); // Walk and workers done
+            };
+            pending.insert(seq, (oid, changes));
+            while let Some((oid, changes)) = pending.remove(&next_seq) {
+                next_seq += 1;
+                if start.elapsed() >= budget || indexed as usize >= commit_limit {
+                    hit_end = false; // Stopped early (time or limit), not end of history
+                    break 'write Ok(());
+                }
 
-        last_oid = Some(hash);
-        indexed += 1;
-        batch_count += 1;
+                let was_indexed = match quarantine.run(db, oid, || Ok(changes?)) {
+                    Ok(was_indexed) => was_indexed,
+                    Err(e) => break 'write Err(e),
+                };
+                last_oid = Some(oid.to_string());
+                let Some(Some(changes)) = was_indexed else {
+                    continue;
+                };
+                if let Err(e) = record_commit(db, &changes) {
+                    break 'write Err(e.into());
+                }
+                indexed += 1;
+                batch_count += 1;
+
+                // Commit in batches to yield the write lock
+                if batch_count >= batch_size {
+                    db.commit_transaction()?;
+                    db.begin_transaction()?;
+                    batch_count = 0;
+                }
+            }
+        };
 
-        // Commit in batches to yield the write lock
-        if batch_count >= batch_size {
+        // Release the walker and workers before the scope joins them
+        stop.store(true, Ordering::Relaxed);
+        drop(result_rx);
+        written?;
+        if let WalkEnd::ResumeNotFound = walker.join().expect("walker thread panicked")? {
             db.commit_transaction()?;
-            db.begin_transaction()?;
-            batch_count = 0;
+            return Ok((0, None, true));
         }
-    }
+        db.commit_transaction()?;
 
-    db.commit_transaction()?;
+        Ok((indexed, last_oid, hit_end))
+    })
+}
 
-    Ok((indexed, last_oid, hit_end))
+/// Worker threads for a global index: the cores left after the walker
+/// and writer, capped because the single writer becomes the bottleneck.
+fn index_workers() -> usize {
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    cores.saturating_sub(2).clamp(1, MAX_INDEX_WORKERS)
+}
+
+/// The changes a global walk records for `oid`, or `None` for a commit it
+/// skips.
+fn diff_for_global_walk(
+    repo: &Repository,
+    opts: &IndexOptions,
+    oid: Oid,
+) -> Result<Option<CommitChanges>, git2::Error> {
+    let commit = repo.find_commit(oid)?;
+
+    // The merged branch's own commits are part of this walk, so the
+    // merge itself adds nothing but a first-parent diff of the branch.
+    if commit.parent_count() > 1 && !opts.diff_merges {
+        return Ok(None);
+    }
+    if is_shallow_boundary(repo, &commit) {
+        return Ok(None);
+    }
+
+    let tree = commit.tree()?;
+    let parent_tree = if commit.parent_count() > 0 {
+        Some(commit.parent(0)?.tree()?)
+    } else {
+        None
+    };
+    diff_commit(repo, opts, &commit, parent_tree.as_ref(), &tree).map(Some)
 }
 
 /// Path-filtered indexing for huge repos. Scans commits cheaply using
 /// blob OID comparison, only doing full diffs when the target file changed.
+/// Where the target was renamed, the walk carries on under its previous
+/// name, so its history from before the rename is indexed too.
 ///
 /// When `resume_from` is Some, skips the revwalk to that OID and continues
 /// from where the previous run left off (delayed detection context is
@@ -218,20 +836,21 @@ pub fn budgeted_global_index(
 pub fn path_filtered_index(
     repo: &Repository,
     db: &Database,
+    opts: &IndexOptions,
     file_path: &str,
     budget: Duration,
     resume_from: Option<&str>,
     batch_size: usize,
 ) -> Result<(u32, Option<String>, bool), Box<dyn std::error::Error>> {
     let start = Instant::now();
-    let target = Path::new(file_path);
+    // The target's name in the commit being walked; older than a rename of
+    // the file, the walk follows its previous name
+    let mut target = PathBuf::from(file_path);
 
-    let mut revwalk = repo.revwalk()?;
-    revwalk.push_head()?;
-    revwalk.set_sorting(git2::Sort::TIME)?;
     // Follow only first-parent links — drastically reduces commit count
-    // on merge-heavy repos (Linux kernel: 1.2M → ~100K commits)
-    revwalk.simplify_first_parent()?;
+    // on merge-heavy repos (Linux kernel: 1.2M → ~100K commits). Reads
+    // parents and trees from the commit-graph when the repo has one.
+    let mut revwalk = FirstParentWalk::new(repo, opts.tip(repo)?);
 
     let mut indexed = 0u32;
     let mut last_oid: Option<String> = None;
@@ -248,22 +867,26 @@ pub fn path_filtered_index(
     let mut prev_entry: Option<(Oid, Option<Oid>)> = None; // (commit_oid, blob_oid)
 
     // Resume: skip to the resume point and reconstruct delayed detection context
-    if let Some(oid_str) = resume_from {
-        let resume_oid = Oid::from_str(oid_str)?;
+    let resume_oid = resume_from.map(Oid::from_str).transpose()?;
+    if let Some(resume_oid) = resume_oid {
         let mut skip_count = 0u32;
         let mut found = false;
         loop {
             skip_count += 1;
-            if skip_count % 1000 == 0 && start.elapsed() >= budget {
+            if skip_count.is_multiple_of(1000) && start.elapsed() >= budget {
                 // Budget exhausted during skip — return no progress
                 return Ok((0, None, false));
             }
             match revwalk.next() {
-                Some(Ok(oid)) if oid == resume_oid => {
-                    // Reconstruct prev_entry from the resume commit's blob
-                    let commit = repo.find_commit(oid)?;
-                    let tree = commit.tree()?;
-                    let blob = tree.get_path(target).ok().map(|e| e.id());
+                Some(Ok((oid, tree_oid))) if oid == resume_oid => {
+                    // Reconstruct prev_entry from the resume commit's blob,
+                    // under the name the file had then
+                    let tree = repo.find_tree(tree_oid)?;
+                    let renamed = PathBuf::from(db.path_at(file_path, repo.find_commit(oid)?.time().seconds())?);
+                    if tree.get_path(&renamed).is_ok() {
+                        target = renamed;
+                    }
+                    let blob = tree.get_path(&target).ok().map(|e| e.id());
                     prev_entry = Some((oid, blob));
                     last_oid = Some(oid.to_string());
                     found = true;
@@ -279,6 +902,7 @@ pub fn path_filtered_index(
         }
     }
 
+    let mut quarantine = Quarantine::load(db)?;
     db.begin_transaction()?;
 
     for oid_result in revwalk {
@@ -287,69 +911,77 @@ pub fn path_filtered_index(
             break;
         }
 
-        let oid = oid_result?;
-        let commit = repo.find_commit(oid)?;
-        let tree = commit.tree()?;
-        let blob = tree.get_path(target).ok().map(|e| e.id());
+        let (oid, tree_oid) = oid_result?;
+        let Some(tree) = quarantine.run(db, oid, || Ok(repo.find_tree(tree_oid)?))? else {
+            // Without this commit's tree the newer commit's change can't be
+            // told apart from this one's, so drop it rather than misattribute.
+            last_oid = Some(oid.to_string());
+            prev_entry = None;
+            continue;
+        };
+        let mut blob = tree.get_path(&target).ok().map(|e| e.id());
 
         // Check if the PREVIOUS (newer) commit changed the file
-        if let Some((prev_oid, prev_blob)) = prev_entry.take() {
-            if prev_blob != blob {
-                // Safety margin: don't start an expensive diff if we can't
-                // afford it. A kernel merge diff can take 500ms+.
-                let elapsed = start.elapsed();
-                let remaining_ms = budget.as_millis().saturating_sub(elapsed.as_millis());
-                if elapsed >= budget || remaining_ms < DIFF_SAFETY_MARGIN_MS {
-                    hit_end = false;
-                    break;
-                }
+        if let Some((prev_oid, prev_blob)) = prev_entry.take()
+            && prev_blob != blob
+        {
+            // Safety margin: don't start an expensive diff if we can't
+            // afford it. A kernel merge diff can take 500ms+.
+            let elapsed = start.elapsed();
+            let remaining_ms = budget.as_millis().saturating_sub(elapsed.as_millis());
+            if elapsed >= budget || remaining_ms < DIFF_SAFETY_MARGIN_MS {
+                hit_end = false;
+                break;
+            }
 
+            let child_commit = repo.find_commit(prev_oid)?;
+            if child_commit.parent_count() > 1 && !opts.diff_merges {
+                // The file arrived via a merge: credit the branch commits
+                // that actually changed it instead of the whole merge diff.
+                let deadline = budget.saturating_sub(Duration::from_millis(DIFF_SAFETY_MARGIN_MS as u64));
+                let branch = index_merged_branch(
+                    repo, db, opts, &mut quarantine, &child_commit, &target, start, deadline,
+                )?;
+                let branch_indexed = match branch {
+                    MergedBranch::Indexed(n) => n,
+                    MergedBranch::TooLarge => 0,
+                    // Leave the merge for the next run, which resumes at it
+                    // and starts the branch over; unless this run already
+                    // did, so a branch no budget covers cannot stall the walk
+                    MergedBranch::OutOfBudget(_) if resume_oid != Some(prev_oid) => {
+                        db.commit_transaction()?;
+                        return Ok((indexed, last_oid, false));
+                    }
+                    MergedBranch::OutOfBudget(n) => n,
+                };
+                indexed += branch_indexed;
+                batch_count += branch_indexed as usize;
+            } else {
                 // prev commit changed the file — do full diff
                 // current `tree` is the parent tree (since this commit IS the parent)
-                let child_commit = repo.find_commit(prev_oid)?;
-                let child_tree = child_commit.tree()?;
-
-                let mut diff_opts = git2::DiffOptions::new();
-                diff_opts.skip_binary_check(true);
-
-                let diff = repo.diff_tree_to_tree(
-                    Some(&tree),
-                    Some(&child_tree),
-                    Some(&mut diff_opts),
-                )?;
-
-                let hash = prev_oid.to_string();
-                let timestamp = child_commit.time().seconds();
-                let mut files_in_commit: Vec<String> = Vec::new();
-                diff.foreach(
-                    &mut |delta, _| {
-                        if let Some(path) = delta.new_file().path() {
-                            if let Some(path_str) = path.to_str() {
-                                if should_index_file(path_str) {
-                                    files_in_commit.push(path_str.to_string());
-                                }
-                            }
-                        }
-                        true
-                    },
-                    None,
-                    None,
-                    None,
-                )?;
-
-                if !files_in_commit.is_empty() {
-                    let file_refs: Vec<&str> = files_in_commit.iter().map(|s| s.as_str()).collect();
-                    db.insert_commit(&hash, &file_refs, timestamp)?;
+                let diffed = quarantine.run(db, prev_oid, || {
+                    let child_tree = child_commit.tree()?;
+                    index_commit(repo, db, opts, &child_commit, Some(&tree), &child_tree)
+                })?;
+                if let Some(changes) = diffed {
+                    indexed += 1;
+                    batch_count += 1;
+
+                    // The file appeared here under this name: if it was
+                    // renamed, follow its history under the old one
+                    if blob.is_none()
+                        && let Some((old, _)) = changes.renames.iter().find(|(_, new)| Path::new(new) == target)
+                    {
+                        target = PathBuf::from(old);
+                        blob = tree.get_path(&target).ok().map(|e| e.id());
+                    }
                 }
+            }
 
-                indexed += 1;
-                batch_count += 1;
-
-                if batch_count >= batch_size {
-                    db.commit_transaction()?;
-                    db.begin_transaction()?;
-                    batch_count = 0;
-                }
+            if batch_count >= batch_size {
+                db.commit_transaction()?;
+                db.begin_transaction()?;
+                batch_count = 0;
             }
         }
 
@@ -358,51 +990,18 @@ pub fn path_filtered_index(
     }
 
     // Handle root commit: if it has the file, it's the initial add
-    if let Some((prev_oid, prev_blob)) = prev_entry {
-        if prev_blob.is_some() && hit_end {
-            let commit = repo.find_commit(prev_oid)?;
-            if commit.parent_count() == 0 {
-                // Safety margin for root diff too
-                let remaining_ms = budget.as_millis().saturating_sub(start.elapsed().as_millis());
-                if remaining_ms >= DIFF_SAFETY_MARGIN_MS {
-                    let tree = commit.tree()?;
-                    let hash = prev_oid.to_string();
-                    let timestamp = commit.time().seconds();
-
-                    let mut diff_opts = git2::DiffOptions::new();
-                    diff_opts.skip_binary_check(true);
-
-                    let diff = repo.diff_tree_to_tree(
-                        None,
-                        Some(&tree),
-                        Some(&mut diff_opts),
-                    )?;
-
-                    let mut files_in_commit: Vec<String> = Vec::new();
-                    diff.foreach(
-                        &mut |delta, _| {
-                            if let Some(path) = delta.new_file().path() {
-                                if let Some(path_str) = path.to_str() {
-                                    if should_index_file(path_str) {
-                                        files_in_commit.push(path_str.to_string());
-                                    }
-                                }
-                            }
-                            true
-                        },
-                        None,
-                        None,
-                        None,
-                    )?;
-
-                    if !files_in_commit.is_empty() {
-                        let file_refs: Vec<&str> =
-                            files_in_commit.iter().map(|s| s.as_str()).collect();
-                        db.insert_commit(&hash, &file_refs, timestamp)?;
-                    }
-
-                    indexed += 1;
-                }
+    if let Some((prev_oid, prev_blob)) = prev_entry
+        && prev_blob.is_some()
+        && hit_end
+    {
+        let commit = repo.find_commit(prev_oid)?;
+        if commit.parent_count() == 0 && !is_shallow_boundary(repo, &commit) {
+            // Safety margin for root diff too
+            let remaining_ms = budget.as_millis().saturating_sub(start.elapsed().as_millis());
+            if remaining_ms >= DIFF_SAFETY_MARGIN_MS {
+                let tree = commit.tree()?;
+                index_commit(repo, db, opts, &commit, None, &tree)?;
+                indexed += 1;
             }
         }
     }
@@ -412,65 +1011,271 @@ pub fn path_filtered_index(
     Ok((indexed, last_oid, hit_end))
 }
 
-fn unix_now() -> i64 {
+/// How attributing a merge to its branch commits ended.
+enum MergedBranch {
+    /// Every branch commit that changed the target was indexed (this many)
+    Indexed(u32),
+    /// The branch has more than `max_merged_branch` commits; the merge is
+    /// left unattributed
+    TooLarge,
+    /// The budget ran out partway, after indexing this many
+    OutOfBudget(u32),
+}
+
+/// Index the commits a merge brought in (reachable from its non-first
+/// parents but not from its first parent) that changed `target`, unless
+/// there are more than `opts.max_merged_branch` of them. Stops once
+/// `budget` (measured from `start`) is used up.
+#[allow(clippy::too_many_arguments)]
+fn index_merged_branch(
+    repo: &Repository,
+    db: &Database,
+    opts: &IndexOptions,
+    quarantine: &mut Quarantine,
+    merge: &git2::Commit,
+    target: &Path,
+    start: Instant,
+    budget: Duration,
+) -> Result<MergedBranch, Box<dyn std::error::Error>> {
+    let mut revwalk = repo.revwalk()?;
+    for parent in merge.parent_ids().skip(1) {
+        revwalk.push(parent)?;
+    }
+    revwalk.hide(merge.parent_id(0)?)?;
+
+    let cap = opts.max_merged_branch.unwrap_or(usize::MAX);
+    let branch = revwalk.take(cap.saturating_add(1)).collect::<Result<Vec<Oid>, _>>()?;
+    if branch.len() > cap {
+        return Ok(MergedBranch::TooLarge);
+    }
+
+    let mut indexed = 0u32;
+    for oid in branch {
+        if start.elapsed() >= budget {
+            return Ok(MergedBranch::OutOfBudget(indexed));
+        }
+        let was_indexed = quarantine.run(db, oid, || {
+            let commit = repo.find_commit(oid)?;
+            if commit.parent_count() > 1
+                || is_shallow_boundary(repo, &commit)
+                || !file_changed_in_commit(&commit, target)
+            {
+                return Ok(false);
+            }
+            let parent_tree = if commit.parent_count() > 0 {
+                Some(commit.parent(0)?.tree()?)
+            } else {
+                None
+            };
+            index_commit(repo, db, opts, &commit, parent_tree.as_ref(), &commit.tree()?)?;
+            Ok(true)
+        })?;
+        if was_indexed == Some(true) {
+            indexed += 1;
+        }
+    }
+
+    Ok(MergedBranch::Indexed(indexed))
+}
+
+pub(crate) fn unix_now() -> i64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap_or_default()
         .as_secs() as i64
 }
 
+/// Add (or re-prioritize) a file in the background queue, keeping the queue bounded.
+fn queue_path_filtered(
+    db: &Database,
+    file_path: &str,
+    resume_oid: Option<&str>,
+) -> Result<(), rusqlite::Error> {
+    db.enqueue_background(file_path, resume_oid, unix_now())?;
+    db.trim_background_queue(MAX_QUEUE_LEN)
+}
+
+/// Drain the background queue in priority order. The budget is split evenly
+/// across the jobs picked for this cycle (each gets at least `MIN_JOB_SLICE`,
+/// so a deep resume skip can still make progress). Finished jobs, and jobs
+/// that exhausted `MAX_JOB_ATTEMPTS`, leave the queue.
+///
+/// Returns the number of commits indexed across all jobs.
+fn drain_background_queue(
+    repo: &Repository,
+    db: &Database,
+    opts: &IndexOptions,
+    budget: Duration,
+) -> Result<u32, Box<dyn std::error::Error>> {
+    let start = Instant::now();
+    let max_jobs = (budget.as_millis() / MIN_JOB_SLICE.as_millis()).max(1) as usize;
+    let jobs = db.next_background_jobs(max_jobs)?;
+
+    let mut indexed = 0u32;
+    for (i, job) in jobs.iter().enumerate() {
+        let remaining = budget.saturating_sub(start.elapsed());
+        if remaining.is_zero() {
+            break;
+        }
+        let slice = remaining / (jobs.len() - i) as u32;
+
+        let (job_indexed, last_oid, hit_end) = path_filtered_index(
+            repo,
+            db,
+            opts,
+            &job.target_path,
+            slice,
+            job.resume_oid.as_deref(),
+            BACKGROUND_BATCH_SIZE,
+        )?;
+        indexed += job_indexed;
+
+        if hit_end || job.attempts + 1 >= MAX_JOB_ATTEMPTS {
+            db.remove_background_job(&job.target_path)?;
+        } else {
+            db.update_background_job(&job.target_path, last_oid.as_deref())?;
+        }
+    }
+
+    Ok(indexed)
+}
+
+fn throughput(indexed: u32, start: Instant) -> Option<f64> {
+    let secs = start.elapsed().as_secs_f64();
+    if indexed == 0 || secs <= 0.0 {
+        None
+    } else {
+        Some(indexed as f64 / secs)
+    }
+}
+
+/// Estimate the background work left after a foreground index call, so
+/// adapters can schedule a re-query instead of polling. `remaining` is the
+/// commits left to walk, from `commits_remaining`.
+///
+/// Each analyze call that needs background work runs one background cycle
+/// of `BACKGROUND_BUDGET`, so a cycle is also one re-query. Without a
+/// commit estimate, at least one more cycle is assumed.
+pub fn warmup_plan(result: &SmartIndexResult, remaining: Option<u32>) -> WarmupPlan {
+    let suggested_requery_ms = BACKGROUND_BUDGET.as_millis() as u64;
+
+    let estimated_cycles_remaining = match (remaining, result.commits_per_sec) {
+        (Some(remaining), Some(rate)) => {
+            let per_cycle = (rate * BACKGROUND_BUDGET.as_secs_f64()).max(1.0);
+            ((remaining as f64 / per_cycle).ceil() as u32).max(1)
+        }
+        _ => 1,
+    };
+
+    WarmupPlan {
+        estimated_cycles_remaining,
+        estimated_commits_remaining: remaining,
+        suggested_requery_ms,
+    }
+}
+
+/// Commits the walk of an incomplete global index still has ahead: those
+/// after its resume point, up to what is left of `DEFAULT_COMMIT_LIMIT`.
+/// Walks commits without diffing them. `None` for PathFiltered walks,
+/// whose end is not known in advance, or if the history cannot be walked.
+pub fn commits_remaining(repo: &Repository, state: &IndexingState) -> Option<u32> {
+    if state.is_complete {
+        return Some(0);
+    }
+    if Strategy::from_str(&state.strategy) == Strategy::PathFiltered {
+        return None;
+    }
+    let mut revwalk = repo.revwalk().ok()?;
+    revwalk.set_sorting(git2::Sort::TIME).ok()?;
+    revwalk.push(Oid::from_str(&state.head_commit).ok()?).ok()?;
+    let mut commits = revwalk.filter_map(Result::ok);
+    if let Some(resume) = state.resume_oid.as_deref() {
+        let resume = Oid::from_str(resume).ok()?;
+        commits.find(|oid| *oid == resume)?;
+    }
+    let left = DEFAULT_COMMIT_LIMIT.saturating_sub(state.commits_indexed as usize);
+    Some(commits.take(left).count() as u32)
+}
+
 /// Orchestrator: scopes the repo, decides strategy, executes, saves state.
+/// Each call is recorded as an `indexing` metrics event.
 pub fn smart_index(
     repo: &Repository,
     db: &Database,
+    opts: &IndexOptions,
     file_path: &str,
     foreground_budget: Duration,
 ) -> Result<SmartIndexResult, Box<dyn std::error::Error>> {
+    let start = Instant::now();
+    let result = smart_index_run(repo, db, opts, file_path, foreground_budget, start)?;
+    opts.record_run(db, &result, start, foreground_budget, false);
+    Ok(result)
+}
+
+fn smart_index_run(
+    repo: &Repository,
+    db: &Database,
+    opts: &IndexOptions,
+    file_path: &str,
+    foreground_budget: Duration,
+    start: Instant,
+) -> Result<SmartIndexResult, Box<dyn std::error::Error>> {
+    ensure_target(db, opts)?;
     let existing_state = db.get_indexing_state()?;
 
     // Subsequent call: short budget, check if HEAD moved
     if let Some(ref state) = existing_state {
-        let head = repo.head()?.peel_to_commit()?.id().to_string();
-
-        if state.head_commit == head && state.is_complete {
-            // Already fully indexed at this HEAD
+        let head = opts.tip(repo)?.to_string();
+        let prev_strategy = Strategy::from_str(&state.strategy);
+
+        // PathFiltered with different file: the resume_oid and progress
+        // are from a different file's walk. Start fresh for the new file
+        // with a short budget — the safety margin guarantees no expensive
+        // diffs happen, so this returns quickly. Background will build
+        // coupling data for the new file.
+        //
+        // The temporal_index data from the old file's walk is retained
+        // (it's valid coupling data, just for a different file), and the
+        // old file keeps its own resume point in the background queue.
+        //
+        // A completed PathFiltered state only covers its own target, so a
+        // different file is never "already fully indexed".
+        let file_changed = prev_strategy == Strategy::PathFiltered
+            && state
+                .target_path
+                .as_ref()
+                .is_some_and(|p| p != file_path);
+
+        if state.head_commit == head && state.is_complete && !file_changed {
+            // Already fully indexed at this HEAD. An old index is still
+            // answered from, but background re-checks its revision.
             return Ok(SmartIndexResult {
-                strategy: Strategy::from_str(&state.strategy),
+                strategy: prev_strategy,
                 commits_indexed: state.commits_indexed,
                 is_complete: true,
-                needs_background: false,
+                needs_background: opts.is_outdated(state),
+                commits_per_sec: None,
             });
         }
 
-        if state.head_commit == head && !state.is_complete {
-            let prev_strategy = Strategy::from_str(&state.strategy);
-
-            // PathFiltered with different file: the resume_oid and progress
-            // are from a different file's walk. Start fresh for the new file
-            // with a short budget — the safety margin guarantees no expensive
-            // diffs happen, so this returns quickly. Background will build
-            // coupling data for the new file.
-            //
-            // The temporal_index data from the old file's walk is retained
-            // (it's valid coupling data, just for a different file).
-            let file_changed = prev_strategy == Strategy::PathFiltered
-                && state
-                    .target_path
-                    .as_ref()
-                    .is_some_and(|p| p != file_path);
-
+        if state.head_commit == head {
             if file_changed {
                 // Full foreground budget — this is effectively a first call
                 // for the new file, so it deserves the same time as any cold start.
                 let (indexed, last_oid, hit_end) = path_filtered_index(
                     repo,
                     db,
+                    opts,
                     file_path,
                     foreground_budget,
                     None, // Fresh walk from HEAD for the new file
                     FOREGROUND_BATCH_SIZE,
                 )?;
 
+                if !hit_end {
+                    queue_path_filtered(db, file_path, last_oid.as_deref())?;
+                }
+
                 db.set_indexing_state(&IndexingState {
                     head_commit: head,
                     resume_oid: if hit_end { None } else { last_oid },
@@ -486,6 +1291,7 @@ pub fn smart_index(
                     commits_indexed: indexed,
                     is_complete: hit_end,
                     needs_background: !hit_end,
+                    commits_per_sec: throughput(indexed, start),
                 });
             }
 
@@ -497,15 +1303,37 @@ pub fn smart_index(
             // foreground work here is counterproductive. The DB already
             // has coupling data from the first call + previous backgrounds.
             if prev_strategy == Strategy::PathFiltered {
+                // Re-requesting the file raises its background priority.
+                queue_path_filtered(db, file_path, state.resume_oid.as_deref())?;
                 return Ok(SmartIndexResult {
                     strategy: prev_strategy,
                     commits_indexed: state.commits_indexed,
                     is_complete: false,
                     needs_background: true,
+                    commits_per_sec: None,
                 });
             }
 
-            // For global strategies: try to resume with a short budget.
+            // For global strategies: try to resume with a short budget,
+            // the commits HEAD moved on by first.
+            if let Some(catch_up) = db.get_catch_up()? {
+                let (indexed, is_complete) = run_catch_up(
+                    repo,
+                    db,
+                    opts,
+                    state,
+                    catch_up,
+                    Duration::from_millis(150),
+                    FOREGROUND_BATCH_SIZE,
+                )?;
+                return Ok(SmartIndexResult {
+                    strategy: prev_strategy,
+                    commits_indexed: state.commits_indexed + indexed,
+                    is_complete,
+                    needs_background: !is_complete,
+                    commits_per_sec: throughput(indexed, start),
+                });
+            }
             let is_stale = (unix_now() - state.last_updated) > 10;
 
             if is_stale || state.resume_oid.is_some() {
@@ -515,6 +1343,7 @@ pub fn smart_index(
                 let (indexed, last_oid, hit_end) = budgeted_global_index(
                     repo,
                     db,
+                    opts,
                     remaining_budget,
                     DEFAULT_COMMIT_LIMIT.saturating_sub(state.commits_indexed as usize),
                     resume,
@@ -543,6 +1372,7 @@ pub fn smart_index(
                     commits_indexed: total,
                     is_complete,
                     needs_background: !is_complete,
+                    commits_per_sec: throughput(indexed, start),
                 });
             }
 
@@ -552,14 +1382,33 @@ pub fn smart_index(
                 commits_indexed: state.commits_indexed,
                 is_complete: false,
                 needs_background: false,
+                commits_per_sec: None,
+            });
+        }
+
+        // HEAD moved forward (a pull, a new commit): index just the new
+        // commits, stopping where the previous walk began
+        if prev_strategy != Strategy::PathFiltered
+            && let Some(catch_up) = catch_up_from(repo, db, state, opts.tip(repo)?)?
+        {
+            let state = IndexingState { head_commit: head, ..state.clone() };
+            let (indexed, is_complete) =
+                run_catch_up(repo, db, opts, &state, catch_up, foreground_budget, FOREGROUND_BATCH_SIZE)?;
+            return Ok(SmartIndexResult {
+                strategy: prev_strategy,
+                commits_indexed: state.commits_indexed + indexed,
+                is_complete,
+                needs_background: !is_complete,
+                commits_per_sec: throughput(indexed, start),
             });
         }
 
-        // HEAD moved — start fresh indexing
+        // HEAD moved elsewhere — start fresh indexing
+        db.clear_catch_up()?;
     }
 
     // First call (or HEAD moved)
-    let head = repo.head()?.peel_to_commit()?.id().to_string();
+    let head = opts.tip(repo)?.to_string();
 
     // Circuit breaker: check repo size before scoping.
     // If repo has >20K tracked files, a single diff_tree_to_tree on a merge
@@ -581,6 +1430,7 @@ pub fn smart_index(
         let (indexed, last_oid, hit_end) = budgeted_global_index(
             repo,
             db,
+            opts,
             scope_budget,
             DEFAULT_COMMIT_LIMIT,
             None,
@@ -606,6 +1456,7 @@ pub fn smart_index(
             commits_indexed: scope_indexed,
             is_complete: true,
             needs_background: false,
+            commits_per_sec: throughput(scope_indexed, start),
         });
     }
 
@@ -619,12 +1470,12 @@ pub fn smart_index(
 
     let (exec_indexed, exec_last_oid, exec_hit_end) = match strategy {
         Strategy::PathFiltered => {
-            path_filtered_index(repo, db, file_path, remaining, None, FOREGROUND_BATCH_SIZE)?
+            path_filtered_index(repo, db, opts, file_path, remaining, None, FOREGROUND_BATCH_SIZE)?
         }
         Strategy::ContinueGlobal | Strategy::BudgetedGlobal => {
             let resume = scope_last_oid.as_deref();
             let remaining_limit = DEFAULT_COMMIT_LIMIT.saturating_sub(scope_indexed as usize);
-            budgeted_global_index(repo, db, remaining, remaining_limit, resume, FOREGROUND_BATCH_SIZE)?
+            budgeted_global_index(repo, db, opts, remaining, remaining_limit, resume, FOREGROUND_BATCH_SIZE)?
         }
         Strategy::Complete => unreachable!(),
     };
@@ -634,80 +1485,269 @@ pub fn smart_index(
     let final_resume = if is_complete { None } else { exec_last_oid.or(scope_last_oid) };
 
     let target_path = if strategy == Strategy::PathFiltered {
+        if !is_complete {
+            queue_path_filtered(db, file_path, final_resume.as_deref())?;
+        }
         Some(file_path.to_string())
     } else {
         None
     };
 
     db.set_indexing_state(&IndexingState {
-        head_commit: head,
-        resume_oid: final_resume,
-        commits_indexed: total_indexed,
-        strategy: strategy.as_str().to_string(),
-        is_complete,
+        head_commit: head,
+        resume_oid: final_resume,
+        commits_indexed: total_indexed,
+        strategy: strategy.as_str().to_string(),
+        is_complete,
+        last_updated: unix_now(),
+        target_path,
+    })?;
+
+    Ok(SmartIndexResult {
+        strategy,
+        commits_indexed: total_indexed,
+        is_complete,
+        needs_background: !is_complete,
+        commits_per_sec: throughput(total_indexed, start),
+    })
+}
+
+/// The catch-up to run now that HEAD moved to `head`: the commits since
+/// the indexed head, or since the base of a catch-up still in progress.
+/// `None` unless `head` descends from it; after a rebase or a checkout of
+/// another branch the index starts over.
+fn catch_up_from(
+    repo: &Repository,
+    db: &Database,
+    state: &IndexingState,
+    head: Oid,
+) -> Result<Option<CatchUp>, Box<dyn std::error::Error>> {
+    let (base_commit, history_complete) = match db.get_catch_up()? {
+        Some(pending) => (pending.base_commit, pending.history_complete),
+        None => (state.head_commit.clone(), state.is_complete),
+    };
+    let Ok(base) = Oid::from_str(&base_commit) else {
+        return Ok(None);
+    };
+    if !repo.graph_descendant_of(head, base).unwrap_or(false) {
+        return Ok(None);
+    }
+    // A pending walk's resume point is in the history of the old head, and
+    // walking from the new one would skip the commits newer than it
+    Ok(Some(CatchUp { base_commit, resume_oid: None, history_complete }))
+}
+
+/// Index the commits of `catch_up`, from `state.head_commit` down to its
+/// base, for up to `budget`, and save the progress. Once the base is
+/// reached the index is as complete as it was before HEAD moved.
+///
+/// Returns (commits_indexed, is_complete).
+fn run_catch_up(
+    repo: &Repository,
+    db: &Database,
+    opts: &IndexOptions,
+    state: &IndexingState,
+    catch_up: CatchUp,
+    budget: Duration,
+    batch_size: usize,
+) -> Result<(u32, bool), Box<dyn std::error::Error>> {
+    let tip = Oid::from_str(&state.head_commit)?;
+    let base = Oid::from_str(&catch_up.base_commit)?;
+    let (indexed, last_oid, hit_end) = budgeted_index_from(
+        repo,
+        db,
+        opts,
+        tip,
+        Some(base),
+        budget,
+        usize::MAX,
+        catch_up.resume_oid.as_deref(),
+        batch_size,
+    )?;
+
+    let is_complete = if hit_end {
+        db.clear_catch_up()?;
+        catch_up.history_complete
+    } else {
+        db.set_catch_up(&CatchUp { resume_oid: last_oid.or(catch_up.resume_oid), ..catch_up })?;
+        false
+    };
+    db.set_indexing_state(&IndexingState {
+        commits_indexed: state.commits_indexed + indexed,
+        is_complete,
+        last_updated: unix_now(),
+        ..state.clone()
+    })?;
+    Ok((indexed, is_complete))
+}
+
+/// Background re-check of a complete index older than `max_index_age`. If
+/// its revision moved, index up to where it points now. If not, the history
+/// under it can still have grown (a shallow clone deepened by `git fetch
+/// --deepen` or `--unshallow`): walk on past the oldest indexed commit and
+/// index whatever follows, leaving the index incomplete if the budget runs
+/// out first. A fresh index is left alone.
+fn refresh_outdated(
+    repo_root: &Path,
+    db: &Database,
+    state: IndexingState,
+    budget: Duration,
+    record_metrics: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let start = Instant::now();
+    let mut opts = IndexOptions::load(repo_root, &Config::load(repo_root)?).with_target(db.get_index_target()?);
+    if !opts.is_outdated(&state) {
+        return Ok(());
+    }
+    if !record_metrics {
+        opts.metrics_repo_root = None;
+    }
+    let repo = Repository::open(repo_root)?;
+    let head = opts.tip(&repo)?.to_string();
+    if head != state.head_commit {
+        let target = state.target_path.unwrap_or_default();
+        smart_index(&repo, db, &opts, &target, budget)?;
+        return Ok(());
+    }
+
+    let strategy = Strategy::from_str(&state.strategy);
+    let oldest = db.oldest_indexed_commit()?;
+    let (indexed, last_oid, hit_end) = match (&strategy, state.target_path.as_deref()) {
+        (Strategy::PathFiltered, Some(target)) => {
+            path_filtered_index(&repo, db, &opts, target, budget, oldest.as_deref(), BACKGROUND_BATCH_SIZE)?
+        }
+        // A global walk that took its full commit limit has nothing to add
+        (Strategy::PathFiltered, None) => (0, None, true),
+        _ if state.commits_indexed as usize >= DEFAULT_COMMIT_LIMIT => (0, None, true),
+        _ => {
+            let limit = DEFAULT_COMMIT_LIMIT - state.commits_indexed as usize;
+            budgeted_global_index(&repo, db, &opts, budget, limit, oldest.as_deref(), BACKGROUND_BATCH_SIZE)?
+        }
+    };
+
+    let commits_indexed = state.commits_indexed + indexed;
+    let resume_oid = if hit_end { None } else { last_oid.or(oldest) };
+    if let (false, Some(target)) = (hit_end, state.target_path.as_deref()) {
+        queue_path_filtered(db, target, resume_oid.as_deref())?;
+    }
+    db.set_indexing_state(&IndexingState {
+        resume_oid,
+        commits_indexed,
+        is_complete: hit_end,
         last_updated: unix_now(),
-        target_path,
+        ..state
     })?;
-
-    Ok(SmartIndexResult {
+    let result = SmartIndexResult {
         strategy,
-        commits_indexed: total_indexed,
-        is_complete,
-        needs_background: !is_complete,
-    })
+        commits_indexed,
+        is_complete: hit_end,
+        needs_background: !hit_end,
+        commits_per_sec: throughput(indexed, start),
+    };
+    opts.record_run(db, &result, start, budget, true);
+    Ok(())
 }
 
 /// Background continuation: reopens repo+DB, reads indexing_state,
 /// continues from resume_oid for the given budget.
 ///
-/// `file_path` is passed directly from the foreground caller (main.rs)
-/// so that PathFiltered repos can continue their file-specific walk
-/// without needing to store the path in the database.
+/// PathFiltered repos drain the background work queue, so every file an
+/// agent touched keeps making progress rather than only the most recent
+/// one. `file_path` is the file the foreground caller (main.rs) analyzed;
+/// it seeds the queue when the queue is empty (e.g. state written before
+/// the queue existed).
+///
+/// The run is recorded as a background `indexing` metrics event unless
+/// `record_metrics` is false or the config sets `no_metrics`.
+///
+/// The walk continues the index that is there: same scope, and from the
+/// commit the foreground call indexed, even if HEAD (or `--rev`) has moved
+/// on since.
 pub fn background_index(
     repo_root: &Path,
     budget: Duration,
     file_path: Option<&str>,
+    record_metrics: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    let start = Instant::now();
     let engram_dir = repo_root.join(".engram");
     let db_path = engram_dir.join("engram.db");
     let db = Database::open(&db_path)?;
 
     let state = match db.get_indexing_state()? {
         Some(s) if !s.is_complete => s,
-        _ => return Ok(()), // Nothing to do
+        Some(s) => return refresh_outdated(repo_root, &db, s, budget, record_metrics),
+        None => return Ok(()), // Nothing to do
     };
 
     let strategy = Strategy::from_str(&state.strategy);
     let repo = Repository::open(repo_root)?;
-    let resume = state.resume_oid.as_deref();
+    let mut opts = IndexOptions::load(repo_root, &Config::load(repo_root)?)
+        .with_target(db.get_index_target()?)
+        .with_rev(&state.head_commit);
+    if !record_metrics {
+        opts.metrics_repo_root = None;
+    }
 
-    let (indexed, last_oid, hit_end) = match strategy {
-        Strategy::PathFiltered => {
-            match file_path {
-                Some(path) => path_filtered_index(
-                    &repo,
-                    &db,
-                    path,
-                    budget,
-                    resume,
-                    BACKGROUND_BATCH_SIZE,
-                )?,
-                None => return Ok(()), // No file path — can't do PathFiltered
-            }
-        }
-        _ => {
-            let remaining_limit =
-                DEFAULT_COMMIT_LIMIT.saturating_sub(state.commits_indexed as usize);
-            budgeted_global_index(
-                &repo,
-                &db,
-                budget,
-                remaining_limit,
-                resume,
-                BACKGROUND_BATCH_SIZE,
-            )?
+    if strategy == Strategy::PathFiltered {
+        if db.background_queue_len()? == 0 {
+            let Some(path) = file_path else {
+                return Ok(()); // No file path — can't do PathFiltered
+            };
+            let resume = if state.target_path.as_deref() == Some(path) {
+                state.resume_oid.as_deref()
+            } else {
+                None
+            };
+            db.enqueue_background(path, resume, unix_now())?;
         }
-    };
+
+        let indexed = drain_background_queue(&repo, &db, &opts, budget)?;
+        let is_complete = db.background_queue_len()? == 0;
+        let total = state.commits_indexed + indexed;
+
+        db.set_indexing_state(&IndexingState {
+            commits_indexed: total,
+            is_complete,
+            last_updated: unix_now(),
+            ..state
+        })?;
+        let result = SmartIndexResult {
+            strategy,
+            commits_indexed: total,
+            is_complete,
+            needs_background: !is_complete,
+            commits_per_sec: throughput(indexed, start),
+        };
+        opts.record_run(&db, &result, start, budget, true);
+        return Ok(());
+    }
+
+    if let Some(catch_up) = db.get_catch_up()? {
+        let (indexed, is_complete) =
+            run_catch_up(&repo, &db, &opts, &state, catch_up, budget, BACKGROUND_BATCH_SIZE)?;
+        let result = SmartIndexResult {
+            strategy,
+            commits_indexed: state.commits_indexed + indexed,
+            is_complete,
+            needs_background: !is_complete,
+            commits_per_sec: throughput(indexed, start),
+        };
+        opts.record_run(&db, &result, start, budget, true);
+        return Ok(());
+    }
+
+    let resume = state.resume_oid.as_deref();
+    let remaining_limit = DEFAULT_COMMIT_LIMIT.saturating_sub(state.commits_indexed as usize);
+    let (indexed, last_oid, hit_end) = budgeted_global_index(
+        &repo,
+        &db,
+        &opts,
+        budget,
+        remaining_limit,
+        resume,
+        BACKGROUND_BATCH_SIZE,
+    )?;
 
     let total = state.commits_indexed + indexed;
     let is_complete = hit_end;
@@ -719,8 +1759,16 @@ pub fn background_index(
         strategy: state.strategy,
         is_complete,
         last_updated: unix_now(),
-        target_path: file_path.map(|s| s.to_string()).or(state.target_path),
+        target_path: state.target_path,
     })?;
+    let result = SmartIndexResult {
+        strategy,
+        commits_indexed: total,
+        is_complete,
+        needs_background: !is_complete,
+        commits_per_sec: throughput(indexed, start),
+    };
+    opts.record_run(&db, &result, start, budget, true);
 
     Ok(())
 }
@@ -728,6 +1776,7 @@ pub fn background_index(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::commit_at;
     use git2::Signature;
     use std::fs;
     use tempfile::TempDir;
@@ -769,6 +1818,114 @@ mod tests {
         dir
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_paths_are_indexed_lossily() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let sig = Signature::now("Test", "test@test.com").unwrap();
+        let raw = b"caf\xe9.rs";
+        let mut parent: Option<git2::Commit> = None;
+        for i in 0..2 {
+            let blob = repo.blob(format!("v{i}").as_bytes()).unwrap();
+            let mut builder = repo.treebuilder(None).unwrap();
+            builder.insert(Path::new(std::ffi::OsStr::from_bytes(raw)), blob, 0o100644).unwrap();
+            builder.insert("main.rs", blob, 0o100644).unwrap();
+            let tree = repo.find_tree(builder.write().unwrap()).unwrap();
+            let parents: Vec<&git2::Commit> = parent.iter().collect();
+            let oid = repo.commit(Some("HEAD"), &sig, &sig, "edit", &tree, &parents).unwrap();
+            parent = Some(repo.find_commit(oid).unwrap());
+        }
+
+        let db = Database::in_memory().unwrap();
+        budgeted_global_index(&repo, &db, &IndexOptions::default(), Duration::from_secs(10), 1000, None, 100)
+            .unwrap();
+
+        let lossy = String::from_utf8_lossy(raw).into_owned();
+        assert_eq!(lossy, "caf\u{fffd}.rs");
+        assert_eq!(db.commit_count(&lossy).unwrap(), 2);
+        assert_eq!(db.co_change_count("main.rs", &lossy).unwrap(), 2);
+        assert!(db.lossy_paths().unwrap().contains(&lossy));
+        assert!(!db.lossy_paths().unwrap().contains("main.rs"));
+    }
+
+    #[test]
+    fn test_shallow_boundary_is_not_diffed_as_root() {
+        let dir = create_test_repo(&[
+            vec![("x.rs", "1"), ("y.rs", "1"), ("a.rs", "1")],
+            vec![("a.rs", "2"), ("b.rs", "2")],
+            vec![("a.rs", "3"), ("b.rs", "3")],
+            vec![("a.rs", "4"), ("b.rs", "4")],
+        ]);
+        // Simulate `git clone --depth 3`: the second commit is the cut-off
+        let repo = Repository::open(dir.path()).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let boundary = head.parent(0).unwrap().parent_id(0).unwrap();
+        fs::write(dir.path().join(".git").join("shallow"), format!("{boundary}\n")).unwrap();
+        let repo = Repository::open(dir.path()).unwrap();
+        assert!(repo.is_shallow());
+        assert!(is_shallow_boundary(&repo, &repo.find_commit(boundary).unwrap()));
+
+        let db = Database::in_memory().unwrap();
+        let (indexed, _, hit_end) =
+            budgeted_global_index(&repo, &db, &IndexOptions::default(), Duration::from_secs(10), 1000, None, 100)
+                .unwrap();
+        assert!(hit_end);
+        assert_eq!(indexed, 2);
+        assert_eq!(db.co_change_count("a.rs", "b.rs").unwrap(), 2);
+        assert_eq!(db.commit_count("x.rs").unwrap(), 0);
+
+        let db = Database::in_memory().unwrap();
+        path_filtered_index(&repo, &db, &IndexOptions::default(), "a.rs", Duration::from_secs(10), None, 100).unwrap();
+        assert_eq!(db.commit_count("a.rs").unwrap(), 2);
+        assert_eq!(db.commit_count("x.rs").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_corrupt_commit_is_quarantined() {
+        let commits: Vec<Vec<(&str, &str)>> = (0..4)
+            .map(|i| vec![("a.rs", ["v0", "v1", "v2", "v3"][i]), ("b.rs", ["v0", "v1", "v2", "v3"][i])])
+            .collect();
+        let dir = create_test_repo(&commits);
+
+        // Delete the root tree of commit 1, as odb corruption would
+        let repo = Repository::open(dir.path()).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let tree_hex = head.parent(0).unwrap().parent(0).unwrap().tree_id().to_string();
+        fs::remove_file(dir.path().join(".git/objects").join(&tree_hex[..2]).join(&tree_hex[2..]))
+            .unwrap();
+        let repo = Repository::open(dir.path()).unwrap();
+        let db = Database::in_memory().unwrap();
+
+        let walk = || {
+            budgeted_global_index(
+                &repo, &db, &IndexOptions::default(), Duration::from_secs(10), 1000, None, 100,
+            )
+        };
+        let mut errors = 0u32;
+        let (_, _, hit_end) = loop {
+            match walk() {
+                Ok(result) => break result,
+                Err(e) => {
+                    errors += 1;
+                    assert!(errors < 10, "quarantine never kicked in: {e}");
+                }
+            }
+        };
+
+        // Commit 1 (missing tree) and commit 2 (diffs against it) each fail
+        // until quarantined; commits 0 and 3 are indexed.
+        assert!(hit_end);
+        assert_eq!(errors, 2 * (MAX_COMMIT_FAILURES - 1));
+        assert_eq!(db.quarantined_commits(MAX_COMMIT_FAILURES).unwrap().len(), 2);
+        assert_eq!(db.commit_count("a.rs").unwrap(), 2);
+
+        // Later walks skip them without erroring
+        assert!(walk().is_ok());
+    }
+
     #[test]
     fn test_decide_strategy_complete() {
         assert_eq!(decide_strategy(50, true, 1000), Strategy::Complete);
@@ -838,7 +1995,7 @@ mod tests {
         let db = Database::in_memory().unwrap();
 
         let (indexed, last_oid, hit_end) = budgeted_global_index(
-            &repo, &db, Duration::from_secs(10), 1000, None, 100,
+            &repo, &db, &IndexOptions::default(), Duration::from_secs(10), 1000, None, 100,
         ).unwrap();
 
         assert_eq!(indexed, 3);
@@ -861,7 +2018,7 @@ mod tests {
         let db = Database::in_memory().unwrap();
 
         let (indexed, _last_oid, hit_end) = budgeted_global_index(
-            &repo, &db, Duration::from_secs(10), 5, None, 100,
+            &repo, &db, &IndexOptions::default(), Duration::from_secs(10), 5, None, 100,
         ).unwrap();
 
         assert_eq!(indexed, 5);
@@ -882,13 +2039,13 @@ mod tests {
 
         // Index first 2
         let (indexed1, last_oid1, _) = budgeted_global_index(
-            &repo, &db, Duration::from_secs(10), 2, None, 100,
+            &repo, &db, &IndexOptions::default(), Duration::from_secs(10), 2, None, 100,
         ).unwrap();
         assert_eq!(indexed1, 2);
 
         // Resume from where we left off
         let (indexed2, _, hit_end) = budgeted_global_index(
-            &repo, &db, Duration::from_secs(10), 2, last_oid1.as_deref(), 100,
+            &repo, &db, &IndexOptions::default(), Duration::from_secs(10), 2, last_oid1.as_deref(), 100,
         ).unwrap();
         assert_eq!(indexed2, 2);
         assert!(hit_end);
@@ -897,6 +2054,33 @@ mod tests {
         assert_eq!(db.commit_count("a.rs").unwrap(), 4);
     }
 
+    #[test]
+    fn test_parallel_walk_resumes_without_gaps() {
+        let commits: Vec<Vec<(&str, &str)>> =
+            (0..60).map(|i| vec![("a.rs", ["v0", "v1"][i % 2]), ("b.rs", ["v0", "v1"][i % 2])]).collect();
+        let dir = create_test_repo(&commits);
+        let repo = Repository::open(dir.path()).unwrap();
+        let db = Database::in_memory().unwrap();
+
+        // Small slices: results arrive out of order from the workers but
+        // each resume point must cover every commit before it
+        let mut resume = None;
+        let mut total = 0;
+        loop {
+            let (indexed, last_oid, hit_end) = budgeted_global_index(
+                &repo, &db, &IndexOptions::default(), Duration::from_secs(10), 7, resume.as_deref(), 3,
+            ).unwrap();
+            total += indexed;
+            resume = last_oid.or(resume);
+            if hit_end {
+                break;
+            }
+        }
+        assert_eq!(total, 60);
+        assert_eq!(db.commit_count("a.rs").unwrap(), 60);
+        assert_eq!(db.co_change_count("a.rs", "b.rs").unwrap(), 60);
+    }
+
     #[test]
     fn test_path_filtered_index() {
         let commits = vec![
@@ -910,7 +2094,7 @@ mod tests {
         let db = Database::in_memory().unwrap();
 
         let (indexed, _, _) = path_filtered_index(
-            &repo, &db, "src/target.rs", Duration::from_secs(10), None, 100,
+            &repo, &db, &IndexOptions::default(), "src/target.rs", Duration::from_secs(10), None, 100,
         ).unwrap();
 
         // Should have indexed 2 commits where target.rs changed
@@ -922,6 +2106,133 @@ mod tests {
         assert!(has_coupled, "coupled.rs should be co-changed with target.rs");
     }
 
+    #[test]
+    fn test_commits_over_file_cap_are_left_out() {
+        let commits = vec![
+            vec![("a.rs", "v0"), ("b.rs", "v0")],
+            // A formatting sweep
+            vec![("a.rs", "v1"), ("b.rs", "v1"), ("c.rs", "v1"), ("d.rs", "v1")],
+            vec![("a.rs", "v2"), ("c.rs", "v2")],
+        ];
+        let dir = create_test_repo(&commits);
+        let repo = Repository::open(dir.path()).unwrap();
+        let opts = IndexOptions { max_commit_files: Some(3), ..Default::default() };
+        let budget = Duration::from_secs(10);
+
+        let db = Database::in_memory().unwrap();
+        budgeted_global_index(&repo, &db, &opts, budget, 100, None, 100).unwrap();
+        assert_eq!(db.co_change_count("a.rs", "b.rs").unwrap(), 1);
+        assert_eq!(db.co_change_count("a.rs", "c.rs").unwrap(), 1);
+        assert_eq!(db.co_change_count("a.rs", "d.rs").unwrap(), 0);
+
+        let db = Database::in_memory().unwrap();
+        path_filtered_index(&repo, &db, &opts, "a.rs", budget, None, 100).unwrap();
+        assert_eq!(db.co_change_count("a.rs", "b.rs").unwrap(), 1);
+        assert_eq!(db.co_change_count("a.rs", "d.rs").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_is_fix_message() {
+        assert!(is_fix_message("fix(auth): refresh expired tokens"));
+        assert!(is_fix_message("Fixed crash on empty cart"));
+        assert!(is_fix_message("Hotfix for the login page"));
+        assert!(is_fix_message("Handle empty carts\n\nCloses #412"));
+        assert!(is_fix_message("Handle empty carts\n\nresolves PAY-431"));
+        assert!(!is_fix_message("Add prefix option to the debug logger"));
+        assert!(!is_fix_message("Refactor cart\n\nNo bugs were harmed"));
+        assert!(!is_fix_message("Release 1.2 (#88)"));
+    }
+
+    #[test]
+    fn test_ticket_ids() {
+        assert_eq!(ticket_ids("PAY-431: retry refunds (#567)"), ["PAY-431", "#567"]);
+        assert_eq!(ticket_ids("Refs #12, #12 and OPS-7\n\nSee #3"), ["#12", "OPS-7", "#3"]);
+        assert!(ticket_ids("Decode UTF-8 names with SHA-256 checks").is_empty());
+        assert!(ticket_ids("Escape &#39; in titles, bump pay-api-2").is_empty());
+    }
+
+    #[test]
+    fn test_conventional_type() {
+        assert_eq!(conventional_type("feat: add cart"), Some("feat"));
+        assert_eq!(conventional_type("Fix(auth)!: drop legacy tokens"), Some("fix"));
+        assert_eq!(conventional_type("chore(deps): bump serde"), Some("chore"));
+        assert_eq!(conventional_type("Note: this is not a type"), None);
+        assert_eq!(conventional_type("Add feat: to the parser"), None);
+    }
+
+    /// Commit a move of `from` to `to` on top of HEAD, with `changes`.
+    fn commit_rename(dir: &Path, from: &str, to: &str, changes: &[(&str, &str)]) {
+        let repo = Repository::open(dir).unwrap();
+        fs::rename(dir.join(from), dir.join(to)).unwrap();
+        for (path, content) in changes {
+            fs::write(dir.join(path), content).unwrap();
+        }
+        let mut index = repo.index().unwrap();
+        index.remove_path(Path::new(from)).unwrap();
+        index.add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = Signature::now("Test", "test@test.com").unwrap();
+        let parent = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, &format!("move {from}"), &tree, &[&parent]).unwrap();
+    }
+
+    fn source(version: &str) -> String {
+        format!("fn helper() {{\n    one();\n    two();\n    three();\n}}\n// {version}\n")
+    }
+
+    #[test]
+    fn test_path_filtered_index_follows_renamed_coupled_file() {
+        let (v0, v1) = (source("v0"), source("v1"));
+        let commits = vec![
+            vec![("src/target.rs", "v0"), ("src/helper.rs", v0.as_str())],
+            vec![("src/target.rs", "v1"), ("src/helper.rs", v1.as_str())],
+        ];
+        let dir = create_test_repo(&commits);
+        // Move helper.rs along with a change to the target
+        commit_rename(dir.path(), "src/helper.rs", "src/util.rs", &[("src/target.rs", "v2")]);
+        let repo = Repository::open(dir.path()).unwrap();
+
+        let db = Database::in_memory().unwrap();
+        let (indexed, _, hit_end) = path_filtered_index(
+            &repo, &db, &IndexOptions::default(), "src/target.rs", Duration::from_secs(10), None, 100,
+        ).unwrap();
+        assert_eq!(indexed, 3);
+        assert!(hit_end);
+        assert_eq!(db.co_change_count("src/target.rs", "src/util.rs").unwrap(), 3);
+        assert_eq!(db.co_change_count("src/target.rs", "src/helper.rs").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_path_filtered_index_follows_target_across_rename() {
+        let (v0, v1, v3) = (source("v0"), source("v1"), source("v3"));
+        let commits = vec![
+            vec![("src/old.rs", v0.as_str()), ("src/partner.rs", "v0")],
+            vec![("src/old.rs", v1.as_str()), ("src/partner.rs", "v1")],
+        ];
+        let dir = create_test_repo(&commits);
+        commit_rename(dir.path(), "src/old.rs", "src/new.rs", &[("src/partner.rs", "v2")]);
+        fs::write(dir.path().join("src/new.rs"), &v3).unwrap();
+        fs::write(dir.path().join("src/partner.rs"), "v3").unwrap();
+        let repo = Repository::open(dir.path()).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = Signature::now("Test", "test@test.com").unwrap();
+        let parent = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "after move", &tree, &[&parent]).unwrap();
+
+        let db = Database::in_memory().unwrap();
+        let (indexed, _, hit_end) = path_filtered_index(
+            &repo, &db, &IndexOptions::default(), "src/new.rs", Duration::from_secs(10), None, 100,
+        ).unwrap();
+        assert!(hit_end);
+        assert_eq!(indexed, 4);
+        assert_eq!(db.commit_count("src/new.rs").unwrap(), 4);
+        assert_eq!(db.co_change_count("src/new.rs", "src/partner.rs").unwrap(), 4);
+    }
+
     #[test]
     fn test_path_filtered_index_with_resume() {
         // Create a repo where target.rs changes in commits 0, 2, and 4
@@ -942,7 +2253,7 @@ mod tests {
         //
         // Better approach: index first 3 revwalk commits (budget-limited), get resume_oid
         let (indexed1, last_oid1, hit_end1) = path_filtered_index(
-            &repo, &db, "src/target.rs", Duration::from_secs(10), None, 100,
+            &repo, &db, &IndexOptions::default(), "src/target.rs", Duration::from_secs(10), None, 100,
         ).unwrap();
 
         // Should index all changes (small repo completes within budget)
@@ -954,7 +2265,7 @@ mod tests {
         if let Some(ref resume_oid) = last_oid1 {
             let db2 = Database::in_memory().unwrap();
             let (indexed2, _, _) = path_filtered_index(
-                &repo, &db2, "src/target.rs", Duration::from_secs(10),
+                &repo, &db2, &IndexOptions::default(), "src/target.rs", Duration::from_secs(10),
                 Some(resume_oid), 100,
             ).unwrap();
             // Resuming from the last OID: only root commit (if any) remains
@@ -978,7 +2289,7 @@ mod tests {
         // Budget of 100ms is less than DIFF_SAFETY_MARGIN_MS (200ms)
         // The blob walk should run but no diffs should execute
         let (indexed, _, hit_end) = path_filtered_index(
-            &repo, &db, "src/target.rs", Duration::from_millis(100), None, 100,
+            &repo, &db, &IndexOptions::default(), "src/target.rs", Duration::from_millis(100), None, 100,
         ).unwrap();
 
         // The safety margin should prevent any diffs from running
@@ -1012,7 +2323,7 @@ mod tests {
 
         // Now call smart_index for a DIFFERENT file
         let result = smart_index(
-            &repo, &db, "src/b.rs", Duration::from_secs(5),
+            &repo, &db, &IndexOptions::default(), "src/b.rs", Duration::from_secs(5),
         ).unwrap();
 
         // Should detect file change, start fresh for b.rs
@@ -1029,6 +2340,43 @@ mod tests {
         assert_ne!(state.resume_oid, Some("deadbeef".to_string()));
     }
 
+    #[test]
+    fn test_background_index_drains_queue_for_all_files() {
+        let commits = vec![
+            vec![("src/a.rs", "v0"), ("src/b.rs", "v0"), ("src/x.rs", "v0")],
+            vec![("src/a.rs", "v1"), ("src/x.rs", "v1")],
+            vec![("src/b.rs", "v1"), ("src/y.rs", "v0")],
+        ];
+        let dir = create_test_repo(&commits);
+        let repo = Repository::open(dir.path()).unwrap();
+        let engram_dir = dir.path().join(".engram");
+        fs::create_dir_all(&engram_dir).unwrap();
+        let db = Database::open(&engram_dir.join("engram.db")).unwrap();
+
+        let head = repo.head().unwrap().peel_to_commit().unwrap().id().to_string();
+        db.set_indexing_state(&IndexingState {
+            head_commit: head,
+            resume_oid: None,
+            commits_indexed: 0,
+            strategy: "path_filtered".to_string(),
+            is_complete: false,
+            last_updated: unix_now(),
+            target_path: Some("src/b.rs".to_string()),
+        }).unwrap();
+        // Both files were requested by the agent; b.rs most recently
+        db.enqueue_background("src/a.rs", None, 100).unwrap();
+        db.enqueue_background("src/b.rs", None, 200).unwrap();
+
+        background_index(dir.path(), Duration::from_secs(10), Some("src/b.rs"), true).unwrap();
+
+        assert_eq!(db.background_queue_len().unwrap(), 0);
+        let coupled_a = db.coupled_files("src/a.rs").unwrap();
+        assert!(coupled_a.iter().any(|(p, _)| p == "src/x.rs"), "a.rs was not starved");
+        let coupled_b = db.coupled_files("src/b.rs").unwrap();
+        assert!(coupled_b.iter().any(|(p, _)| p == "src/y.rs"));
+        assert!(db.get_indexing_state().unwrap().unwrap().is_complete);
+    }
+
     #[test]
     fn test_smart_index_small_repo() {
         let commits = vec![
@@ -1040,7 +2388,7 @@ mod tests {
         let db = Database::in_memory().unwrap();
 
         let result = smart_index(
-            &repo, &db, "a.rs", Duration::from_secs(5),
+            &repo, &db, &IndexOptions::default(), "a.rs", Duration::from_secs(5),
         ).unwrap();
 
         assert_eq!(result.strategy, Strategy::Complete);
@@ -1060,12 +2408,12 @@ mod tests {
         let db = Database::in_memory().unwrap();
 
         // First call indexes everything
-        let r1 = smart_index(&repo, &db, "a.rs", Duration::from_secs(5)).unwrap();
+        let r1 = smart_index(&repo, &db, &IndexOptions::default(), "a.rs", Duration::from_secs(5)).unwrap();
         assert!(r1.is_complete);
 
         // Second call should be instant (already complete at same HEAD)
         let start = Instant::now();
-        let r2 = smart_index(&repo, &db, "a.rs", Duration::from_secs(5)).unwrap();
+        let r2 = smart_index(&repo, &db, &IndexOptions::default(), "a.rs", Duration::from_secs(5)).unwrap();
         let elapsed = start.elapsed();
 
         assert!(r2.is_complete);
@@ -1073,6 +2421,246 @@ mod tests {
         assert!(elapsed.as_millis() < 50, "Subsequent call took too long: {:?}", elapsed);
     }
 
+    #[test]
+    fn test_smart_index_catches_up_when_head_moves_forward() {
+        let (v0, v1) = (source("v0"), source("v1"));
+        let commits = vec![vec![("a.rs", v0.as_str()), ("b.rs", "v0")], vec![("a.rs", v1.as_str()), ("b.rs", "v1")]];
+        let dir = create_test_repo(&commits);
+        let repo = Repository::open(dir.path()).unwrap();
+        let db = Database::in_memory().unwrap();
+        let budget = Duration::from_secs(5);
+        smart_index(&repo, &db, &IndexOptions::default(), "a.rs", budget).unwrap();
+        // Stands in for a long history: a rewalk would reset the count
+        let state = db.get_indexing_state().unwrap().unwrap();
+        db.set_indexing_state(&IndexingState { commits_indexed: 100, ..state }).unwrap();
+
+        commit_rename(dir.path(), "a.rs", "c.rs", &[("b.rs", "v2")]);
+        let result = smart_index(&repo, &db, &IndexOptions::default(), "c.rs", budget).unwrap();
+
+        assert_eq!(result.strategy, Strategy::Complete);
+        assert!(result.is_complete);
+        assert_eq!(result.commits_indexed, 101);
+        assert!(db.get_catch_up().unwrap().is_none());
+        let head = repo.head().unwrap().target().unwrap().to_string();
+        assert_eq!(db.get_indexing_state().unwrap().unwrap().head_commit, head);
+        // History indexed before the rename moved to the new name
+        assert_eq!(db.co_change_count("c.rs", "b.rs").unwrap(), 3);
+        assert_eq!(db.co_change_count("a.rs", "b.rs").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_outdated_complete_index_is_refreshed_in_background() {
+        let commits = vec![vec![("a.rs", "v0"), ("b.rs", "v0")], vec![("a.rs", "v1"), ("b.rs", "v1")]];
+        let dir = create_test_repo(&commits);
+        let repo = Repository::open(dir.path()).unwrap();
+        let engram_dir = dir.path().join(".engram");
+        fs::create_dir_all(&engram_dir).unwrap();
+        let db = Database::open(&engram_dir.join("engram.db")).unwrap();
+        let opts = IndexOptions { max_index_age: Some(Duration::from_secs(24 * 3600)), ..Default::default() };
+        let budget = Duration::from_secs(5);
+
+        assert!(!smart_index(&repo, &db, &opts, "a.rs", budget).unwrap().needs_background);
+        let state = db.get_indexing_state().unwrap().unwrap();
+        db.set_indexing_state(&IndexingState { last_updated: unix_now() - 25 * 3600, ..state }).unwrap();
+
+        let result = smart_index(&repo, &db, &opts, "a.rs", budget).unwrap();
+        assert!(result.is_complete);
+        assert!(result.needs_background);
+        assert!(!smart_index(&repo, &db, &IndexOptions::default(), "a.rs", budget).unwrap().needs_background);
+
+        background_index(dir.path(), budget, Some("a.rs"), false).unwrap();
+        let state = db.get_indexing_state().unwrap().unwrap();
+        assert!(state.is_complete);
+        assert!(unix_now() - state.last_updated < 3600);
+        assert!(!smart_index(&repo, &db, &opts, "a.rs", budget).unwrap().needs_background);
+    }
+
+    #[test]
+    fn test_outdated_index_picks_up_deepened_history() {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        commit_at(&repo, &["x.rs", "a.rs"], "c1", 1_700_000_000);
+        commit_at(&repo, &["a.rs", "b.rs"], "c2", 1_700_000_100);
+        commit_at(&repo, &["a.rs", "b.rs"], "c3", 1_700_000_200);
+
+        // A `--depth 2` clone: the second commit is the cut-off
+        let boundary = repo.head().unwrap().peel_to_commit().unwrap().parent_id(0).unwrap();
+        let shallow = dir.path().join(".git").join("shallow");
+        fs::write(&shallow, format!("{boundary}\n")).unwrap();
+        let engram_dir = dir.path().join(".engram");
+        fs::create_dir_all(&engram_dir).unwrap();
+        let db = Database::open(&engram_dir.join("engram.db")).unwrap();
+        let budget = Duration::from_secs(5);
+        let repo = Repository::open(dir.path()).unwrap();
+        assert!(smart_index(&repo, &db, &IndexOptions::default(), "a.rs", budget).unwrap().is_complete);
+        assert_eq!(db.commit_count("a.rs").unwrap(), 1);
+
+        // `git fetch --unshallow`, then the index ages past max_index_age
+        fs::remove_file(&shallow).unwrap();
+        let state = db.get_indexing_state().unwrap().unwrap();
+        db.set_indexing_state(&IndexingState { last_updated: unix_now() - 25 * 3600, ..state.clone() }).unwrap();
+
+        background_index(dir.path(), budget, Some("a.rs"), false).unwrap();
+        let refreshed = db.get_indexing_state().unwrap().unwrap();
+        assert!(refreshed.is_complete);
+        assert_eq!(refreshed.head_commit, state.head_commit);
+        assert_eq!(refreshed.commits_indexed, 3);
+        assert_eq!(db.co_change_count("a.rs", "b.rs").unwrap(), 2);
+        assert_eq!(db.commit_count("x.rs").unwrap(), 1);
+
+        // Nothing more to find: the next re-check only restarts the clock
+        db.set_indexing_state(&IndexingState { last_updated: unix_now() - 25 * 3600, ..refreshed }).unwrap();
+        background_index(dir.path(), budget, Some("a.rs"), false).unwrap();
+        assert_eq!(db.get_indexing_state().unwrap().unwrap().commits_indexed, 3);
+        assert_eq!(db.commit_count("a.rs").unwrap(), 3);
+    }
+
+    #[test]
+    fn test_commits_remaining_counts_past_resume_point() {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        for i in 0..5 {
+            commit_at(&repo, &["a.rs"], &format!("c{i}"), 1_700_000_000 + i);
+        }
+        let repo = Repository::open(dir.path()).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let state = IndexingState {
+            head_commit: head.id().to_string(),
+            resume_oid: Some(head.parent_id(0).unwrap().to_string()),
+            commits_indexed: 2,
+            strategy: Strategy::BudgetedGlobal.as_str().to_string(),
+            is_complete: false,
+            last_updated: unix_now(),
+            target_path: None,
+        };
+        assert_eq!(commits_remaining(&repo, &state), Some(3));
+        let capped = IndexingState { commits_indexed: DEFAULT_COMMIT_LIMIT as u32 - 1, ..state.clone() };
+        assert_eq!(commits_remaining(&repo, &capped), Some(1));
+        let complete = IndexingState { is_complete: true, ..state.clone() };
+        assert_eq!(commits_remaining(&repo, &complete), Some(0));
+        let path_filtered = IndexingState { strategy: Strategy::PathFiltered.as_str().to_string(), ..state };
+        assert_eq!(commits_remaining(&repo, &path_filtered), None);
+    }
+
+    #[test]
+    fn test_catch_up_resumes_and_restores_completeness() {
+        let commits = vec![vec![("a.rs", "v0"), ("b.rs", "v0")], vec![("a.rs", "v1"), ("b.rs", "v1")]];
+        let dir = create_test_repo(&commits);
+        let repo = Repository::open(dir.path()).unwrap();
+        let db = Database::in_memory().unwrap();
+        smart_index(&repo, &db, &IndexOptions::default(), "a.rs", Duration::from_secs(5)).unwrap();
+        let state = db.get_indexing_state().unwrap().unwrap();
+        let base = state.head_commit.clone();
+        commit_rename(dir.path(), "a.rs", "c.rs", &[("b.rs", "v2")]);
+        let head = repo.head().unwrap().target().unwrap().to_string();
+
+        // Out of time before the first new commit
+        let catch_up = catch_up_from(&repo, &db, &state, Oid::from_str(&head).unwrap()).unwrap().unwrap();
+        let state = IndexingState { head_commit: head, ..state };
+        let (indexed, is_complete) =
+            run_catch_up(&repo, &db, &IndexOptions::default(), &state, catch_up, Duration::ZERO, 10).unwrap();
+        assert_eq!((indexed, is_complete), (0, false));
+        let pending = db.get_catch_up().unwrap().unwrap();
+        assert_eq!(pending.base_commit, base);
+        assert!(pending.history_complete);
+        assert!(!db.get_indexing_state().unwrap().unwrap().is_complete);
+
+        // The next call finishes it
+        let result = smart_index(&repo, &db, &IndexOptions::default(), "c.rs", Duration::from_secs(5)).unwrap();
+        assert!(result.is_complete);
+        assert_eq!(result.commits_indexed, 3);
+        assert!(db.get_catch_up().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_scope_restricts_index_and_rebuilds_on_change() {
+        // Shared release commits touch every package
+        let commits = vec![
+            vec![("pkg/a/x.rs", "v0"), ("pkg/a/y.rs", "v0"), ("pkg/b/z.rs", "v0")],
+            vec![("pkg/a/x.rs", "v1"), ("pkg/a/y.rs", "v1"), ("pkg/b/z.rs", "v1")],
+        ];
+        let dir = create_test_repo(&commits);
+        let repo = Repository::open(dir.path()).unwrap();
+        let db = Database::in_memory().unwrap();
+        let budget = Duration::from_secs(5);
+
+        let scoped = IndexOptions::default().with_scope("./pkg/a");
+        assert_eq!(scoped.scope.as_deref(), Some("pkg/a/"));
+        smart_index(&repo, &db, &scoped, "pkg/a/x.rs", budget).unwrap();
+        assert_eq!(db.co_change_count("pkg/a/x.rs", "pkg/a/y.rs").unwrap(), 2);
+        assert_eq!(db.co_change_count("pkg/a/x.rs", "pkg/b/z.rs").unwrap(), 0);
+        assert_eq!(db.get_index_target().unwrap().scope.as_deref(), Some("pkg/a/"));
+
+        // Dropping the scope rebuilds over the whole repo
+        smart_index(&repo, &db, &IndexOptions::default(), "pkg/a/x.rs", budget).unwrap();
+        assert_eq!(db.co_change_count("pkg/a/x.rs", "pkg/b/z.rs").unwrap(), 2);
+        assert_eq!(db.get_index_target().unwrap(), IndexTarget::default());
+    }
+
+    #[test]
+    fn test_rev_indexes_history_of_that_revision() {
+        let commits = vec![
+            vec![("a.rs", "v0"), ("b.rs", "v0")],
+            vec![("a.rs", "v1"), ("b.rs", "v1")],
+            vec![("a.rs", "v2"), ("local.rs", "v2")],
+        ];
+        let dir = create_test_repo(&commits);
+        let repo = Repository::open(dir.path()).unwrap();
+        let mainline = repo.head().unwrap().peel_to_commit().unwrap().parent(0).unwrap();
+        repo.branch("mainline", &mainline, false).unwrap();
+        let db = Database::in_memory().unwrap();
+        let budget = Duration::from_secs(5);
+
+        let at_mainline = IndexOptions::default().with_rev("mainline");
+        let result = smart_index(&repo, &db, &at_mainline, "a.rs", budget).unwrap();
+        assert_eq!(result.commits_indexed, 2);
+        assert_eq!(db.get_indexing_state().unwrap().unwrap().head_commit, mainline.id().to_string());
+        assert_eq!(db.co_change_count("a.rs", "local.rs").unwrap(), 0);
+
+        // Back to HEAD: rebuilt, with the local commit
+        smart_index(&repo, &db, &IndexOptions::default(), "a.rs", budget).unwrap();
+        assert_eq!(db.co_change_count("a.rs", "local.rs").unwrap(), 1);
+        assert_eq!(db.co_change_count("a.rs", "b.rs").unwrap(), 2);
+    }
+
+    #[test]
+    fn test_normalize_scope() {
+        assert_eq!(normalize_scope("packages/payments").as_deref(), Some("packages/payments/"));
+        assert_eq!(normalize_scope("./packages\\payments/").as_deref(), Some("packages/payments/"));
+        assert_eq!(normalize_scope("/"), None);
+        assert_eq!(normalize_scope("."), None);
+    }
+
+    #[test]
+    fn test_warmup_plan_global_uses_throughput() {
+        let result = SmartIndexResult {
+            strategy: Strategy::BudgetedGlobal,
+            commits_indexed: 400,
+            is_complete: false,
+            needs_background: true,
+            commits_per_sec: Some(40.0),
+        };
+        let plan = warmup_plan(&result, Some(600));
+        // 600 remaining at 40 commits/s * 5s = 200 per cycle
+        assert_eq!(plan.estimated_commits_remaining, Some(600));
+        assert_eq!(plan.estimated_cycles_remaining, 3);
+        assert_eq!(plan.suggested_requery_ms, BACKGROUND_BUDGET.as_millis() as u64);
+    }
+
+    #[test]
+    fn test_warmup_plan_path_filtered_unknown_remaining() {
+        let result = SmartIndexResult {
+            strategy: Strategy::PathFiltered,
+            commits_indexed: 12,
+            is_complete: false,
+            needs_background: true,
+            commits_per_sec: None,
+        };
+        let plan = warmup_plan(&result, None);
+        assert_eq!(plan.estimated_commits_remaining, None);
+        assert_eq!(plan.estimated_cycles_remaining, 1);
+    }
+
     #[test]
     fn test_strategy_round_trip() {
         for strategy in &[
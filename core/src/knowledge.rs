@@ -1,13 +1,19 @@
-use crate::persistence::Database;
-use crate::types::{AddNoteResponse, CoupledFile, ListNotesResponse, SearchNotesResponse};
+use std::path::Path;
+
+use git2::{Oid, Repository};
+use globset::{GlobBuilder, GlobMatcher};
+
+use crate::persistence::{Database, NoteOrigin, Page};
+use crate::types::{AddNoteResponse, CoupledFile, ListNotesResponse, Memory, NoteDrift, SearchNotesResponse};
 
 pub fn add_note(
     db: &Database,
     file_path: &str,
     symbol_name: Option<&str>,
     content: &str,
+    origin: &NoteOrigin,
 ) -> Result<AddNoteResponse, Box<dyn std::error::Error>> {
-    let id = db.add_memory(file_path, symbol_name, content)?;
+    let id = db.add_memory(file_path, symbol_name, content, origin)?;
     Ok(AddNoteResponse {
         id,
         file_path: file_path.to_string(),
@@ -18,25 +24,96 @@ pub fn add_note(
 pub fn search_notes(
     db: &Database,
     query: &str,
+    page: Page,
 ) -> Result<SearchNotesResponse, Box<dyn std::error::Error>> {
-    let memories = db.search_memories(query)?;
+    let mut memories = db.search_memories(query, page.probe())?;
+    let next_offset = page.finish(&mut memories);
     Ok(SearchNotesResponse {
         query: query.to_string(),
         memories,
+        next_offset,
     })
 }
 
 pub fn list_notes(
     db: &Database,
     file_path: Option<&str>,
+    author: Option<&str>,
+    page: Page,
 ) -> Result<ListNotesResponse, Box<dyn std::error::Error>> {
-    let memories = db.list_memories(file_path)?;
+    let mut memories = db.list_memories(file_path, author, page.probe())?;
+    let next_offset = page.finish(&mut memories);
     Ok(ListNotesResponse {
         file_path: file_path.map(|s| s.to_string()),
         memories,
+        next_offset,
+    })
+}
+
+/// The configured git identity, HEAD, and `file_path`'s blob at HEAD, for a
+/// note being added now. Whatever git cannot tell (no identity, unborn
+/// branch, file not committed yet) is left out.
+pub fn note_origin(repo: &Repository, file_path: &str) -> NoteOrigin {
+    let signature = repo.signature().ok();
+    let head = repo.head().and_then(|h| h.peel_to_commit()).ok();
+    NoteOrigin {
+        author_name: signature.as_ref().and_then(|s| s.name()).map(String::from),
+        author_email: signature.as_ref().and_then(|s| s.email()).map(String::from),
+        head_commit: head.as_ref().map(|c| c.id().to_string()),
+        blob_oid: head.and_then(|c| blob_at(&c.tree().ok()?, file_path)),
+    }
+}
+
+fn blob_at(tree: &git2::Tree, file_path: &str) -> Option<String> {
+    Some(tree.get_path(Path::new(file_path)).ok()?.id().to_string())
+}
+
+/// Mark the memories on each coupled file with how far the file has moved
+/// on since the note was written. Notes without a recorded HEAD, or whose
+/// HEAD is no longer in the repository, are left unmarked.
+pub fn mark_note_drift(repo: &Repository, db: &Database, coupled_files: &mut [CoupledFile]) {
+    let head_tree = repo.head().and_then(|h| h.peel_to_tree()).ok();
+    for file in coupled_files.iter_mut() {
+        let current_blob = head_tree.as_ref().and_then(|tree| blob_at(tree, &file.path));
+        for memory in file.memories.iter_mut() {
+            memory.drift = note_drift(repo, db, memory, current_blob.as_deref());
+        }
+    }
+}
+
+fn note_drift(repo: &Repository, db: &Database, memory: &Memory, current_blob: Option<&str>) -> Option<NoteDrift> {
+    let noted_at = repo.find_commit(Oid::from_str(memory.head_commit.as_deref()?).ok()?).ok()?.time().seconds();
+    let total = db.commit_count(&memory.file_path).ok()?;
+    let up_to_note = db.commit_count_before(&memory.file_path, noted_at + 1).ok()?;
+    Some(NoteDrift {
+        commits_since: total.saturating_sub(up_to_note),
+        file_changed: memory.blob_oid.as_deref() != current_blob,
     })
 }
 
+/// Append the user-level notes whose glob pattern (stored as the note's
+/// `file_path`, e.g. `**/*.proto`) matches each coupled file. Patterns that
+/// are not valid globs are skipped.
+pub fn enrich_with_global_notes(global: &Database, coupled_files: &mut [CoupledFile]) {
+    let Ok(notes) = global.list_memories(None, None, Page::default()) else {
+        return;
+    };
+    let patterns: Vec<(GlobMatcher, Memory)> = notes
+        .into_iter()
+        .filter_map(|note| {
+            let glob = GlobBuilder::new(&note.file_path).literal_separator(true).build().ok()?;
+            Some((glob.compile_matcher(), Memory { global: true, ..note }))
+        })
+        .collect();
+    for file in coupled_files.iter_mut() {
+        for (glob, note) in &patterns {
+            if glob.is_match(&file.path) {
+                file.memories.push(note.clone());
+            }
+        }
+    }
+}
+
 pub fn enrich_with_memories(
     db: &Database,
     coupled_files: &mut [CoupledFile],
@@ -55,17 +132,103 @@ mod tests {
     #[test]
     fn test_add_note_response() {
         let db = Database::in_memory().unwrap();
-        let resp = add_note(&db, "src/Auth.ts", Some("login"), "Handles OAuth flow").unwrap();
+        let origin = NoteOrigin {
+            author_name: Some("Ada".to_string()),
+            author_email: Some("ada@example.com".to_string()),
+            ..Default::default()
+        };
+        let resp = add_note(&db, "src/Auth.ts", Some("login"), "Handles OAuth flow", &origin).unwrap();
 
         assert!(resp.id > 0);
         assert_eq!(resp.file_path, "src/Auth.ts");
         assert_eq!(resp.content, "Handles OAuth flow");
+
+        let listed = list_notes(&db, None, Some("ADA@example.com"), Page::default()).unwrap();
+        assert_eq!(listed.memories.len(), 1);
+        assert_eq!(listed.memories[0].author_name.as_deref(), Some("Ada"));
+        assert!(list_notes(&db, None, Some("bob"), Page::default()).unwrap().memories.is_empty());
+    }
+
+    #[test]
+    fn test_list_notes_pages() {
+        let db = Database::in_memory().unwrap();
+        for content in ["first", "second", "third"] {
+            add_note(&db, "src/a.rs", None, content, &NoteOrigin::default()).unwrap();
+        }
+
+        let page = |offset, limit| Page { offset, limit: Some(limit) };
+        let first = list_notes(&db, None, None, page(0, 2)).unwrap();
+        let contents: Vec<&str> = first.memories.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, vec!["third", "second"]);
+        assert_eq!(first.next_offset, Some(2));
+
+        let last = list_notes(&db, None, None, page(2, 2)).unwrap();
+        assert_eq!(last.memories[0].content, "first");
+        assert_eq!(last.next_offset, None);
+
+        let search = search_notes(&db, "ir", page(0, 1)).unwrap();
+        assert_eq!((search.memories.len(), search.next_offset), (1, Some(1)));
+        assert_eq!(list_notes(&db, None, None, Page::default()).unwrap().memories.len(), 3);
+    }
+
+    fn commit_at(repo: &Repository, path: &str, content: &str, time: i64) -> Oid {
+        std::fs::write(repo.workdir().unwrap().join(path), content).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(path)).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = git2::Signature::new("Ada", "ada@example.com", &git2::Time::new(time, 0)).unwrap();
+        let parents: Vec<git2::Commit> = repo.head().ok().and_then(|h| h.peel_to_commit().ok()).into_iter().collect();
+        let parent_refs: Vec<&git2::Commit> = parents.iter().collect();
+        repo.commit(Some("HEAD"), &sig, &sig, "change", &tree, &parent_refs).unwrap()
+    }
+
+    #[test]
+    fn test_note_drift_counts_commits_since_the_note() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let db = Database::in_memory().unwrap();
+        let first = commit_at(&repo, "session.ts", "v1", 1000);
+        db.insert_commit(&first.to_string(), &["session.ts"], 1000).unwrap();
+
+        let origin = note_origin(&repo, "session.ts");
+        assert_eq!(origin.head_commit, Some(first.to_string()));
+        assert!(origin.blob_oid.is_some());
+        add_note(&db, "session.ts", None, "Cache is per tenant", &origin).unwrap();
+
+        let mut files = vec![CoupledFile { path: "session.ts".to_string(), ..Default::default() }];
+        enrich_with_memories(&db, &mut files);
+        mark_note_drift(&repo, &db, &mut files);
+        assert_eq!(files[0].memories[0].drift, Some(NoteDrift { commits_since: 0, file_changed: false }));
+
+        for (content, time) in [("v2", 2000), ("v3", 3000)] {
+            let oid = commit_at(&repo, "session.ts", content, time);
+            db.insert_commit(&oid.to_string(), &["session.ts"], time).unwrap();
+        }
+        mark_note_drift(&repo, &db, &mut files);
+        assert_eq!(files[0].memories[0].drift, Some(NoteDrift { commits_since: 2, file_changed: true }));
+    }
+
+    #[test]
+    fn test_global_notes_match_by_glob() {
+        let global = Database::in_memory().unwrap();
+        global.add_memory("**/*.proto", None, "Regenerate stubs after editing", &NoteOrigin::default()).unwrap();
+        global.add_memory("[bad", None, "Never matches", &NoteOrigin::default()).unwrap();
+
+        let file = |path: &str| CoupledFile { path: path.to_string(), ..Default::default() };
+        let mut files = vec![file("api/v1/user.proto"), file("api/v1/user.rs")];
+        enrich_with_global_notes(&global, &mut files);
+
+        assert_eq!(files[0].memories.len(), 1);
+        assert!(files[0].memories[0].global);
+        assert_eq!(files[0].memories[0].file_path, "**/*.proto");
+        assert!(files[1].memories.is_empty());
     }
 
     #[test]
     fn test_enrich_coupled_files() {
         let db = Database::in_memory().unwrap();
-        db.add_memory("src/Session.ts", None, "Session note").unwrap();
+        db.add_memory("src/Session.ts", None, "Session note", &NoteOrigin::default()).unwrap();
 
         let mut files = vec![
             CoupledFile {
@@ -73,16 +236,14 @@ mod tests {
                 coupling_score: 0.9,
                 co_change_count: 48,
                 risk_score: 0.89,
-                memories: Vec::new(),
-                test_intents: Vec::new(),
+                ..Default::default()
             },
             CoupledFile {
                 path: "src/Utils.ts".to_string(),
                 coupling_score: 0.1,
                 co_change_count: 1,
                 risk_score: 0.2,
-                memories: Vec::new(),
-                test_intents: Vec::new(),
+                ..Default::default()
             },
         ];
 
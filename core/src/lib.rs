@@ -1,17 +1,72 @@
+pub mod alerts;
+pub mod authors;
+pub mod blame;
+pub mod budget;
+pub mod check;
+#[cfg(feature = "cli")]
 pub mod cli;
+pub mod clusters;
+mod commit_graph;
+pub mod compare;
+pub mod config;
+pub mod dashboard;
+mod engram;
+pub mod enrichers;
+pub mod evidence;
+pub mod export;
+mod fingerprint;
+pub mod github;
+pub mod graph;
+pub mod hooks;
+pub mod ignore_rules;
+pub mod import_graph;
 pub mod indexing;
 pub mod knowledge;
+pub mod maintenance;
+pub mod markdown;
 pub mod metrics;
+#[cfg(feature = "async")]
+pub mod nonblocking;
+pub mod orient;
+pub mod packages;
 pub mod persistence;
+pub mod placement;
+pub mod reindex;
 pub mod risk;
+mod sandbox;
+pub mod schema;
+pub mod symbols;
 pub mod temporal;
+pub mod terminal;
 pub mod test_intents;
+pub mod test_plan;
+pub mod test_results;
+pub mod test_runners;
+#[cfg(test)]
+mod test_support;
+mod timestamps;
 pub mod types;
+pub mod upstream;
+pub mod watch;
 
+// libgit2, bundled SQLite and notify have no wasm32 support; fail with a
+// clear message rather than a C toolchain error deep in a dependency
+#[cfg(target_arch = "wasm32")]
+compile_error!("engram-core needs libgit2 and SQLite and does not build for wasm32");
+
+use std::io::Write;
 use std::path::Path;
+use std::time::{Duration, Instant};
 
-use persistence::Database;
-use types::{AddNoteResponse, AnalysisResponse, ListNotesResponse, MetricsResponse, SearchNotesResponse};
+pub use engram::Engram;
+use persistence::{Database, Page};
+use types::{
+    AddNoteResponse, AlertsResponse, AnalysisResponse, BusFactorResponse, CheckResponse, ClustersResponse,
+    CollabGraphResponse, CompactResponse, CompareResponse, CouplingEvidence, CouplingGraph, DashboardSnapshot,
+    DoctorReport, ExpertsResponse, ImportTestResultsResponse, InstallHooksResponse, ListNotesResponse,
+    MatrixExportResponse, MetricsResponse, MoveSuggestionsResponse, OrientationBrief, ReindexResponse,
+    SearchNotesResponse, StatusResponse, TestsToRunResponse, WatchUpdate,
+};
 
 /// Result of an analysis call, including whether background indexing is needed.
 pub struct AnalyzeResult {
@@ -21,11 +76,104 @@ pub struct AnalyzeResult {
     pub file_path: String,
 }
 
+/// Open the repo's database. If it was built from a different repository
+/// (fingerprint mismatch), it is moved aside and a fresh one is created, so
+/// another repo's coupling data is never served. Notes are written by
+/// people rather than derived from history, so they are carried over.
 fn open_db(repo_root: &Path) -> Result<Database, Box<dyn std::error::Error>> {
     let engram_dir = repo_root.join(".engram");
     std::fs::create_dir_all(&engram_dir)?;
     let db_path = engram_dir.join("engram.db");
-    Ok(Database::open(&db_path)?)
+    let db = Database::open(&db_path)?;
+
+    let Ok(repo) = git2::Repository::open(repo_root) else {
+        return Ok(db);
+    };
+    if fingerprint::verify(&repo, &db)? {
+        return Ok(db);
+    }
+
+    drop(db);
+    let moved = fingerprint::set_aside(&db_path)?;
+    let db = Database::open(&db_path)?;
+    let notes = db.copy_memories_from(&Database::open(&moved)?)?;
+    eprintln!(
+        "Warning: {} belongs to a different repository; moved it to {} and starting a fresh index \
+         (kept {notes} note(s))",
+        db_path.display(),
+        moved.display()
+    );
+    fingerprint::verify(&repo, &db)?;
+    Ok(db)
+}
+
+/// Fill in authors missing from an index built before they were recorded.
+fn backfill_authors(repo_root: &Path, db: &Database) -> Result<(), Box<dyn std::error::Error>> {
+    if let Ok(repo) = git2::Repository::open(repo_root) {
+        authors::backfill_authors(db, &repo)?;
+    }
+    Ok(())
+}
+
+/// Optional inputs to `analyze_with_options`.
+#[derive(Debug, Clone, Default)]
+pub struct AnalyzeOptions {
+    /// Caller-provided content for the analyzed file (e.g. an unsaved editor
+    /// buffer). Used for content-dependent enrichment instead of the file on
+    /// disk; coupling still comes from git history.
+    pub content: Option<String>,
+    /// Skip every enrichment step that reads the working tree (test
+    /// discovery and test intents), for hosts analyzing untrusted repos.
+    pub no_fs: bool,
+    /// Trim the response to this size with `budget::prioritize_context`.
+    pub context_budget: Option<budget::ContextBudget>,
+    /// Do not record the analysis in the usage metrics.
+    pub no_metrics: bool,
+    /// Restrict indexing and coupling to this subtree, overriding the
+    /// `scope` config. Changing scope rebuilds the index.
+    pub scope: Option<String>,
+    /// Analyze coupling as of this revision (`origin/main`) instead of
+    /// HEAD. Switching revisions rebuilds the index.
+    pub rev: Option<String>,
+    /// Count files the same author changed within this long of the
+    /// analyzed file as coupled, overriding `coupling_window_hours`.
+    pub coupling_window: Option<std::time::Duration>,
+    /// Drop couplings this likely to be chance, overriding `max_p_value`.
+    pub max_p_value: Option<f64>,
+    /// Half-life of co-change weight in risk scores, overriding
+    /// `coupling_half_life_days`.
+    pub half_life: Option<std::time::Duration>,
+    /// Coupling hops to follow; 2 adds files coupled with the top coupled
+    /// files, attenuated and marked with the file they were reached via.
+    pub depth: Option<u32>,
+    /// Blame the analyzed file to find the line regions each coupled file
+    /// co-changes with.
+    pub regions: bool,
+    /// Report at most this many coupled files, instead of the default ten.
+    pub limit: Option<usize>,
+    /// Report every coupled file, however many; overrides `limit`.
+    pub no_limit: bool,
+    /// Skip this many of the highest-risk coupled files, to page through
+    /// the rest.
+    pub offset: usize,
+    /// Only report coupled files matching one of these globs (`src/**`).
+    pub include: Vec<String>,
+    /// Never report coupled files matching these globs (`docs/**`).
+    pub exclude: Vec<String>,
+    /// Only report coupled files with at least this coupling score.
+    pub min_coupling: Option<f64>,
+    /// Only report coupled files with at least this risk score.
+    pub min_risk: Option<f64>,
+    /// Only report coupled files in another package than the analyzed file
+    /// (`CoupledFile::cross_package`).
+    pub cross_package: bool,
+}
+
+/// Options for `add_note_with_options`.
+#[derive(Debug, Clone, Default)]
+pub struct NoteOptions {
+    /// Do not record the note in the usage metrics.
+    pub no_metrics: bool,
 }
 
 /// Main entry point for analysis. Opens/creates the SQLite database
@@ -35,14 +183,142 @@ pub fn analyze(
     repo_root: &Path,
     file_path: &str,
 ) -> Result<AnalyzeResult, Box<dyn std::error::Error>> {
-    let db = open_db(repo_root)?;
-    let (mut response, needs_background) = temporal::analyze(repo_root, file_path, &db)?;
-    knowledge::enrich_with_memories(&db, &mut response.coupled_files);
-    test_intents::enrich_with_test_intents(repo_root, &mut response.coupled_files);
-    response.test_info = test_intents::discover_test_info(repo_root, file_path);
+    analyze_with_options(repo_root, file_path, &AnalyzeOptions::default())
+}
+
+/// `analyze` with caller-provided options.
+pub fn analyze_with_options(
+    repo_root: &Path,
+    file_path: &str,
+    options: &AnalyzeOptions,
+) -> Result<AnalyzeResult, Box<dyn std::error::Error>> {
+    analyze_in(&open_db(repo_root)?, repo_root, file_path, options)
+}
+
+/// `analyze_with_options` against an already open database.
+fn analyze_in(
+    db: &Database,
+    repo_root: &Path,
+    file_path: &str,
+    options: &AnalyzeOptions,
+) -> Result<AnalyzeResult, Box<dyn std::error::Error>> {
+    let start = Instant::now();
+    let config = config::Config::load(repo_root)?;
+    let mut index_options = indexing::IndexOptions::load(repo_root, &config);
+    if options.no_metrics {
+        index_options.metrics_repo_root = None;
+    }
+    if let Some(scope) = &options.scope {
+        index_options = index_options.with_scope(scope);
+    }
+    if let Some(rev) = &options.rev {
+        index_options = index_options.with_rev(rev);
+    }
+    if options.coupling_window.is_some() {
+        index_options.coupling_window = options.coupling_window;
+    }
+    if options.max_p_value.is_some() {
+        index_options.max_p_value = options.max_p_value;
+    }
+    if options.half_life.is_some() {
+        index_options.half_life = options.half_life;
+    }
+    if let Some(depth) = options.depth {
+        index_options.depth = depth;
+    }
+    index_options.paths = ignore_rules::PathFilter::new(&options.include, &options.exclude)?;
+    index_options.min_coupling = options.min_coupling;
+    index_options.min_risk = options.min_risk;
+    index_options.cross_package_only = options.cross_package;
+    if options.no_limit {
+        index_options.limit = None;
+    } else if options.limit.is_some() {
+        index_options.limit = options.limit;
+    }
+    // Score through the end of the page, then drop the pages before it
+    let page = Page { offset: options.offset, limit: index_options.limit };
+    index_options.limit = page.probe().limit.map(|limit| page.offset + limit);
+    let (mut response, needs_background) = temporal::analyze(repo_root, file_path, db, &index_options)?;
+    response.coupled_files.drain(..page.offset.min(response.coupled_files.len()));
+    response.next_offset = page.finish(&mut response.coupled_files);
+    let mut timings = response.timings.take().unwrap_or_default();
+    let phase = Instant::now();
+    let repo = git2::Repository::open(repo_root).ok();
+    knowledge::enrich_with_memories(db, &mut response.coupled_files);
+    if let Some(repo) = &repo {
+        knowledge::mark_note_drift(repo, db, &mut response.coupled_files);
+    }
+    if let Some(path) = global_db_path().filter(|path| path.exists()) {
+        match Database::open(&path) {
+            Ok(global) => knowledge::enrich_with_global_notes(&global, &mut response.coupled_files),
+            Err(e) => eprintln!("Warning: Failed to open global notes {}: {}", path.display(), e),
+        }
+    }
+    timings.enrich_memories_ms = phase.elapsed().as_millis() as u64;
+    // Drift is relative to the checked-out branch, not another revision
+    if let Some(repo) = &repo
+        && options.rev.is_none()
+        && let Err(e) = upstream::mark_upstream_drift(repo, &mut response)
+    {
+        eprintln!("Warning: Failed to compare with upstream: {}", e);
+    }
+    let mut tests_time = Duration::ZERO;
+    if !options.no_fs && !config.no_fs {
+        let source = options.content.clone().or_else(|| sandbox::read_repo_file(repo_root, file_path));
+        import_graph::mark_static_links(repo_root, file_path, source.as_deref(), &mut response.coupled_files);
+        let phase = Instant::now();
+        let mut patterns = test_intents::TestPatterns::from_config(&config.test_patterns)
+            .with_test_runners(repo_root, phase + test_intents::TEST_DISCOVERY_BUDGET)
+            .with_import_scan(config.test_discovery == config::TestDiscovery::Imports)
+            .with_intent_selection(config.max_intents_per_file, config.intent_selection);
+        if config.intent_selection == config::IntentSelection::Symbols
+            && let Some(source) = &source
+        {
+            patterns = patterns.with_target_symbols(symbols::extract_symbols(source));
+        }
+        test_intents::enrich_with_test_intents(repo_root, &mut response.coupled_files, &patterns);
+        response.test_info = test_intents::discover_test_info_with_content(
+            repo_root,
+            file_path,
+            source.as_deref(),
+            &patterns,
+            Instant::now() + test_intents::TEST_DISCOVERY_BUDGET,
+        );
+        tests_time += phase.elapsed();
+    }
+    let phase = Instant::now();
+    if let Err(e) = test_results::mark_recent_failures(db, &mut response, indexing::unix_now()) {
+        eprintln!("Warning: Failed to read imported test results: {}", e);
+    }
+    if let Err(e) = test_results::mark_stale_tests(db, &mut response, config.stale_test_commits) {
+        eprintln!("Warning: Failed to check for stale tests: {}", e);
+    }
+    timings.enrich_tests_ms = (tests_time + phase.elapsed()).as_millis() as u64;
+    if options.regions
+        && let Err(e) = blame::annotate_regions(repo_root, db, options.rev.as_deref(), &mut response)
+    {
+        eprintln!("Warning: Failed to blame {}: {}", file_path, e);
+    }
+    budget::apply_enrichment_caps(&mut response, &config);
+    // External enrichers are commands from repo config, so they are held to
+    // the same trust decision as reading the working tree.
+    if !options.no_fs && !config.no_fs {
+        enrichers::run_enrichers(repo_root, &config.enrichers, &mut response);
+    }
+    if let Some(context_budget) = options.context_budget {
+        response = budget::prioritize_context(&response, context_budget);
+    }
+    timings.total_ms = start.elapsed().as_millis() as u64;
+    timings.other_ms = timings.total_ms.saturating_sub(
+        timings.index_ms + timings.query_ms + timings.enrich_memories_ms + timings.enrich_tests_ms,
+    );
+    response.timings = Some(timings);
 
     // Record metrics (non-blocking - errors are logged but don't fail the analysis)
-    if let Err(e) = metrics::record_analysis_event(&db, &response, &repo_root.to_string_lossy()) {
+    if !options.no_metrics
+        && !config.no_metrics
+        && let Err(e) = metrics::record_analysis_event(db, &response, &repo_root.to_string_lossy())
+    {
         eprintln!("Warning: Failed to record analysis metrics: {}", e);
     }
 
@@ -60,31 +336,114 @@ pub fn add_note(
     symbol_name: Option<&str>,
     content: &str,
 ) -> Result<AddNoteResponse, Box<dyn std::error::Error>> {
-    let db = open_db(repo_root)?;
-    let response = knowledge::add_note(&db, file_path, symbol_name, content)?;
+    add_note_with_options(repo_root, file_path, symbol_name, content, &NoteOptions::default())
+}
+
+/// `add_note` with caller-provided options.
+pub fn add_note_with_options(
+    repo_root: &Path,
+    file_path: &str,
+    symbol_name: Option<&str>,
+    content: &str,
+    options: &NoteOptions,
+) -> Result<AddNoteResponse, Box<dyn std::error::Error>> {
+    add_note_in(&open_db(repo_root)?, repo_root, file_path, symbol_name, content, options)
+}
+
+/// `add_note_with_options` against an already open database.
+fn add_note_in(
+    db: &Database,
+    repo_root: &Path,
+    file_path: &str,
+    symbol_name: Option<&str>,
+    content: &str,
+    options: &NoteOptions,
+) -> Result<AddNoteResponse, Box<dyn std::error::Error>> {
+    let config = config::Config::load(repo_root)?;
+    let origin = git2::Repository::open(repo_root)
+        .map(|repo| knowledge::note_origin(&repo, file_path))
+        .unwrap_or_default();
+    let response = knowledge::add_note(db, file_path, symbol_name, content, &origin)?;
 
     // Record metrics (non-blocking - errors are logged but don't fail the note creation)
-    if let Err(e) = metrics::record_note_event(&db, response.id, &response.file_path, &repo_root.to_string_lossy()) {
+    if !options.no_metrics
+        && !config.no_metrics
+        && let Err(e) = metrics::record_note_event(db, response.id, &response.file_path, &repo_root.to_string_lossy())
+    {
         eprintln!("Warning: Failed to record note metrics: {}", e);
     }
 
     Ok(response)
 }
 
+/// The user-level notes database shared by every repository:
+/// `$ENGRAM_GLOBAL_DB`, else `engram/notes.db` under `$XDG_DATA_HOME` or
+/// `~/.local/share`.
+pub fn global_db_path() -> Option<std::path::PathBuf> {
+    if let Some(path) = std::env::var_os("ENGRAM_GLOBAL_DB") {
+        return Some(path.into());
+    }
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .map(std::path::PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local").join("share")))?;
+    Some(data_home.join("engram").join("notes.db"))
+}
+
+/// Save a note to the user-level database, where it applies to every file
+/// matching the glob `pattern` in any repository. Attributed to the git
+/// identity configured for `repo_root`.
+pub fn add_global_note(
+    repo_root: &Path,
+    pattern: &str,
+    symbol_name: Option<&str>,
+    content: &str,
+) -> Result<AddNoteResponse, Box<dyn std::error::Error>> {
+    globset::Glob::new(pattern)?;
+    let path = global_db_path().ok_or("cannot locate the global notes database: HOME is not set")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let global = Database::open(&path)?;
+    let signature = git2::Repository::open(repo_root).and_then(|repo| repo.signature()).ok();
+    let origin = persistence::NoteOrigin {
+        author_name: signature.as_ref().and_then(|s| s.name()).map(String::from),
+        author_email: signature.as_ref().and_then(|s| s.email()).map(String::from),
+        ..Default::default()
+    };
+    knowledge::add_note(&global, pattern, symbol_name, content, &origin)
+}
+
+/// List the user-level notes.
+pub fn list_global_notes(page: Page) -> Result<ListNotesResponse, Box<dyn std::error::Error>> {
+    let mut response = match global_db_path().filter(|path| path.exists()) {
+        Some(path) => knowledge::list_notes(&Database::open(&path)?, None, None, page)?,
+        None => ListNotesResponse { file_path: None, memories: Vec::new(), next_offset: None },
+    };
+    for memory in &mut response.memories {
+        memory.global = true;
+    }
+    Ok(response)
+}
+
 pub fn search_notes(
     repo_root: &Path,
     query: &str,
+    page: Page,
 ) -> Result<SearchNotesResponse, Box<dyn std::error::Error>> {
     let db = open_db(repo_root)?;
-    knowledge::search_notes(&db, query)
+    knowledge::search_notes(&db, query, page)
 }
 
+/// List a page of notes, optionally only those on `file_path` and those
+/// added by `author` (git user name or email).
 pub fn list_notes(
     repo_root: &Path,
     file_path: Option<&str>,
+    author: Option<&str>,
+    page: Page,
 ) -> Result<ListNotesResponse, Box<dyn std::error::Error>> {
     let db = open_db(repo_root)?;
-    knowledge::list_notes(&db, file_path)
+    knowledge::list_notes(&db, file_path, author, page)
 }
 
 pub fn get_metrics(
@@ -93,3 +452,413 @@ pub fn get_metrics(
     let db = open_db(repo_root)?;
     metrics::get_metrics(&db, &repo_root.to_string_lossy())
 }
+
+/// `get_metrics` with a per-day or per-week series (for trend dashboards)
+/// and per-file analysis metrics.
+pub fn get_metrics_with_options(
+    repo_root: &Path,
+    options: &metrics::MetricsOptions,
+) -> Result<MetricsResponse, Box<dyn std::error::Error>> {
+    let db = open_db(repo_root)?;
+    metrics::get_metrics_with_options(&db, &repo_root.to_string_lossy(), options)
+}
+
+/// Report files and directories dominated by a single author, based on
+/// the authors recorded in the temporal index.
+pub fn bus_factor(
+    repo_root: &Path,
+    path_prefix: Option<&str>,
+) -> Result<BusFactorResponse, Box<dyn std::error::Error>> {
+    let db = open_db(repo_root)?;
+    backfill_authors(repo_root, &db)?;
+    authors::bus_factor(&db, &repo_root.to_string_lossy(), path_prefix)
+}
+
+/// Author x module contributions and author co-editing weights, from the
+/// authors recorded in the temporal index.
+pub fn collab_graph(
+    repo_root: &Path,
+    module_depth: u32,
+) -> Result<CollabGraphResponse, Box<dyn std::error::Error>> {
+    let db = open_db(repo_root)?;
+    backfill_authors(repo_root, &db)?;
+    Ok(authors::collab_graph(&db, &repo_root.to_string_lossy(), module_depth)?)
+}
+
+/// Suggest moving files whose strongest couplings all live in another
+/// directory, based on the temporal index.
+pub fn suggest_moves(
+    repo_root: &Path,
+    path_prefix: Option<&str>,
+) -> Result<MoveSuggestionsResponse, Box<dyn std::error::Error>> {
+    let db = open_db(repo_root)?;
+    Ok(placement::move_suggestions(&db, &repo_root.to_string_lossy(), path_prefix)?)
+}
+
+/// Write the co-change counts of every pair of indexed files, both under
+/// `path_prefix` when given, to `out` as `format`.
+pub fn export_matrix(
+    repo_root: &Path,
+    path_prefix: Option<&str>,
+    format: export::MatrixFormat,
+    out: impl Write + Send,
+) -> Result<MatrixExportResponse, Box<dyn std::error::Error>> {
+    let db = open_db(repo_root)?;
+    let pairs = db.co_change_matrix(path_prefix.unwrap_or(""))?;
+    export::write_matrix(&pairs, format, out)?;
+    Ok(MatrixExportResponse {
+        repo_root: repo_root.to_string_lossy().to_string(),
+        format: format.as_str().to_string(),
+        pairs: pairs.len(),
+    })
+}
+
+/// Group files into implicit modules by community detection over the
+/// co-change graph, flagging groups that span declared packages.
+pub fn clusters(
+    repo_root: &Path,
+    path_prefix: Option<&str>,
+) -> Result<ClustersResponse, Box<dyn std::error::Error>> {
+    let db = open_db(repo_root)?;
+    let package_roots = packages::package_roots(&git2::Repository::open(repo_root)?)?;
+    Ok(clusters::coupling_clusters(&db, &repo_root.to_string_lossy(), path_prefix, &package_roots)?)
+}
+
+/// Report couplings that became significant within the last `window_days`
+/// of indexed history.
+pub fn alerts(
+    repo_root: &Path,
+    window_days: u32,
+) -> Result<AlertsResponse, Box<dyn std::error::Error>> {
+    let db = open_db(repo_root)?;
+    alerts::coupling_alerts(&db, &repo_root.to_string_lossy(), window_days)
+}
+
+/// Build the coupling graph around a file, up to `depth` hops, from the
+/// existing temporal index.
+pub fn coupling_graph(
+    repo_root: &Path,
+    file_path: &str,
+    depth: u32,
+) -> Result<CouplingGraph, Box<dyn std::error::Error>> {
+    let db = open_db(repo_root)?;
+    Ok(graph::coupling_graph(&db, file_path, depth)?)
+}
+
+/// Drop and rebuild the temporal index over the whole history (or that of
+/// `options.from`), or resume an interrupted rebuild. Stops after
+/// `max_duration`, leaving a checkpoint.
+pub fn reindex(
+    repo_root: &Path,
+    options: &reindex::ReindexOptions,
+) -> Result<ReindexResponse, Box<dyn std::error::Error>> {
+    let db = open_db(repo_root)?;
+    reindex::reindex(repo_root, &db, options)
+}
+
+/// Shrink `.engram/engram.db`: vacuum, `ANALYZE`, and WAL checkpoint.
+pub fn compact(repo_root: &Path) -> Result<CompactResponse, Box<dyn std::error::Error>> {
+    // Open once for the fingerprint check, which may set a stale DB aside
+    drop(open_db(repo_root)?);
+    maintenance::compact(&repo_root.join(".engram").join("engram.db"), &repo_root.to_string_lossy())
+}
+
+/// Indexing progress and database stats for `repo_root`.
+pub fn status(repo_root: &Path) -> Result<StatusResponse, Box<dyn std::error::Error>> {
+    drop(open_db(repo_root)?);
+    maintenance::status(repo_root, &repo_root.join(".engram").join("engram.db"))
+}
+
+/// Index new commits as they land in `repo_root`, calling `on_update`
+/// after each catch-up. Runs until the file watcher fails.
+pub fn watch(
+    repo_root: &Path,
+    options: &watch::WatchOptions,
+    on_update: impl FnMut(&WatchUpdate),
+) -> Result<(), Box<dyn std::error::Error>> {
+    drop(open_db(repo_root)?);
+    watch::watch(repo_root, &repo_root.join(".engram").join("engram.db"), options, on_update)
+}
+
+/// Check the pending commit (staged files, or all uncommitted changes) for
+/// strongly coupled files it leaves out.
+pub fn check(repo_root: &Path, options: &check::CheckOptions) -> Result<CheckResponse, Box<dyn std::error::Error>> {
+    let db = open_db(repo_root)?;
+    let config = config::Config::load(repo_root)?;
+    check::check(repo_root, &db, &indexing::IndexOptions::load(repo_root, &config), options)
+}
+
+/// The tests to run for a change to `file_path`, or, without one, to the
+/// pending commit (staged files, or all uncommitted changes), with a
+/// runnable command per test framework.
+pub fn tests_to_run(
+    repo_root: &Path,
+    file_path: Option<&str>,
+    staged: bool,
+) -> Result<TestsToRunResponse, Box<dyn std::error::Error>> {
+    let db = open_db(repo_root)?;
+    let config = config::Config::load(repo_root)?;
+    let opts = indexing::IndexOptions::load(repo_root, &config);
+    let mut patterns = test_intents::TestPatterns::from_config(&config.test_patterns);
+    if !config.no_fs {
+        patterns = patterns.with_test_runners(repo_root, Instant::now() + test_intents::TEST_DISCOVERY_BUDGET);
+    }
+    let files = match file_path {
+        Some(file_path) => vec![file_path.to_string()],
+        None => check::changed_files(&git2::Repository::open(repo_root)?, staged)?,
+    };
+    test_plan::tests_to_run(repo_root, &db, &opts, &patterns, &files)
+}
+
+/// Index the commits that landed since the last run. `None` until the
+/// first analysis has built an index to keep current.
+pub fn update_index(repo_root: &Path) -> Result<Option<WatchUpdate>, Box<dyn std::error::Error>> {
+    let db_path = repo_root.join(".engram").join("engram.db");
+    if !db_path.exists() {
+        return Ok(None); // Hooks run on every commit; don't create .engram/ for them
+    }
+    drop(open_db(repo_root)?);
+    watch::update(repo_root, &db_path, indexing::BACKGROUND_BUDGET)
+}
+
+/// Install git hooks that run `engram-core update` (this binary) after
+/// commits, merges and rewrites.
+pub fn install_hooks(repo_root: &Path) -> Result<InstallHooksResponse, Box<dyn std::error::Error>> {
+    hooks::install_hooks(repo_root, &std::env::current_exe()?)
+}
+
+/// Check `.engram/engram.db` for corruption, an index that no longer
+/// matches the repo's history, and wasted space, suggesting a fix for each.
+pub fn doctor(repo_root: &Path) -> Result<DoctorReport, Box<dyn std::error::Error>> {
+    maintenance::doctor(repo_root, &repo_root.join(".engram").join("engram.db"))
+}
+
+/// Import JUnit XML reports so that recently failing tests are flagged in
+/// later analyses.
+pub fn import_test_results(
+    repo_root: &Path,
+    reports: &[String],
+) -> Result<ImportTestResultsResponse, Box<dyn std::error::Error>> {
+    let db = open_db(repo_root)?;
+    test_results::import_junit(&db, repo_root, reports, indexing::unix_now())
+}
+
+/// Build an orientation brief for a file: ownership, age, top coupled files,
+/// notes, and recent commit subjects. Returns (brief, needs_background).
+pub fn orient(
+    repo_root: &Path,
+    file_path: &str,
+) -> Result<(OrientationBrief, bool), Box<dyn std::error::Error>> {
+    let db = open_db(repo_root)?;
+    let config = config::Config::load(repo_root)?;
+    let opts = indexing::IndexOptions::load(repo_root, &config);
+    orient::orient(repo_root, file_path, &db, &opts)
+}
+
+/// List the commits behind the coupling between two files. Returns
+/// (evidence, needs_background).
+pub fn why(
+    repo_root: &Path,
+    file_path: &str,
+    coupled_path: &str,
+) -> Result<(CouplingEvidence, bool), Box<dyn std::error::Error>> {
+    let db = open_db(repo_root)?;
+    let config = config::Config::load(repo_root)?;
+    let opts = indexing::IndexOptions::load(repo_root, &config);
+    evidence::why(repo_root, file_path, coupled_path, &db, &opts)
+}
+
+/// Suggest up to `limit` reviewers for a change to `file_path`, from the
+/// authors of it and its top coupled files. Indexes the same way `analyze`
+/// does. Returns (experts, needs_background_indexing).
+pub fn experts(
+    repo_root: &Path,
+    file_path: &str,
+    limit: usize,
+) -> Result<(ExpertsResponse, bool), Box<dyn std::error::Error>> {
+    let db = open_db(repo_root)?;
+    let config = config::Config::load(repo_root)?;
+    let opts = indexing::IndexOptions::load(repo_root, &config);
+    let (analysis, needs_background) = temporal::analyze(repo_root, file_path, &db, &opts)?;
+    backfill_authors(repo_root, &db)?;
+    let response = ExpertsResponse {
+        file_path: file_path.to_string(),
+        repo_root: repo_root.to_string_lossy().to_string(),
+        experts: authors::experts(&db, file_path, &analysis.coupled_files, limit)?,
+    };
+    Ok((response, needs_background))
+}
+
+/// How the couplings of `file_path` changed between `from` and `to`.
+/// Indexes the same way `analyze` does. Returns (comparison,
+/// needs_background_indexing).
+pub fn compare(
+    repo_root: &Path,
+    file_path: &str,
+    from: &str,
+    to: &str,
+) -> Result<(CompareResponse, bool), Box<dyn std::error::Error>> {
+    let db = open_db(repo_root)?;
+    let config = config::Config::load(repo_root)?;
+    let opts = indexing::IndexOptions::load(repo_root, &config);
+    let (_, needs_background) = temporal::analyze(repo_root, file_path, &db, &opts)?;
+    let repo = git2::Repository::open(repo_root)?;
+    let response = compare::compare(&repo, &db, &opts, &repo_root.to_string_lossy(), file_path, from, to)?;
+    Ok((response, needs_background))
+}
+
+/// Read-only dashboard data: index status, hotspots, recent analyses and
+/// notes. Opens the database read-only, so it never creates or migrates it.
+pub fn dashboard(repo_root: &Path) -> Result<DashboardSnapshot, Box<dyn std::error::Error>> {
+    let db_path = repo_root.join(".engram").join("engram.db");
+    if !db_path.exists() {
+        return Err(format!("no engram database at {}; run analyze first", db_path.display()).into());
+    }
+    Ok(dashboard::snapshot(&Database::open_read_only(&db_path)?, repo_root)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::{Repository, Signature};
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// A repo with `src/auth.ts` and its test, committed once.
+    fn auth_repo() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/auth.ts"), "export function login() {}\n").unwrap();
+        fs::write(dir.path().join("src/auth.test.ts"), "it('login succeeds', () => {});\n").unwrap();
+        let sig = Signature::now("Test", "test@test.com").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None).unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "init", &tree, &[]).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_analyze_populates_test_info() {
+        let dir = auth_repo();
+        let result = analyze(dir.path(), "src/auth.ts").unwrap();
+
+        let info = result.response.test_info.unwrap();
+        assert_eq!(info.test_files[0].path, "src/auth.test.ts");
+        assert_eq!(info.test_files[0].test_intents[0].covers_symbols, vec!["login"]);
+        assert_eq!(info.coverage_hint.as_deref(), Some("1 test covering a 1-line source file"));
+        assert!(!info.partial);
+    }
+    #[test]
+    fn test_phase_timings_add_up() {
+        let dir = auth_repo();
+        // An enricher that takes a while and produces nothing: only other_ms
+        // has room for it
+        fs::create_dir_all(dir.path().join(".engram")).unwrap();
+        fs::write(
+            dir.path().join(".engram/config.json"),
+            r#"{"enrichers": [{"name": "slow", "command": ["sh", "-c", "sleep 0.1"]}]}"#,
+        )
+        .unwrap();
+
+        let response = analyze(dir.path(), "src/auth.ts").unwrap().response;
+        let t = response.timings.unwrap();
+        assert_eq!(t.index_ms + t.query_ms, response.analysis_time_ms);
+        assert_eq!(t.index_ms + t.query_ms + t.enrich_memories_ms + t.enrich_tests_ms + t.other_ms, t.total_ms);
+        assert!(t.other_ms >= 100, "{t:?}");
+    }
+
+    #[test]
+    fn test_foreign_database_is_reset_but_keeps_notes() {
+        let a = auth_repo();
+        analyze(a.path(), "src/auth.ts").unwrap();
+        add_note(a.path(), "src/auth.ts", None, "login is rate limited").unwrap();
+
+        let b = TempDir::new().unwrap();
+        let repo = Repository::init(b.path()).unwrap();
+        let sig = Signature::now("Test", "test@test.com").unwrap();
+        let tree = repo.find_tree(repo.index().unwrap().write_tree().unwrap()).unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "another project", &tree, &[]).unwrap();
+        fs::create_dir_all(b.path().join(".engram")).unwrap();
+        for name in ["engram.db", "engram.db-wal"] {
+            let from = a.path().join(".engram").join(name);
+            if from.exists() {
+                fs::copy(from, b.path().join(".engram").join(name)).unwrap();
+            }
+        }
+
+        let db = open_db(b.path()).unwrap();
+        assert_eq!(db.indexed_commit_count().unwrap(), 0);
+        let notes = db.memories_for_file("src/auth.ts").unwrap();
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].content, "login is rate limited");
+    }
+
+    #[test]
+    fn test_analyze_pages_through_coupled_files() {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let sig = Signature::now("Test", "test@test.com").unwrap();
+        // Three commits of a.rs, each with more of the other files
+        let mut parent: Option<git2::Oid> = None;
+        for round in 1..=3 {
+            for file in ["a.rs", "b.rs", "c.rs", "d.rs"].iter().take(round + 1) {
+                fs::write(dir.path().join(file), format!("{round}")).unwrap();
+            }
+            let mut index = repo.index().unwrap();
+            index.add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None).unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let parents: Vec<git2::Commit> = parent.map(|p| repo.find_commit(p).unwrap()).into_iter().collect();
+            let parent_refs: Vec<&git2::Commit> = parents.iter().collect();
+            parent = Some(repo.commit(Some("HEAD"), &sig, &sig, "change", &tree, &parent_refs).unwrap());
+        }
+
+        let page = |offset| AnalyzeOptions { limit: Some(2), offset, ..Default::default() };
+        let first = analyze_with_options(dir.path(), "a.rs", &page(0)).unwrap().response;
+        let paths: Vec<&str> = first.coupled_files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec!["b.rs", "c.rs"]);
+        assert_eq!(first.next_offset, Some(2));
+
+        let last = analyze_with_options(dir.path(), "a.rs", &page(2)).unwrap().response;
+        let paths: Vec<&str> = last.coupled_files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec!["d.rs"]);
+        assert_eq!(last.next_offset, None);
+
+        // Filtered-out files do not take up slots under the limit
+        let exclude = AnalyzeOptions { limit: Some(2), exclude: vec!["b.*".to_string()], ..Default::default() };
+        let filtered = analyze_with_options(dir.path(), "a.rs", &exclude).unwrap().response;
+        let paths: Vec<&str> = filtered.coupled_files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec!["c.rs", "d.rs"]);
+        let strong = AnalyzeOptions { min_coupling: Some(0.5), ..Default::default() };
+        let filtered = analyze_with_options(dir.path(), "a.rs", &strong).unwrap().response;
+        let paths: Vec<&str> = filtered.coupled_files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec!["b.rs", "c.rs"]);
+        let risky = AnalyzeOptions { min_risk: Some(2.0), ..Default::default() };
+        assert!(analyze_with_options(dir.path(), "a.rs", &risky).unwrap().response.coupled_files.is_empty());
+        let include = AnalyzeOptions { include: vec!["[d".to_string()], ..Default::default() };
+        assert!(analyze_with_options(dir.path(), "a.rs", &include).is_err());
+    }
+
+    #[test]
+    fn test_no_metrics_skips_usage_events() {
+        let dir = auth_repo();
+        let options = AnalyzeOptions { no_metrics: true, ..Default::default() };
+        analyze_with_options(dir.path(), "src/auth.ts", &options).unwrap();
+        add_note_with_options(dir.path(), "src/auth.ts", None, "n1", &NoteOptions { no_metrics: true }).unwrap();
+        let summary = get_metrics(dir.path()).unwrap().summary;
+        assert_eq!((summary.total_analyses, summary.notes_created, summary.indexing_runs), (0, 0, 0));
+
+        fs::write(dir.path().join(".engram/config.json"), r#"{"no_metrics": true}"#).unwrap();
+        analyze(dir.path(), "src/auth.ts").unwrap();
+        add_note(dir.path(), "src/auth.ts", None, "n2").unwrap();
+        let summary = get_metrics(dir.path()).unwrap().summary;
+        assert_eq!((summary.total_analyses, summary.notes_created, summary.indexing_runs), (0, 0, 0));
+
+        fs::remove_file(dir.path().join(".engram/config.json")).unwrap();
+        analyze(dir.path(), "src/auth.ts").unwrap();
+        add_note(dir.path(), "src/auth.ts", None, "n3").unwrap();
+        let summary = get_metrics(dir.path()).unwrap().summary;
+        assert_eq!((summary.total_analyses, summary.notes_created, summary.indexing_runs), (1, 1, 1));
+    }
+}
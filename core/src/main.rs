@@ -1,63 +1,345 @@
 use clap::Parser;
+use std::io::IsTerminal;
 use std::path::Path;
 use std::process;
-use std::time::Duration;
 
-use engram_core::cli::{Cli, Command};
+use engram_core::check::FailOn;
+use engram_core::cli::{
+    CheckFormat, Cli, Command, DashboardFormat, FailOnLevel, GraphFormat, JsonFormat, MatrixFormat, MetricsGroupBy,
+    OutputFormat, TestResultsFormat,
+};
+use engram_core::export;
+use engram_core::persistence::Page;
+use engram_core::schema::{to_json, to_json_list};
+use engram_core::terminal;
+use engram_core::types::MetricsPeriod;
 
 /// Background task info: repo root + optional file path for PathFiltered indexing.
 struct BackgroundTask {
     repo_root: std::path::PathBuf,
     file_path: Option<String>,
+    /// Record the run in the usage metrics (not `--no-metrics`)
+    record_metrics: bool,
 }
 
-/// Run the requested command, returning (json_string, optional_background_task).
+/// Resolve a `--content` argument: `-` reads the content from stdin.
+fn read_content_arg(arg: String) -> std::io::Result<String> {
+    if arg != "-" {
+        return Ok(arg);
+    }
+    let mut buf = String::new();
+    std::io::Read::read_to_string(&mut std::io::stdin(), &mut buf)?;
+    Ok(buf)
+}
+
+/// Run the requested command, returning (output, optional_background_task).
 /// The background task continues indexing after stdout is flushed.
 fn run() -> Result<(String, Option<BackgroundTask>), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
+    // Readable output for a person at a terminal; scripts, pipes and the
+    // adapter keep getting JSON
+    let human = !cli.json && cli.output_format.is_none() && std::io::stdout().is_terminal();
+    let color = human && terminal::color_enabled();
+    let output_format = cli.output_format.unwrap_or(JsonFormat::Json);
 
     match cli.command {
-        Command::Analyze { file, repo_root } => {
-            let result = engram_core::analyze(Path::new(&repo_root), &file)?;
-            let json = serde_json::to_string(&result.response)?;
+        Command::Analyze {
+            file,
+            content,
+            no_fs,
+            no_metrics,
+            max_tokens,
+            scope,
+            rev,
+            coupling_window,
+            max_p_value,
+            half_life,
+            depth,
+            regions,
+            limit,
+            all,
+            offset,
+            include,
+            exclude,
+            min_coupling,
+            min_risk,
+            cross_package,
+            format,
+            repo_root,
+        } => {
+            let options = engram_core::AnalyzeOptions {
+                content: content.map(read_content_arg).transpose()?,
+                no_fs,
+                no_metrics,
+                context_budget: max_tokens.map(engram_core::budget::ContextBudget::Tokens),
+                scope,
+                rev,
+                coupling_window: coupling_window.map(|hours| std::time::Duration::from_secs(hours * 3600)),
+                max_p_value,
+                half_life: half_life.map(|days| std::time::Duration::from_secs(days * 86400)),
+                depth: Some(depth),
+                regions,
+                limit: Some(limit as usize),
+                no_limit: all,
+                offset,
+                include,
+                exclude,
+                min_coupling,
+                min_risk,
+                cross_package,
+            };
+            let result = engram_core::analyze_with_options(Path::new(&repo_root), &file, &options)?;
+            let output = match format {
+                None if human => terminal::render_analysis(&result.response, color),
+                None | Some(OutputFormat::Json) => to_json_list(output_format, &result.response, "coupled_files")?,
+                Some(OutputFormat::Markdown) => engram_core::markdown::render_analysis(&result.response),
+            };
             let bg = if result.needs_background {
                 Some(BackgroundTask {
                     repo_root: result.repo_root,
                     file_path: Some(result.file_path),
+                    record_metrics: !no_metrics,
                 })
             } else {
                 None
             };
-            Ok((json, bg))
+            Ok((output, bg))
+        }
+        Command::AddNote { file, symbol, content, global: true, repo_root, .. } => {
+            let response = engram_core::add_global_note(Path::new(&repo_root), &file, symbol.as_deref(), &content)?;
+            Ok((to_json(output_format, &response)?, None))
         }
-        Command::AddNote { file, symbol, content, repo_root } => {
-            let response = engram_core::add_note(
+        Command::AddNote { file, symbol, content, no_metrics, global: false, repo_root } => {
+            let response = engram_core::add_note_with_options(
                 Path::new(&repo_root),
                 &file,
                 symbol.as_deref(),
                 &content,
+                &engram_core::NoteOptions { no_metrics },
             )?;
-            Ok((serde_json::to_string(&response)?, None))
+            Ok((to_json(output_format, &response)?, None))
+        }
+        Command::SearchNotes { query, offset, limit, repo_root } => {
+            let response = engram_core::search_notes(Path::new(&repo_root), &query, Page { offset, limit })?;
+            if human {
+                return Ok((terminal::render_notes(&response.memories, color), None));
+            }
+            Ok((to_json_list(output_format, &response, "memories")?, None))
+        }
+        Command::ListNotes { global: true, offset, limit, .. } => {
+            let response = engram_core::list_global_notes(Page { offset, limit })?;
+            if human {
+                return Ok((terminal::render_notes(&response.memories, color), None));
+            }
+            Ok((to_json_list(output_format, &response, "memories")?, None))
+        }
+        Command::ListNotes { file, author, global: false, offset, limit, repo_root } => {
+            let page = Page { offset, limit };
+            let response = engram_core::list_notes(Path::new(&repo_root), file.as_deref(), author.as_deref(), page)?;
+            if human {
+                return Ok((terminal::render_notes(&response.memories, color), None));
+            }
+            Ok((to_json_list(output_format, &response, "memories")?, None))
+        }
+        Command::GetMetrics { group_by, file, repo_root } => {
+            let options = engram_core::metrics::MetricsOptions {
+                group_by: group_by.map(|g| match g {
+                    MetricsGroupBy::Day => MetricsPeriod::Day,
+                    MetricsGroupBy::Week => MetricsPeriod::Week,
+                }),
+                file,
+            };
+            let response = engram_core::get_metrics_with_options(Path::new(&repo_root), &options)?;
+            Ok((to_json(output_format, &response)?, None))
+        }
+        Command::BusFactor { path, repo_root } => {
+            let response = engram_core::bus_factor(Path::new(&repo_root), path.as_deref())?;
+            Ok((to_json(output_format, &response)?, None))
+        }
+        Command::CollabGraph { module_depth, repo_root } => {
+            let response = engram_core::collab_graph(Path::new(&repo_root), module_depth)?;
+            Ok((to_json(output_format, &response)?, None))
+        }
+        Command::SuggestMoves { path, repo_root } => {
+            let response = engram_core::suggest_moves(Path::new(&repo_root), path.as_deref())?;
+            Ok((to_json(output_format, &response)?, None))
+        }
+        Command::ExportMatrix { path, format, output, repo_root } => {
+            let format = match format {
+                MatrixFormat::Csv => export::MatrixFormat::Csv,
+                MatrixFormat::Parquet => export::MatrixFormat::Parquet,
+            };
+            let repo_root = Path::new(&repo_root);
+            match output {
+                Some(output) => {
+                    let file = std::io::BufWriter::new(std::fs::File::create(&output)?);
+                    let response = engram_core::export_matrix(repo_root, path.as_deref(), format, file)?;
+                    Ok((to_json(output_format, &response)?, None))
+                }
+                None if format == export::MatrixFormat::Parquet => {
+                    Err("Parquet output is binary; write it to a file with --output".into())
+                }
+                None => {
+                    let mut csv = Vec::new();
+                    engram_core::export_matrix(repo_root, path.as_deref(), format, &mut csv)?;
+                    // println adds the last line's newline back
+                    Ok((String::from_utf8(csv)?.trim_end().to_string(), None))
+                }
+            }
+        }
+        Command::Clusters { path, repo_root } => {
+            let response = engram_core::clusters(Path::new(&repo_root), path.as_deref())?;
+            Ok((to_json_list(output_format, &response, "clusters")?, None))
+        }
+        Command::Alerts { days, repo_root } => {
+            let response = engram_core::alerts(Path::new(&repo_root), days)?;
+            Ok((to_json_list(output_format, &response, "alerts")?, None))
         }
-        Command::SearchNotes { query, repo_root } => {
-            let response = engram_core::search_notes(Path::new(&repo_root), &query)?;
-            Ok((serde_json::to_string(&response)?, None))
+        Command::Reindex { full, from, max_minutes, repo_root } => {
+            let options = engram_core::reindex::ReindexOptions {
+                full,
+                from,
+                max_duration: max_minutes.map(|m| std::time::Duration::from_secs(m * 60)),
+            };
+            let response = engram_core::reindex(Path::new(&repo_root), &options)?;
+            Ok((to_json(output_format, &response)?, None))
+        }
+        Command::Compact { repo_root } => {
+            let response = engram_core::compact(Path::new(&repo_root))?;
+            Ok((to_json(output_format, &response)?, None))
         }
-        Command::ListNotes { file, repo_root } => {
-            let response = engram_core::list_notes(Path::new(&repo_root), file.as_deref())?;
-            Ok((serde_json::to_string(&response)?, None))
+        Command::Check { staged, fail, fail_on, min_risk_score, min_co_changes, format, repo_root } => {
+            let options = engram_core::check::CheckOptions {
+                staged,
+                min_risk_score,
+                min_co_changes,
+                fail_on: fail_on.map(|level| match level {
+                    FailOnLevel::Critical => FailOn::Critical,
+                    FailOnLevel::High => FailOn::High,
+                    FailOnLevel::Medium => FailOn::Medium,
+                }),
+            };
+            let response = engram_core::check(Path::new(&repo_root), &options)?;
+            let output = match format {
+                CheckFormat::Json => to_json(output_format, &response)?,
+                CheckFormat::Markdown => engram_core::markdown::render_check(&response),
+                CheckFormat::Github => engram_core::github::render_check_annotations(&response),
+            };
+            if (fail || fail_on.is_some()) && !response.passed {
+                println!("{output}");
+                process::exit(1);
+            }
+            Ok((output, None))
+        }
+        Command::TestsToRun { file, staged, repo_root } => {
+            let response = engram_core::tests_to_run(Path::new(&repo_root), file.as_deref(), staged)?;
+            if human {
+                return Ok((terminal::render_tests_to_run(&response, color), None));
+            }
+            Ok((to_json_list(output_format, &response, "tests")?, None))
         }
-        Command::GetMetrics { repo_root } => {
-            let response = engram_core::get_metrics(Path::new(&repo_root))?;
-            Ok((serde_json::to_string(&response)?, None))
+        Command::Update { repo_root } => {
+            let response = engram_core::update_index(Path::new(&repo_root))?;
+            Ok((to_json(output_format, &response)?, None))
+        }
+        Command::InstallHooks { repo_root } => {
+            let response = engram_core::install_hooks(Path::new(&repo_root))?;
+            Ok((to_json_list(output_format, &response, "hooks")?, None))
+        }
+        Command::Watch { debounce_ms, dashboard, repo_root } => {
+            let options = engram_core::watch::WatchOptions {
+                debounce: std::time::Duration::from_millis(debounce_ms),
+                dashboard,
+                ..Default::default()
+            };
+            engram_core::watch(Path::new(&repo_root), &options, |update| match to_json(output_format, update) {
+                Ok(line) => println!("{line}"),
+                Err(e) => eprintln!("Warning: could not serialize watch update: {e}"),
+            })?;
+            Ok((String::new(), None))
+        }
+        Command::Status { repo_root } => {
+            let response = engram_core::status(Path::new(&repo_root))?;
+            Ok((to_json(output_format, &response)?, None))
+        }
+        Command::Doctor { repo_root } => {
+            let response = engram_core::doctor(Path::new(&repo_root))?;
+            Ok((to_json(output_format, &response)?, None))
+        }
+        Command::ImportTestResults { format, files, repo_root } => {
+            let reports = files.iter().map(std::fs::read_to_string).collect::<Result<Vec<_>, _>>()?;
+            let response = match format {
+                TestResultsFormat::Junit => engram_core::import_test_results(Path::new(&repo_root), &reports)?,
+            };
+            Ok((to_json(output_format, &response)?, None))
+        }
+        Command::Why { file, coupled, format, repo_root } => {
+            let (evidence, needs_background) = engram_core::why(Path::new(&repo_root), &file, &coupled)?;
+            let output = match format {
+                OutputFormat::Json => to_json(output_format, &evidence)?,
+                OutputFormat::Markdown => engram_core::markdown::render_evidence(&evidence),
+            };
+            let bg = needs_background.then(|| BackgroundTask {
+                repo_root: repo_root.into(),
+                file_path: Some(file),
+                record_metrics: true,
+            });
+            Ok((output, bg))
+        }
+        Command::Compare { file, from, to, repo_root } => {
+            let (response, needs_background) = engram_core::compare(Path::new(&repo_root), &file, &from, &to)?;
+            let bg = needs_background.then(|| BackgroundTask {
+                repo_root: repo_root.into(),
+                file_path: Some(file),
+                record_metrics: true,
+            });
+            Ok((to_json(output_format, &response)?, bg))
+        }
+        Command::Experts { file, limit, repo_root } => {
+            let (response, needs_background) = engram_core::experts(Path::new(&repo_root), &file, limit)?;
+            let bg = needs_background.then(|| BackgroundTask {
+                repo_root: repo_root.into(),
+                file_path: Some(file),
+                record_metrics: true,
+            });
+            Ok((to_json_list(output_format, &response, "experts")?, bg))
+        }
+        Command::Orient { file, format, repo_root } => {
+            let (brief, needs_background) = engram_core::orient(Path::new(&repo_root), &file)?;
+            let output = match format {
+                OutputFormat::Json => to_json(output_format, &brief)?,
+                OutputFormat::Markdown => engram_core::markdown::render_orientation(&brief),
+            };
+            let bg = needs_background.then(|| BackgroundTask {
+                repo_root: repo_root.into(),
+                file_path: Some(file),
+                record_metrics: true,
+            });
+            Ok((output, bg))
+        }
+        Command::Dashboard { format, repo_root } => {
+            let snapshot = engram_core::dashboard(Path::new(&repo_root))?;
+            let output = match format {
+                DashboardFormat::Json => to_json(output_format, &snapshot)?,
+                DashboardFormat::Html => engram_core::dashboard::render_html(&snapshot),
+            };
+            Ok((output, None))
+        }
+        Command::Graph { file, depth, format, repo_root } => {
+            let graph = engram_core::coupling_graph(Path::new(&repo_root), &file, depth)?;
+            let output = match format {
+                GraphFormat::Json => to_json(output_format, &graph)?,
+                GraphFormat::Dot => engram_core::graph::to_dot(&graph),
+            };
+            Ok((output, None))
         }
     }
 }
 
 fn main() {
     match run() {
-        Ok((json, background_task)) => {
-            println!("{json}");
+        Ok((output, background_task)) => {
+            println!("{output}");
 
             // Flush stdout so the adapter sees the JSON immediately
             use std::io::Write;
@@ -66,18 +348,19 @@ fn main() {
             }
 
             // Background indexing (runs after adapter has received the response)
-            if let Some(task) = background_task {
-                if let Err(e) = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            if let Some(task) = background_task
+                && let Err(e) = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
                     if let Err(e) = engram_core::indexing::background_index(
                         &task.repo_root,
-                        Duration::from_secs(5),
+                        engram_core::indexing::BACKGROUND_BUDGET,
                         task.file_path.as_deref(),
+                        task.record_metrics,
                     ) {
                         eprintln!("Background indexing error: {e}");
                     }
-                })) {
-                    eprintln!("Background indexing panicked: {e:?}");
-                }
+                }))
+            {
+                eprintln!("Background indexing panicked: {e:?}");
             }
         }
         Err(e) => {
@@ -0,0 +1,352 @@
+use std::path::Path;
+use std::time::Instant;
+
+use git2::{Oid, Repository};
+
+use crate::indexing::{self, MAX_COMMIT_FAILURES};
+use crate::persistence::{Database, IndexingState};
+use crate::types::{CompactResponse, DoctorIssue, DoctorReport, IndexStateInfo, IndexingStatus, StatusResponse};
+
+/// Free space worth a `compact` suggestion: a quarter of the file, at
+/// least 1 MiB and at most 16 MiB.
+const COMPACT_FREE_BYTES: std::ops::RangeInclusive<u64> = 1024 * 1024..=16 * 1024 * 1024;
+
+/// An incomplete index untouched for this long is not being worked on.
+const STALLED_INDEX_SECS: i64 = 24 * 60 * 60;
+
+/// Shrink the database at `db_path`: reclaim free pages, refresh the query
+/// planner statistics, and fold the WAL back into the main file. Sizes are
+/// measured on disk before and after, including the WAL.
+pub fn compact(db_path: &Path, repo_root: &str) -> Result<CompactResponse, Box<dyn std::error::Error>> {
+    let start = Instant::now();
+    let bytes_before = on_disk_size(db_path);
+    {
+        let db = Database::open(db_path)?;
+        db.compact()?;
+    }
+    Ok(CompactResponse {
+        repo_root: repo_root.to_string(),
+        bytes_before,
+        bytes_after: on_disk_size(db_path),
+        elapsed_ms: start.elapsed().as_millis() as u64,
+    })
+}
+
+/// Check the database at `db_path` and the index state against the repo,
+/// and suggest fixes. Reads only; a missing, corrupt or unreadable database
+/// is reported as an issue rather than created, repaired or an error.
+pub fn doctor(repo_root: &Path, db_path: &Path) -> Result<DoctorReport, Box<dyn std::error::Error>> {
+    let mut report = DoctorReport {
+        repo_root: repo_root.to_string_lossy().to_string(),
+        healthy: true,
+        integrity: Vec::new(),
+        db_bytes: on_disk_size(db_path),
+        free_bytes: 0,
+        temporal_index_rows: 0,
+        indexed_commits: 0,
+        indexed_files: 0,
+        indexing_status: None,
+        indexed_head: None,
+        background_queue_len: 0,
+        quarantined_commits: 0,
+        issues: Vec::new(),
+    };
+    if !db_path.exists() {
+        report.issues.push(issue("No engram database yet", "run an analysis, or `engram-core reindex --full`"));
+        report.healthy = false;
+        return Ok(report);
+    }
+
+    // Read-only and without the schema setup `Database::open` runs, which
+    // would fail (or write) on exactly the databases doctor is for
+    let integrity = Database::open_read_only(db_path).and_then(|db| Ok((db.integrity_check()?, db)));
+    let db = match integrity {
+        Ok((integrity, db)) if integrity == ["ok"] => {
+            report.integrity = integrity;
+            db
+        }
+        Ok((integrity, _)) => return Ok(corrupt(report, integrity)),
+        Err(e) => return Ok(corrupt(report, vec![e.to_string()])),
+    };
+
+    let state = match read_tables(&db, &mut report) {
+        Ok(state) => state,
+        Err(e) => {
+            report.issues.push(issue(
+                &format!("Could not read the index tables (written by another engram version?): {e}"),
+                "run any engram-core command to migrate the database, or `engram-core reindex --full`",
+            ));
+            report.healthy = false;
+            return Ok(report);
+        }
+    };
+
+    let threshold = (report.db_bytes / 4).clamp(*COMPACT_FREE_BYTES.start(), *COMPACT_FREE_BYTES.end());
+    if report.free_bytes >= threshold {
+        report.issues.push(issue(
+            &format!("{} bytes of free pages", report.free_bytes),
+            "run `engram-core compact`",
+        ));
+    }
+    if report.quarantined_commits > 0 {
+        report.issues.push(issue(
+            &format!("{} commits skipped because their objects failed to load", report.quarantined_commits),
+            "fetch the missing objects (`git fetch --refetch`), then run `engram-core reindex --full`",
+        ));
+    }
+
+    match state {
+        None => report.issues.push(issue("Not indexed yet", "run an analysis, or `engram-core reindex --full`")),
+        Some(state) => {
+            let repo = Repository::open(repo_root)?;
+            let exists = |oid: &str| Oid::from_str(oid).is_ok_and(|oid| repo.find_commit(oid).is_ok());
+            if !exists(&state.head_commit) || state.resume_oid.as_deref().is_some_and(|oid| !exists(oid)) {
+                report.issues.push(issue(
+                    "Indexing state refers to commits that no longer exist (history rewritten?)",
+                    "run `engram-core reindex --full`",
+                ));
+            } else if !state.is_complete && indexing::unix_now() - state.last_updated > STALLED_INDEX_SECS {
+                report.issues.push(issue(
+                    &format!("Index stuck incomplete in the {} strategy", state.strategy),
+                    "run `engram-core reindex --full` to finish it in one go",
+                ));
+            }
+            if repo.is_shallow() {
+                report.issues.push(issue(
+                    "Shallow clone: only the fetched history is indexed, so older couplings are missing",
+                    "run `git fetch --unshallow`, then `engram-core reindex --full`",
+                ));
+            }
+            report.indexing_status = Some(IndexingStatus {
+                strategy: state.strategy,
+                commits_indexed: state.commits_indexed,
+                is_complete: state.is_complete,
+                complete_to_depth: state.is_complete && repo.is_shallow(),
+            });
+            report.indexed_head = Some(state.head_commit);
+        }
+    }
+
+    report.healthy = report.issues.is_empty();
+    Ok(report)
+}
+
+/// `report` for a database that failed to open or its integrity check.
+fn corrupt(mut report: DoctorReport, integrity: Vec<String>) -> DoctorReport {
+    report.integrity = integrity;
+    report.issues.push(issue(
+        "Database integrity check failed",
+        "back up .engram/engram.db (it holds your notes), delete it, and run `engram-core reindex --full`",
+    ));
+    report.healthy = false;
+    report
+}
+
+/// Fill in the table stats of `report`, returning the indexing state.
+fn read_tables(db: &Database, report: &mut DoctorReport) -> Result<Option<IndexingState>, rusqlite::Error> {
+    report.free_bytes = db.free_bytes()?;
+    (report.temporal_index_rows, report.indexed_commits, report.indexed_files) = db.index_row_counts()?;
+    report.background_queue_len = db.background_queue_len()?;
+    report.quarantined_commits = db.quarantined_commits(MAX_COMMIT_FAILURES)?.len() as u32;
+    db.get_indexing_state()
+}
+
+/// Report the indexing state and database stats for `repo_root`. Counting
+/// the indexed history walks it once, without diffing.
+pub fn status(repo_root: &Path, db_path: &Path) -> Result<StatusResponse, Box<dyn std::error::Error>> {
+    let db = Database::open(db_path)?;
+    let (temporal_index_rows, indexed_commits, indexed_files) = db.index_row_counts()?;
+    let indexing = match db.get_indexing_state()? {
+        Some(state) => {
+            let repo = Repository::open(repo_root)?;
+            let progress = history_progress(&repo, &state);
+            let total_commits = progress.map(|(_, total)| total);
+            let percent_complete = progress.map(|(walked, total)| match total {
+                0 => 100.0,
+                _ => (walked as f64 * 1000.0 / total as f64).round() / 10.0,
+            });
+            Some(IndexStateInfo {
+                strategy: state.strategy,
+                commits_indexed: state.commits_indexed,
+                is_complete: state.is_complete,
+                last_updated: state.last_updated,
+                target_path: state.target_path,
+                head_commit: state.head_commit,
+                total_commits,
+                shallow: repo.is_shallow(),
+                percent_complete,
+            })
+        }
+        None => None,
+    };
+    Ok(StatusResponse {
+        repo_root: repo_root.to_string_lossy().to_string(),
+        indexing,
+        reindex_in_progress: db.get_reindex_checkpoint()?.is_some(),
+        background_queue_len: db.background_queue_len()?,
+        db_bytes: on_disk_size(db_path),
+        temporal_index_rows,
+        indexed_commits,
+        indexed_files,
+    })
+}
+
+/// (commits walked, commits in history) for an index built at
+/// `state.head_commit`, walking in the same order indexing does. A walk
+/// that stopped without a resume point is measured by commits indexed.
+fn history_progress(repo: &Repository, state: &IndexingState) -> Option<(u32, u32)> {
+    let mut revwalk = repo.revwalk().ok()?;
+    revwalk.set_sorting(git2::Sort::TIME).ok()?;
+    revwalk.push(Oid::from_str(&state.head_commit).ok()?).ok()?;
+    let resume = state.resume_oid.as_deref().and_then(|oid| Oid::from_str(oid).ok());
+
+    let mut total = 0u32;
+    let mut resume_position = None;
+    for oid in revwalk {
+        total += 1;
+        if Some(oid.ok()?) == resume {
+            resume_position = Some(total);
+        }
+    }
+    let walked = match (state.is_complete, resume_position) {
+        (true, _) => total,
+        (false, Some(position)) => position,
+        (false, None) => state.commits_indexed.min(total),
+    };
+    Some((walked, total))
+}
+
+fn issue(problem: &str, fix: &str) -> DoctorIssue {
+    DoctorIssue { problem: problem.to_string(), fix: fix.to_string() }
+}
+
+/// Size of the database file plus its WAL and shared-memory files.
+fn on_disk_size(db_path: &Path) -> u64 {
+    ["", "-wal", "-shm"]
+        .iter()
+        .filter_map(|suffix| {
+            let mut path = db_path.as_os_str().to_owned();
+            path.push(suffix);
+            std::fs::metadata(path).ok()
+        })
+        .map(|m| m.len())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::commit_at;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_compact_reclaims_deleted_rows() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("engram.db");
+        {
+            let db = Database::open(&db_path).unwrap();
+            let files: Vec<String> = (0..50).map(|i| format!("src/some/long/directory/file_{i}.ts")).collect();
+            let files: Vec<&str> = files.iter().map(String::as_str).collect();
+            for i in 0..200 {
+                db.insert_commit(&format!("commit{i:036}"), &files, i).unwrap();
+            }
+            db.clear_index().unwrap();
+        }
+
+        let resp = compact(&db_path, "/repo").unwrap();
+        assert!(resp.bytes_after < resp.bytes_before, "{} -> {}", resp.bytes_before, resp.bytes_after);
+
+        // Compacting again still works once the database is incremental
+        let again = compact(&db_path, "/repo").unwrap();
+        assert!(again.bytes_after <= resp.bytes_after);
+        assert!(Database::open(&db_path).unwrap().get_indexing_state().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_status_reports_progress() {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let commits: Vec<Oid> =
+            (0..4).map(|i| commit_at(&repo, &["a.rs"], &format!("c{i}"), 1_700_000_000 + i)).collect();
+        let db_path = dir.path().join("engram.db");
+
+        assert!(status(dir.path(), &db_path).unwrap().indexing.is_none());
+
+        // Walked the two newest commits of four
+        Database::open(&db_path)
+            .unwrap()
+            .set_indexing_state(&IndexingState {
+                head_commit: commits[3].to_string(),
+                resume_oid: Some(commits[2].to_string()),
+                commits_indexed: 2,
+                strategy: "budgeted_global".to_string(),
+                is_complete: false,
+                last_updated: 1,
+                target_path: None,
+            })
+            .unwrap();
+
+        let resp = status(dir.path(), &db_path).unwrap();
+        let indexing = resp.indexing.unwrap();
+        assert_eq!(indexing.total_commits, Some(4));
+        assert_eq!(indexing.percent_complete, Some(50.0));
+        assert!(!resp.reindex_in_progress);
+        assert!(resp.db_bytes > 0);
+    }
+
+    #[test]
+    fn test_doctor_reports_orphaned_index_state() {
+        let dir = TempDir::new().unwrap();
+        Repository::init(dir.path()).unwrap();
+        let db_path = dir.path().join("engram.db");
+
+        let missing = doctor(dir.path(), &db_path).unwrap();
+        assert!(!missing.healthy);
+        assert!(!db_path.exists());
+
+        let db = Database::open(&db_path).unwrap();
+        db.insert_commit("c1", &["a.rs", "b.rs"], 1).unwrap();
+        db.set_indexing_state(&IndexingState {
+            head_commit: "1111111111111111111111111111111111111111".to_string(),
+            resume_oid: None,
+            commits_indexed: 1,
+            strategy: "full".to_string(),
+            is_complete: true,
+            last_updated: indexing::unix_now(),
+            target_path: None,
+        })
+        .unwrap();
+        drop(db);
+
+        let report = doctor(dir.path(), &db_path).unwrap();
+        assert_eq!(report.integrity, ["ok"]);
+        assert_eq!((report.temporal_index_rows, report.indexed_commits, report.indexed_files), (2, 1, 2));
+        assert!(!report.healthy);
+        assert_eq!(report.issues.len(), 1);
+        assert!(report.issues[0].problem.contains("no longer exist"));
+        assert_eq!(report.issues[0].fix, "run `engram-core reindex --full`");
+    }
+
+    #[test]
+    fn test_doctor_reports_unreadable_databases() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("engram.db");
+
+        std::fs::write(&db_path, vec![0xAB; 8192]).unwrap();
+        let report = doctor(dir.path(), &db_path).unwrap();
+        assert!(!report.healthy);
+        assert_eq!(report.issues[0].problem, "Database integrity check failed");
+        assert!(report.integrity[0].contains("not a database"), "{:?}", report.integrity);
+        assert_eq!(std::fs::read(&db_path).unwrap(), vec![0xAB; 8192]);
+
+        // A database without engram's tables is neither migrated nor created
+        std::fs::remove_file(&db_path).unwrap();
+        rusqlite::Connection::open(&db_path).unwrap().execute_batch("CREATE TABLE other (x);").unwrap();
+        let report = doctor(dir.path(), &db_path).unwrap();
+        assert_eq!(report.integrity, ["ok"]);
+        assert!(report.issues[0].problem.contains("no such table"), "{:?}", report.issues);
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        let tables: i64 = conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| row.get(0)).unwrap();
+        assert_eq!(tables, 1);
+    }
+}
@@ -0,0 +1,491 @@
+use crate::indexing::unix_now;
+use crate::timestamps::date;
+use crate::types::{
+    AnalysisResponse, CheckResponse, CoupledFile, CouplingEvidence, DiscoveredTestFile, OrientationBrief,
+    RiskThresholds, StaticLink, TestIntent, UpstreamDrift,
+};
+
+const RISK_LEVELS: [&str; 4] = ["Critical", "High", "Medium", "Low"];
+
+/// Render an analysis as a Markdown section ready to paste into a PR comment.
+///
+/// Layout: a summary line, a risk table linking to per-file detail headings,
+/// then one `###` heading per coupled file that carries notes or test intents,
+/// and finally the tests discovered for the analyzed file.
+pub fn render_analysis(response: &AnalysisResponse) -> String {
+    let mut out = format!("## Blast radius: `{}`\n\n", response.file_path);
+    out.push_str(&summary_line(response));
+    out.push_str("\n\n");
+    if response.indexing_status.as_ref().is_some_and(|s| s.complete_to_depth) {
+        out.push_str("> Shallow clone: only the fetched history was analyzed, so older couplings may be missing.\n\n");
+    }
+    if let Some(drift) = &response.upstream_drift {
+        out.push_str(&format!("> {}\n\n", upstream_line(drift)));
+    }
+
+    if response.coupled_files.is_empty() {
+        return out;
+    }
+
+    let thresholds = response.risk_thresholds.unwrap_or(RiskThresholds::FIXED);
+    out.push_str("| Risk | File | Score | Co-changes |\n");
+    out.push_str("| --- | --- | ---: | ---: |\n");
+    for file in &response.coupled_files {
+        let name = if has_details(file) {
+            format!("[`{}`](#{})", file.path, anchor(&file.path))
+        } else {
+            format!("`{}`", file.path)
+        };
+        let emerging = if file.emerging { " (emerging)" } else { "" };
+        let hidden = if file.static_link == Some(StaticLink::None) { " (no static link)" } else { "" };
+        let other_package = if file.cross_package == Some(true) { " (other package)" } else { "" };
+        let untested = if file.has_tests == Some(false) { " (no tests)" } else { "" };
+        let upstream = if file.upstream_commits > 0 { " (changed upstream)" } else { "" };
+        let name = format!(
+            "{name}{hidden}{other_package}{untested}{upstream}{}",
+            failing_marker(file.recently_failing)
+        );
+        out.push_str(&format!(
+            "| {}{emerging} | {name} | {:.2} | {} of {} |\n",
+            thresholds.level(file.risk_score),
+            file.risk_score,
+            file.co_change_count,
+            response.commit_count,
+        ));
+    }
+
+    for file in response.coupled_files.iter().filter(|f| has_details(f)) {
+        out.push_str(&format!("\n### `{}`\n", file.path));
+        if !file.regions.is_empty() {
+            let spans: Vec<String> = file
+                .regions
+                .iter()
+                .map(|r| {
+                    if r.start_line == r.end_line {
+                        format!("line {}", r.start_line)
+                    } else {
+                        format!("lines {}-{}", r.start_line, r.end_line)
+                    }
+                })
+                .collect();
+            out.push_str(&format!("\nCo-changes with {} of `{}`.\n", spans.join(", "), response.file_path));
+        }
+        if !file.memories.is_empty() {
+            out.push_str("\n**Notes**\n\n");
+            for memory in &file.memories {
+                out.push_str(&format!("- {}\n", memory.content));
+            }
+        }
+        if !file.test_intents.is_empty() {
+            out.push_str("\n**Current test behavior (may need updating)**\n\n");
+            for intent in &file.test_intents {
+                out.push_str(&format!("- {}\n", intent_line(intent)));
+            }
+        }
+    }
+
+    if let Some(info) = &response.test_info {
+        out.push_str("\n### Tests\n\n");
+        if let Some(hint) = &info.coverage_hint {
+            out.push_str(&format!("{hint}\n\n"));
+        }
+        for test_file in &info.test_files {
+            out.push_str(&format!(
+                "- `{}` ({} tests){}{}\n",
+                test_file.path,
+                test_file.test_count,
+                stale_marker(test_file),
+                failing_marker(test_file.recently_failing)
+            ));
+            for intent in &test_file.test_intents {
+                out.push_str(&format!("  - {}\n", intent_line(intent)));
+            }
+        }
+    }
+
+    out
+}
+
+/// Intent title with its parameterized case count and failure marker.
+pub(crate) fn intent_line(intent: &TestIntent) -> String {
+    let cases = if intent.cases > 0 { format!(" ({} cases)", intent.cases) } else { String::new() };
+    format!("{}{cases}{}", intent.title, failing_marker(intent.failing))
+}
+
+/// Suffix for tests that failed in a recently imported test run.
+pub(crate) fn failing_marker(failing: bool) -> &'static str {
+    if failing { " (failing)" } else { "" }
+}
+
+/// How far the upstream moved on, for a warning line.
+pub(crate) fn upstream_line(drift: &UpstreamDrift) -> String {
+    format!(
+        "`{}` has {} commit{} since this branch forked, {} of them changing this file.",
+        drift.upstream,
+        drift.commits,
+        if drift.commits == 1 { "" } else { "s" },
+        drift.file_commits,
+    )
+}
+
+/// Marker for a test file that has not kept up with its source.
+pub(crate) fn stale_marker(test_file: &DiscoveredTestFile) -> String {
+    match test_file.source_commits_since {
+        Some(since) if test_file.stale => format!(" (stale: unchanged for {since} source commits)"),
+        _ => String::new(),
+    }
+}
+
+/// Render an orientation brief as a compact Markdown section.
+pub fn render_orientation(brief: &OrientationBrief) -> String {
+    render_orientation_at(brief, unix_now())
+}
+
+fn render_orientation_at(brief: &OrientationBrief, now: i64) -> String {
+    let mut out = format!("## Orientation: `{}`\n\n", brief.file_path);
+
+    let mut facts = vec![format!(
+        "{} commit{}",
+        brief.commit_count,
+        if brief.commit_count == 1 { "" } else { "s" }
+    )];
+    if let Some(first) = brief.first_seen_at {
+        facts.push(format!("first seen {}", age(first, now)));
+    }
+    if let Some(last) = brief.last_changed_at {
+        facts.push(format!("last changed {}", age(last, now)));
+    }
+    if let Some(owner) = &brief.ownership {
+        facts.push(format!(
+            "mostly {} ({:.0}% of commits, {} author{})",
+            owner.dominant_author,
+            owner.dominant_share * 100.0,
+            owner.author_count,
+            if owner.author_count == 1 { "" } else { "s" }
+        ));
+    }
+    out.push_str(&facts.join(", "));
+    out.push_str(".\n");
+
+    if !brief.top_coupled_files.is_empty() {
+        out.push_str("\n**Usually changes with**\n\n");
+        for file in &brief.top_coupled_files {
+            out.push_str(&format!(
+                "- `{}` ({} of {} commits)\n",
+                file.path, file.co_change_count, brief.commit_count
+            ));
+        }
+    }
+
+    if !brief.notes.is_empty() {
+        out.push_str("\n**Notes**\n\n");
+        for note in &brief.notes {
+            out.push_str(&format!("- {}\n", note.content));
+        }
+    }
+
+    if !brief.recent_commits.is_empty() {
+        out.push_str("\n**Recent commits**\n\n");
+        for commit in &brief.recent_commits {
+            let short = &commit.hash[..commit.hash.len().min(8)];
+            let by = commit.author.as_deref().map(|a| format!(", {a}")).unwrap_or_default();
+            out.push_str(&format!(
+                "- `{short}` {} ({}{by})\n",
+                commit.summary,
+                age(commit.timestamp, now)
+            ));
+        }
+    }
+
+    out
+}
+
+/// Render coupling evidence as a Markdown section: the score, then one line
+/// per shared commit with its date and subject.
+pub fn render_evidence(evidence: &CouplingEvidence) -> String {
+    let mut out = format!("## Why `{}` changes with `{}`
+
+", evidence.file_path, evidence.coupled_path);
+    out.push_str(&format!(
+        "Changed together in {} of {} commits (coupling {:.2}",
+        evidence.co_change_count, evidence.commit_count, evidence.coupling_score
+    ));
+    if let Some(risk_score) = evidence.risk_score {
+        out.push_str(&format!(", risk {risk_score:.2}"));
+    }
+    out.push_str(").
+");
+
+    if !evidence.commits.is_empty() {
+        out.push_str("
+**Shared commits**
+
+");
+        for commit in &evidence.commits {
+            let short = &commit.hash[..commit.hash.len().min(8)];
+            let by = commit.author.as_deref().map(|a| format!(", {a}")).unwrap_or_default();
+            out.push_str(&format!("- `{short}` {} ({}{by})
+", commit.summary, date(commit.timestamp)));
+        }
+    }
+    out
+}
+
+/// Render a blast-radius check as a Markdown section: the verdict, then
+/// one row per coupled file the commit leaves out.
+pub fn render_check(response: &CheckResponse) -> String {
+    let scope = if response.staged { "staged changes" } else { "uncommitted changes" };
+    let mut out = format!("## Blast radius check: {scope}\n\n");
+    if response.findings.is_empty() {
+        out.push_str(&format!(
+            "No strongly coupled files are missing from {} changed file{}.\n",
+            response.changed_files.len(),
+            if response.changed_files.len() == 1 { "" } else { "s" },
+        ));
+        return out;
+    }
+
+    if !response.passed {
+        out.push_str("**Check failed.** ");
+    }
+    out.push_str(&format!(
+        "{} coupled file{} not part of this change:\n\n",
+        response.findings.len(),
+        if response.findings.len() == 1 { " is" } else { "s are" },
+    ));
+    out.push_str("| Risk | File | Score | Usually changes with |\n");
+    out.push_str("| --- | --- | ---: | --- |\n");
+    for finding in &response.findings {
+        let partners: Vec<String> = finding.coupled_to.iter().map(|p| format!("`{p}`")).collect();
+        out.push_str(&format!(
+            "| {} | `{}` | {:.2} | {} |\n",
+            finding.risk_level,
+            finding.path,
+            finding.risk_score,
+            partners.join(", "),
+        ));
+    }
+    out
+}
+
+/// Coarse relative age: "today", "3 days ago", "5 months ago", "2 years ago".
+fn age(timestamp: i64, now: i64) -> String {
+    let days = (now - timestamp).max(0) / 86_400;
+    let (n, unit) = match days {
+        0 => return "today".to_string(),
+        1..=59 => (days, "day"),
+        60..=729 => (days / 30, "month"),
+        _ => (days / 365, "year"),
+    };
+    format!("{n} {unit}{} ago", if n == 1 { "" } else { "s" })
+}
+
+fn summary_line(response: &AnalysisResponse) -> String {
+    let files = &response.coupled_files;
+    if files.is_empty() {
+        return format!("Changing `{}` has no coupled files.", response.file_path);
+    }
+
+    let thresholds = response.risk_thresholds.unwrap_or(RiskThresholds::FIXED);
+    let counts: Vec<String> = RISK_LEVELS
+        .iter()
+        .filter_map(|level| {
+            let n = files.iter().filter(|f| thresholds.level(f.risk_score) == *level).count();
+            (n > 0).then(|| format!("{n} {}", level.to_lowercase()))
+        })
+        .collect();
+
+    format!(
+        "Changing `{}` may affect {} file{} ({} risk), based on {} commit{}.",
+        response.file_path,
+        files.len(),
+        if files.len() == 1 { "" } else { "s" },
+        counts.join(", "),
+        response.commit_count,
+        if response.commit_count == 1 { "" } else { "s" },
+    )
+}
+
+fn has_details(file: &CoupledFile) -> bool {
+    !file.memories.is_empty() || !file.test_intents.is_empty() || !file.regions.is_empty()
+}
+
+/// GitHub's heading anchor: lowercase, punctuation dropped, spaces to hyphens.
+fn anchor(heading: &str) -> String {
+    heading
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{CheckFinding, Memory, OwnershipEntry, RecentCommit};
+
+    fn response(coupled_files: Vec<CoupledFile>) -> AnalysisResponse {
+        AnalysisResponse {
+            file_path: "src/Auth.ts".to_string(),
+            repo_root: "/repo".to_string(),
+            coupled_files,
+            commit_count: 10,
+            analysis_time_ms: 5,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_renders_table_and_detail_sections() {
+        let md = render_analysis(&response(vec![
+            CoupledFile {
+                path: "src/Session.ts".to_string(),
+                risk_score: 0.85,
+                co_change_count: 8,
+                memories: vec![Memory {
+                    id: 1,
+                    file_path: "src/Session.ts".to_string(),
+                    symbol_name: None,
+                    content: "Token refresh is fragile".to_string(),
+                    created_at: String::new(),
+                    author_name: None,
+                    author_email: None,
+                    head_commit: None,
+                    blob_oid: None,
+                    drift: None,
+                    global: false,
+                }],
+                test_intents: vec![
+                    TestIntent {
+                        title: "refreshes expired tokens".to_string(),
+                        ..Default::default()
+                    },
+                    TestIntent {
+                        title: "rejects reused tokens".to_string(),
+                        cases: 3,
+                        failing: true,
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            },
+            CoupledFile {
+                path: "src/util.ts".to_string(),
+                risk_score: 0.4,
+                co_change_count: 3,
+                ..Default::default()
+            },
+        ]));
+
+        assert!(md.starts_with("## Blast radius: `src/Auth.ts`\n"));
+        assert!(md.contains("may affect 2 files (1 critical, 1 medium risk), based on 10 commits."));
+        assert!(md.contains("| Critical | [`src/Session.ts`](#srcsessionts) | 0.85 | 8 of 10 |"));
+        assert!(md.contains("| Medium | `src/util.ts` | 0.40 | 3 of 10 |"));
+        assert!(md.contains("\n### `src/Session.ts`\n"));
+        assert!(md.contains("- Token refresh is fragile\n"));
+        assert!(md.contains("- refreshes expired tokens\n"));
+        assert!(md.contains("- rejects reused tokens (3 cases) (failing)\n"));
+        assert!(!md.contains("### `src/util.ts`"));
+    }
+
+    #[test]
+    fn test_renders_empty_response() {
+        let md = render_analysis(&response(vec![]));
+        assert!(md.contains("Changing `src/Auth.ts` has no coupled files."));
+        assert!(!md.contains('|'));
+    }
+
+    #[test]
+    fn test_renders_check() {
+        let mut check = CheckResponse {
+            repo_root: "/repo".to_string(),
+            staged: true,
+            changed_files: vec!["src/api.ts".to_string()],
+            findings: vec![CheckFinding {
+                path: "src/client.ts".to_string(),
+                risk_level: "Critical".to_string(),
+                risk_score: 0.91,
+                co_change_count: 9,
+                coupled_to: vec!["src/api.ts".to_string()],
+                blocking: true,
+            }],
+            passed: false,
+        };
+        let md = render_check(&check);
+        assert!(md.contains("**Check failed.** 1 coupled file is not part of this change"));
+        assert!(md.contains("| Critical | `src/client.ts` | 0.91 | `src/api.ts` |"));
+
+        check.findings.clear();
+        assert!(render_check(&check).contains("No strongly coupled files are missing from 1 changed file."));
+    }
+
+    #[test]
+    fn test_renders_orientation_brief() {
+        let now = 1_000 * 86_400;
+        let brief = OrientationBrief {
+            file_path: "src/Auth.ts".to_string(),
+            repo_root: "/repo".to_string(),
+            commit_count: 12,
+            first_seen_at: Some(now - 400 * 86_400),
+            last_changed_at: Some(now - 86_400),
+            ownership: Some(OwnershipEntry {
+                path: "src/Auth.ts".to_string(),
+                bus_factor: 1,
+                dominant_author: "alice".to_string(),
+                dominant_author_email: "alice@example.com".to_string(),
+                dominant_share: 0.75,
+                author_count: 3,
+                commit_count: 12,
+            }),
+            top_coupled_files: vec![CoupledFile {
+                path: "src/Session.ts".to_string(),
+                co_change_count: 7,
+                ..Default::default()
+            }],
+            notes: vec![],
+            recent_commits: vec![RecentCommit {
+                hash: "0123456789abcdef".to_string(),
+                summary: "Fix login redirect".to_string(),
+                author: Some("bob".to_string()),
+                timestamp: now - 86_400,
+            }],
+        };
+
+        let md = render_orientation_at(&brief, now);
+        assert!(md.starts_with("## Orientation: `src/Auth.ts`\n"));
+        assert!(md.contains(
+            "12 commits, first seen 13 months ago, last changed 1 day ago, mostly alice (75% of commits, 3 authors)."
+        ));
+        assert!(md.contains("- `src/Session.ts` (7 of 12 commits)\n"));
+        assert!(!md.contains("**Notes**"));
+        assert!(md.contains("- `01234567` Fix login redirect (1 day ago, bob)\n"));
+    }
+
+    #[test]
+    fn test_renders_evidence() {
+        let evidence = CouplingEvidence {
+            file_path: "src/Auth.ts".to_string(),
+            coupled_path: "src/Session.ts".to_string(),
+            repo_root: "/repo".to_string(),
+            commit_count: 4,
+            co_change_count: 1,
+            coupling_score: 0.25,
+            risk_score: None,
+            commits: vec![RecentCommit {
+                hash: "0123456789abcdef".to_string(),
+                summary: "Refresh tokens".to_string(),
+                author: None,
+                timestamp: 1_709_251_200, // 2024-03-01
+            }],
+        };
+
+        let md = render_evidence(&evidence);
+        assert!(md.starts_with("## Why `src/Auth.ts` changes with `src/Session.ts`\n"));
+        assert!(md.contains("Changed together in 1 of 4 commits (coupling 0.25).\n"));
+        assert!(md.contains("- `01234567` Refresh tokens (2024-03-01)\n"));
+    }
+}
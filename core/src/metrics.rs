@@ -1,5 +1,8 @@
-use crate::persistence::Database;
-use crate::types::{AnalysisResponse, MetricsResponse};
+use std::time::Duration;
+
+use crate::indexing::SmartIndexResult;
+use crate::persistence::{Database, IndexingEvent, PayloadSizes};
+use crate::types::{AnalysisResponse, MetricsPeriod, MetricsResponse};
 use std::error::Error;
 
 // Event type constants to prevent typos
@@ -39,7 +42,7 @@ pub fn record_analysis_event(
         }
     }
 
-    db.insert_metrics_event(
+    let event_id = db.insert_metrics_event(
         EVENT_ANALYSIS,
         Some(&response.file_path),
         response.coupled_files.len() as u32,
@@ -54,10 +57,37 @@ pub fn record_analysis_event(
         None,
         repo_root,
     )?;
+    db.set_event_payload_sizes(event_id, &payload_sizes(response)?)?;
 
     Ok(())
 }
 
+/// Measure the serialized response and its enrichment sections. Memories and
+/// test intents are counted both inside `coupled_files_bytes` and on their
+/// own; `test_intents_bytes` also covers the `test_info` section.
+fn payload_sizes(response: &AnalysisResponse) -> Result<PayloadSizes, serde_json::Error> {
+    let mut memories_bytes = 0;
+    let mut test_intents_bytes = 0;
+    for file in &response.coupled_files {
+        if !file.memories.is_empty() {
+            memories_bytes += serde_json::to_vec(&file.memories)?.len() as u64;
+        }
+        if !file.test_intents.is_empty() {
+            test_intents_bytes += serde_json::to_vec(&file.test_intents)?.len() as u64;
+        }
+    }
+    if let Some(info) = &response.test_info {
+        test_intents_bytes += serde_json::to_vec(info)?.len() as u64;
+    }
+
+    Ok(PayloadSizes {
+        response_bytes: serde_json::to_vec(response)?.len() as u64,
+        coupled_files_bytes: serde_json::to_vec(&response.coupled_files)?.len() as u64,
+        memories_bytes,
+        test_intents_bytes,
+    })
+}
+
 /// Record a note creation event.
 pub fn record_note_event(
     db: &Database,
@@ -84,15 +114,69 @@ pub fn record_note_event(
     Ok(())
 }
 
+/// Record an indexing run: the strategy it used, how far the index got,
+/// how much of its `budget` it took, and its throughput.
+pub fn record_indexing_event(
+    db: &Database,
+    result: &SmartIndexResult,
+    elapsed: Duration,
+    budget: Duration,
+    background: bool,
+    repo_root: &str,
+) -> Result<(), Box<dyn Error>> {
+    db.insert_indexing_event(
+        &IndexingEvent {
+            strategy: result.strategy.as_str().to_string(),
+            commits_indexed: result.commits_indexed,
+            elapsed_ms: elapsed.as_millis() as u64,
+            budget_ms: budget.as_millis() as u64,
+            needs_background: result.needs_background,
+            commits_per_sec: result.commits_per_sec,
+            background,
+        },
+        repo_root,
+    )?;
+    Ok(())
+}
+
+/// Optional sections of a metrics response.
+#[derive(Debug, Clone, Default)]
+pub struct MetricsOptions {
+    /// Add a series of summaries per day or week.
+    pub group_by: Option<MetricsPeriod>,
+    /// Add the analysis metrics of this file.
+    pub file: Option<String>,
+}
+
 /// Get aggregated metrics for a repository.
 pub fn get_metrics(
     db: &Database,
     repo_root: &str,
+) -> Result<MetricsResponse, Box<dyn Error>> {
+    get_metrics_with_options(db, repo_root, &MetricsOptions::default())
+}
+
+/// `get_metrics` with the optional sections in `options`.
+pub fn get_metrics_with_options(
+    db: &Database,
+    repo_root: &str,
+    options: &MetricsOptions,
 ) -> Result<MetricsResponse, Box<dyn Error>> {
     let summary = db.get_metrics_summary(repo_root)?;
+    let series = match options.group_by {
+        Some(period) => db.get_metrics_series(repo_root, period)?,
+        None => Vec::new(),
+    };
+    let file = match &options.file {
+        Some(file_path) => Some(db.get_file_metrics(repo_root, file_path)?),
+        None => None,
+    };
     Ok(MetricsResponse {
         repo_root: repo_root.to_string(),
         summary,
+        group_by: options.group_by,
+        series,
+        file,
     })
 }
 
@@ -114,22 +198,19 @@ mod tests {
                     coupling_score: 0.9,
                     co_change_count: 10,
                     risk_score: 0.85,
-                    memories: vec![],
-                    test_intents: vec![],
+                    ..Default::default()
                 },
                 CoupledFile {
                     path: "src/C.ts".to_string(),
                     coupling_score: 0.6,
                     co_change_count: 5,
                     risk_score: 0.6,
-                    memories: vec![],
-                    test_intents: vec![],
+                    ..Default::default()
                 },
             ],
             commit_count: 15,
             analysis_time_ms: 150,
-            test_info: None,
-            indexing_status: None,
+            ..Default::default()
         };
 
         record_analysis_event(&db, &response, "/repo").unwrap();
@@ -140,6 +221,11 @@ mod tests {
         assert_eq!(metrics.critical_risk_count, 1);
         assert_eq!(metrics.high_risk_count, 1);
         assert_eq!(metrics.avg_analysis_time_ms, 150);
+
+        let expected = serde_json::to_vec(&response).unwrap().len() as u64;
+        assert_eq!(metrics.avg_response_bytes, expected);
+        assert!(metrics.avg_coupled_files_bytes > 0);
+        assert_eq!(metrics.avg_memories_bytes, 0);
     }
 
     #[test]
@@ -155,38 +241,33 @@ mod tests {
                     coupling_score: 1.0,
                     co_change_count: 10,
                     risk_score: 0.8,
-                    memories: vec![],
-                    test_intents: vec![],
+                    ..Default::default()
                 },
                 CoupledFile {
                     path: "high.ts".to_string(),
                     coupling_score: 0.7,
                     co_change_count: 7,
                     risk_score: 0.5,
-                    memories: vec![],
-                    test_intents: vec![],
+                    ..Default::default()
                 },
                 CoupledFile {
                     path: "medium.ts".to_string(),
                     coupling_score: 0.4,
                     co_change_count: 4,
                     risk_score: 0.25,
-                    memories: vec![],
-                    test_intents: vec![],
+                    ..Default::default()
                 },
                 CoupledFile {
                     path: "low.ts".to_string(),
                     coupling_score: 0.2,
                     co_change_count: 2,
                     risk_score: 0.1,
-                    memories: vec![],
-                    test_intents: vec![],
+                    ..Default::default()
                 },
             ],
             commit_count: 10,
             analysis_time_ms: 100,
-            test_info: None,
-            indexing_status: None,
+            ..Default::default()
         };
 
         record_analysis_event(&db, &response, "/repo").unwrap();
@@ -215,11 +296,14 @@ mod tests {
                     test_intents: vec![
                         TestIntent {
                             title: "test 1".to_string(),
+                            ..Default::default()
                         },
                         TestIntent {
                             title: "test 2".to_string(),
+                            ..Default::default()
                         },
                     ],
+                    ..Default::default()
                 },
                 CoupledFile {
                     path: "test2.ts".to_string(),
@@ -229,21 +313,21 @@ mod tests {
                     memories: vec![],
                     test_intents: vec![TestIntent {
                         title: "test 3".to_string(),
+                        ..Default::default()
                     }],
+                    ..Default::default()
                 },
                 CoupledFile {
                     path: "notest.ts".to_string(),
                     coupling_score: 0.3,
                     co_change_count: 3,
                     risk_score: 0.3,
-                    memories: vec![],
-                    test_intents: vec![],
+                    ..Default::default()
                 },
             ],
             commit_count: 5,
             analysis_time_ms: 100,
-            test_info: None,
-            indexing_status: None,
+            ..Default::default()
         };
 
         record_analysis_event(&db, &response, "/repo").unwrap();
@@ -260,21 +344,17 @@ mod tests {
         let response1 = AnalysisResponse {
             file_path: "src/A.ts".to_string(),
             repo_root: "/repo1".to_string(),
-            coupled_files: vec![],
             commit_count: 5,
             analysis_time_ms: 100,
-            test_info: None,
-            indexing_status: None,
+            ..Default::default()
         };
 
         let response2 = AnalysisResponse {
             file_path: "src/B.ts".to_string(),
             repo_root: "/repo2".to_string(),
-            coupled_files: vec![],
             commit_count: 10,
             analysis_time_ms: 200,
-            test_info: None,
-            indexing_status: None,
+            ..Default::default()
         };
 
         record_analysis_event(&db, &response1, "/repo1").unwrap();
@@ -297,11 +377,9 @@ mod tests {
             let response = AnalysisResponse {
                 file_path: format!("src/{i}.ts"),
                 repo_root: "/repo".to_string(),
-                coupled_files: vec![],
                 commit_count: 5,
                 analysis_time_ms: 100 + (i as u64 * 50),
-                test_info: None,
-                indexing_status: None,
+                ..Default::default()
             };
             record_analysis_event(&db, &response, "/repo").unwrap();
         }
@@ -320,6 +398,43 @@ mod tests {
         assert_eq!(result.summary.total_coupled_files, 0);
     }
 
+    #[test]
+    fn test_file_metrics() {
+        let db = Database::in_memory().unwrap();
+        let analysis = |file_path: &str, risk_scores: &[f64], analysis_time_ms: u64| AnalysisResponse {
+            file_path: file_path.to_string(),
+            repo_root: "/repo".to_string(),
+            coupled_files: risk_scores
+                .iter()
+                .map(|&risk_score| CoupledFile { path: "x.ts".to_string(), risk_score, ..Default::default() })
+                .collect(),
+            commit_count: 5,
+            analysis_time_ms,
+            ..Default::default()
+        };
+        record_analysis_event(&db, &analysis("src/A.ts", &[0.9, 0.6, 0.1], 100), "/repo").unwrap();
+        record_analysis_event(&db, &analysis("src/A.ts", &[0.9], 300), "/repo").unwrap();
+        record_analysis_event(&db, &analysis("src/B.ts", &[0.9, 0.9, 0.9, 0.9], 50), "/repo").unwrap();
+
+        let options = MetricsOptions { file: Some("src/A.ts".to_string()), ..Default::default() };
+        let result = get_metrics_with_options(&db, "/repo", &options).unwrap();
+        assert_eq!(result.summary.total_analyses, 3);
+        let file = result.file.unwrap();
+        assert_eq!(file.times_analyzed, 2);
+        assert_eq!(file.avg_coupled_files, 2.0);
+        assert_eq!(file.avg_critical_count, 1.0);
+        assert_eq!(file.avg_high_count, 0.5);
+        assert_eq!(file.avg_low_count, 0.5);
+        assert_eq!(file.avg_analysis_time_ms, 200);
+        assert!(file.last_analyzed_at.is_some());
+
+        let options = MetricsOptions { file: Some("src/never.ts".to_string()), ..Default::default() };
+        let file = get_metrics_with_options(&db, "/repo", &options).unwrap().file.unwrap();
+        assert_eq!(file.times_analyzed, 0);
+        assert!(file.last_analyzed_at.is_none());
+        assert!(get_metrics(&db, "/repo").unwrap().file.is_none());
+    }
+
     #[test]
     fn test_record_note_event() {
         let db = Database::in_memory().unwrap();
@@ -0,0 +1,80 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::types::WatchUpdate;
+use crate::{AnalyzeOptions, AnalyzeResult};
+
+/// Error of the async API. The underlying error is carried as its message,
+/// since the sync errors cannot cross threads.
+pub type Error = Box<dyn std::error::Error + Send + Sync>;
+
+/// Run `work` on tokio's blocking pool, so git and SQLite work never stalls
+/// a runtime thread.
+async fn blocking<T: Send + 'static>(
+    work: impl FnOnce() -> Result<T, Box<dyn std::error::Error>> + Send + 'static,
+) -> Result<T, Error> {
+    tokio::task::spawn_blocking(move || work().map_err(|e| Error::from(e.to_string()))).await?
+}
+
+/// `analyze_with_options` on the blocking pool. Each call opens its own
+/// database connection, so analyses of the same or different repositories
+/// can run concurrently. When the result's `needs_background` is set, follow
+/// up with `background_index`.
+pub async fn analyze(repo_root: PathBuf, file_path: String, options: AnalyzeOptions) -> Result<AnalyzeResult, Error> {
+    blocking(move || crate::analyze_with_options(&repo_root, &file_path, &options)).await
+}
+
+/// `indexing::background_index` on the blocking pool: keep indexing for up
+/// to `budget` after an analysis that left history partly indexed.
+pub async fn background_index(
+    repo_root: PathBuf,
+    budget: Duration,
+    file_path: Option<String>,
+    record_metrics: bool,
+) -> Result<(), Error> {
+    blocking(move || crate::indexing::background_index(&repo_root, budget, file_path.as_deref(), record_metrics))
+        .await
+}
+
+/// `update_index` on the blocking pool: index the commits that landed since
+/// the last run.
+pub async fn update_index(repo_root: PathBuf) -> Result<Option<WatchUpdate>, Error> {
+    blocking(move || crate::update_index(&repo_root)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::commit_by;
+    use git2::Repository;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_concurrent_analyses() {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        for i in 0..3 {
+            commit_by(&repo, "Ada", &["a.rs", "b.rs", "c.rs"], &format!("change {i}"));
+        }
+
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let results: Vec<AnalyzeResult> = runtime.block_on(async {
+            let handles: Vec<_> = ["a.rs", "b.rs", "c.rs"]
+                .into_iter()
+                .map(|file| tokio::spawn(analyze(dir.path().to_path_buf(), file.to_string(), AnalyzeOptions::default())))
+                .collect();
+            let mut results = Vec::new();
+            for handle in handles {
+                results.push(handle.await.unwrap().unwrap());
+            }
+            results
+        });
+
+        for result in &results {
+            assert_eq!(result.response.commit_count, 3);
+            assert_eq!(result.response.coupled_files.len(), 2);
+        }
+        let missing = runtime.block_on(analyze(dir.path().join("missing"), "a.rs".to_string(), Default::default()));
+        assert!(missing.is_err());
+    }
+}
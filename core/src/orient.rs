@@ -0,0 +1,102 @@
+use std::path::Path;
+
+use git2::{Oid, Repository};
+
+use crate::authors;
+use crate::indexing::IndexOptions;
+use crate::persistence::Database;
+use crate::temporal;
+use crate::types::{OrientationBrief, RecentCommit};
+
+const TOP_COUPLED_FILES: usize = 5;
+const RECENT_COMMITS: usize = 5;
+
+/// Build an orientation brief for `file_path`: ownership, age, the files it
+/// most often changes with, notes left on it, and its recent commit subjects.
+///
+/// Indexes the same way `analyze` does, so the first call on a cold repo is
+/// time-budgeted. Returns (brief, needs_background_indexing).
+pub fn orient(
+    repo_root: &Path,
+    file_path: &str,
+    db: &Database,
+    opts: &IndexOptions,
+) -> Result<(OrientationBrief, bool), Box<dyn std::error::Error>> {
+    let (analysis, needs_background) = temporal::analyze(repo_root, file_path, db, opts)?;
+    let repo = Repository::open(repo_root)?;
+
+    let mut top_coupled_files = analysis.coupled_files;
+    top_coupled_files.truncate(TOP_COUPLED_FILES);
+
+    let time_range = db.file_time_range(file_path)?;
+
+    // Subjects come from git, not the index; commits that no longer exist
+    // (rewritten history not yet reindexed) are skipped.
+    let recent_commits = db
+        .recent_commits_for_file(file_path, RECENT_COMMITS)?
+        .into_iter()
+        .filter_map(|(hash, timestamp, author)| {
+            let commit = repo.find_commit(Oid::from_str(&hash).ok()?).ok()?;
+            Some(RecentCommit {
+                summary: commit.summary().unwrap_or("").to_string(),
+                hash,
+                author,
+                timestamp,
+            })
+        })
+        .collect();
+
+    let brief = OrientationBrief {
+        file_path: file_path.to_string(),
+        repo_root: repo_root.to_string_lossy().to_string(),
+        commit_count: analysis.commit_count,
+        first_seen_at: time_range.map(|(oldest, _)| oldest),
+        last_changed_at: time_range.map(|(_, newest)| newest),
+        ownership: authors::file_ownership(db, file_path)?,
+        top_coupled_files,
+        notes: db.memories_for_file(file_path)?,
+        recent_commits,
+    };
+
+    Ok((brief, needs_background))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::commit_by;
+    use crate::persistence::NoteOrigin;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_orientation_brief() {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        commit_by(&repo, "alice", &["src/auth.rs", "src/session.rs"], "Add auth");
+        commit_by(&repo, "alice", &["src/auth.rs", "src/session.rs"], "Refresh tokens");
+        commit_by(&repo, "bob", &["src/auth.rs"], "Fix login redirect\n\nLong body");
+        commit_by(&repo, "bob", &["src/other.rs"], "Unrelated");
+
+        let db = Database::in_memory().unwrap();
+        db.add_memory("src/auth.rs", None, "Tokens are cached per tenant", &NoteOrigin::default()).unwrap();
+
+        let (brief, _) = orient(dir.path(), "src/auth.rs", &db, &IndexOptions::default()).unwrap();
+
+        assert_eq!(brief.commit_count, 3);
+        assert!(brief.first_seen_at.is_some());
+        assert!(brief.first_seen_at <= brief.last_changed_at);
+
+        let ownership = brief.ownership.unwrap();
+        assert_eq!(ownership.dominant_author, "alice");
+        assert_eq!(ownership.author_count, 2);
+
+        assert_eq!(brief.top_coupled_files.len(), 1);
+        assert_eq!(brief.top_coupled_files[0].path, "src/session.rs");
+        assert_eq!(brief.notes.len(), 1);
+
+        let summaries: Vec<&str> = brief.recent_commits.iter().map(|c| c.summary.as_str()).collect();
+        assert_eq!(summaries.len(), 3);
+        assert!(summaries.contains(&"Fix login redirect"));
+        assert!(!summaries.contains(&"Unrelated"));
+    }
+}
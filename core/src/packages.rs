@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use git2::{Repository, Tree, TreeWalkMode, TreeWalkResult};
+
+use crate::types::CoupledFile;
+
+/// Files whose presence makes a directory a package root. Cargo workspace
+/// members, npm/yarn/pnpm workspaces and Go modules each have their own.
+const MANIFESTS: &[&str] = &[
+    "Cargo.toml",
+    "package.json",
+    "go.mod",
+    "pyproject.toml",
+    "setup.py",
+    "pom.xml",
+    "build.gradle",
+    "build.gradle.kts",
+];
+
+/// Directories at HEAD that hold a package manifest, sorted, with the
+/// repository root as `""`. An unborn HEAD has no packages.
+pub fn package_roots(repo: &Repository) -> Result<Vec<String>, git2::Error> {
+    let Ok(head) = repo.head() else {
+        return Ok(Vec::new());
+    };
+    let tree = head.peel_to_tree()?;
+
+    let mut roots = Vec::new();
+    tree.walk(TreeWalkMode::PreOrder, |dir, entry| {
+        if entry.kind() == Some(git2::ObjectType::Blob)
+            && entry.name().is_some_and(|name| MANIFESTS.contains(&name))
+        {
+            roots.push(dir.trim_end_matches('/').to_string());
+        }
+        TreeWalkResult::Ok
+    })?;
+    roots.sort();
+    roots.dedup();
+    Ok(roots)
+}
+
+/// The innermost package root (from `package_roots`) containing `path`.
+pub fn package_of<'a>(path: &str, roots: &'a [String]) -> Option<&'a str> {
+    roots
+        .iter()
+        .filter(|root| {
+            root.is_empty() || path.strip_prefix(root.as_str()).is_some_and(|rest| rest.starts_with('/'))
+        })
+        .max_by_key(|root| root.len())
+        .map(String::as_str)
+}
+
+/// Mark each coupled file with whether it lies in another package than
+/// `file_path`, by the manifests in `tree`. Left unmarked when neither
+/// file is in a package.
+pub fn mark_cross_package(tree: &Tree, file_path: &str, coupled_files: &mut [CoupledFile]) {
+    let mut manifests: HashMap<String, bool> = HashMap::new();
+    let own = package_in_tree(tree, file_path, &mut manifests);
+    for file in coupled_files.iter_mut() {
+        let theirs = package_in_tree(tree, &file.path, &mut manifests);
+        file.cross_package = (own.is_some() || theirs.is_some()).then(|| own != theirs);
+    }
+}
+
+/// The innermost directory above `path` holding a manifest in `tree`,
+/// with the repository root as `""`. `manifests` caches which directories
+/// hold one.
+fn package_in_tree(tree: &Tree, path: &str, manifests: &mut HashMap<String, bool>) -> Option<String> {
+    Path::new(path).ancestors().skip(1).find_map(|dir| {
+        let dir = dir.to_str()?;
+        let has_manifest = *manifests.entry(dir.to_string()).or_insert_with(|| {
+            MANIFESTS.iter().any(|name| tree.get_path(&Path::new(dir).join(name)).is_ok())
+        });
+        has_manifest.then(|| dir.to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::commit;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_package_roots_and_membership() {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        commit(&repo, &["Cargo.toml", "crates/api/Cargo.toml", "web/package.json", "web/src/app.ts"], "init");
+
+        let roots = package_roots(&repo).unwrap();
+        assert_eq!(roots, vec!["", "crates/api", "web"]);
+
+        assert_eq!(package_of("web/src/app.ts", &roots), Some("web"));
+        assert_eq!(package_of("crates/api/src/lib.rs", &roots), Some("crates/api"));
+        assert_eq!(package_of("crates/apis/lib.rs", &roots), Some(""));
+        assert_eq!(package_of("web/src/app.ts", &[]), None);
+
+        let tree = repo.head().unwrap().peel_to_tree().unwrap();
+        let file = |path: &str| CoupledFile { path: path.to_string(), ..Default::default() };
+        let mut files = vec![file("web/index.html"), file("crates/api/src/lib.rs"), file("README.md")];
+        mark_cross_package(&tree, "web/src/app.ts", &mut files);
+        let cross: Vec<Option<bool>> = files.iter().map(|f| f.cross_package).collect();
+        assert_eq!(cross, vec![Some(false), Some(true), Some(true)]);
+    }
+}
@@ -1,7 +1,12 @@
-use rusqlite::{Connection, params};
+use rusqlite::{Connection, OpenFlags, OptionalExtension, params};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::Path;
 
-use crate::types::Memory;
+use crate::risk::{CoChange, RawCoupledFileStats};
+use crate::types::{FileMetrics, Memory, MetricsPeriod, MetricsPeriodSummary, RecentAnalysis, RiskThresholds};
+
+/// Longest chain of renames `current_path` follows.
+const MAX_RENAME_CHAIN: usize = 32;
 
 /// Persisted state for the adaptive indexing engine.
 /// Single-row table (id=1) tracking progress across process restarts.
@@ -19,10 +24,165 @@ pub struct IndexingState {
     pub target_path: Option<String>,
 }
 
+/// A pending PathFiltered walk in the background work queue.
+#[derive(Debug, Clone)]
+pub struct BackgroundJob {
+    pub target_path: String,
+    pub priority: i64,
+    pub attempts: u32,
+    pub resume_oid: Option<String>,
+}
+
+/// What the temporal index was built over: a subtree (`--scope`) of the
+/// history of a revision (`--rev`). `None` means the whole repo at HEAD.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IndexTarget {
+    pub scope: Option<String>,
+    pub rev: Option<String>,
+}
+
+/// Indexing of the commits between a previously indexed HEAD and the
+/// current one, when HEAD moved forward and the catch-up did not finish
+/// in one run.
+#[derive(Debug, Clone)]
+pub struct CatchUp {
+    /// The HEAD indexed before; the walk stops at it
+    pub base_commit: String,
+    pub resume_oid: Option<String>,
+    /// Whether the history up to `base_commit` was completely indexed
+    pub history_complete: bool,
+}
+
+/// Two files changed in the same commits, `file_a` sorting first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoChangePair {
+    pub file_a: String,
+    pub file_b: String,
+    pub co_changes: u32,
+    /// Indexed commits of each file, shared or not
+    pub commits_a: u32,
+    pub commits_b: u32,
+}
+
+/// A file's co-changes with the analyzed file and its own commits, each
+/// also counted before a point in time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoChangeSplit {
+    pub path: String,
+    pub co_total: u32,
+    pub co_before: u32,
+    pub commits_total: u32,
+    pub commits_before: u32,
+}
+
+/// Progress of an interrupted `reindex --full` run.
+#[derive(Debug, Clone)]
+pub struct ReindexCheckpoint {
+    pub head_commit: String,
+    pub resume_oid: Option<String>,
+    pub commits_indexed: u32,
+    pub started_at: i64,
+}
+
+/// Serialized size of an analysis response and its enrichment sections, in bytes.
+#[derive(Debug, Clone, Default)]
+pub struct PayloadSizes {
+    pub response_bytes: u64,
+    pub coupled_files_bytes: u64,
+    pub memories_bytes: u64,
+    pub test_intents_bytes: u64,
+}
+
+/// One indexing run, foreground or background, for the usage metrics.
+#[derive(Debug, Clone, Default)]
+pub struct IndexingEvent {
+    pub strategy: String,
+    /// Commits in the index after the run
+    pub commits_indexed: u32,
+    pub elapsed_ms: u64,
+    pub budget_ms: u64,
+    pub needs_background: bool,
+    /// Commits indexed per second during the run, if it walked any
+    pub commits_per_sec: Option<f64>,
+    pub background: bool,
+}
+
 pub struct Database {
     conn: Connection,
 }
 
+/// Co-changes from a `GROUP_CONCAT` of `timestamp:is_fix` pairs.
+fn parse_co_changes(concatenated: &str) -> Vec<CoChange> {
+    concatenated
+        .split(',')
+        .filter_map(|pair| {
+            let (timestamp, is_fix) = pair.split_once(':')?;
+            Some(CoChange { timestamp: timestamp.parse().ok()?, is_fix: is_fix == "1" })
+        })
+        .collect()
+}
+
+/// Where a note came from: the git identity that wrote it, and HEAD and the
+/// file's blob at the time, so later readers can tell how far the code has
+/// moved on since.
+#[derive(Debug, Clone, Default)]
+pub struct NoteOrigin {
+    pub author_name: Option<String>,
+    pub author_email: Option<String>,
+    pub head_commit: Option<String>,
+    pub blob_oid: Option<String>,
+}
+
+/// One page of a listing: skip `offset` rows, then return at most `limit`
+/// (all that remain when `None`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Page {
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+impl Page {
+    /// This page with one row more, to tell whether another page follows.
+    pub fn probe(self) -> Page {
+        Page { limit: self.limit.map(|limit| limit + 1), ..self }
+    }
+
+    /// Cut `rows` fetched with `probe` back to the page, returning the
+    /// offset of the next page if there is one.
+    pub fn finish<T>(self, rows: &mut Vec<T>) -> Option<usize> {
+        let limit = self.limit?;
+        (rows.len() > limit).then(|| {
+            rows.truncate(limit);
+            self.offset + limit
+        })
+    }
+
+    /// LIMIT and OFFSET values for SQLite, where a negative limit means none.
+    fn sql(self) -> (i64, i64) {
+        (self.limit.map_or(-1, |limit| limit as i64), self.offset as i64)
+    }
+}
+
+/// Columns read by `memory_from_row`.
+const MEMORY_COLUMNS: &str =
+    "id, file_path, symbol_name, content, created_at, author_name, author_email, head_commit, blob_oid";
+
+fn memory_from_row(row: &rusqlite::Row) -> Result<Memory, rusqlite::Error> {
+    Ok(Memory {
+        id: row.get(0)?,
+        file_path: row.get(1)?,
+        symbol_name: row.get(2)?,
+        content: row.get(3)?,
+        created_at: row.get(4)?,
+        author_name: row.get(5)?,
+        author_email: row.get(6)?,
+        head_commit: row.get(7)?,
+        blob_oid: row.get(8)?,
+        drift: None,
+        global: false,
+    })
+}
+
 impl Database {
     /// Open or create a SQLite database at the given path.
     /// Uses WAL mode for concurrent read performance.
@@ -34,6 +194,15 @@ impl Database {
         Ok(db)
     }
 
+    /// Open an existing database without writing to it: no schema setup or
+    /// migrations, and a missing file is an error rather than created.
+    pub fn open_read_only(path: &Path) -> Result<Self, rusqlite::Error> {
+        let flags = OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+        let conn = Connection::open_with_flags(path, flags)?;
+        conn.execute_batch("PRAGMA busy_timeout=5000;")?;
+        Ok(Self { conn })
+    }
+
     /// Create an in-memory database (for testing).
     pub fn in_memory() -> Result<Self, rusqlite::Error> {
         let conn = Connection::open_in_memory()?;
@@ -43,20 +212,58 @@ impl Database {
     }
 
     fn init(&self) -> Result<(), rusqlite::Error> {
-        self.conn.execute_batch("PRAGMA journal_mode=WAL;")?;
+        // Wait on other connections from the start: switching a new file to
+        // WAL needs the write lock, as does the schema setup below
         self.conn.execute_batch("PRAGMA busy_timeout=5000;")?;
+        self.conn.execute_batch("PRAGMA journal_mode=WAL;")?;
 
+        // One transaction, so connections opening the database at the same
+        // time do not both see a column missing and both try to add it
+        self.begin_transaction()?;
         self.conn.execute_batch(
             "CREATE TABLE IF NOT EXISTS temporal_index (
                 commit_hash      TEXT NOT NULL,
                 file_path        TEXT NOT NULL,
                 commit_timestamp INTEGER NOT NULL DEFAULT 0,
+                is_fix           INTEGER NOT NULL DEFAULT 0,
                 PRIMARY KEY (commit_hash, file_path)
             );
 
             CREATE INDEX IF NOT EXISTS idx_temporal_file
                 ON temporal_index(file_path);
 
+            -- Per-file aggregates of temporal_index, kept current on insert
+            -- so coupling queries need not count each coupled file's rows.
+            CREATE TABLE IF NOT EXISTS file_stats (
+                file_path       TEXT PRIMARY KEY,
+                commit_count    INTEGER NOT NULL DEFAULT 0,
+                last_commit_ts  INTEGER NOT NULL DEFAULT 0
+            );
+
+            CREATE TRIGGER IF NOT EXISTS file_stats_on_insert
+            AFTER INSERT ON temporal_index
+            BEGIN
+                INSERT INTO file_stats (file_path, commit_count, last_commit_ts)
+                VALUES (NEW.file_path, 1, NEW.commit_timestamp)
+                ON CONFLICT(file_path) DO UPDATE SET
+                    commit_count = commit_count + 1,
+                    last_commit_ts = MAX(last_commit_ts, excluded.last_commit_ts);
+            END;
+
+            CREATE TABLE IF NOT EXISTS commits (
+                commit_hash      TEXT PRIMARY KEY,
+                author_name      TEXT NOT NULL DEFAULT '',
+                author_email     TEXT NOT NULL DEFAULT '',
+                commit_timestamp INTEGER NOT NULL DEFAULT 0,
+                commit_type      TEXT
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_commits_author
+                ON commits(author_email);
+
+            CREATE INDEX IF NOT EXISTS idx_commits_author_time
+                ON commits(author_email, commit_timestamp);
+
             CREATE TABLE IF NOT EXISTS indexing_state (
                 id               INTEGER PRIMARY KEY CHECK (id = 1),
                 head_commit      TEXT NOT NULL,
@@ -68,12 +275,104 @@ impl Database {
                 target_path      TEXT
             );
 
+            CREATE TABLE IF NOT EXISTS reindex_checkpoint (
+                id               INTEGER PRIMARY KEY CHECK (id = 1),
+                head_commit      TEXT NOT NULL,
+                resume_oid       TEXT,
+                commits_indexed  INTEGER NOT NULL DEFAULT 0,
+                started_at       INTEGER NOT NULL DEFAULT 0
+            );
+
+            -- Issue IDs (`PAY-431`, `#567`) named in commit messages.
+            CREATE TABLE IF NOT EXISTS commit_tickets (
+                ticket       TEXT NOT NULL,
+                commit_hash  TEXT NOT NULL,
+                PRIMARY KEY (ticket, commit_hash)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_commit_tickets_commit
+                ON commit_tickets(commit_hash);
+
+            CREATE TABLE IF NOT EXISTS index_catch_up (
+                id                INTEGER PRIMARY KEY CHECK (id = 1),
+                base_commit       TEXT NOT NULL,
+                resume_oid        TEXT,
+                history_complete  INTEGER NOT NULL DEFAULT 0
+            );
+
+            CREATE TABLE IF NOT EXISTS index_target (
+                id     INTEGER PRIMARY KEY CHECK (id = 1),
+                scope  TEXT,
+                rev    TEXT
+            );
+
+            -- Risk level thresholds drawn from the scores of the index
+            -- as it was at head_commit.
+            CREATE TABLE IF NOT EXISTS risk_calibration (
+                id           INTEGER PRIMARY KEY CHECK (id = 1),
+                head_commit  TEXT NOT NULL,
+                critical     REAL NOT NULL,
+                high         REAL NOT NULL,
+                medium       REAL NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS repo_fingerprint (
+                id           INTEGER PRIMARY KEY CHECK (id = 1),
+                origin_hash  TEXT NOT NULL DEFAULT '',
+                root_commit  TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS background_queue (
+                target_path  TEXT PRIMARY KEY,
+                priority     INTEGER NOT NULL DEFAULT 0,
+                attempts     INTEGER NOT NULL DEFAULT 0,
+                resume_oid   TEXT,
+                enqueued_at  INTEGER NOT NULL DEFAULT 0
+            );
+
+            CREATE TABLE IF NOT EXISTS test_results (
+                test_file    TEXT NOT NULL,
+                test_name    TEXT NOT NULL,
+                passed       INTEGER NOT NULL,
+                imported_at  INTEGER NOT NULL,
+                PRIMARY KEY (test_file, test_name)
+            );
+
+            -- A file renamed at `renamed_at`: commits up to then that
+            -- touched old_path are recorded under new_path.
+            CREATE TABLE IF NOT EXISTS path_renames (
+                old_path    TEXT NOT NULL,
+                new_path    TEXT NOT NULL,
+                renamed_at  INTEGER NOT NULL,
+                PRIMARY KEY (old_path, renamed_at)
+            );
+
+            -- `path_at` follows renames back from the new name
+            CREATE INDEX IF NOT EXISTS idx_path_renames_new
+                ON path_renames(new_path, renamed_at);
+
+            CREATE TABLE IF NOT EXISTS lossy_paths (
+                file_path  TEXT PRIMARY KEY,
+                raw_path   BLOB NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS commit_quarantine (
+                commit_hash  TEXT PRIMARY KEY,
+                failures     INTEGER NOT NULL DEFAULT 0,
+                last_error   TEXT NOT NULL DEFAULT '',
+                updated_at   INTEGER NOT NULL DEFAULT 0
+            );
+
             CREATE TABLE IF NOT EXISTS memories (
                 id          INTEGER PRIMARY KEY AUTOINCREMENT,
                 file_path   TEXT NOT NULL,
                 symbol_name TEXT,
                 content     TEXT NOT NULL,
-                created_at  DATETIME DEFAULT CURRENT_TIMESTAMP
+                created_at  DATETIME DEFAULT CURRENT_TIMESTAMP,
+                author_name  TEXT,
+                author_email TEXT,
+                head_commit  TEXT,
+                blob_oid     TEXT
             );
 
             CREATE INDEX IF NOT EXISTS idx_memories_file
@@ -104,12 +403,98 @@ impl Database {
             CREATE INDEX IF NOT EXISTS idx_metrics_timestamp ON metrics_events(timestamp);
             CREATE INDEX IF NOT EXISTS idx_metrics_repo ON metrics_events(repo_root);",
         )?;
+
+        // Columns added after the initial schema; older databases get them here.
+        self.ensure_column("temporal_index", "is_fix", "INTEGER NOT NULL DEFAULT 0")?;
+        self.ensure_column("commits", "commit_type", "TEXT")?;
+        self.ensure_column("memories", "author_name", "TEXT")?;
+        self.ensure_column("memories", "author_email", "TEXT")?;
+        self.ensure_column("memories", "head_commit", "TEXT")?;
+        self.ensure_column("memories", "blob_oid", "TEXT")?;
+        self.ensure_column("metrics_events", "response_bytes", "INTEGER DEFAULT 0")?;
+        self.ensure_column("metrics_events", "coupled_files_bytes", "INTEGER DEFAULT 0")?;
+        self.ensure_column("metrics_events", "memories_bytes", "INTEGER DEFAULT 0")?;
+        self.ensure_column("metrics_events", "test_intents_bytes", "INTEGER DEFAULT 0")?;
+        self.ensure_column("metrics_events", "strategy", "TEXT")?;
+        self.ensure_column("metrics_events", "budget_ms", "INTEGER DEFAULT 0")?;
+        self.ensure_column("metrics_events", "needs_background", "INTEGER DEFAULT 0")?;
+        self.ensure_column("metrics_events", "commits_per_sec", "REAL")?;
+        self.ensure_column("metrics_events", "background", "INTEGER DEFAULT 0")?;
+
+        // Databases indexed before file_stats existed
+        self.conn.execute_batch(
+            "INSERT INTO file_stats (file_path, commit_count, last_commit_ts)
+             SELECT file_path, COUNT(*), MAX(commit_timestamp) FROM temporal_index
+             WHERE NOT EXISTS (SELECT 1 FROM file_stats)
+             GROUP BY file_path;",
+        )?;
+        self.commit_transaction()
+    }
+
+    /// Add `column` to `table` if an older schema is missing it.
+    fn ensure_column(&self, table: &str, column: &str, decl: &str) -> Result<(), rusqlite::Error> {
+        let mut stmt = self.conn.prepare(&format!("PRAGMA table_info({table})"))?;
+        let exists = stmt
+            .query_map([], |row| row.get::<_, String>(1))?
+            .filter_map(|r| r.ok())
+            .any(|name| name == column);
+        if !exists {
+            self.conn
+                .execute_batch(&format!("ALTER TABLE {table} ADD COLUMN {column} {decl}"))?;
+        }
         Ok(())
     }
 
-    /// Begin an explicit transaction for batch inserts.
+    /// Reclaim free pages, refresh planner statistics, and checkpoint the
+    /// WAL. The first run switches the database to incremental auto-vacuum
+    /// with a full `VACUUM`; later runs only need `incremental_vacuum`.
+    pub fn compact(&self) -> Result<(), rusqlite::Error> {
+        let auto_vacuum: i64 = self.conn.query_row("PRAGMA auto_vacuum", [], |row| row.get(0))?;
+        if auto_vacuum == 2 {
+            self.conn.execute_batch("PRAGMA incremental_vacuum;")?;
+        } else {
+            self.conn.execute_batch("PRAGMA auto_vacuum = INCREMENTAL; VACUUM;")?;
+        }
+        self.conn.execute_batch("ANALYZE; PRAGMA wal_checkpoint(TRUNCATE);")?;
+        Ok(())
+    }
+
+    /// `PRAGMA integrity_check` messages; `["ok"]` for an intact database.
+    pub fn integrity_check(&self) -> Result<Vec<String>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare("PRAGMA integrity_check")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    /// Bytes held by free pages, which `compact` would give back.
+    pub fn free_bytes(&self) -> Result<u64, rusqlite::Error> {
+        let free_pages: i64 = self.conn.query_row("PRAGMA freelist_count", [], |row| row.get(0))?;
+        let page_size: i64 = self.conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+        Ok((free_pages * page_size) as u64)
+    }
+
+    /// Rows in the temporal index, indexed commits, and distinct indexed files.
+    pub fn index_row_counts(&self) -> Result<(u64, u64, u64), rusqlite::Error> {
+        self.conn.query_row(
+            "SELECT (SELECT COUNT(*) FROM temporal_index),
+                    (SELECT COUNT(DISTINCT commit_hash) FROM temporal_index),
+                    (SELECT COUNT(DISTINCT file_path) FROM temporal_index)",
+            [],
+            |row| Ok((row.get::<_, i64>(0)? as u64, row.get::<_, i64>(1)? as u64, row.get::<_, i64>(2)? as u64)),
+        )
+    }
+
+    /// Commits in the temporal index.
+    pub fn indexed_commit_count(&self) -> Result<u32, rusqlite::Error> {
+        self.conn.query_row("SELECT COUNT(DISTINCT commit_hash) FROM temporal_index", [], |row| row.get(0))
+    }
+
+    /// Begin an explicit transaction for batch inserts. It takes the write
+    /// lock up front: a deferred transaction that reads first fails at once
+    /// with "database is locked" if another connection wrote meanwhile,
+    /// rather than waiting out the busy timeout.
     pub fn begin_transaction(&self) -> Result<(), rusqlite::Error> {
-        self.conn.execute_batch("BEGIN")?;
+        self.conn.execute_batch("BEGIN IMMEDIATE")?;
         Ok(())
     }
 
@@ -136,6 +521,185 @@ impl Database {
         Ok(())
     }
 
+    /// Flag a commit's rows as a bug fix (see `indexing::is_fix_message`).
+    pub fn mark_fix_commit(&self, commit_hash: &str) -> Result<(), rusqlite::Error> {
+        self.conn.execute("UPDATE temporal_index SET is_fix = 1 WHERE commit_hash = ?1", params![commit_hash])?;
+        Ok(())
+    }
+
+    /// Record a commit's conventional-commit type (`feat`, `fix`, ...).
+    pub fn set_commit_type(&self, commit_hash: &str, commit_type: &str) -> Result<(), rusqlite::Error> {
+        self.conn.execute(
+            "UPDATE commits SET commit_type = ?2 WHERE commit_hash = ?1",
+            params![commit_hash, commit_type],
+        )?;
+        Ok(())
+    }
+
+    pub fn insert_commit_ticket(&self, commit_hash: &str, ticket: &str) -> Result<(), rusqlite::Error> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO commit_tickets (ticket, commit_hash) VALUES (?1, ?2)",
+            params![ticket, commit_hash],
+        )?;
+        Ok(())
+    }
+
+    /// Record the author of an indexed commit.
+    pub fn insert_commit_author(
+        &self,
+        commit_hash: &str,
+        author_name: &str,
+        author_email: &str,
+        timestamp: i64,
+    ) -> Result<(), rusqlite::Error> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO commits (commit_hash, author_name, author_email, commit_timestamp)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![commit_hash, author_name, author_email, timestamp],
+        )?;
+        Ok(())
+    }
+
+    /// Indexed commits with no recorded author, e.g. from indexes built
+    /// before authors were recorded.
+    pub fn commits_without_author(&self) -> Result<Vec<String>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT t.commit_hash FROM temporal_index t
+             LEFT JOIN commits c ON c.commit_hash = t.commit_hash
+             WHERE c.commit_hash IS NULL",
+        )?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    /// Per-file, per-author commit counts for files under `path_prefix`
+    /// (all files when empty). Authors are keyed by lowercased email:
+    /// (file_path, author_email, author_name, commit_count)
+    pub fn file_author_counts(
+        &self,
+        path_prefix: &str,
+    ) -> Result<Vec<(String, String, String, u32)>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT t.file_path, LOWER(c.author_email), MAX(c.author_name),
+                    COUNT(DISTINCT t.commit_hash)
+             FROM temporal_index t
+             JOIN commits c ON c.commit_hash = t.commit_hash
+             WHERE substr(t.file_path, 1, length(?1)) = ?1
+             GROUP BY t.file_path, LOWER(c.author_email)",
+        )?;
+        let rows = stmt.query_map(params![path_prefix], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+        })?;
+        rows.collect()
+    }
+
+    /// Authors of the indexed commits touching `file_path`, keyed by
+    /// lowercased email: (author_email, author_name, commit_count,
+    /// last_commit_timestamp)
+    pub fn file_authors(&self, file_path: &str) -> Result<Vec<(String, String, u32, i64)>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT LOWER(c.author_email), MAX(c.author_name),
+                    COUNT(DISTINCT t.commit_hash), MAX(t.commit_timestamp)
+             FROM temporal_index t
+             JOIN commits c ON c.commit_hash = t.commit_hash
+             WHERE t.file_path = ?1
+             GROUP BY LOWER(c.author_email)",
+        )?;
+        let rows = stmt.query_map(params![file_path], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+        })?;
+        rows.collect()
+    }
+
+    /// Remember the raw bytes of a path that is not valid UTF-8 and was
+    /// indexed under its lossy form `file_path`.
+    pub fn insert_lossy_path(&self, file_path: &str, raw_path: &[u8]) -> Result<(), rusqlite::Error> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO lossy_paths (file_path, raw_path) VALUES (?1, ?2)",
+            params![file_path, raw_path],
+        )?;
+        Ok(())
+    }
+
+    pub fn insert_rename(&self, old_path: &str, new_path: &str, renamed_at: i64) -> Result<(), rusqlite::Error> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO path_renames (old_path, new_path, renamed_at) VALUES (?1, ?2, ?3)",
+            params![old_path, new_path, renamed_at],
+        )?;
+        Ok(())
+    }
+
+    /// Move history already indexed under `old_path`, up to `renamed_at`,
+    /// to `new_path`. For renames in commits newer than the index (HEAD
+    /// moved on), whose older history was recorded under the old name.
+    pub fn rename_indexed_path(&self, old_path: &str, new_path: &str, renamed_at: i64) -> Result<(), rusqlite::Error> {
+        let moved = self.conn.execute(
+            "UPDATE OR IGNORE temporal_index SET file_path = ?2
+             WHERE file_path = ?1 AND commit_timestamp <= ?3",
+            params![old_path, new_path, renamed_at],
+        )?;
+        if moved == 0 {
+            return Ok(());
+        }
+        // Commits that touched both names keep the new one
+        self.conn.execute(
+            "DELETE FROM temporal_index WHERE file_path = ?1 AND commit_timestamp <= ?2",
+            params![old_path, renamed_at],
+        )?;
+        self.conn.execute("DELETE FROM file_stats WHERE file_path IN (?1, ?2)", params![old_path, new_path])?;
+        self.conn.execute(
+            "INSERT INTO file_stats (file_path, commit_count, last_commit_ts)
+             SELECT file_path, COUNT(*), MAX(commit_timestamp) FROM temporal_index
+             WHERE file_path IN (?1, ?2) GROUP BY file_path",
+            params![old_path, new_path],
+        )?;
+        Ok(())
+    }
+
+    /// The name `path`, as of `timestamp`, has after the renames recorded
+    /// since, following chains of renames.
+    pub fn current_path(&self, path: &str, timestamp: i64) -> Result<String, rusqlite::Error> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT new_path, renamed_at FROM path_renames
+             WHERE old_path = ?1 AND renamed_at >= ?2
+             ORDER BY renamed_at LIMIT 1",
+        )?;
+        let (mut path, mut timestamp) = (path.to_string(), timestamp);
+        // Bounded, in case of a rename cycle within one second
+        for _ in 0..MAX_RENAME_CHAIN {
+            let next: Option<(String, i64)> =
+                stmt.query_row(params![path, timestamp], |row| Ok((row.get(0)?, row.get(1)?))).optional()?;
+            let Some((new_path, renamed_at)) = next else { break };
+            (path, timestamp) = (new_path, renamed_at);
+        }
+        Ok(path)
+    }
+
+    /// The name the file now at `path` had as of `timestamp`:
+    /// `current_path` in reverse.
+    pub fn path_at(&self, path: &str, timestamp: i64) -> Result<String, rusqlite::Error> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT old_path, renamed_at FROM path_renames
+             WHERE new_path = ?1 AND renamed_at >= ?2 AND renamed_at <= ?3
+             ORDER BY renamed_at DESC LIMIT 1",
+        )?;
+        let (mut path, mut before) = (path.to_string(), i64::MAX);
+        for _ in 0..MAX_RENAME_CHAIN {
+            let previous: Option<(String, i64)> =
+                stmt.query_row(params![path, timestamp, before], |row| Ok((row.get(0)?, row.get(1)?))).optional()?;
+            let Some((old_path, renamed_at)) = previous else { break };
+            (path, before) = (old_path, renamed_at);
+        }
+        Ok(path)
+    }
+
+    /// Indexed paths that were not valid UTF-8 (stored in lossy form).
+    pub fn lossy_paths(&self) -> Result<HashSet<String>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare("SELECT file_path FROM lossy_paths")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect()
+    }
+
     /// Get the co-change count between two files: how many commits contain both.
     pub fn co_change_count(&self, file_a: &str, file_b: &str) -> Result<u32, rusqlite::Error> {
         let mut stmt = self.conn.prepare(
@@ -171,191 +735,902 @@ impl Database {
         Ok(result)
     }
 
-    /// Get all files coupled with the given file, along with stats needed for risk scoring:
-    /// (path, co_change_count, total_commits_for_coupled_file, max_commit_timestamp)
+    /// Every pair of indexed files that changed together, both under
+    /// `prefix`, ordered by path.
+    pub fn co_change_matrix(&self, prefix: &str) -> Result<Vec<CoChangePair>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT a.file_path, b.file_path, COUNT(*), sa.commit_count, sb.commit_count
+             FROM temporal_index a
+             JOIN temporal_index b ON a.commit_hash = b.commit_hash AND a.file_path < b.file_path
+             JOIN file_stats sa ON sa.file_path = a.file_path
+             JOIN file_stats sb ON sb.file_path = b.file_path
+             WHERE substr(a.file_path, 1, length(?1)) = ?1 AND substr(b.file_path, 1, length(?1)) = ?1
+             GROUP BY a.file_path, b.file_path
+             ORDER BY a.file_path, b.file_path",
+        )?;
+        let rows = stmt.query_map(params![prefix], |row| {
+            Ok(CoChangePair {
+                file_a: row.get(0)?,
+                file_b: row.get(1)?,
+                co_changes: row.get(2)?,
+                commits_a: row.get(3)?,
+                commits_b: row.get(4)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// Get all files coupled with the given file, along with stats needed for risk scoring.
+    /// `last_timestamp` is that of the newest commit the two files share.
     pub fn coupled_files_with_stats(
         &self,
         file_path: &str,
-    ) -> Result<Vec<(String, u32, u32, i64)>, rusqlite::Error> {
+    ) -> Result<Vec<RawCoupledFileStats>, rusqlite::Error> {
         let mut stmt = self.conn.prepare(
             "SELECT
                 b.file_path,
                 COUNT(DISTINCT a.commit_hash) as co_change_count,
-                (SELECT COUNT(DISTINCT commit_hash)
-                 FROM temporal_index
-                 WHERE file_path = b.file_path) as total_commits,
-                MAX(b.commit_timestamp) as last_timestamp
+                s.commit_count as total_commits,
+                MAX(b.commit_timestamp) as last_timestamp,
+                SUM(b.is_fix) as fix_co_change_count,
+                GROUP_CONCAT(a.commit_timestamp || ':' || a.is_fix) as co_changes
              FROM temporal_index a
              JOIN temporal_index b ON a.commit_hash = b.commit_hash
+             JOIN file_stats s ON s.file_path = b.file_path
              WHERE a.file_path = ?1 AND b.file_path != ?1
              GROUP BY b.file_path
              ORDER BY co_change_count DESC",
         )?;
 
         let rows = stmt.query_map(params![file_path], |row| {
+            Ok(RawCoupledFileStats {
+                path: row.get(0)?,
+                co_change_count: row.get(1)?,
+                total_commits: row.get(2)?,
+                last_timestamp: row.get(3)?,
+                fix_co_change_count: row.get(4)?,
+                co_changes: parse_co_changes(&row.get::<_, String>(5)?),
+            })
+        })?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(row?);
+        }
+        Ok(result)
+    }
+
+    /// `coupled_files_with_stats` with co-change widened from one commit to
+    /// a time window: a commit to `file_path` co-changes every file its
+    /// author changed within `window_secs` of it, in that commit or another.
+    /// Commits indexed without an author row are left out.
+    pub fn coupled_files_in_window(
+        &self,
+        file_path: &str,
+        window_secs: i64,
+    ) -> Result<Vec<RawCoupledFileStats>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT
+                pairs.file_path,
+                COUNT(*) as co_change_count,
+                s.commit_count as total_commits,
+                MAX(pairs.last_timestamp) as last_timestamp,
+                SUM(pairs.is_fix) as fix_co_change_count,
+                GROUP_CONCAT(pairs.commit_timestamp || ':' || pairs.is_fix) as co_changes
+             FROM (
+                SELECT b.file_path, a.commit_timestamp, a.is_fix, MAX(b.commit_timestamp) as last_timestamp
+                FROM temporal_index a
+                JOIN commits ca ON ca.commit_hash = a.commit_hash
+                JOIN commits cb ON cb.author_email = ca.author_email
+                    AND cb.commit_timestamp BETWEEN ca.commit_timestamp - ?2 AND ca.commit_timestamp + ?2
+                JOIN temporal_index b ON b.commit_hash = cb.commit_hash
+                WHERE a.file_path = ?1 AND b.file_path != ?1
+                GROUP BY b.file_path, a.commit_hash
+             ) pairs
+             JOIN file_stats s ON s.file_path = pairs.file_path
+             GROUP BY pairs.file_path
+             ORDER BY co_change_count DESC",
+        )?;
+        let rows = stmt.query_map(params![file_path, window_secs], |row| {
+            Ok(RawCoupledFileStats {
+                path: row.get(0)?,
+                co_change_count: row.get(1)?,
+                total_commits: row.get(2)?,
+                last_timestamp: row.get(3)?,
+                fix_co_change_count: row.get(4)?,
+                co_changes: parse_co_changes(&row.get::<_, String>(5)?),
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// For each file coupled with `file_path`, its co-changes counted by the
+    /// shared commit's conventional-commit type (`other` for untyped ones).
+    /// Files whose co-changes are all untyped are left out.
+    pub fn co_change_types(
+        &self,
+        file_path: &str,
+    ) -> Result<HashMap<String, BTreeMap<String, u32>>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT b.file_path, COALESCE(c.commit_type, 'other'), COUNT(*)
+             FROM temporal_index a
+             JOIN temporal_index b ON a.commit_hash = b.commit_hash
+             LEFT JOIN commits c ON c.commit_hash = a.commit_hash
+             WHERE a.file_path = ?1 AND b.file_path != ?1
+             GROUP BY b.file_path, c.commit_type",
+        )?;
+        let rows = stmt.query_map(params![file_path], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, u32>(2)?))
+        })?;
+
+        let mut result: HashMap<String, BTreeMap<String, u32>> = HashMap::new();
+        for row in rows {
+            let (path, commit_type, count) = row?;
+            result.entry(path).or_default().insert(commit_type, count);
+        }
+        result.retain(|_, types| types.keys().any(|t| t != "other"));
+        Ok(result)
+    }
+
+    /// Files that were changed under at least `min_shared` of the tickets
+    /// `file_path` was changed under, whether or not in the same commits:
+    /// (path, shared_tickets), most shared first.
+    pub fn ticket_coupled_files(
+        &self,
+        file_path: &str,
+        min_shared: u32,
+    ) -> Result<Vec<(String, u32)>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "WITH tickets AS (
+                SELECT DISTINCT t.ticket FROM commit_tickets t
+                JOIN temporal_index a ON a.commit_hash = t.commit_hash
+                WHERE a.file_path = ?1
+             )
+             SELECT b.file_path, COUNT(DISTINCT t.ticket) AS shared
+             FROM tickets x
+             JOIN commit_tickets t ON t.ticket = x.ticket
+             JOIN temporal_index b ON b.commit_hash = t.commit_hash
+             WHERE b.file_path != ?1
+             GROUP BY b.file_path
+             HAVING shared >= ?2
+             ORDER BY shared DESC, b.file_path",
+        )?;
+        let rows = stmt.query_map(params![file_path, min_shared], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    /// Get the oldest and newest commit timestamps in the database.
+    /// Returns (oldest_ts, newest_ts). If no data, returns (0, 0).
+    pub fn commit_time_range(&self) -> Result<(i64, i64), rusqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT COALESCE(MIN(commit_timestamp), 0), COALESCE(MAX(commit_timestamp), 0)
+             FROM temporal_index",
+        )?;
+        let (oldest, newest) = stmt.query_row([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?))
+        })?;
+        Ok((oldest, newest))
+    }
+
+    /// Timestamps of the commits that touch the given file.
+    pub fn commit_timestamps(&self, file_path: &str) -> Result<Vec<i64>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare("SELECT commit_timestamp FROM temporal_index WHERE file_path = ?1")?;
+        let rows = stmt.query_map(params![file_path], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    /// Get the number of commits that touch the given file.
+    pub fn commit_count(&self, file_path: &str) -> Result<u32, rusqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT COUNT(DISTINCT commit_hash) FROM temporal_index WHERE file_path = ?1",
+        )?;
+        let count: u32 = stmt.query_row(params![file_path], |row| row.get(0))?;
+        Ok(count)
+    }
+
+    /// Timestamps of the oldest and newest indexed commits touching the file,
+    /// or None if it has no indexed commits.
+    pub fn file_time_range(&self, file_path: &str) -> Result<Option<(i64, i64)>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT MIN(commit_timestamp), MAX(commit_timestamp)
+             FROM temporal_index WHERE file_path = ?1",
+        )?;
+        let (oldest, newest): (Option<i64>, Option<i64>) =
+            stmt.query_row(params![file_path], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(oldest.zip(newest))
+    }
+
+    /// The most recent indexed commits touching the file, newest first:
+    /// (commit_hash, commit_timestamp, author_name). The author is None for
+    /// commits indexed before authors were recorded.
+    pub fn recent_commits_for_file(
+        &self,
+        file_path: &str,
+        limit: usize,
+    ) -> Result<Vec<(String, i64, Option<String>)>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT t.commit_hash, t.commit_timestamp, c.author_name
+             FROM temporal_index t
+             LEFT JOIN commits c ON c.commit_hash = t.commit_hash
+             WHERE t.file_path = ?1
+             ORDER BY t.commit_timestamp DESC, t.commit_hash
+             LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![file_path, limit as i64], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?;
+        rows.collect()
+    }
+
+    /// The indexed commits touching `file_path`, each with the other indexed
+    /// files it changed: commit_hash -> paths.
+    pub fn commits_with_files(&self, file_path: &str) -> Result<BTreeMap<String, Vec<String>>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT a.commit_hash, b.file_path
+             FROM temporal_index a
+             LEFT JOIN temporal_index b ON b.commit_hash = a.commit_hash AND b.file_path != ?1
+             WHERE a.file_path = ?1",
+        )?;
+        let rows = stmt.query_map(params![file_path], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+        })?;
+        let mut commits: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for row in rows {
+            let (hash, other) = row?;
+            commits.entry(hash).or_default().extend(other);
+        }
+        Ok(commits)
+    }
+
+    /// Commits that changed both files, newest first:
+    /// (commit_hash, commit_timestamp, author_name)
+    pub fn shared_commits(
+        &self,
+        file_a: &str,
+        file_b: &str,
+    ) -> Result<Vec<(String, i64, Option<String>)>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT a.commit_hash, a.commit_timestamp, c.author_name
+             FROM temporal_index a
+             JOIN temporal_index b ON b.commit_hash = a.commit_hash AND b.file_path = ?2
+             LEFT JOIN commits c ON c.commit_hash = a.commit_hash
+             WHERE a.file_path = ?1
+             ORDER BY a.commit_timestamp DESC, a.commit_hash",
+        )?;
+        let rows = stmt.query_map(params![file_a, file_b], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?;
+        rows.collect()
+    }
+
+    /// Get the number of commits that touch the given file before `before_ts`.
+    pub fn commit_count_before(&self, file_path: &str, before_ts: i64) -> Result<u32, rusqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT COUNT(DISTINCT commit_hash) FROM temporal_index
+             WHERE file_path = ?1 AND commit_timestamp < ?2",
+        )?;
+        let count: u32 = stmt.query_row(params![file_path, before_ts], |row| row.get(0))?;
+        Ok(count)
+    }
+
+    /// Per-file commit counts: path -> (total_commits, commits_before `before_ts`).
+    pub fn file_commit_counts_split(
+        &self,
+        before_ts: i64,
+    ) -> Result<HashMap<String, (u32, u32)>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT file_path,
+                    COUNT(DISTINCT commit_hash),
+                    COUNT(DISTINCT CASE WHEN commit_timestamp < ?1 THEN commit_hash END)
+             FROM temporal_index
+             GROUP BY file_path",
+        )?;
+
+        let rows = stmt.query_map(params![before_ts], |row| {
+            Ok((row.get::<_, String>(0)?, (row.get::<_, u32>(1)?, row.get::<_, u32>(2)?)))
+        })?;
+
+        let mut result = HashMap::new();
+        for row in rows {
+            let (path, counts) = row?;
+            result.insert(path, counts);
+        }
+        Ok(result)
+    }
+
+    /// Co-change counts of `file_path` with each of `paths`, and each of
+    /// their own commit counts, split at `before_ts`, in one query. Paths
+    /// that never changed with `file_path` are left out.
+    pub fn co_change_splits(
+        &self,
+        file_path: &str,
+        paths: &[&str],
+        before_ts: i64,
+    ) -> Result<Vec<CoChangeSplit>, rusqlite::Error> {
+        if paths.is_empty() {
+            return Ok(Vec::new());
+        }
+        let placeholders: Vec<String> = (0..paths.len()).map(|i| format!("?{}", i + 3)).collect();
+        let mut stmt = self.conn.prepare(&format!(
+            "WITH co AS (
+                 SELECT b.file_path AS path,
+                        COUNT(DISTINCT a.commit_hash) AS co_total,
+                        COUNT(DISTINCT CASE WHEN a.commit_timestamp < ?2 THEN a.commit_hash END) AS co_before
+                 FROM temporal_index a
+                 JOIN temporal_index b ON a.commit_hash = b.commit_hash
+                 WHERE a.file_path = ?1 AND b.file_path IN ({})
+                 GROUP BY b.file_path
+             )
+             SELECT co.path, co.co_total, co.co_before,
+                    COUNT(DISTINCT t.commit_hash),
+                    COUNT(DISTINCT CASE WHEN t.commit_timestamp < ?2 THEN t.commit_hash END)
+             FROM co
+             JOIN temporal_index t ON t.file_path = co.path
+             GROUP BY co.path",
+            placeholders.join(", ")
+        ))?;
+
+        let mut values: Vec<rusqlite::types::Value> = vec![file_path.to_string().into(), before_ts.into()];
+        values.extend(paths.iter().map(|p| p.to_string().into()));
+        let rows = stmt.query_map(rusqlite::params_from_iter(values), |row| {
+            Ok(CoChangeSplit {
+                path: row.get(0)?,
+                co_total: row.get(1)?,
+                co_before: row.get(2)?,
+                commits_total: row.get(3)?,
+                commits_before: row.get(4)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// Every file pair that co-changed at least `min_co_changes` times:
+    /// (file_a, file_b, co_change_count). `file_a < file_b`.
+    pub fn pair_counts(&self, min_co_changes: u32) -> Result<Vec<(String, String, u32)>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT a.file_path, b.file_path, COUNT(DISTINCT a.commit_hash) AS cnt
+             FROM temporal_index a
+             JOIN temporal_index b
+               ON a.commit_hash = b.commit_hash AND a.file_path < b.file_path
+             GROUP BY a.file_path, b.file_path
+             HAVING cnt >= ?1",
+        )?;
+        let rows = stmt.query_map(params![min_co_changes], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?;
+        rows.collect()
+    }
+
+    /// File pairs that co-changed at least once since `since_ts`, with their
+    /// co-change counts split at that point:
+    /// (file_a, file_b, co_change_count, co_changes_before). `file_a < file_b`.
+    pub fn recent_pair_counts(
+        &self,
+        since_ts: i64,
+    ) -> Result<Vec<(String, String, u32, u32)>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "WITH recent AS (
+                SELECT DISTINCT a.file_path AS fa, b.file_path AS fb
+                FROM temporal_index a
+                JOIN temporal_index b
+                  ON a.commit_hash = b.commit_hash AND a.file_path < b.file_path
+                WHERE a.commit_timestamp >= ?1
+             )
+             SELECT r.fa, r.fb,
+                    COUNT(DISTINCT a.commit_hash),
+                    COUNT(DISTINCT CASE WHEN a.commit_timestamp < ?1 THEN a.commit_hash END)
+             FROM recent r
+             JOIN temporal_index a ON a.file_path = r.fa
+             JOIN temporal_index b ON b.commit_hash = a.commit_hash AND b.file_path = r.fb
+             GROUP BY r.fa, r.fb",
+        )?;
+
+        let rows = stmt.query_map(params![since_ts], |row| {
             Ok((
                 row.get::<_, String>(0)?,
-                row.get::<_, u32>(1)?,
+                row.get::<_, String>(1)?,
                 row.get::<_, u32>(2)?,
-                row.get::<_, i64>(3)?,
+                row.get::<_, u32>(3)?,
             ))
         })?;
 
-        let mut result = Vec::new();
-        for row in rows {
-            result.push(row?);
-        }
-        Ok(result)
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(row?);
+        }
+        Ok(result)
+    }
+
+    /// Get the current indexing state, if any.
+    pub fn get_indexing_state(&self) -> Result<Option<IndexingState>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT head_commit, resume_oid, commits_indexed, strategy, is_complete, last_updated, target_path
+             FROM indexing_state WHERE id = 1",
+        )?;
+        let result = stmt.query_row([], |row| {
+            Ok(IndexingState {
+                head_commit: row.get(0)?,
+                resume_oid: row.get(1)?,
+                commits_indexed: row.get(2)?,
+                strategy: row.get(3)?,
+                is_complete: row.get::<_, i32>(4)? != 0,
+                last_updated: row.get(5)?,
+                target_path: row.get(6)?,
+            })
+        });
+        match result {
+            Ok(state) => Ok(Some(state)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Insert or replace the indexing state.
+    pub fn set_indexing_state(&self, state: &IndexingState) -> Result<(), rusqlite::Error> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO indexing_state
+             (id, head_commit, resume_oid, commits_indexed, strategy, is_complete, last_updated, target_path)
+             VALUES (1, ?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                state.head_commit,
+                state.resume_oid,
+                state.commits_indexed,
+                state.strategy,
+                state.is_complete as i32,
+                state.last_updated,
+                state.target_path,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Drop all history-derived data (temporal index, commit authors,
+    /// lossy paths, indexing state, background queue and commit quarantine).
+    /// Notes and metrics are kept.
+    pub fn clear_index(&self) -> Result<(), rusqlite::Error> {
+        self.conn.execute_batch(
+            "DELETE FROM temporal_index;
+             DELETE FROM file_stats;
+             DELETE FROM path_renames;
+             DELETE FROM commits;
+             DELETE FROM commit_tickets;
+             DELETE FROM lossy_paths;
+             DELETE FROM indexing_state;
+             DELETE FROM index_catch_up;
+             DELETE FROM background_queue;
+             DELETE FROM commit_quarantine;
+             DELETE FROM risk_calibration;",
+        )
+    }
+
+    pub fn get_catch_up(&self) -> Result<Option<CatchUp>, rusqlite::Error> {
+        self.conn
+            .query_row(
+                "SELECT base_commit, resume_oid, history_complete FROM index_catch_up WHERE id = 1",
+                [],
+                |row| {
+                    Ok(CatchUp {
+                        base_commit: row.get(0)?,
+                        resume_oid: row.get(1)?,
+                        history_complete: row.get(2)?,
+                    })
+                },
+            )
+            .optional()
+    }
+
+    pub fn set_catch_up(&self, catch_up: &CatchUp) -> Result<(), rusqlite::Error> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO index_catch_up (id, base_commit, resume_oid, history_complete)
+             VALUES (1, ?1, ?2, ?3)",
+            params![catch_up.base_commit, catch_up.resume_oid, catch_up.history_complete],
+        )?;
+        Ok(())
+    }
+
+    pub fn clear_catch_up(&self) -> Result<(), rusqlite::Error> {
+        self.conn.execute("DELETE FROM index_catch_up", [])?;
+        Ok(())
+    }
+
+    pub fn get_reindex_checkpoint(&self) -> Result<Option<ReindexCheckpoint>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT head_commit, resume_oid, commits_indexed, started_at
+             FROM reindex_checkpoint WHERE id = 1",
+        )?;
+        let mut rows = stmt.query_map([], |row| {
+            Ok(ReindexCheckpoint {
+                head_commit: row.get(0)?,
+                resume_oid: row.get(1)?,
+                commits_indexed: row.get(2)?,
+                started_at: row.get(3)?,
+            })
+        })?;
+        rows.next().transpose()
+    }
+
+    pub fn set_reindex_checkpoint(&self, checkpoint: &ReindexCheckpoint) -> Result<(), rusqlite::Error> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO reindex_checkpoint
+             (id, head_commit, resume_oid, commits_indexed, started_at)
+             VALUES (1, ?1, ?2, ?3, ?4)",
+            params![
+                checkpoint.head_commit,
+                checkpoint.resume_oid,
+                checkpoint.commits_indexed,
+                checkpoint.started_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn clear_reindex_checkpoint(&self) -> Result<(), rusqlite::Error> {
+        self.conn.execute("DELETE FROM reindex_checkpoint", [])?;
+        Ok(())
+    }
+
+    /// Returns true if no indexing has been done yet (no indexing_state row).
+    pub fn is_first_index_call(&self) -> Result<bool, rusqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT COUNT(*) FROM indexing_state WHERE id = 1",
+        )?;
+        let count: i32 = stmt.query_row([], |row| row.get(0))?;
+        Ok(count == 0)
+    }
+
+    /// The fingerprint of the repository this database was built from:
+    /// (origin_hash, root_commit). The commit is empty until one is known.
+    pub fn get_fingerprint(&self) -> Result<Option<(String, String)>, rusqlite::Error> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT origin_hash, root_commit FROM repo_fingerprint WHERE id = 1")?;
+        let mut rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.next().transpose()
+    }
+
+    pub fn set_fingerprint(&self, origin_hash: &str, root_commit: &str) -> Result<(), rusqlite::Error> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO repo_fingerprint (id, origin_hash, root_commit)
+             VALUES (1, ?1, ?2)",
+            params![origin_hash, root_commit],
+        )?;
+        Ok(())
+    }
+
+    /// Record a root commit of the indexed history as the commit the
+    /// fingerprint is keyed on.
+    pub fn set_root_commit(&self, root_commit: &str) -> Result<(), rusqlite::Error> {
+        self.conn.execute(
+            "INSERT INTO repo_fingerprint (id, root_commit) VALUES (1, ?1)
+             ON CONFLICT(id) DO UPDATE SET root_commit = excluded.root_commit",
+            params![root_commit],
+        )?;
+        Ok(())
+    }
+
+    /// The oldest commit in the temporal index.
+    pub fn oldest_indexed_commit(&self) -> Result<Option<String>, rusqlite::Error> {
+        self.conn
+            .query_row(
+                "SELECT commit_hash FROM temporal_index ORDER BY commit_timestamp LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .optional()
+    }
+
+    pub fn get_index_target(&self) -> Result<IndexTarget, rusqlite::Error> {
+        let mut stmt = self.conn.prepare("SELECT scope, rev FROM index_target WHERE id = 1")?;
+        let mut rows = stmt.query_map([], |row| Ok(IndexTarget { scope: row.get(0)?, rev: row.get(1)? }))?;
+        Ok(rows.next().transpose()?.unwrap_or_default())
+    }
+
+    pub fn set_index_target(&self, target: &IndexTarget) -> Result<(), rusqlite::Error> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO index_target (id, scope, rev) VALUES (1, ?1, ?2)",
+            params![target.scope, target.rev],
+        )?;
+        Ok(())
+    }
+
+    /// The stored risk calibration and the HEAD it was drawn at.
+    pub fn get_risk_calibration(&self) -> Result<Option<(String, RiskThresholds)>, rusqlite::Error> {
+        self.conn
+            .query_row("SELECT head_commit, critical, high, medium FROM risk_calibration WHERE id = 1", [], |row| {
+                Ok((row.get(0)?, RiskThresholds { critical: row.get(1)?, high: row.get(2)?, medium: row.get(3)? }))
+            })
+            .optional()
     }
 
-    /// Get the oldest and newest commit timestamps in the database.
-    /// Returns (oldest_ts, newest_ts). If no data, returns (0, 0).
-    pub fn commit_time_range(&self) -> Result<(i64, i64), rusqlite::Error> {
-        let mut stmt = self.conn.prepare(
-            "SELECT COALESCE(MIN(commit_timestamp), 0), COALESCE(MAX(commit_timestamp), 0)
-             FROM temporal_index",
+    pub fn set_risk_calibration(&self, head_commit: &str, thresholds: &RiskThresholds) -> Result<(), rusqlite::Error> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO risk_calibration (id, head_commit, critical, high, medium)
+             VALUES (1, ?1, ?2, ?3, ?4)",
+            params![head_commit, thresholds.critical, thresholds.high, thresholds.medium],
         )?;
-        let (oldest, newest) = stmt.query_row([], |row| {
-            Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?))
-        })?;
-        Ok((oldest, newest))
+        Ok(())
     }
 
-    /// Get the number of commits that touch the given file.
-    pub fn commit_count(&self, file_path: &str) -> Result<u32, rusqlite::Error> {
-        let mut stmt = self.conn.prepare(
-            "SELECT COUNT(DISTINCT commit_hash) FROM temporal_index WHERE file_path = ?1",
+    /// Queue a file for background PathFiltered indexing. Re-queuing a file
+    /// that is already pending bumps its priority and keeps the existing
+    /// resume point, if any, so a walk under way carries on where it stopped.
+    pub fn enqueue_background(
+        &self,
+        target_path: &str,
+        resume_oid: Option<&str>,
+        now: i64,
+    ) -> Result<(), rusqlite::Error> {
+        self.conn.execute(
+            "INSERT INTO background_queue (target_path, priority, attempts, resume_oid, enqueued_at)
+             VALUES (?1, 1, 0, ?2, ?3)
+             ON CONFLICT(target_path) DO UPDATE SET
+                priority = priority + 1,
+                resume_oid = COALESCE(background_queue.resume_oid, excluded.resume_oid),
+                enqueued_at = excluded.enqueued_at",
+            params![target_path, resume_oid, now],
         )?;
-        let count: u32 = stmt.query_row(params![file_path], |row| row.get(0))?;
-        Ok(count)
+        Ok(())
     }
 
-    /// Get the current indexing state, if any.
-    pub fn get_indexing_state(&self) -> Result<Option<IndexingState>, rusqlite::Error> {
+    /// Highest-priority pending jobs first; ties go to the most recently requested.
+    pub fn next_background_jobs(&self, limit: usize) -> Result<Vec<BackgroundJob>, rusqlite::Error> {
         let mut stmt = self.conn.prepare(
-            "SELECT head_commit, resume_oid, commits_indexed, strategy, is_complete, last_updated, target_path
-             FROM indexing_state WHERE id = 1",
+            "SELECT target_path, priority, attempts, resume_oid
+             FROM background_queue
+             ORDER BY priority DESC, enqueued_at DESC
+             LIMIT ?1",
         )?;
-        let result = stmt.query_row([], |row| {
-            Ok(IndexingState {
-                head_commit: row.get(0)?,
-                resume_oid: row.get(1)?,
-                commits_indexed: row.get(2)?,
-                strategy: row.get(3)?,
-                is_complete: row.get::<_, i32>(4)? != 0,
-                last_updated: row.get(5)?,
-                target_path: row.get(6)?,
+        let rows = stmt.query_map(params![limit as i64], |row| {
+            Ok(BackgroundJob {
+                target_path: row.get(0)?,
+                priority: row.get(1)?,
+                attempts: row.get(2)?,
+                resume_oid: row.get(3)?,
             })
-        });
-        match result {
-            Ok(state) => Ok(Some(state)),
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(e),
-        }
+        })?;
+        rows.collect()
     }
 
-    /// Insert or replace the indexing state.
-    pub fn set_indexing_state(&self, state: &IndexingState) -> Result<(), rusqlite::Error> {
+    /// Record a background attempt on a job and its new resume point.
+    pub fn update_background_job(
+        &self,
+        target_path: &str,
+        resume_oid: Option<&str>,
+    ) -> Result<(), rusqlite::Error> {
         self.conn.execute(
-            "INSERT OR REPLACE INTO indexing_state
-             (id, head_commit, resume_oid, commits_indexed, strategy, is_complete, last_updated, target_path)
-             VALUES (1, ?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            params![
-                state.head_commit,
-                state.resume_oid,
-                state.commits_indexed,
-                state.strategy,
-                state.is_complete as i32,
-                state.last_updated,
-                state.target_path,
-            ],
+            "UPDATE background_queue
+             SET attempts = attempts + 1, resume_oid = COALESCE(?2, resume_oid)
+             WHERE target_path = ?1",
+            params![target_path, resume_oid],
         )?;
         Ok(())
     }
 
-    /// Returns true if no indexing has been done yet (no indexing_state row).
-    pub fn is_first_index_call(&self) -> Result<bool, rusqlite::Error> {
+    /// Remove a job from the background queue (finished or abandoned).
+    pub fn remove_background_job(&self, target_path: &str) -> Result<(), rusqlite::Error> {
+        self.conn.execute(
+            "DELETE FROM background_queue WHERE target_path = ?1",
+            params![target_path],
+        )?;
+        Ok(())
+    }
+
+    /// Drop the lowest-priority jobs so at most `max_len` remain.
+    pub fn trim_background_queue(&self, max_len: usize) -> Result<(), rusqlite::Error> {
+        self.conn.execute(
+            "DELETE FROM background_queue WHERE target_path NOT IN (
+                SELECT target_path FROM background_queue
+                ORDER BY priority DESC, enqueued_at DESC
+                LIMIT ?1
+             )",
+            params![max_len as i64],
+        )?;
+        Ok(())
+    }
+
+    /// Number of jobs waiting in the background queue.
+    pub fn background_queue_len(&self) -> Result<u32, rusqlite::Error> {
+        self.conn
+            .query_row("SELECT COUNT(*) FROM background_queue", [], |row| row.get(0))
+    }
+
+    /// Record a failed attempt to load or diff a commit. Returns the
+    /// commit's failure count including this one.
+    pub fn record_commit_failure(
+        &self,
+        commit_hash: &str,
+        error: &str,
+        now: i64,
+    ) -> Result<u32, rusqlite::Error> {
+        self.conn.query_row(
+            "INSERT INTO commit_quarantine (commit_hash, failures, last_error, updated_at)
+             VALUES (?1, 1, ?2, ?3)
+             ON CONFLICT(commit_hash) DO UPDATE SET
+                failures = failures + 1,
+                last_error = excluded.last_error,
+                updated_at = excluded.updated_at
+             RETURNING failures",
+            params![commit_hash, error, now],
+            |row| row.get(0),
+        )
+    }
+
+    /// Commits that have failed at least `min_failures` times.
+    pub fn quarantined_commits(&self, min_failures: u32) -> Result<Vec<String>, rusqlite::Error> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT commit_hash FROM commit_quarantine WHERE failures >= ?1")?;
+        let rows = stmt.query_map(params![min_failures], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    /// Replace the stored results for `test_file` with those of the latest
+    /// run: `(test name, passed)` pairs.
+    pub fn replace_test_results(
+        &self,
+        test_file: &str,
+        results: &[(String, bool)],
+        now: i64,
+    ) -> Result<(), rusqlite::Error> {
+        self.begin_transaction()?;
+        self.conn.execute("DELETE FROM test_results WHERE test_file = ?1", params![test_file])?;
         let mut stmt = self.conn.prepare(
-            "SELECT COUNT(*) FROM indexing_state WHERE id = 1",
+            "INSERT OR REPLACE INTO test_results (test_file, test_name, passed, imported_at)
+             VALUES (?1, ?2, ?3, ?4)",
         )?;
-        let count: i32 = stmt.query_row([], |row| row.get(0))?;
-        Ok(count == 0)
+        for (name, passed) in results {
+            stmt.execute(params![test_file, name, passed, now])?;
+        }
+        self.commit_transaction()
+    }
+
+    /// Names of the tests in `test_file` that failed in a run imported at
+    /// or after `since`.
+    pub fn failing_tests(&self, test_file: &str, since: i64) -> Result<Vec<String>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT test_name FROM test_results
+             WHERE test_file = ?1 AND passed = 0 AND imported_at >= ?2
+             ORDER BY test_name",
+        )?;
+        let rows = stmt.query_map(params![test_file, since], |row| row.get(0))?;
+        rows.collect()
     }
 
-    /// Add a memory (note) for a file, optionally scoped to a symbol.
+    /// Add a memory (note) for a file, optionally scoped to a symbol, with
+    /// whatever is known of who wrote it and against which revision.
     pub fn add_memory(
         &self,
         file_path: &str,
         symbol_name: Option<&str>,
         content: &str,
+        origin: &NoteOrigin,
     ) -> Result<i64, rusqlite::Error> {
         self.conn.execute(
-            "INSERT INTO memories (file_path, symbol_name, content) VALUES (?1, ?2, ?3)",
-            params![file_path, symbol_name, content],
+            "INSERT INTO memories
+                 (file_path, symbol_name, content, author_name, author_email, head_commit, blob_oid)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                file_path,
+                symbol_name,
+                content,
+                origin.author_name,
+                origin.author_email,
+                origin.head_commit,
+                origin.blob_oid,
+            ],
         )?;
         Ok(self.conn.last_insert_rowid())
     }
 
+    /// Copy every note in `other` into this database, keeping authors and
+    /// creation times. Returns the number copied.
+    pub fn copy_memories_from(&self, other: &Database) -> Result<usize, rusqlite::Error> {
+        let mut stmt = other.conn.prepare(
+            "SELECT file_path, symbol_name, content, created_at, author_name, author_email, head_commit, blob_oid
+             FROM memories ORDER BY id",
+        )?;
+        let mut rows = stmt.query([])?;
+        let mut copied = 0;
+        while let Some(row) = rows.next()? {
+            self.conn.execute(
+                "INSERT INTO memories
+                     (file_path, symbol_name, content, created_at, author_name, author_email, head_commit, blob_oid)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                    row.get::<_, Option<String>>(5)?,
+                    row.get::<_, Option<String>>(6)?,
+                    row.get::<_, Option<String>>(7)?,
+                ],
+            )?;
+            copied += 1;
+        }
+        Ok(copied)
+    }
+
     /// Get all memories for a specific file.
     pub fn memories_for_file(&self, file_path: &str) -> Result<Vec<Memory>, rusqlite::Error> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, file_path, symbol_name, content, created_at
-             FROM memories WHERE file_path = ?1 ORDER BY created_at DESC",
-        )?;
-        let rows = stmt.query_map(params![file_path], |row| {
-            Ok(Memory {
-                id: row.get(0)?,
-                file_path: row.get(1)?,
-                symbol_name: row.get(2)?,
-                content: row.get(3)?,
-                created_at: row.get(4)?,
-            })
-        })?;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {MEMORY_COLUMNS} FROM memories WHERE file_path = ?1 ORDER BY created_at DESC"
+        ))?;
+        let rows = stmt.query_map(params![file_path], memory_from_row)?;
         rows.collect()
     }
 
     /// Search memories by content or file path substring.
-    pub fn search_memories(&self, query: &str) -> Result<Vec<Memory>, rusqlite::Error> {
+    pub fn search_memories(&self, query: &str, page: Page) -> Result<Vec<Memory>, rusqlite::Error> {
         let pattern = format!("%{query}%");
-        let mut stmt = self.conn.prepare(
-            "SELECT id, file_path, symbol_name, content, created_at
+        let (limit, offset) = page.sql();
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {MEMORY_COLUMNS}
              FROM memories
              WHERE content LIKE ?1 OR file_path LIKE ?1
-             ORDER BY created_at DESC",
-        )?;
-        let rows = stmt.query_map(params![pattern], |row| {
-            Ok(Memory {
-                id: row.get(0)?,
-                file_path: row.get(1)?,
-                symbol_name: row.get(2)?,
-                content: row.get(3)?,
-                created_at: row.get(4)?,
-            })
-        })?;
+             ORDER BY created_at DESC, id DESC
+             LIMIT ?2 OFFSET ?3"
+        ))?;
+        let rows = stmt.query_map(params![pattern, limit, offset], memory_from_row)?;
         rows.collect()
     }
 
-    /// List all memories, optionally filtered by file path.
-    pub fn list_memories(&self, file_path: Option<&str>) -> Result<Vec<Memory>, rusqlite::Error> {
-        match file_path {
-            Some(path) => self.memories_for_file(path),
-            None => {
-                let mut stmt = self.conn.prepare(
-                    "SELECT id, file_path, symbol_name, content, created_at
-                     FROM memories ORDER BY created_at DESC",
-                )?;
-                let rows = stmt.query_map([], |row| {
-                    Ok(Memory {
-                        id: row.get(0)?,
-                        file_path: row.get(1)?,
-                        symbol_name: row.get(2)?,
-                        content: row.get(3)?,
-                        created_at: row.get(4)?,
-                    })
-                })?;
-                rows.collect()
-            }
-        }
+    /// List memories, newest first, optionally filtered by file path and by
+    /// author (name or email, case-insensitive).
+    pub fn list_memories(
+        &self,
+        file_path: Option<&str>,
+        author: Option<&str>,
+        page: Page,
+    ) -> Result<Vec<Memory>, rusqlite::Error> {
+        let (limit, offset) = page.sql();
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {MEMORY_COLUMNS}
+             FROM memories
+             WHERE (?1 IS NULL OR file_path = ?1)
+               AND (?2 IS NULL OR author_name = ?2 COLLATE NOCASE OR author_email = ?2 COLLATE NOCASE)
+             ORDER BY created_at DESC, id DESC
+             LIMIT ?3 OFFSET ?4"
+        ))?;
+        let rows = stmt.query_map(params![file_path, author, limit, offset], memory_from_row)?;
+        rows.collect()
+    }
+
+    /// Files with the most indexed commits: (file_path, commit_count).
+    pub fn hotspots(&self, limit: usize) -> Result<Vec<(String, u32)>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT file_path, COUNT(DISTINCT commit_hash) AS cnt
+             FROM temporal_index
+             GROUP BY file_path
+             ORDER BY cnt DESC, file_path
+             LIMIT ?1",
+        )?;
+        let rows = stmt.query_map(params![limit as i64], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    /// The most recent analysis events, newest first.
+    pub fn recent_analyses(&self, limit: usize) -> Result<Vec<RecentAnalysis>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT COALESCE(file_path, ''), timestamp, coupled_files_count,
+                    critical_count, high_count, analysis_time_ms
+             FROM metrics_events
+             WHERE event_type = 'analysis'
+             ORDER BY id DESC
+             LIMIT ?1",
+        )?;
+        let rows = stmt.query_map(params![limit as i64], |row| {
+            Ok(RecentAnalysis {
+                file_path: row.get(0)?,
+                timestamp: row.get(1)?,
+                coupled_files_count: row.get(2)?,
+                critical_count: row.get(3)?,
+                high_count: row.get(4)?,
+                analysis_time_ms: row.get::<_, i64>(5)? as u64,
+            })
+        })?;
+        rows.collect()
     }
 
     /// Insert a metrics event.
@@ -375,7 +1650,7 @@ impl Database {
         analysis_time_ms: u64,
         note_id: Option<i64>,
         repo_root: &str,
-    ) -> Result<(), rusqlite::Error> {
+    ) -> Result<i64, rusqlite::Error> {
         self.conn.execute(
             "INSERT INTO metrics_events (
                 event_type, file_path, coupled_files_count,
@@ -399,6 +1674,50 @@ impl Database {
                 repo_root,
             ],
         )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Attach response payload sizes to a previously inserted metrics event.
+    pub fn set_event_payload_sizes(
+        &self,
+        event_id: i64,
+        sizes: &PayloadSizes,
+    ) -> Result<(), rusqlite::Error> {
+        self.conn.execute(
+            "UPDATE metrics_events
+             SET response_bytes = ?2, coupled_files_bytes = ?3,
+                 memories_bytes = ?4, test_intents_bytes = ?5
+             WHERE id = ?1",
+            params![
+                event_id,
+                sizes.response_bytes as i64,
+                sizes.coupled_files_bytes as i64,
+                sizes.memories_bytes as i64,
+                sizes.test_intents_bytes as i64,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Insert an `indexing` metrics event. Commits indexed and elapsed time
+    /// go in the `commit_count` and `analysis_time_ms` columns.
+    pub fn insert_indexing_event(&self, event: &IndexingEvent, repo_root: &str) -> Result<(), rusqlite::Error> {
+        self.conn.execute(
+            "INSERT INTO metrics_events
+                (event_type, commit_count, analysis_time_ms, strategy, budget_ms,
+                 needs_background, commits_per_sec, background, repo_root)
+             VALUES ('indexing', ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                event.commits_indexed,
+                event.elapsed_ms as i64,
+                event.strategy,
+                event.budget_ms as i64,
+                event.needs_background,
+                event.commits_per_sec,
+                event.background,
+                repo_root,
+            ],
+        )?;
         Ok(())
     }
 
@@ -407,45 +1726,126 @@ impl Database {
         &self,
         repo_root: &str,
     ) -> Result<crate::types::MetricsSummary, rusqlite::Error> {
-        let mut stmt = self.conn.prepare(
-            "SELECT
-                COUNT(*) FILTER (WHERE event_type = 'analysis') as total_analyses,
-                COUNT(*) FILTER (WHERE event_type = 'add_note') as notes_created,
-                COUNT(*) FILTER (WHERE event_type = 'search_notes') as searches_performed,
-                COUNT(*) FILTER (WHERE event_type = 'list_notes') as lists_performed,
-                COALESCE(SUM(coupled_files_count), 0) as total_coupled_files,
-                COALESCE(SUM(critical_count), 0) as critical_risk_count,
-                COALESCE(SUM(high_count), 0) as high_risk_count,
-                COALESCE(SUM(medium_count), 0) as medium_risk_count,
-                COALESCE(SUM(low_count), 0) as low_risk_count,
-                COALESCE(SUM(test_files_found), 0) as test_files_found,
-                COALESCE(SUM(test_intents_total), 0) as test_intents_extracted,
-                COALESCE(AVG(analysis_time_ms) FILTER (WHERE event_type = 'analysis'), 0) as avg_analysis_time_ms
-            FROM metrics_events
-            WHERE repo_root = ?1",
-        )?;
-
-        let summary = stmt.query_row(params![repo_root], |row| {
-            Ok(crate::types::MetricsSummary {
-                total_analyses: row.get::<_, i64>(0)? as u32,
-                notes_created: row.get::<_, i64>(1)? as u32,
-                searches_performed: row.get::<_, i64>(2)? as u32,
-                lists_performed: row.get::<_, i64>(3)? as u32,
-                total_coupled_files: row.get::<_, i64>(4)? as u32,
-                critical_risk_count: row.get::<_, i64>(5)? as u32,
-                high_risk_count: row.get::<_, i64>(6)? as u32,
-                medium_risk_count: row.get::<_, i64>(7)? as u32,
-                low_risk_count: row.get::<_, i64>(8)? as u32,
-                test_files_found: row.get::<_, i64>(9)? as u32,
-                test_intents_extracted: row.get::<_, i64>(10)? as u32,
-                avg_analysis_time_ms: row.get::<_, f64>(11)? as u64,
+        let mut stmt = self
+            .conn
+            .prepare(&format!("SELECT {METRICS_SUMMARY_COLUMNS} FROM metrics_events WHERE repo_root = ?1"))?;
+        stmt.query_row(params![repo_root], |row| metrics_summary_from_row(row, 0))
+    }
+
+    /// Analysis metrics for one file of a repository.
+    pub fn get_file_metrics(&self, repo_root: &str, file_path: &str) -> Result<FileMetrics, rusqlite::Error> {
+        self.conn.query_row(
+            "SELECT COUNT(*),
+                    COALESCE(AVG(coupled_files_count), 0),
+                    COALESCE(AVG(critical_count), 0),
+                    COALESCE(AVG(high_count), 0),
+                    COALESCE(AVG(medium_count), 0),
+                    COALESCE(AVG(low_count), 0),
+                    COALESCE(AVG(analysis_time_ms), 0),
+                    MAX(timestamp)
+             FROM metrics_events
+             WHERE repo_root = ?1 AND file_path = ?2 AND event_type = 'analysis'",
+            params![repo_root, file_path],
+            |row| {
+                Ok(FileMetrics {
+                    file_path: file_path.to_string(),
+                    times_analyzed: row.get(0)?,
+                    avg_coupled_files: row.get(1)?,
+                    avg_critical_count: row.get(2)?,
+                    avg_high_count: row.get(3)?,
+                    avg_medium_count: row.get(4)?,
+                    avg_low_count: row.get(5)?,
+                    avg_analysis_time_ms: row.get::<_, f64>(6)? as u64,
+                    last_analyzed_at: row.get(7)?,
+                })
+            },
+        )
+    }
+
+    /// Metrics summaries per UTC day or week (weeks start on Monday), oldest
+    /// first. Periods without events are left out.
+    pub fn get_metrics_series(
+        &self,
+        repo_root: &str,
+        period: MetricsPeriod,
+    ) -> Result<Vec<MetricsPeriodSummary>, rusqlite::Error> {
+        let period_start = match period {
+            MetricsPeriod::Day => "date(timestamp)",
+            MetricsPeriod::Week => "date(timestamp, 'weekday 0', '-6 days')",
+        };
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {period_start} AS period_start, {METRICS_SUMMARY_COLUMNS}
+             FROM metrics_events
+             WHERE repo_root = ?1
+             GROUP BY period_start
+             ORDER BY period_start"
+        ))?;
+        let rows = stmt.query_map(params![repo_root], |row| {
+            Ok(MetricsPeriodSummary {
+                period_start: row.get(0)?,
+                summary: metrics_summary_from_row(row, 1)?,
             })
         })?;
-
-        Ok(summary)
+        rows.collect()
     }
 }
 
+/// Aggregates over `metrics_events`, read back by `metrics_summary_from_row`.
+const METRICS_SUMMARY_COLUMNS: &str = "
+    COUNT(*) FILTER (WHERE event_type = 'analysis') as total_analyses,
+    COUNT(*) FILTER (WHERE event_type = 'add_note') as notes_created,
+    COUNT(*) FILTER (WHERE event_type = 'search_notes') as searches_performed,
+    COUNT(*) FILTER (WHERE event_type = 'list_notes') as lists_performed,
+    COALESCE(SUM(coupled_files_count), 0) as total_coupled_files,
+    COALESCE(SUM(critical_count), 0) as critical_risk_count,
+    COALESCE(SUM(high_count), 0) as high_risk_count,
+    COALESCE(SUM(medium_count), 0) as medium_risk_count,
+    COALESCE(SUM(low_count), 0) as low_risk_count,
+    COALESCE(SUM(test_files_found), 0) as test_files_found,
+    COALESCE(SUM(test_intents_total), 0) as test_intents_extracted,
+    COALESCE(AVG(analysis_time_ms) FILTER (WHERE event_type = 'analysis'), 0) as avg_analysis_time_ms,
+    COALESCE(AVG(response_bytes) FILTER (WHERE event_type = 'analysis'), 0) as avg_response_bytes,
+    COALESCE(MAX(response_bytes) FILTER (WHERE event_type = 'analysis'), 0) as max_response_bytes,
+    COALESCE(AVG(coupled_files_bytes) FILTER (WHERE event_type = 'analysis'), 0) as avg_coupled_files_bytes,
+    COALESCE(AVG(memories_bytes) FILTER (WHERE event_type = 'analysis'), 0) as avg_memories_bytes,
+    COALESCE(AVG(test_intents_bytes) FILTER (WHERE event_type = 'analysis'), 0) as avg_test_intents_bytes,
+    COUNT(*) FILTER (WHERE event_type = 'indexing') as indexing_runs,
+    COUNT(*) FILTER (WHERE event_type = 'indexing' AND background) as background_indexing_runs,
+    COALESCE(AVG(analysis_time_ms) FILTER (WHERE event_type = 'indexing'), 0) as avg_indexing_time_ms,
+    COALESCE(AVG(commits_per_sec) FILTER (WHERE event_type = 'indexing'), 0) as avg_commits_per_sec";
+
+/// Read `METRICS_SUMMARY_COLUMNS` starting at column `offset`.
+fn metrics_summary_from_row(
+    row: &rusqlite::Row,
+    offset: usize,
+) -> Result<crate::types::MetricsSummary, rusqlite::Error> {
+    let count = |i: usize| row.get::<_, i64>(offset + i).map(|n| n as u32);
+    let avg = |i: usize| row.get::<_, f64>(offset + i).map(|n| n as u64);
+    Ok(crate::types::MetricsSummary {
+        total_analyses: count(0)?,
+        notes_created: count(1)?,
+        searches_performed: count(2)?,
+        lists_performed: count(3)?,
+        total_coupled_files: count(4)?,
+        critical_risk_count: count(5)?,
+        high_risk_count: count(6)?,
+        medium_risk_count: count(7)?,
+        low_risk_count: count(8)?,
+        test_files_found: count(9)?,
+        test_intents_extracted: count(10)?,
+        avg_analysis_time_ms: avg(11)?,
+        avg_response_bytes: avg(12)?,
+        max_response_bytes: row.get::<_, i64>(offset + 13)? as u64,
+        avg_coupled_files_bytes: avg(14)?,
+        avg_memories_bytes: avg(15)?,
+        avg_test_intents_bytes: avg(16)?,
+        indexing_runs: count(17)?,
+        background_indexing_runs: count(18)?,
+        avg_indexing_time_ms: avg(19)?,
+        avg_commits_per_sec: row.get(offset + 20)?,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -466,6 +1866,27 @@ mod tests {
         assert_eq!(db.co_change_count("src/B.ts", "src/C.ts").unwrap(), 0);
     }
 
+    #[test]
+    fn test_file_time_range_and_recent_commits() {
+        let db = Database::in_memory().unwrap();
+        assert_eq!(db.file_time_range("src/A.ts").unwrap(), None);
+
+        db.insert_commit("c1", &["src/A.ts"], 1000).unwrap();
+        db.insert_commit("c2", &["src/A.ts", "src/B.ts"], 3000).unwrap();
+        db.insert_commit("c3", &["src/A.ts"], 2000).unwrap();
+        db.insert_commit_author("c2", "Alice", "alice@example.com", 3000).unwrap();
+
+        assert_eq!(db.file_time_range("src/A.ts").unwrap(), Some((1000, 3000)));
+        let recent = db.recent_commits_for_file("src/A.ts", 2).unwrap();
+        assert_eq!(
+            recent,
+            vec![
+                ("c2".to_string(), 3000, Some("Alice".to_string())),
+                ("c3".to_string(), 2000, None),
+            ]
+        );
+    }
+
     #[test]
     fn test_coupled_files() {
         let db = Database::in_memory().unwrap();
@@ -603,18 +2024,145 @@ mod tests {
         assert_eq!(stats.len(), 2);
 
         // B: co_change=3, total_commits=4, last_timestamp=3000 (from co-commits with A)
-        let (path, co_change, total, last_ts) = &stats[0];
-        assert_eq!(path, "B.ts");
-        assert_eq!(*co_change, 3);
-        assert_eq!(*total, 4);
-        assert_eq!(*last_ts, 3000);
+        let b = &stats[0];
+        assert_eq!(b.path, "B.ts");
+        assert_eq!(b.co_change_count, 3);
+        assert_eq!(b.total_commits, 4);
+        assert_eq!(b.last_timestamp, 3000);
+        assert_eq!(b.fix_co_change_count, 0);
+        let mut timestamps: Vec<i64> = b.co_changes.iter().map(|c| c.timestamp).collect();
+        timestamps.sort();
+        assert_eq!(timestamps, [1000, 2000, 3000]);
 
         // C: co_change=1, total_commits=1, last_timestamp=3000
-        let (path, co_change, total, last_ts) = &stats[1];
-        assert_eq!(path, "C.ts");
-        assert_eq!(*co_change, 1);
-        assert_eq!(*total, 1);
-        assert_eq!(*last_ts, 3000);
+        let c = &stats[1];
+        assert_eq!(c.path, "C.ts");
+        assert_eq!(c.co_change_count, 1);
+        assert_eq!(c.total_commits, 1);
+        assert_eq!(c.last_timestamp, 3000);
+
+        // Duplicate rows leave the per-file counts alone
+        db.insert_commit("c4", &["B.ts"], 4000).unwrap();
+        assert_eq!(db.coupled_files_with_stats("A.ts").unwrap()[0].total_commits, 4);
+
+        db.mark_fix_commit("c2").unwrap();
+        assert_eq!(db.coupled_files_with_stats("A.ts").unwrap()[0].fix_co_change_count, 1);
+    }
+
+    #[test]
+    fn test_co_change_types() {
+        let db = Database::in_memory().unwrap();
+        for (hash, commit_type) in [("c1", Some("fix")), ("c2", Some("chore")), ("c3", Some("chore")), ("c4", None)] {
+            db.insert_commit(hash, &["A.ts", "B.ts"], 0).unwrap();
+            db.insert_commit_author(hash, "Ann", "ann@example.com", 0).unwrap();
+            if let Some(commit_type) = commit_type {
+                db.set_commit_type(hash, commit_type).unwrap();
+            }
+        }
+        db.insert_commit("c5", &["A.ts", "C.ts"], 0).unwrap();
+
+        let types = db.co_change_types("A.ts").unwrap();
+        let expected: BTreeMap<String, u32> =
+            [("chore".to_string(), 2), ("fix".to_string(), 1), ("other".to_string(), 1)].into();
+        assert_eq!(types["B.ts"], expected);
+        // Only untyped co-changes
+        assert!(!types.contains_key("C.ts"));
+    }
+
+    #[test]
+    fn test_coupled_files_in_window() {
+        let db = Database::in_memory().unwrap();
+        let hour = 3600;
+        // Ann splits a change over three commits; Bob touches B.ts meanwhile
+        for (hash, file, author, ts) in [
+            ("c1", "A.ts", "ann@example.com", 0),
+            ("c2", "B.ts", "ann@example.com", hour),
+            ("c3", "C.ts", "ann@example.com", 30 * hour),
+            ("c4", "B.ts", "bob@example.com", 0),
+        ] {
+            db.insert_commit(hash, &[file], ts).unwrap();
+            db.insert_commit_author(hash, "", author, ts).unwrap();
+        }
+
+        let stats = db.coupled_files_in_window("A.ts", 24 * hour).unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].path, "B.ts");
+        assert_eq!((stats[0].co_change_count, stats[0].total_commits), (1, 2));
+        assert_eq!(stats[0].last_timestamp, hour);
+        assert!(db.coupled_files_with_stats("A.ts").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_ticket_coupled_files() {
+        let db = Database::in_memory().unwrap();
+        // Separate commits under the same tickets
+        for (hash, file, ticket) in [
+            ("c1", "api.ts", "PAY-1"),
+            ("c2", "ui.tsx", "PAY-1"),
+            ("c3", "api.ts", "PAY-2"),
+            ("c4", "ui.tsx", "PAY-2"),
+            ("c5", "docs.md", "PAY-2"),
+        ] {
+            db.insert_commit(hash, &[file], 0).unwrap();
+            db.insert_commit_ticket(hash, ticket).unwrap();
+        }
+
+        assert_eq!(db.ticket_coupled_files("api.ts", 2).unwrap(), [("ui.tsx".to_string(), 2)]);
+        assert_eq!(db.ticket_coupled_files("api.ts", 1).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_rename_chains_resolve_both_ways() {
+        let db = Database::in_memory().unwrap();
+        db.insert_rename("a.rs", "b.rs", 100).unwrap();
+        db.insert_rename("b.rs", "c.rs", 200).unwrap();
+
+        assert_eq!(db.current_path("a.rs", 50).unwrap(), "c.rs");
+        assert_eq!(db.current_path("b.rs", 150).unwrap(), "c.rs");
+        // A later file that reuses the old name is not renamed
+        assert_eq!(db.current_path("a.rs", 300).unwrap(), "a.rs");
+
+        assert_eq!(db.path_at("c.rs", 50).unwrap(), "a.rs");
+        assert_eq!(db.path_at("c.rs", 150).unwrap(), "b.rs");
+        assert_eq!(db.path_at("c.rs", 250).unwrap(), "c.rs");
+
+        // Lookups by new name use an index rather than scanning renames
+        let plan: String = db
+            .conn
+            .query_row(
+                "EXPLAIN QUERY PLAN SELECT old_path FROM path_renames WHERE new_path = 'c.rs' AND renamed_at >= 0",
+                [],
+                |row| row.get(3),
+            )
+            .unwrap();
+        assert!(plan.contains("idx_path_renames_new"), "{plan}");
+    }
+
+    #[test]
+    fn test_file_stats_backfilled_for_old_databases() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("engram.db");
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE temporal_index (
+                    commit_hash      TEXT NOT NULL,
+                    file_path        TEXT NOT NULL,
+                    commit_timestamp INTEGER NOT NULL DEFAULT 0,
+                    PRIMARY KEY (commit_hash, file_path)
+                );
+                INSERT INTO temporal_index VALUES ('c1', 'A.ts', 1), ('c1', 'B.ts', 1), ('c2', 'B.ts', 2);",
+            )
+            .unwrap();
+        }
+
+        let db = Database::open(&path).unwrap();
+        let stats = db.coupled_files_with_stats("A.ts").unwrap();
+        assert_eq!((stats[0].co_change_count, stats[0].total_commits, stats[0].last_timestamp), (1, 2, 1));
+
+        db.insert_commit("c3", &["A.ts", "B.ts"], 3).unwrap();
+        let stats = db.coupled_files_with_stats("A.ts").unwrap();
+        assert_eq!((stats[0].co_change_count, stats[0].total_commits, stats[0].last_timestamp), (2, 3, 3));
     }
 
     #[test]
@@ -635,10 +2183,76 @@ mod tests {
         assert_eq!(newest, 5000);
     }
 
+    #[test]
+    fn test_file_author_counts() {
+        let db = Database::in_memory().unwrap();
+        db.insert_commit("c1", &["src/a.ts", "lib/b.ts"], 100).unwrap();
+        db.insert_commit_author("c1", "Alice", "alice@example.com", 100).unwrap();
+        db.insert_commit("c2", &["src/a.ts"], 200).unwrap();
+        db.insert_commit_author("c2", "Alice", "Alice@Example.com", 200).unwrap();
+        db.insert_commit("c3", &["src/a.ts"], 300).unwrap();
+        db.insert_commit_author("c3", "Bob", "bob@example.com", 300).unwrap();
+
+        let mut counts = db.file_author_counts("src/").unwrap();
+        counts.sort();
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts[0].0, "src/a.ts");
+        assert_eq!(counts[0].1, "alice@example.com");
+        assert_eq!(counts[0].3, 2, "emails should be matched case-insensitively");
+        assert_eq!(counts[1].1, "bob@example.com");
+        assert_eq!(counts[1].3, 1);
+
+        assert_eq!(db.file_author_counts("").unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_background_queue_priority_order() {
+        let db = Database::in_memory().unwrap();
+        db.enqueue_background("a.c", None, 100).unwrap();
+        db.enqueue_background("b.c", Some("oid-b"), 200).unwrap();
+        db.enqueue_background("a.c", Some("oid-a"), 300).unwrap();
+
+        let jobs = db.next_background_jobs(10).unwrap();
+        assert_eq!(jobs.len(), 2);
+        // a.c was requested twice, so it outranks the more recent b.c
+        assert_eq!(jobs[0].target_path, "a.c");
+        assert_eq!(jobs[0].priority, 2);
+        assert_eq!(jobs[0].resume_oid, Some("oid-a".to_string()));
+        assert_eq!(jobs[1].target_path, "b.c");
+
+        db.update_background_job("b.c", Some("oid-b2")).unwrap();
+        db.enqueue_background("b.c", Some("oid-fresh"), 400).unwrap();
+        let b = db
+            .next_background_jobs(10)
+            .unwrap()
+            .into_iter()
+            .find(|j| j.target_path == "b.c")
+            .unwrap();
+        assert_eq!(b.attempts, 1);
+        assert_eq!(b.resume_oid, Some("oid-b2".to_string()), "existing resume point kept");
+
+        db.remove_background_job("a.c").unwrap();
+        assert_eq!(db.background_queue_len().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_trim_background_queue() {
+        let db = Database::in_memory().unwrap();
+        for i in 0..5 {
+            db.enqueue_background(&format!("f{i}.c"), None, i).unwrap();
+        }
+        db.trim_background_queue(2).unwrap();
+
+        let jobs = db.next_background_jobs(10).unwrap();
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[0].target_path, "f4.c");
+        assert_eq!(jobs[1].target_path, "f3.c");
+    }
+
     #[test]
     fn test_add_and_retrieve_memory() {
         let db = Database::in_memory().unwrap();
-        let id = db.add_memory("src/Auth.ts", None, "Auth handles JWT tokens").unwrap();
+        let id = db.add_memory("src/Auth.ts", None, "Auth handles JWT tokens", &NoteOrigin::default()).unwrap();
         assert!(id > 0);
 
         let memories = db.memories_for_file("src/Auth.ts").unwrap();
@@ -651,7 +2265,7 @@ mod tests {
     #[test]
     fn test_memory_with_symbol_name() {
         let db = Database::in_memory().unwrap();
-        db.add_memory("src/Auth.ts", Some("validateToken"), "Must check expiry").unwrap();
+        db.add_memory("src/Auth.ts", Some("validateToken"), "Must check expiry", &NoteOrigin::default()).unwrap();
 
         let memories = db.memories_for_file("src/Auth.ts").unwrap();
         assert_eq!(memories.len(), 1);
@@ -661,10 +2275,10 @@ mod tests {
     #[test]
     fn test_search_memories_by_content() {
         let db = Database::in_memory().unwrap();
-        db.add_memory("src/Auth.ts", None, "Uses JWT for authentication").unwrap();
-        db.add_memory("src/Session.ts", None, "Session persistence layer").unwrap();
+        db.add_memory("src/Auth.ts", None, "Uses JWT for authentication", &NoteOrigin::default()).unwrap();
+        db.add_memory("src/Session.ts", None, "Session persistence layer", &NoteOrigin::default()).unwrap();
 
-        let results = db.search_memories("JWT").unwrap();
+        let results = db.search_memories("JWT", Page::default()).unwrap();
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].file_path, "src/Auth.ts");
     }
@@ -672,10 +2286,10 @@ mod tests {
     #[test]
     fn test_search_memories_by_path() {
         let db = Database::in_memory().unwrap();
-        db.add_memory("src/Auth.ts", None, "Handles login").unwrap();
-        db.add_memory("src/Session.ts", None, "Handles sessions").unwrap();
+        db.add_memory("src/Auth.ts", None, "Handles login", &NoteOrigin::default()).unwrap();
+        db.add_memory("src/Session.ts", None, "Handles sessions", &NoteOrigin::default()).unwrap();
 
-        let results = db.search_memories("Auth").unwrap();
+        let results = db.search_memories("Auth", Page::default()).unwrap();
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].file_path, "src/Auth.ts");
     }
@@ -683,20 +2297,20 @@ mod tests {
     #[test]
     fn test_list_all_memories() {
         let db = Database::in_memory().unwrap();
-        db.add_memory("src/A.ts", None, "Note A").unwrap();
-        db.add_memory("src/B.ts", None, "Note B").unwrap();
+        db.add_memory("src/A.ts", None, "Note A", &NoteOrigin::default()).unwrap();
+        db.add_memory("src/B.ts", None, "Note B", &NoteOrigin::default()).unwrap();
 
-        let all = db.list_memories(None).unwrap();
+        let all = db.list_memories(None, None, Page::default()).unwrap();
         assert_eq!(all.len(), 2);
     }
 
     #[test]
     fn test_list_memories_filtered() {
         let db = Database::in_memory().unwrap();
-        db.add_memory("src/A.ts", None, "Note A").unwrap();
-        db.add_memory("src/B.ts", None, "Note B").unwrap();
+        db.add_memory("src/A.ts", None, "Note A", &NoteOrigin::default()).unwrap();
+        db.add_memory("src/B.ts", None, "Note B", &NoteOrigin::default()).unwrap();
 
-        let filtered = db.list_memories(Some("src/A.ts")).unwrap();
+        let filtered = db.list_memories(Some("src/A.ts"), None, Page::default()).unwrap();
         assert_eq!(filtered.len(), 1);
         assert_eq!(filtered[0].content, "Note A");
     }
@@ -721,7 +2335,7 @@ mod tests {
         let memories = db.memories_for_file("src/NoExist.ts").unwrap();
         assert!(memories.is_empty());
 
-        let search = db.search_memories("nothing").unwrap();
+        let search = db.search_memories("nothing", Page::default()).unwrap();
         assert!(search.is_empty());
     }
 
@@ -846,6 +2460,118 @@ mod tests {
         assert_eq!(summary2.total_coupled_files, 3);
     }
 
+    #[test]
+    fn test_ensure_column_migrates_old_metrics_table() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("engram.db");
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE metrics_events (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    event_type TEXT NOT NULL,
+                    timestamp DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    file_path TEXT,
+                    coupled_files_count INTEGER DEFAULT 0,
+                    critical_count INTEGER DEFAULT 0,
+                    high_count INTEGER DEFAULT 0,
+                    medium_count INTEGER DEFAULT 0,
+                    low_count INTEGER DEFAULT 0,
+                    test_files_found INTEGER DEFAULT 0,
+                    test_intents_total INTEGER DEFAULT 0,
+                    commit_count INTEGER DEFAULT 0,
+                    analysis_time_ms INTEGER DEFAULT 0,
+                    note_id INTEGER,
+                    repo_root TEXT NOT NULL
+                );",
+            )
+            .unwrap();
+        }
+
+        let db = Database::open(&path).unwrap();
+        let id = db
+            .insert_metrics_event("analysis", None, 0, 0, 0, 0, 0, 0, 0, 0, 10, None, "/repo")
+            .unwrap();
+        db.set_event_payload_sizes(
+            id,
+            &PayloadSizes { response_bytes: 300, coupled_files_bytes: 200, memories_bytes: 50, test_intents_bytes: 40 },
+        )
+        .unwrap();
+
+        let summary = db.get_metrics_summary("/repo").unwrap();
+        assert_eq!(summary.avg_response_bytes, 300);
+        assert_eq!(summary.max_response_bytes, 300);
+        assert_eq!(summary.avg_memories_bytes, 50);
+    }
+
+    #[test]
+    fn test_indexing_events_in_summary() {
+        let db = Database::in_memory().unwrap();
+        let run = IndexingEvent {
+            strategy: "budgeted_global".to_string(),
+            commits_indexed: 120,
+            elapsed_ms: 1500,
+            budget_ms: 1500,
+            needs_background: true,
+            commits_per_sec: Some(80.0),
+            background: false,
+        };
+        db.insert_indexing_event(&run, "/repo").unwrap();
+        db.insert_indexing_event(
+            &IndexingEvent { elapsed_ms: 500, commits_per_sec: Some(40.0), background: true, ..run.clone() },
+            "/repo",
+        )
+        .unwrap();
+        db.insert_indexing_event(&IndexingEvent { commits_per_sec: None, ..run }, "/repo").unwrap();
+
+        let summary = db.get_metrics_summary("/repo").unwrap();
+        assert_eq!(summary.indexing_runs, 3);
+        assert_eq!(summary.background_indexing_runs, 1);
+        assert_eq!(summary.avg_indexing_time_ms, 1166);
+        assert_eq!(summary.avg_commits_per_sec, 60.0);
+        assert_eq!(summary.total_analyses, 0);
+        assert_eq!(summary.avg_analysis_time_ms, 0);
+
+        let strategy: String = db
+            .conn
+            .query_row("SELECT strategy FROM metrics_events WHERE event_type = 'indexing' LIMIT 1", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(strategy, "budgeted_global");
+    }
+
+    #[test]
+    fn test_metrics_series_by_day_and_week() {
+        let db = Database::in_memory().unwrap();
+        // Tue 2024-01-02 twice, Sun 2024-01-07, Mon 2024-01-08
+        for (time_ms, timestamp) in [
+            (100, "2024-01-02 09:00:00"),
+            (300, "2024-01-02 17:00:00"),
+            (500, "2024-01-07 12:00:00"),
+            (700, "2024-01-08 08:00:00"),
+        ] {
+            let id = db
+                .insert_metrics_event("analysis", None, 1, 0, 0, 0, 1, 0, 0, 0, time_ms, None, "/repo")
+                .unwrap();
+            db.conn
+                .execute("UPDATE metrics_events SET timestamp = ?1 WHERE id = ?2", params![timestamp, id])
+                .unwrap();
+        }
+
+        let days = db.get_metrics_series("/repo", MetricsPeriod::Day).unwrap();
+        let days: Vec<(&str, u32, u64)> = days
+            .iter()
+            .map(|d| (d.period_start.as_str(), d.summary.total_analyses, d.summary.avg_analysis_time_ms))
+            .collect();
+        assert_eq!(days, vec![("2024-01-02", 2, 200), ("2024-01-07", 1, 500), ("2024-01-08", 1, 700)]);
+
+        let weeks = db.get_metrics_series("/repo", MetricsPeriod::Week).unwrap();
+        let weeks: Vec<(&str, u32)> =
+            weeks.iter().map(|w| (w.period_start.as_str(), w.summary.total_analyses)).collect();
+        assert_eq!(weeks, vec![("2024-01-01", 3), ("2024-01-08", 1)]);
+
+        assert!(db.get_metrics_series("/other", MetricsPeriod::Day).unwrap().is_empty());
+    }
+
     #[test]
     fn test_empty_metrics() {
         let db = Database::in_memory().unwrap();
@@ -855,4 +2581,26 @@ mod tests {
         assert_eq!(summary.total_coupled_files, 0);
         assert_eq!(summary.avg_analysis_time_ms, 0);
     }
+
+    #[test]
+    fn test_co_change_splits_in_one_query() {
+        let db = Database::in_memory().unwrap();
+        db.insert_commit("c1", &["a.rs", "b.rs"], 1000).unwrap();
+        db.insert_commit("c2", &["b.rs"], 1500).unwrap();
+        db.insert_commit("c3", &["a.rs", "b.rs", "c.rs"], 3000).unwrap();
+        db.insert_commit("c4", &["d.rs"], 3500).unwrap();
+
+        let splits = db.co_change_splits("a.rs", &["b.rs", "d.rs"], 2000).unwrap();
+        assert_eq!(
+            splits,
+            vec![CoChangeSplit {
+                path: "b.rs".to_string(),
+                co_total: 2,
+                co_before: 1,
+                commits_total: 3,
+                commits_before: 2,
+            }]
+        );
+        assert!(db.co_change_splits("a.rs", &[], 2000).unwrap().is_empty());
+    }
 }
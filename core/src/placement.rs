@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+
+use crate::persistence::Database;
+use crate::test_intents::is_test_file;
+use crate::types::{MoveSuggestion, MoveSuggestionsResponse};
+
+/// Files with fewer indexed commits have too little history to place.
+const MIN_COMMITS: u32 = 5;
+/// A coupling counts as strong when the pair co-changed at least this often
+/// and in at least `MIN_COUPLING` of the file's commits.
+const MIN_CO_CHANGES: u32 = 3;
+const MIN_COUPLING: f64 = 0.3;
+/// Share of strong coupling that must point into a single other directory.
+const MIN_MISPLACEMENT: f64 = 0.75;
+const MAX_LISTED_FILES: usize = 5;
+
+fn dir_of(path: &str) -> &str {
+    path.rfind('/').map_or(".", |idx| &path[..idx])
+}
+
+/// Suggest moves for files under `path_prefix` whose strong couplings point
+/// almost entirely into one other directory.
+///
+/// Each strong coupling is weighted by its co-change count. A file's
+/// misplacement score is the share of that weight held by the best foreign
+/// directory. Test files are skipped because they normally live apart from
+/// the code they exercise.
+pub fn move_suggestions(
+    db: &Database,
+    repo_root: &str,
+    path_prefix: Option<&str>,
+) -> Result<MoveSuggestionsResponse, rusqlite::Error> {
+    let commit_counts = db.file_commit_counts_split(0)?;
+    let prefix = path_prefix.unwrap_or("");
+
+    // path -> [(coupled path, co-change count)], strong couplings only
+    let mut strong: HashMap<&str, Vec<(String, u32)>> = HashMap::new();
+    let pairs = db.pair_counts(MIN_CO_CHANGES)?;
+    for (a, b, co_changes) in &pairs {
+        for (file, other) in [(a, b), (b, a)] {
+            let commits = commit_counts.get(file).map_or(0, |c| c.0);
+            if commits >= MIN_COMMITS && *co_changes as f64 / commits as f64 >= MIN_COUPLING {
+                strong.entry(file.as_str()).or_default().push((other.clone(), *co_changes));
+            }
+        }
+    }
+
+    let mut suggestions: Vec<MoveSuggestion> = strong
+        .into_iter()
+        .filter(|(path, _)| path.starts_with(prefix) && !is_test_file(path))
+        .filter_map(|(path, mut couplings)| {
+            let current_dir = dir_of(path);
+            let total: u32 = couplings.iter().map(|(_, c)| c).sum();
+            let mut by_dir: HashMap<&str, u32> = HashMap::new();
+            for (other, co_changes) in &couplings {
+                *by_dir.entry(dir_of(other)).or_default() += co_changes;
+            }
+            let (suggested_dir, weight) = by_dir
+                .into_iter()
+                .filter(|(dir, _)| *dir != current_dir)
+                .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))?;
+            let score = weight as f64 / total as f64;
+            if score < MIN_MISPLACEMENT {
+                return None;
+            }
+
+            let suggested_dir = suggested_dir.to_string();
+            couplings.retain(|(other, _)| dir_of(other) == suggested_dir);
+            couplings.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            Some(MoveSuggestion {
+                path: path.to_string(),
+                current_dir: current_dir.to_string(),
+                suggested_dir,
+                misplacement_score: score,
+                coupled_files: couplings
+                    .into_iter()
+                    .take(MAX_LISTED_FILES)
+                    .map(|(other, _)| other)
+                    .collect(),
+            })
+        })
+        .collect();
+
+    suggestions.sort_by(|a, b| {
+        b.misplacement_score
+            .partial_cmp(&a.misplacement_score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+
+    Ok(MoveSuggestionsResponse {
+        repo_root: repo_root.to_string(),
+        suggestions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commits(db: &Database, prefix: &str, n: usize, files: &[&str]) {
+        for i in 0..n {
+            db.insert_commit(&format!("{prefix}{i}"), files, i as i64).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_suggests_file_coupled_to_another_directory() {
+        let db = Database::in_memory().unwrap();
+        // billing/tax.rs only ever changes with checkout/
+        commits(&db, "a", 6, &["billing/tax.rs", "checkout/cart.rs", "checkout/total.rs"]);
+        // billing/invoice.rs and billing/ledger.rs belong together
+        commits(&db, "b", 6, &["billing/invoice.rs", "billing/ledger.rs"]);
+        // A test coupled to its source elsewhere is not a suggestion
+        commits(&db, "c", 6, &["tests/tax.test.ts", "billing/invoice.rs"]);
+
+        let resp = move_suggestions(&db, "/repo", None).unwrap();
+
+        let tax = resp.suggestions.iter().find(|s| s.path == "billing/tax.rs").unwrap();
+        assert_eq!(tax.current_dir, "billing");
+        assert_eq!(tax.suggested_dir, "checkout");
+        assert!((tax.misplacement_score - 1.0).abs() < 1e-9);
+        assert_eq!(tax.coupled_files, vec!["checkout/cart.rs", "checkout/total.rs"]);
+
+        assert!(!resp.suggestions.iter().any(|s| s.path == "billing/ledger.rs"));
+        assert!(!resp.suggestions.iter().any(|s| s.path == "tests/tax.test.ts"));
+    }
+
+    #[test]
+    fn test_mixed_coupling_is_not_misplaced() {
+        let db = Database::in_memory().unwrap();
+        commits(&db, "a", 6, &["core/a.rs", "core/b.rs"]);
+        commits(&db, "b", 6, &["core/a.rs", "web/view.rs"]);
+
+        let resp = move_suggestions(&db, "/repo", Some("core/")).unwrap();
+        assert!(resp.suggestions.iter().all(|s| s.path != "core/a.rs"));
+    }
+}
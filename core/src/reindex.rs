@@ -0,0 +1,218 @@
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use git2::Repository;
+
+use crate::config::Config;
+use crate::indexing::{self, IndexOptions};
+use crate::persistence::{Database, IndexingState, ReindexCheckpoint};
+use crate::types::ReindexResponse;
+
+/// How often progress is checkpointed during a full reindex.
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(10);
+
+const REINDEX_BATCH_SIZE: usize = 500;
+
+#[derive(Debug, Clone, Default)]
+pub struct ReindexOptions {
+    /// Start a fresh rebuild when there is nothing to resume
+    pub full: bool,
+    /// Rebuild from this commit (any revision git understands) instead of
+    /// HEAD. Implies `full`.
+    pub from: Option<String>,
+    /// Stop after this long, leaving a checkpoint
+    pub max_duration: Option<Duration>,
+}
+
+/// Rebuild the temporal index from scratch over the whole history (no
+/// commit limit), using the current indexing config.
+///
+/// Progress is checkpointed every `CHECKPOINT_INTERVAL`; if the run stops
+/// (time limit, Ctrl-C) the next call resumes from the checkpoint as long as
+/// HEAD has not moved. With `full`, a fresh rebuild is started only when
+/// there is nothing to resume. Without `full`, the call only resumes.
+///
+/// With `from`, the index is rebuilt over the history of that commit and
+/// recorded as built at it, so the next analysis indexes only the commits
+/// between it and HEAD. Useful for dropping data from rewritten history
+/// without walking everything again.
+pub fn reindex(
+    repo_root: &Path,
+    db: &Database,
+    options: &ReindexOptions,
+) -> Result<ReindexResponse, Box<dyn std::error::Error>> {
+    let start = Instant::now();
+    let repo = Repository::open(repo_root)?;
+    let opts = IndexOptions::load(repo_root, &Config::load(repo_root)?);
+    let tip = match &options.from {
+        Some(rev) => repo.revparse_single(rev)?.peel_to_commit()?.id(),
+        None => repo.head()?.peel_to_commit()?.id(),
+    };
+    let head = tip.to_string();
+
+    // A checkpoint from a run under another scope holds the wrong files
+    let same_scope = db.get_index_target()?.scope == opts.scope;
+    let (mut checkpoint, resumed) = match db.get_reindex_checkpoint()? {
+        Some(cp) if cp.head_commit == head && same_scope => (cp, true),
+        _ if !options.full && options.from.is_none() => {
+            return Err("no interrupted reindex to resume; pass --full to rebuild".into());
+        }
+        _ => {
+            db.clear_index()?;
+            db.set_index_target(&opts.target())?;
+            let cp = ReindexCheckpoint {
+                head_commit: head,
+                resume_oid: None,
+                commits_indexed: 0,
+                started_at: indexing::unix_now(),
+            };
+            db.set_reindex_checkpoint(&cp)?;
+            (cp, false)
+        }
+    };
+
+    let mut indexed_this_run = 0u32;
+    let mut is_complete = false;
+    loop {
+        let slice = match options.max_duration {
+            Some(max) => CHECKPOINT_INTERVAL.min(max.saturating_sub(start.elapsed())),
+            None => CHECKPOINT_INTERVAL,
+        };
+        if slice.is_zero() {
+            break;
+        }
+
+        let (indexed, last_oid, hit_end) = indexing::budgeted_index_from(
+            &repo,
+            db,
+            &opts,
+            tip,
+            None,
+            slice,
+            usize::MAX,
+            checkpoint.resume_oid.as_deref(),
+            REINDEX_BATCH_SIZE,
+        )?;
+        indexed_this_run += indexed;
+        checkpoint.commits_indexed += indexed;
+        if last_oid.is_some() {
+            checkpoint.resume_oid = last_oid;
+        }
+
+        if hit_end {
+            is_complete = true;
+            break;
+        }
+        db.set_reindex_checkpoint(&checkpoint)?;
+    }
+
+    if is_complete {
+        db.set_indexing_state(&IndexingState {
+            head_commit: checkpoint.head_commit.clone(),
+            resume_oid: None,
+            commits_indexed: checkpoint.commits_indexed,
+            strategy: indexing::Strategy::Complete.as_str().to_string(),
+            is_complete: true,
+            last_updated: indexing::unix_now(),
+            target_path: None,
+        })?;
+        db.clear_reindex_checkpoint()?;
+    }
+
+    Ok(ReindexResponse {
+        repo_root: repo_root.to_string_lossy().to_string(),
+        resumed,
+        commits_indexed: indexed_this_run,
+        total_commits_indexed: checkpoint.commits_indexed,
+        is_complete,
+        elapsed_ms: start.elapsed().as_millis() as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::commit;
+    use tempfile::TempDir;
+
+    fn create_repo(n: usize) -> TempDir {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        for i in 0..n {
+            commit(&repo, &["a.rs", "b.rs"], &format!("c{i}"));
+        }
+        dir
+    }
+
+    #[test]
+    fn test_full_reindex_rebuilds_and_replaces_stale_data() {
+        let dir = create_repo(5);
+        let db = Database::in_memory().unwrap();
+        db.insert_commit("stale", &["gone.rs", "a.rs"], 1).unwrap();
+
+        let resp = reindex(dir.path(), &db, &ReindexOptions { full: true, ..Default::default() }).unwrap();
+
+        assert!(resp.is_complete);
+        assert!(!resp.resumed);
+        assert_eq!(resp.total_commits_indexed, 5);
+        assert_eq!(db.commit_count("gone.rs").unwrap(), 0);
+        assert_eq!(db.co_change_count("a.rs", "b.rs").unwrap(), 5);
+        let state = db.get_indexing_state().unwrap().unwrap();
+        assert!(state.is_complete);
+        assert!(db.get_reindex_checkpoint().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_resumes_from_checkpoint() {
+        let dir = create_repo(5);
+        let db = Database::in_memory().unwrap();
+        let repo = Repository::open(dir.path()).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+
+        // Simulate an interrupted run that got through the two newest commits
+        let (indexed, last_oid, _) = indexing::budgeted_global_index(
+            &repo, &db, &IndexOptions::default(), Duration::from_secs(10), 2, None, 100,
+        )
+        .unwrap();
+        db.set_reindex_checkpoint(&ReindexCheckpoint {
+            head_commit: head.id().to_string(),
+            resume_oid: last_oid,
+            commits_indexed: indexed,
+            started_at: 0,
+        })
+        .unwrap();
+
+        let resp = reindex(dir.path(), &db, &ReindexOptions::default()).unwrap();
+        assert!(resp.resumed);
+        assert_eq!(resp.commits_indexed, 3);
+        assert_eq!(resp.total_commits_indexed, 5);
+        assert!(resp.is_complete);
+    }
+
+    #[test]
+    fn test_resume_without_checkpoint_requires_full() {
+        let dir = create_repo(1);
+        let db = Database::in_memory().unwrap();
+        assert!(reindex(dir.path(), &db, &ReindexOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_reindex_from_older_commit() {
+        let dir = create_repo(5);
+        let db = Database::in_memory().unwrap();
+        db.insert_commit("stale", &["gone.rs", "a.rs"], 1).unwrap();
+
+        let options = ReindexOptions { from: Some("HEAD~2".to_string()), ..Default::default() };
+        let resp = reindex(dir.path(), &db, &options).unwrap();
+
+        assert!(resp.is_complete);
+        assert_eq!(resp.total_commits_indexed, 3);
+        assert_eq!(db.commit_count("gone.rs").unwrap(), 0);
+        let repo = Repository::open(dir.path()).unwrap();
+        let from = repo.revparse_single("HEAD~2").unwrap().id().to_string();
+        assert_eq!(db.get_indexing_state().unwrap().unwrap().head_commit, from);
+
+        let bad = ReindexOptions { from: Some("nope".to_string()), ..Default::default() };
+        assert!(reindex(dir.path(), &db, &bad).is_err());
+    }
+}
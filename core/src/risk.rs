@@ -1,35 +1,176 @@
-use crate::types::CoupledFile;
+use crate::config::CouplingMetric;
+use crate::timestamps::iso_timestamp;
+use crate::types::{CoupledFile, RiskComponents, RiskThresholds};
 
-const MAX_RESULTS: usize = 10;
+/// Coupled files reported by default, highest risk first.
+pub const DEFAULT_LIMIT: usize = 10;
+
+/// How much a co-change in a bug-fix commit counts toward coupling,
+/// relative to any other co-change.
+const FIX_WEIGHT: f64 = 2.0;
 
 pub struct RawCoupledFileStats {
     pub path: String,
     pub co_change_count: u32,
     pub total_commits: u32,
     pub last_timestamp: i64,
+    /// Co-changes that were in bug-fix commits
+    pub fix_co_change_count: u32,
+    /// Each co-change, for time-decayed scoring
+    pub co_changes: Vec<CoChange>,
+}
+
+/// One commit of the target that the coupled file changed with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CoChange {
+    pub timestamp: i64,
+    pub is_fix: bool,
 }
 
 pub struct TimeWindow {
     pub oldest_ts: i64,
     pub newest_ts: i64,
+    /// Weigh co-changes by age rather than counting them
+    pub decay: Option<Decay>,
+    /// What the coupling score measures
+    pub metric: CouplingMetric,
+}
+
+/// Exponential decay of co-change weight with age, measured back from the
+/// newest indexed commit: a co-change one half-life old counts half.
+pub struct Decay {
+    pub half_life_secs: i64,
+    /// The target's commits, each weighed the same way
+    pub target_weight: f64,
+}
+
+impl Decay {
+    /// The weight of a commit at `timestamp` when the newest is at `now`.
+    pub fn weight(half_life_secs: i64, now: i64, timestamp: i64) -> f64 {
+        0.5f64.powf((now - timestamp).max(0) as f64 / half_life_secs.max(1) as f64)
+    }
+}
+
+/// Fewest risk scores percentile thresholds are drawn from; with fewer,
+/// the fixed thresholds are kept.
+const MIN_CALIBRATION_SCORES: usize = 20;
+
+/// Share of scores at or above each calibrated level: the top tenth is
+/// Critical, the next fifth High, the next three tenths Medium.
+const CRITICAL_SHARE: f64 = 0.1;
+const HIGH_SHARE: f64 = 0.3;
+const MEDIUM_SHARE: f64 = 0.6;
+
+impl RiskThresholds {
+    /// The thresholds every repo gets uncalibrated, matching the adapter's.
+    pub const FIXED: RiskThresholds = RiskThresholds { critical: 0.8, high: 0.6, medium: 0.3 };
+
+    /// Thresholds splitting `scores` by level share. `None` for too few
+    /// scores to tell a distribution.
+    pub fn from_percentiles(mut scores: Vec<f64>) -> Option<Self> {
+        if scores.len() < MIN_CALIBRATION_SCORES {
+            return None;
+        }
+        scores.sort_by(|a, b| b.total_cmp(a));
+        // Lowest score within the top `share`
+        let at = |share: f64| scores[((scores.len() as f64 * share).ceil() as usize).clamp(1, scores.len()) - 1];
+        Some(RiskThresholds { critical: at(CRITICAL_SHARE), high: at(HIGH_SHARE), medium: at(MEDIUM_SHARE) })
+    }
+
+    /// Human-facing risk label for a score.
+    pub fn level(&self, score: f64) -> &'static str {
+        if score >= self.critical {
+            "Critical"
+        } else if score >= self.high {
+            "High"
+        } else if score >= self.medium {
+            "Medium"
+        } else {
+            "Low"
+        }
+    }
+}
+
+/// Human-facing risk label for a score, at the fixed thresholds.
+pub fn risk_level(score: f64) -> &'static str {
+    RiskThresholds::FIXED.level(score)
+}
+
+/// Coupling score under `metric` of a file with `file_commits` commits,
+/// `co_changes` of them shared with a target of `target_commits`.
+pub fn coupling_score(metric: CouplingMetric, co_changes: u32, target_commits: u32, file_commits: u32) -> f64 {
+    let co = co_changes as f64;
+    // A time-window coupling can count more co-changes than the file has commits
+    let (target, file) = (target_commits as f64, (file_commits as f64).max(co));
+    let denominator = match metric {
+        CouplingMetric::Confidence => target,
+        CouplingMetric::Jaccard => target + file - co,
+        CouplingMetric::Cosine => (target * file).sqrt(),
+    };
+    if denominator > 0.0 { co / denominator } else { 0.0 }
+}
+
+/// Probability of a file sharing at least `co_changes` of the target's
+/// commits by chance alone: drawing `file_commits` of `total_commits`
+/// commits at random, how often `co_changes` or more are among the
+/// target's `target_commits` (the hypergeometric upper tail).
+///
+/// A file changed once, alongside the target, in a ten-commit history
+/// scores 0.1+: indistinguishable from chance. Co-changes no random draw
+/// can reach (a time-window coupling) score 0.
+pub fn co_change_p_value(co_changes: u32, target_commits: u32, file_commits: u32, total_commits: u32) -> f64 {
+    let total = total_commits.max(target_commits).max(file_commits) as u64;
+    let (target, file) = (target_commits as u64, file_commits as u64);
+    let denominator = ln_choose(total, file);
+    (co_changes as u64..=target.min(file))
+        .filter(|&i| file - i <= total - target)
+        .map(|i| (ln_choose(target, i) + ln_choose(total - target, file - i) - denominator).exp())
+        .sum::<f64>()
+        .min(1.0)
+}
+
+fn ln_choose(n: u64, k: u64) -> f64 {
+    ln_factorial(n) - ln_factorial(k) - ln_factorial(n - k)
+}
+
+/// `ln(n!)`: summed exactly for small n, Stirling's series beyond.
+fn ln_factorial(n: u64) -> f64 {
+    if n < 32 {
+        return (2..=n).map(|i| (i as f64).ln()).sum();
+    }
+    let n = n as f64;
+    n * n.ln() - n + 0.5 * (2.0 * std::f64::consts::PI * n).ln() + 1.0 / (12.0 * n) - 1.0 / (360.0 * n.powi(3))
 }
 
 /// Compute risk-scored coupled files.
 ///
 /// Formula: `risk_score = (coupling * 0.5) + (churn * 0.3) + (recency * 0.2)`
 ///
-/// - **Coupling**: `co_change_count / target_commit_count` — what % of target's commits include this file
+/// - **Coupling**: `co_change_count / target_commit_count` — what % of target's commits include this file.
+///   Co-changes in bug-fix commits count `FIX_WEIGHT` times toward the score (capped at 1.0):
+///   files that break together matter more than files refactored together. The reported
+///   `coupling_score` stays unweighted. With a `Decay`, each co-change counts its age weight
+///   instead of 1, over the target's commits weighed alike, so a pair that co-changed often
+///   years ago ranks below one that co-changes now.
 /// - **Churn**: `total_commits / max_total_commits` across the result set (highest = 1.0) — how active the file is
 /// - **Recency**: linear mapping of `last_timestamp` into `[0.0, 1.0]` over the time window.
 ///   Most recent = 1.0, oldest = 0.0. If all timestamps are equal, recency = 1.0.
 ///
 /// **Coupling gate**: Files with coupling < 0.5 cannot exceed risk_score 0.79 (capping them at High risk).
 ///
-/// Results are filtered to `risk_score > 0.0` and sorted descending by `risk_score`.
+/// Each file also gets association-rule metrics over the `total_commits` indexed: `confidence`
+/// (of the file changing when the target does; the coupling ratio), `reverse_confidence` (of the
+/// target changing when the file does) and `lift` (how much likelier they change together than
+/// independently). A file with high confidence but lift near 1 changes constantly regardless.
+///
+/// Results are filtered to `risk_score > 0.0`, sorted descending by `risk_score`, and cut to
+/// `limit` (`None` keeps them all).
 pub fn score_coupled_files(
     files: Vec<RawCoupledFileStats>,
     target_commit_count: u32,
+    total_commits: u32,
     window: &TimeWindow,
+    limit: Option<usize>,
 ) -> Vec<CoupledFile> {
     if files.is_empty() {
         return Vec::new();
@@ -50,18 +191,51 @@ pub fn score_coupled_files(
                 (f.last_timestamp - window.oldest_ts) as f64 / time_span as f64
             };
 
-            let coupling = if target_commit_count > 0 {
+            let confidence = if target_commit_count > 0 {
                 f.co_change_count as f64 / target_commit_count as f64
             } else {
                 0.0
             };
+            let coupling = coupling_score(window.metric, f.co_change_count, target_commit_count, f.total_commits);
+
+            // A time-window coupling can count more co-changes than the file has commits
+            let reverse_confidence = (f.co_change_count as f64 / f.total_commits.max(1) as f64).min(1.0);
+            let lift = if target_commit_count > 0 && f.total_commits > 0 {
+                f.co_change_count as f64 * total_commits as f64
+                    / (target_commit_count as f64 * f.total_commits as f64)
+            } else {
+                0.0
+            };
+
+            let weighted_coupling = match &window.decay {
+                Some(decay) if decay.target_weight > 0.0 => {
+                    let weight: f64 = f
+                        .co_changes
+                        .iter()
+                        .map(|c| {
+                            let fix = if c.is_fix { FIX_WEIGHT } else { 1.0 };
+                            fix * Decay::weight(decay.half_life_secs, window.newest_ts, c.timestamp)
+                        })
+                        .sum();
+                    (weight / decay.target_weight).min(1.0)
+                }
+                _ if target_commit_count > 0 => {
+                    let fixes = f.fix_co_change_count.min(f.co_change_count) as f64;
+                    ((f.co_change_count as f64 + fixes * (FIX_WEIGHT - 1.0)) / target_commit_count as f64).min(1.0)
+                }
+                _ => 0.0,
+            };
+            // Weighted like a confidence; other metrics rescale it by their
+            // own denominator
+            let weighted_coupling = if confidence > 0.0 { weighted_coupling * coupling / confidence } else { 0.0 };
 
             // New weights: prioritize coupling over churn
-            let mut risk_score = (coupling * 0.5) + (churn * 0.3) + (recency * 0.2);
+            let mut risk_score = (weighted_coupling * 0.5) + (churn * 0.3) + (recency * 0.2);
 
             // Coupling gate: files below 50% coupling can't be Critical (>= 0.8)
             // Cap them at 0.79 (max High risk)
-            if coupling < 0.5 && risk_score >= 0.8 {
+            let gated = weighted_coupling < 0.5 && risk_score >= 0.8;
+            if gated {
                 risk_score = 0.79;
             }
 
@@ -72,6 +246,24 @@ pub fn score_coupled_files(
                 risk_score,
                 memories: Vec::new(),
                 test_intents: Vec::new(),
+                emerging: false,
+                trend: None,
+                lossy_path: false,
+                upstream_commits: 0,
+                recently_failing: false,
+                co_change_types: Default::default(),
+                confidence,
+                reverse_confidence,
+                lift,
+                via: None,
+                shared_commits: Vec::new(),
+                last_co_change_at: Some(iso_timestamp(f.last_timestamp)),
+                regions: Vec::new(),
+                static_link: None,
+                has_tests: None,
+                cross_package: None,
+                risk_components: RiskComponents { coupling: weighted_coupling, churn, recency },
+                gated,
             }
         })
         .filter(|f| f.risk_score > 0.0)
@@ -79,7 +271,9 @@ pub fn score_coupled_files(
 
     result.sort_by(|a, b| b.risk_score.partial_cmp(&a.risk_score).unwrap_or(std::cmp::Ordering::Equal));
 
-    result.truncate(MAX_RESULTS);
+    if let Some(limit) = limit {
+        result.truncate(limit);
+    }
 
     result
 }
@@ -94,6 +288,8 @@ mod tests {
             co_change_count: co_change,
             total_commits: total,
             last_timestamp: ts,
+            fix_co_change_count: 0,
+            co_changes: Vec::new(),
         }
     }
 
@@ -101,8 +297,8 @@ mod tests {
     fn test_formula_weights() {
         // Single file: churn=1.0 (only file), recency=1.0 (most recent), coupling=0.5
         let files = vec![make_stats("A.ts", 5, 10, 5000)];
-        let window = TimeWindow { oldest_ts: 1000, newest_ts: 5000 };
-        let result = score_coupled_files(files, 10, &window);
+        let window = TimeWindow { oldest_ts: 1000, newest_ts: 5000, decay: None, metric: CouplingMetric::Confidence };
+        let result = score_coupled_files(files, 10, 100, &window, Some(DEFAULT_LIMIT));
 
         assert_eq!(result.len(), 1);
         // New formula: risk = (coupling * 0.5) + (churn * 0.3) + (recency * 0.2)
@@ -117,14 +313,17 @@ mod tests {
             make_stats("High.ts", 5, 20, 5000),
             make_stats("Low.ts", 5, 10, 5000),
         ];
-        let window = TimeWindow { oldest_ts: 1000, newest_ts: 5000 };
-        let result = score_coupled_files(files, 10, &window);
+        let window = TimeWindow { oldest_ts: 1000, newest_ts: 5000, decay: None, metric: CouplingMetric::Confidence };
+        let result = score_coupled_files(files, 10, 100, &window, Some(DEFAULT_LIMIT));
 
         assert_eq!(result.len(), 2);
         // High: churn=20/20=1.0, Low: churn=10/20=0.5
         // Both have same recency and coupling, so High should rank higher
         assert_eq!(result[0].path, "High.ts");
         assert_eq!(result[1].path, "Low.ts");
+        assert_eq!(result[1].risk_components, RiskComponents { coupling: 0.5, churn: 0.5, recency: 1.0 });
+        assert!(!result[1].gated);
+        assert_eq!(result[1].last_co_change_at.as_deref(), Some("1970-01-01T01:23:20Z"));
         // New weights: churn difference = 0.3 * (1.0 - 0.5) = 0.15
         assert!((result[0].risk_score - result[1].risk_score - 0.15).abs() < 1e-9);
     }
@@ -136,8 +335,8 @@ mod tests {
             make_stats("Recent.ts", 5, 10, 5000),
             make_stats("Old.ts", 5, 10, 1000),
         ];
-        let window = TimeWindow { oldest_ts: 1000, newest_ts: 5000 };
-        let result = score_coupled_files(files, 10, &window);
+        let window = TimeWindow { oldest_ts: 1000, newest_ts: 5000, decay: None, metric: CouplingMetric::Confidence };
+        let result = score_coupled_files(files, 10, 100, &window, Some(DEFAULT_LIMIT));
 
         assert_eq!(result.len(), 2);
         assert_eq!(result[0].path, "Recent.ts");
@@ -153,8 +352,8 @@ mod tests {
             make_stats("High.ts", 10, 20, 5000),
             make_stats("Med.ts", 5, 10, 3000),
         ];
-        let window = TimeWindow { oldest_ts: 1000, newest_ts: 5000 };
-        let result = score_coupled_files(files, 20, &window);
+        let window = TimeWindow { oldest_ts: 1000, newest_ts: 5000, decay: None, metric: CouplingMetric::Confidence };
+        let result = score_coupled_files(files, 20, 100, &window, Some(DEFAULT_LIMIT));
 
         assert_eq!(result.len(), 3);
         // Should be sorted descending by risk_score
@@ -166,8 +365,8 @@ mod tests {
     #[test]
     fn test_single_file_edge_case() {
         let files = vec![make_stats("Only.ts", 3, 5, 3000)];
-        let window = TimeWindow { oldest_ts: 1000, newest_ts: 5000 };
-        let result = score_coupled_files(files, 10, &window);
+        let window = TimeWindow { oldest_ts: 1000, newest_ts: 5000, decay: None, metric: CouplingMetric::Confidence };
+        let result = score_coupled_files(files, 10, 100, &window, Some(DEFAULT_LIMIT));
 
         assert_eq!(result.len(), 1);
         // churn = 5/5 = 1.0, recency = (3000-1000)/4000 = 0.5, coupling = 3/10 = 0.3
@@ -183,8 +382,8 @@ mod tests {
             make_stats("A.ts", 5, 10, 3000),
             make_stats("B.ts", 3, 6, 3000),
         ];
-        let window = TimeWindow { oldest_ts: 3000, newest_ts: 3000 };
-        let result = score_coupled_files(files, 10, &window);
+        let window = TimeWindow { oldest_ts: 3000, newest_ts: 3000, decay: None, metric: CouplingMetric::Confidence };
+        let result = score_coupled_files(files, 10, 100, &window, Some(DEFAULT_LIMIT));
 
         // Recency should be 1.0 for all when time range is zero
         assert_eq!(result.len(), 2);
@@ -197,16 +396,16 @@ mod tests {
     #[test]
     fn test_empty_input() {
         let files = vec![];
-        let window = TimeWindow { oldest_ts: 0, newest_ts: 0 };
-        let result = score_coupled_files(files, 10, &window);
+        let window = TimeWindow { oldest_ts: 0, newest_ts: 0, decay: None, metric: CouplingMetric::Confidence };
+        let result = score_coupled_files(files, 10, 100, &window, Some(DEFAULT_LIMIT));
         assert!(result.is_empty());
     }
 
     #[test]
     fn test_coupling_score_preserved() {
         let files = vec![make_stats("A.ts", 8, 10, 5000)];
-        let window = TimeWindow { oldest_ts: 1000, newest_ts: 5000 };
-        let result = score_coupled_files(files, 20, &window);
+        let window = TimeWindow { oldest_ts: 1000, newest_ts: 5000, decay: None, metric: CouplingMetric::Confidence };
+        let result = score_coupled_files(files, 20, 100, &window, Some(DEFAULT_LIMIT));
 
         assert_eq!(result.len(), 1);
         assert!((result[0].coupling_score - 0.4).abs() < 1e-9); // 8/20
@@ -218,23 +417,34 @@ mod tests {
         let files: Vec<RawCoupledFileStats> = (0..15)
             .map(|i| make_stats(&format!("File{i}.ts"), 5, 10 + i, 2000 + i as i64 * 100))
             .collect();
-        let window = TimeWindow { oldest_ts: 1000, newest_ts: 5000 };
-        let result = score_coupled_files(files, 20, &window);
+        let window = TimeWindow { oldest_ts: 1000, newest_ts: 5000, decay: None, metric: CouplingMetric::Confidence };
+        let result = score_coupled_files(files, 20, 100, &window, Some(DEFAULT_LIMIT));
 
-        assert_eq!(result.len(), MAX_RESULTS, "should truncate to MAX_RESULTS");
+        assert_eq!(result.len(), DEFAULT_LIMIT, "should truncate to the limit");
         // Verify still sorted descending
         for i in 1..result.len() {
             assert!(result[i - 1].risk_score >= result[i].risk_score);
         }
     }
 
+    #[test]
+    fn test_limit_overrides_default() {
+        let files = || -> Vec<RawCoupledFileStats> {
+            (0..15).map(|i| make_stats(&format!("File{i}.ts"), 5, 10 + i, 2000 + i as i64 * 100)).collect()
+        };
+        let window = TimeWindow { oldest_ts: 1000, newest_ts: 5000, decay: None, metric: CouplingMetric::Confidence };
+
+        assert_eq!(score_coupled_files(files(), 20, 100, &window, Some(3)).len(), 3);
+        assert_eq!(score_coupled_files(files(), 20, 100, &window, None).len(), 15);
+    }
+
     #[test]
     fn test_coupling_gate_prevents_critical() {
         // File with high churn + high recency but low coupling
         // Should be capped at 0.79 (High risk) even if formula says >= 0.8
         let files = vec![make_stats("HighChurn.ts", 3, 100, 5000)]; // coupling = 3/10 = 0.3 (< 0.5)
-        let window = TimeWindow { oldest_ts: 1000, newest_ts: 5000 };
-        let result = score_coupled_files(files, 10, &window);
+        let window = TimeWindow { oldest_ts: 1000, newest_ts: 5000, decay: None, metric: CouplingMetric::Confidence };
+        let result = score_coupled_files(files, 10, 100, &window, Some(DEFAULT_LIMIT));
 
         assert_eq!(result.len(), 1);
         // Without gate: (0.3 * 0.5) + (1.0 * 0.3) + (1.0 * 0.2) = 0.15 + 0.3 + 0.2 = 0.65
@@ -243,7 +453,7 @@ mod tests {
 
         // Now test a case that WOULD hit the gate
         let files = vec![make_stats("VeryHighChurn.ts", 4, 200, 5000)]; // coupling = 4/10 = 0.4
-        let result = score_coupled_files(files, 10, &window);
+        let result = score_coupled_files(files, 10, 100, &window, Some(DEFAULT_LIMIT));
         // Without gate: (0.4 * 0.5) + (1.0 * 0.3) + (1.0 * 0.2) = 0.2 + 0.3 + 0.2 = 0.7
         // Still below 0.8, no gate
         assert!((result[0].risk_score - 0.7).abs() < 1e-9);
@@ -253,8 +463,8 @@ mod tests {
     fn test_high_coupling_allows_critical() {
         // File with coupling >= 0.5 can be Critical
         let files = vec![make_stats("HighCoupling.ts", 8, 10, 5000)]; // coupling = 8/10 = 0.8
-        let window = TimeWindow { oldest_ts: 1000, newest_ts: 5000 };
-        let result = score_coupled_files(files, 10, &window);
+        let window = TimeWindow { oldest_ts: 1000, newest_ts: 5000, decay: None, metric: CouplingMetric::Confidence };
+        let result = score_coupled_files(files, 10, 100, &window, Some(DEFAULT_LIMIT));
 
         assert_eq!(result.len(), 1);
         // (0.8 * 0.5) + (1.0 * 0.3) + (1.0 * 0.2) = 0.4 + 0.3 + 0.2 = 0.9
@@ -268,9 +478,115 @@ mod tests {
         let files: Vec<RawCoupledFileStats> = (0..5)
             .map(|i| make_stats(&format!("File{i}.ts"), 3, 8, 3000 + i as i64 * 100))
             .collect();
-        let window = TimeWindow { oldest_ts: 1000, newest_ts: 5000 };
-        let result = score_coupled_files(files, 10, &window);
+        let window = TimeWindow { oldest_ts: 1000, newest_ts: 5000, decay: None, metric: CouplingMetric::Confidence };
+        let result = score_coupled_files(files, 10, 100, &window, Some(DEFAULT_LIMIT));
+
+        assert_eq!(result.len(), 5, "should not truncate when under the limit");
+    }
+
+    #[test]
+    fn test_co_change_p_value() {
+        // 1-of-1 in a ten-commit history: a one-in-ten coincidence
+        assert!((co_change_p_value(1, 1, 1, 10) - 0.1).abs() < 1e-9);
+        // Sharing all ten of the target's commits out of a thousand is not chance
+        assert!(co_change_p_value(10, 10, 10, 1000) < 1e-20);
+        // Sharing none is always possible
+        assert!((co_change_p_value(0, 40, 40, 1000) - 1.0).abs() < 1e-9);
+        // Both files in half of all commits, sharing half of those: expected
+        assert!(co_change_p_value(50, 100, 100, 200) > 0.4);
+        // More co-changes than a random draw allows
+        assert_eq!(co_change_p_value(3, 5, 2, 100), 0.0);
+    }
 
-        assert_eq!(result.len(), 5, "should not truncate when under MAX_RESULTS");
+    #[test]
+    fn test_association_metrics() {
+        let window = TimeWindow { oldest_ts: 1000, newest_ts: 1000, decay: None, metric: CouplingMetric::Confidence };
+        // Both change with all 10 of the target's commits; B changes in 90 others too
+        let files = vec![make_stats("A.ts", 10, 10, 1000), make_stats("B.ts", 10, 100, 1000)];
+        let result = score_coupled_files(files, 10, 200, &window, Some(DEFAULT_LIMIT));
+        let a = result.iter().find(|f| f.path == "A.ts").unwrap();
+        let b = result.iter().find(|f| f.path == "B.ts").unwrap();
+
+        assert_eq!((a.confidence, b.confidence), (1.0, 1.0));
+        assert_eq!(a.reverse_confidence, 1.0);
+        assert!((b.reverse_confidence - 0.1).abs() < 1e-9);
+        assert!((a.lift - 20.0).abs() < 1e-9);
+        assert!((b.lift - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_decay_ranks_recent_co_changes_above_old_ones() {
+        let year = 365 * 86400;
+        let now = 6 * year;
+        let co_changes = |n: usize, timestamp: i64| vec![CoChange { timestamp, is_fix: false }; n];
+        // 30 co-changes five years ago, 5 last month
+        let old = RawCoupledFileStats {
+            co_changes: co_changes(30, now - 5 * year),
+            ..make_stats("old.ts", 30, 30, now)
+        };
+        let recent = RawCoupledFileStats {
+            co_changes: co_changes(5, now - 30 * 86400),
+            ..make_stats("new.ts", 5, 30, now)
+        };
+        let half_life_secs = year / 2;
+        let target_weight = 30.0 * Decay::weight(half_life_secs, now, now - 5 * year)
+            + 5.0 * Decay::weight(half_life_secs, now, now - 30 * 86400);
+        let window = TimeWindow {
+            oldest_ts: 0,
+            newest_ts: now,
+            decay: Some(Decay { half_life_secs, target_weight }),
+            metric: CouplingMetric::Confidence,
+        };
+
+        let result = score_coupled_files(vec![old, recent], 35, 100, &window, Some(DEFAULT_LIMIT));
+        assert_eq!(result[0].path, "new.ts");
+        assert!(result[0].risk_score > result[1].risk_score);
+        // Reported coupling stays the plain ratio
+        assert!((result[1].coupling_score - 30.0 / 35.0).abs() < 1e-9);
+
+        assert!((Decay::weight(100, 1000, 900) - 0.5).abs() < 1e-9);
+        assert_eq!(Decay::weight(100, 1000, 1000), 1.0);
+    }
+
+    #[test]
+    fn test_fix_co_changes_weigh_more() {
+        let window = TimeWindow { oldest_ts: 1000, newest_ts: 1000, decay: None, metric: CouplingMetric::Confidence };
+        let refactored = make_stats("A.ts", 2, 10, 1000);
+        let fixed = RawCoupledFileStats { fix_co_change_count: 2, ..make_stats("B.ts", 2, 10, 1000) };
+        let result = score_coupled_files(vec![refactored, fixed], 10, 100, &window, Some(DEFAULT_LIMIT));
+
+        assert_eq!(result[0].path, "B.ts");
+        // (0.4 * 0.5) + 0.3 + 0.2 vs (0.2 * 0.5) + 0.3 + 0.2
+        assert!((result[0].risk_score - 0.7).abs() < 1e-9);
+        assert!((result[1].risk_score - 0.6).abs() < 1e-9);
+        assert_eq!(result[0].coupling_score, result[1].coupling_score);
+    }
+
+    #[test]
+    fn test_percentile_thresholds_split_a_high_scoring_repo() {
+        // A monorepo where every coupling scores above the fixed Critical line
+        let scores: Vec<f64> = (0..100).map(|i| 0.8 + i as f64 * 0.001).collect();
+        let thresholds = RiskThresholds::from_percentiles(scores.clone()).unwrap();
+
+        let count = |level: &str| scores.iter().filter(|&&s| thresholds.level(s) == level).count();
+        assert_eq!((count("Critical"), count("High"), count("Medium"), count("Low")), (10, 20, 30, 40));
+        assert!(scores.iter().all(|&s| risk_level(s) == "Critical"));
+        assert_eq!(RiskThresholds::from_percentiles(vec![0.9; 5]), None);
+    }
+
+    #[test]
+    fn test_symmetric_metrics_discount_small_helpers() {
+        // A helper changed in 4 commits, all with a target changed in 40
+        let window = TimeWindow { oldest_ts: 1000, newest_ts: 1000, decay: None, metric: CouplingMetric::Jaccard };
+        let helper = score_coupled_files(vec![make_stats("helper.ts", 4, 4, 1000)], 40, 100, &window, None);
+        assert!((helper[0].coupling_score - 0.1).abs() < 1e-9);
+        assert_eq!(helper[0].confidence, 0.1);
+
+        // ...and the target seen from the helper: the same score either way
+        let target = coupling_score(CouplingMetric::Jaccard, 4, 4, 40);
+        assert!((target - 0.1).abs() < 1e-9);
+        assert_eq!(coupling_score(CouplingMetric::Confidence, 4, 4, 40), 1.0);
+        assert!((coupling_score(CouplingMetric::Cosine, 4, 4, 40) - 4.0 / 160f64.sqrt()).abs() < 1e-9);
+        assert_eq!(coupling_score(CouplingMetric::Cosine, 0, 0, 40), 0.0);
     }
 }
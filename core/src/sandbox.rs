@@ -0,0 +1,94 @@
+use std::path::{Path, PathBuf};
+
+/// Resolve `rel_path` against `repo_root`, following symlinks, and return
+/// the result only if it stays inside the repository.
+///
+/// Enrichment reads files named by git history and naming conventions, so a
+/// hostile repo could point a "test file" symlink at `~/.ssh` or `/etc`.
+/// Every enrichment read goes through here.
+///
+/// The path is built component by component on the canonical root, which on
+/// Windows is a `\\?\` verbatim path: that lifts the 260-character limit,
+/// but verbatim paths do not translate `/`, so separators must not be
+/// passed through.
+fn resolve_in_repo(repo_root: &Path, rel_path: &str) -> Option<PathBuf> {
+    let root = repo_root.canonicalize().ok()?;
+    let joined = rel_path
+        .split(['/', '\\'])
+        .filter(|c| !c.is_empty())
+        .fold(root.clone(), |path, component| path.join(component));
+    let resolved = joined.canonicalize().ok()?;
+    resolved.starts_with(&root).then_some(resolved)
+}
+
+/// Read a repo-relative file, refusing anything that resolves outside the repo.
+pub(crate) fn read_repo_file(repo_root: &Path, rel_path: &str) -> Option<String> {
+    let path = resolve_in_repo(repo_root, rel_path)?;
+    std::fs::read_to_string(path).ok()
+}
+
+/// The directory `rel_path` resolves to, if it is one inside the repo.
+pub(crate) fn repo_dir(repo_root: &Path, rel_path: &str) -> Option<PathBuf> {
+    resolve_in_repo(repo_root, rel_path).filter(|p| p.is_dir())
+}
+
+/// True if `rel_path` is a regular file inside the repo (after resolving symlinks).
+pub(crate) fn is_repo_file(repo_root: &Path, rel_path: &str) -> bool {
+    resolve_in_repo(repo_root, rel_path).is_some_and(|p| p.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_reads_files_inside_repo() {
+        let repo = TempDir::new().unwrap();
+        fs::create_dir_all(repo.path().join("src")).unwrap();
+        fs::write(repo.path().join("src/a.ts"), "ok").unwrap();
+
+        assert_eq!(read_repo_file(repo.path(), "src/a.ts").as_deref(), Some("ok"));
+        assert!(is_repo_file(repo.path(), "src/a.ts"));
+        assert!(!is_repo_file(repo.path(), "src"));
+        assert!(read_repo_file(repo.path(), "src/missing.ts").is_none());
+    }
+
+    #[test]
+    fn test_reads_deeply_nested_long_paths() {
+        let repo = TempDir::new().unwrap();
+        let rel: Vec<String> = (0..12).map(|i| format!("directory_level_{i:02}_with_a_long_name")).collect();
+        let rel_dir = rel.join("/");
+        assert!(rel_dir.len() > 260);
+        fs::create_dir_all(repo.path().join(&rel_dir)).unwrap();
+        fs::write(repo.path().join(&rel_dir).join("deep.test.ts"), "ok").unwrap();
+
+        let rel_file = format!("{rel_dir}/deep.test.ts");
+        assert_eq!(read_repo_file(repo.path(), &rel_file).as_deref(), Some("ok"));
+    }
+
+    #[test]
+    fn test_rejects_paths_escaping_repo() {
+        let outside = TempDir::new().unwrap();
+        fs::write(outside.path().join("secret.txt"), "secret").unwrap();
+        let repo = TempDir::new().unwrap();
+        let escape = format!("../{}/secret.txt", outside.path().file_name().unwrap().to_str().unwrap());
+
+        assert!(read_repo_file(repo.path(), &escape).is_none());
+        assert!(read_repo_file(repo.path(), outside.path().join("secret.txt").to_str().unwrap()).is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_rejects_symlinks_out_of_repo() {
+        let outside = TempDir::new().unwrap();
+        fs::write(outside.path().join("secret.txt"), "secret").unwrap();
+        let repo = TempDir::new().unwrap();
+        std::os::unix::fs::symlink(outside.path().join("secret.txt"), repo.path().join("a.test.ts"))
+            .unwrap();
+
+        assert!(read_repo_file(repo.path(), "a.test.ts").is_none());
+        assert!(!is_repo_file(repo.path(), "a.test.ts"));
+    }
+}
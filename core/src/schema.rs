@@ -0,0 +1,143 @@
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// JSON output shape, see `to_json`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum JsonFormat {
+    Json,
+    JsonV1,
+    JsonV2,
+    Ndjson,
+}
+
+/// Version of the JSON output shape carried by `json-v2` and `ndjson`.
+/// Bumped when a field is renamed, removed, or changes meaning; new
+/// optional fields do not bump it.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// A response or record with `schema_version` as its first field.
+#[derive(Serialize)]
+struct Versioned<'a, T: Serialize> {
+    schema_version: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    record: Option<&'static str>,
+    #[serde(flatten)]
+    response: &'a T,
+}
+
+/// Serialize a response in the requested JSON format, on a single line.
+///
+/// `json` is the original, unversioned shape, kept as-is so existing
+/// consumers do not break; `json-v1` names that shape for consumers that
+/// pin it. `json-v2` and `ndjson` add `schema_version`. Responses that are
+/// lists go through `to_json_list` instead, where `ndjson` differs.
+pub fn to_json<T: Serialize>(format: JsonFormat, response: &T) -> serde_json::Result<String> {
+    match format {
+        JsonFormat::Json | JsonFormat::JsonV1 => serde_json::to_string(response),
+        JsonFormat::JsonV2 | JsonFormat::Ndjson => {
+            serde_json::to_string(&Versioned { schema_version: SCHEMA_VERSION, record: None, response })
+        }
+    }
+}
+
+/// `to_json` for a response listing its items in the field `items`. With
+/// `ndjson` each item is a line of its own, tagged `"record":"item"`, and a
+/// last line tagged `"record":"summary"` holds the response's other fields
+/// (e.g. `next_offset`), so a stream consumer can act on items as they come.
+pub fn to_json_list<T: Serialize>(format: JsonFormat, response: &T, items: &str) -> serde_json::Result<String> {
+    if format != JsonFormat::Ndjson {
+        return to_json(format, response);
+    }
+    let Value::Object(mut summary) = serde_json::to_value(response)? else {
+        return to_json(format, response);
+    };
+    let items = match summary.remove(items) {
+        Some(Value::Array(items)) => items,
+        _ => Vec::new(),
+    };
+    let record = |record, response: &Value| {
+        serde_json::to_string(&Versioned { schema_version: SCHEMA_VERSION, record: Some(record), response })
+    };
+    let mut lines = items.iter().map(|item| record("item", item)).collect::<serde_json::Result<Vec<_>>>()?;
+    lines.push(record("summary", &Value::Object(summary))?);
+    Ok(lines.join("\n"))
+}
+
+/// Read a response printed as `json`, `json-v1` or `json-v2`, whichever
+/// the engram-core that printed it defaults to. A `schema_version` newer
+/// than this build knows is an error, rather than read with renamed fields
+/// silently missing.
+pub fn from_json<T: DeserializeOwned>(text: &str) -> Result<T, Box<dyn std::error::Error>> {
+    let mut value: Value = serde_json::from_str(text)?;
+    if let Value::Object(map) = &mut value
+        && let Some(version) = map.remove("schema_version")
+    {
+        match version.as_u64() {
+            Some(version) if version <= SCHEMA_VERSION as u64 => {}
+            _ => return Err(format!("unsupported schema_version {version} (newest known: {SCHEMA_VERSION})").into()),
+        }
+    }
+    Ok(serde_json::from_value(value)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::{Database, NoteOrigin, Page};
+    use crate::types::{ListNotesResponse, Memory};
+
+    fn notes(memories: Vec<Memory>) -> ListNotesResponse {
+        ListNotesResponse { file_path: Some("src/a.rs".to_string()), memories, next_offset: None }
+    }
+
+    #[test]
+    fn test_versioned_formats_lead_with_schema_version() {
+        let response = notes(vec![]);
+
+        let v1 = r#"{"file_path":"src/a.rs","memories":[]}"#;
+        assert_eq!(to_json(JsonFormat::Json, &response).unwrap(), v1);
+        assert_eq!(to_json(JsonFormat::JsonV1, &response).unwrap(), v1);
+        let v2 = r#"{"schema_version":2,"file_path":"src/a.rs","memories":[]}"#;
+        assert_eq!(to_json(JsonFormat::JsonV2, &response).unwrap(), v2);
+        assert_eq!(to_json(JsonFormat::Ndjson, &response).unwrap(), v2);
+    }
+
+    #[test]
+    fn test_ndjson_lists_one_item_per_line() {
+        let db = Database::in_memory().unwrap();
+        for content in ["two", "one"] {
+            db.add_memory("src/a.rs", None, content, &NoteOrigin::default()).unwrap();
+        }
+        let memories = db.list_memories(None, None, Page::default()).unwrap();
+        let response = ListNotesResponse { next_offset: Some(2), ..notes(memories) };
+
+        let output = to_json_list(JsonFormat::Ndjson, &response, "memories").unwrap();
+        let lines: Vec<Value> = output.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines.len(), 3);
+        for (line, content) in lines.iter().zip(["one", "two"]) {
+            assert_eq!((line["schema_version"].as_u64(), line["record"].as_str()), (Some(2), Some("item")));
+            assert_eq!(line["content"], content);
+        }
+        assert_eq!(lines[2]["record"], "summary");
+        assert_eq!(lines[2]["next_offset"], 2);
+        assert!(lines[2].get("memories").is_none());
+
+        // Other formats keep the list in one record
+        let v2 = to_json_list(JsonFormat::JsonV2, &response, "memories").unwrap();
+        assert_eq!(v2, to_json(JsonFormat::JsonV2, &response).unwrap());
+        assert_eq!(to_json_list(JsonFormat::Ndjson, &notes(vec![]), "memories").unwrap().lines().count(), 1);
+    }
+
+    #[test]
+    fn test_from_json_reads_every_version_it_knows() {
+        let response = notes(vec![]);
+        for format in [JsonFormat::Json, JsonFormat::JsonV1, JsonFormat::JsonV2] {
+            let read: ListNotesResponse = from_json(&to_json(format, &response).unwrap()).unwrap();
+            assert_eq!(read.file_path.as_deref(), Some("src/a.rs"));
+        }
+        let newer = r#"{"schema_version":3,"file_path":"src/a.rs","memories":[]}"#;
+        assert!(from_json::<ListNotesResponse>(newer).unwrap_err().to_string().contains("schema_version 3"));
+    }
+}
@@ -0,0 +1,124 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+/// Function-like declarations across languages: `function f`, `def f`,
+/// `fn f`, `func (r T) f`, `fun f`, arrow functions bound to a name, and
+/// modifier-prefixed methods (`public async save(`, `private void save(`),
+/// and bare indented methods with a body (`save(item) {`).
+static FUNCTION_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?m)\b(?:function\*?|def|fn|func|fun)\s+(?:\([^)]*\)\s*)?(\w+)|\b(?:const|let|var)\s+(\w+)\s*=\s*(?:async\s+)?(?:function\b|\([^)]*\)\s*(?::[^=]+)?=>|\w+\s*=>)|^[ \t]*(?:(?:public|private|protected|internal|static|async|final|override|virtual|abstract|export)\s+)+(?:[\w<>\[\],.?]+\s+)?(\w+)\s*\(|^[ \t]+(\w+)\s*\([^)]*\)\s*(?::[^{;=]+)?\{",
+    )
+    .unwrap()
+});
+
+static TYPE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\b(?:class|struct|interface|trait|enum|object)\s+(\w+)").unwrap()
+});
+
+/// Names the method regex can pick up that are not symbols.
+const KEYWORDS: [&str; 9] = ["if", "for", "while", "switch", "catch", "return", "new", "constructor", "main"];
+
+/// Declared symbol names (functions, methods, types) in source `content`,
+/// deduplicated in source order. Regex-based and language-agnostic, so it
+/// favours recall over precision.
+pub fn extract_symbols(content: &str) -> Vec<String> {
+    let functions = FUNCTION_RE
+        .captures_iter(content)
+        .filter_map(|cap| (1..=4).find_map(|i| cap.get(i)));
+    let types = TYPE_RE.captures_iter(content).filter_map(|cap| cap.get(1));
+
+    let mut symbols: Vec<String> = Vec::new();
+    for name in functions.chain(types).map(|m| m.as_str()) {
+        if name.len() > 1 && !KEYWORDS.contains(&name) && !symbols.iter().any(|s| s == name) {
+            symbols.push(name.to_string());
+        }
+    }
+    symbols
+}
+
+/// Split an identifier into lowercase words: `refreshToken`,
+/// `refresh_token` and `RefreshToken` all give `["refresh", "token"]`.
+pub fn name_tokens(name: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if !c.is_alphanumeric() {
+            if !current.is_empty() {
+                tokens.push(std::mem::take(&mut current));
+            }
+            prev_lower = false;
+            continue;
+        }
+        if c.is_uppercase() && prev_lower && !current.is_empty() {
+            tokens.push(std::mem::take(&mut current));
+        }
+        prev_lower = c.is_lowercase() || c.is_ascii_digit();
+        current.extend(c.to_lowercase());
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+/// Symbols from `symbols` that a test titled `title` appears to exercise:
+/// every word of the symbol name occurs in the title, allowing inflected
+/// forms (`refreshToken` matches "refreshes expired tokens"). One-word
+/// symbols need at least four letters to count, so `get` or `run` do not
+/// match every title.
+pub fn covered_symbols(title: &str, symbols: &[String]) -> Vec<String> {
+    let words = name_tokens(title);
+    symbols
+        .iter()
+        .filter(|symbol| {
+            let tokens = name_tokens(symbol);
+            match tokens.as_slice() {
+                [] => false,
+                [only] if only.len() < 4 => false,
+                _ => tokens.iter().all(|t| words.iter().any(|w| w.starts_with(t.as_str()))),
+            }
+        })
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_symbols_across_languages() {
+        let ts = r#"
+export class SessionStore {
+  public async refreshToken(id: string): Promise<void> {
+    if (expired(id)) { return; }
+  }
+  logout() {}
+}
+export function login(user: User) {}
+const parseHeader = (raw: string) => raw;
+"#;
+        assert_eq!(extract_symbols(ts), vec!["refreshToken", "logout", "login", "parseHeader", "SessionStore"]);
+
+        let rust = "pub struct Cart;\nimpl Cart {\n    pub fn add_item(&mut self) {}\n}\nfn total() {}\n";
+        assert_eq!(extract_symbols(rust), vec!["add_item", "total", "Cart"]);
+
+        let go = "func (c *Cart) AddItem(sku string) {}\nfunc Total() int { return 0 }\n";
+        assert_eq!(extract_symbols(go), vec!["AddItem", "Total"]);
+    }
+
+    #[test]
+    fn test_covered_symbols() {
+        let symbols: Vec<String> =
+            ["refreshToken", "login", "get", "parse_header", "SessionStore"].map(String::from).to_vec();
+
+        assert_eq!(covered_symbols("refreshes expired tokens", &symbols), vec!["refreshToken"]);
+        assert_eq!(covered_symbols("login redirects to dashboard", &symbols), vec!["login"]);
+        assert_eq!(covered_symbols("parse header rejects blanks", &symbols), vec!["parse_header"]);
+        assert!(covered_symbols("get returns cached value", &symbols).is_empty());
+        assert!(covered_symbols("logs out", &symbols).is_empty());
+    }
+}
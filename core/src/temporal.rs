@@ -1,11 +1,36 @@
 use git2::Repository;
+use std::collections::HashMap;
 use std::path::Path;
 use std::time::{Duration, Instant};
 
-use crate::indexing;
+use crate::alerts;
+use crate::ignore_rules::PathFilter;
+use crate::indexing::{self, IndexOptions};
+use crate::packages;
 use crate::persistence::Database;
-use crate::risk::{self, RawCoupledFileStats, TimeWindow};
-use crate::types::{AnalysisResponse, IndexingStatus};
+use crate::risk::{self, Decay, RawCoupledFileStats, TimeWindow};
+use crate::types::{
+    AnalysisResponse, CoupledFile, IndexingStatus, PhaseTimings, RiskThresholds, SharedCommit, TicketCoupledFile,
+};
+
+/// Files whose couplings risk levels are calibrated against: the most
+/// changed, which are also the ones most often analyzed.
+const CALIBRATION_FILES: usize = 50;
+
+/// Tickets a file must share with the analyzed file to be reported as
+/// related: one shared ticket is as likely a sweeping change.
+const MIN_SHARED_TICKETS: u32 = 2;
+const MAX_TICKET_COUPLED_FILES: usize = 10;
+
+/// Shared commits sampled per coupled file.
+const SAMPLE_COMMITS: usize = 3;
+
+/// Two-hop coupling: how many of the top coupled files to follow, the
+/// least attenuated risk a file reached through one must keep, and how
+/// many such files to report.
+const MAX_HOP_SOURCES: usize = 5;
+const MIN_TRANSITIVE_RISK: f64 = 0.3;
+const MAX_TRANSITIVE_FILES: usize = 5;
 
 /// Files that should be excluded from the temporal index because they
 /// change in nearly every commit and produce misleading coupling signals.
@@ -36,10 +61,10 @@ const IGNORED_EXTENSIONS: &[&str] = &[
 /// Filters out lock files, binary assets, and other noise.
 pub(crate) fn should_index_file(path: &str) -> bool {
     // Check filename matches
-    if let Some(filename) = path.rsplit('/').next() {
-        if IGNORED_FILENAMES.contains(&filename) {
-            return false;
-        }
+    if let Some(filename) = path.rsplit('/').next()
+        && IGNORED_FILENAMES.contains(&filename)
+    {
+        return false;
     }
 
     // Check extension matches
@@ -60,6 +85,7 @@ pub fn analyze(
     repo_root: &Path,
     file_path: &str,
     db: &Database,
+    opts: &IndexOptions,
 ) -> Result<(AnalysisResponse, bool), Box<dyn std::error::Error>> {
     let start = Instant::now();
     let repo = Repository::open(repo_root)?;
@@ -70,56 +96,251 @@ pub fn analyze(
     let index_result = indexing::smart_index(
         &repo,
         db,
+        opts,
         file_path,
         Duration::from_millis(1500),
     )?;
+    let index_time = start.elapsed();
 
-    let coupled_raw = db.coupled_files_with_stats(file_path)?;
-    let commit_count = db.commit_count(file_path)?;
-    let (oldest_ts, newest_ts) = db.commit_time_range()?;
+    // Only a walk still under way has work left; an outdated but complete
+    // index is re-checked in the background without holding analyses up
+    let warmup_plan = match db.get_indexing_state()? {
+        Some(state) if !state.is_complete => {
+            Some(indexing::warmup_plan(&index_result, indexing::commits_remaining(&repo, &state)))
+        }
+        _ => None,
+    };
 
-    let raw_stats: Vec<RawCoupledFileStats> = coupled_raw
+    let history = History { total_commits: db.indexed_commit_count()?, time_range: db.commit_time_range()? };
+    let commit_count = db.commit_count(file_path)?;
+    // Packages are told apart after scoring, so a cross-package filter
+    // has to see every coupled file before the limit
+    let unlimited;
+    let query_opts = if opts.cross_package_only {
+        unlimited = IndexOptions { limit: None, ..opts.clone() };
+        &unlimited
+    } else {
+        opts
+    };
+    let tree = opts.tip(&repo).and_then(|tip| repo.find_commit(tip)?.tree()).ok();
+    let mark_packages = |files: &mut Vec<CoupledFile>| {
+        if let Some(tree) = &tree {
+            packages::mark_cross_package(tree, file_path, files);
+        }
+        if opts.cross_package_only {
+            files.retain(|f| f.cross_package == Some(true));
+        }
+    };
+    let mut coupled_files = scored_coupled_files(db, query_opts, file_path, commit_count, &history)?;
+    mark_packages(&mut coupled_files);
+    coupled_files.truncate(opts.limit.unwrap_or(usize::MAX));
+    if opts.depth >= 2 {
+        let mut transitive = transitive_coupled_files(db, opts, file_path, &coupled_files, &history)?;
+        mark_packages(&mut transitive);
+        coupled_files.extend(transitive);
+    }
+    alerts::mark_trends(db, file_path, &mut coupled_files)?;
+    let lossy = db.lossy_paths()?;
+    let mut types = db.co_change_types(file_path)?;
+    for file in coupled_files.iter_mut() {
+        file.lossy_path = lossy.contains(&file.path);
+        file.co_change_types = types.remove(&file.path).unwrap_or_default();
+        file.shared_commits = db
+            .shared_commits(file.via.as_deref().unwrap_or(file_path), &file.path)?
+            .into_iter()
+            .take(SAMPLE_COMMITS)
+            .map(|(hash, timestamp, _)| SharedCommit { hash, timestamp })
+            .collect();
+    }
+    let ticket_coupled_files = db
+        .ticket_coupled_files(file_path, MIN_SHARED_TICKETS)?
         .into_iter()
-        .map(|(path, co_change_count, total_commits, last_timestamp)| {
-            RawCoupledFileStats {
-                path,
-                co_change_count,
-                total_commits,
-                last_timestamp,
-            }
-        })
+        .filter(|(path, _)| !opts.ignore.is_ignored(path) && opts.in_scope(path))
+        .take(MAX_TICKET_COUPLED_FILES)
+        .map(|(path, shared_tickets)| TicketCoupledFile { path, shared_tickets })
         .collect();
 
-    let window = TimeWindow {
-        oldest_ts,
-        newest_ts,
-    };
-
-    let coupled_files = risk::score_coupled_files(raw_stats, commit_count, &window);
+    let risk_thresholds = if opts.calibrate_risk { calibrated_thresholds(db, opts, &history)? } else { None };
 
-    let elapsed = start.elapsed();
+    let elapsed_ms = start.elapsed().as_millis() as u64;
+    let index_ms = index_time.as_millis() as u64;
 
     let response = AnalysisResponse {
         file_path: file_path.to_string(),
         repo_root: repo_root.to_string_lossy().to_string(),
         coupled_files,
         commit_count,
-        analysis_time_ms: elapsed.as_millis() as u64,
+        analysis_time_ms: elapsed_ms,
+        risk_thresholds,
+        timings: Some(PhaseTimings {
+            index_ms,
+            query_ms: elapsed_ms - index_ms,
+            ..Default::default()
+        }),
         test_info: None,
         indexing_status: Some(IndexingStatus {
             strategy: index_result.strategy.as_str().to_string(),
             commits_indexed: index_result.commits_indexed,
             is_complete: index_result.is_complete,
+            complete_to_depth: index_result.is_complete && repo.is_shallow(),
         }),
+        warmup_plan,
+        enrichment_omitted: None,
+        extensions: None,
+        ticket_coupled_files,
+        next_offset: None,
+        upstream_drift: None,
     };
 
     Ok((response, index_result.needs_background))
 }
 
+/// Risk level thresholds drawn from the repo's own scores, recomputed
+/// once the index is complete at a new HEAD. Until then the last stored
+/// calibration holds; `None` before there is any.
+fn calibrated_thresholds(
+    db: &Database,
+    opts: &IndexOptions,
+    history: &History,
+) -> Result<Option<RiskThresholds>, rusqlite::Error> {
+    let stored = db.get_risk_calibration()?;
+    let Some(state) = db.get_indexing_state()?.filter(|s| s.is_complete) else {
+        return Ok(stored.map(|(_, thresholds)| thresholds));
+    };
+    if let Some((head, thresholds)) = &stored
+        && *head == state.head_commit
+    {
+        return Ok(Some(*thresholds));
+    }
+
+    // Every coupling of the most changed files, unfiltered by the query
+    let opts = IndexOptions {
+        limit: None,
+        paths: PathFilter::default(),
+        min_coupling: None,
+        min_risk: None,
+        cross_package_only: false,
+        ..opts.clone()
+    };
+    let mut scores = Vec::new();
+    for (path, commit_count) in db.hotspots(CALIBRATION_FILES)? {
+        if !opts.ignore.is_ignored(&path) && opts.in_scope(&path) {
+            let coupled = scored_coupled_files(db, &opts, &path, commit_count, history)?;
+            scores.extend(coupled.iter().map(|f| f.risk_score));
+        }
+    }
+    match RiskThresholds::from_percentiles(scores) {
+        Some(thresholds) => {
+            db.set_risk_calibration(&state.head_commit, &thresholds)?;
+            Ok(Some(thresholds))
+        }
+        None => Ok(stored.map(|(_, thresholds)| thresholds)),
+    }
+}
+
+/// The indexed history that coupling is measured against.
+struct History {
+    total_commits: u32,
+    /// Oldest and newest commit timestamps
+    time_range: (i64, i64),
+}
+
+/// Risk-scored files coupled with `file_path`, which has `commit_count`
+/// indexed commits, under the coupling options in `opts`.
+fn scored_coupled_files(
+    db: &Database,
+    opts: &IndexOptions,
+    file_path: &str,
+    commit_count: u32,
+    history: &History,
+) -> Result<Vec<CoupledFile>, rusqlite::Error> {
+    let coupled_raw = match opts.coupling_window {
+        Some(window) => db.coupled_files_in_window(file_path, window.as_secs() as i64)?,
+        None => db.coupled_files_with_stats(file_path)?,
+    };
+    let (oldest_ts, newest_ts) = history.time_range;
+
+    // Filter at query time too, so rows indexed before a path was added to
+    // `.engramignore` disappear without a reindex.
+    let mut raw_stats: Vec<RawCoupledFileStats> = coupled_raw
+        .into_iter()
+        .filter(|f| !opts.ignore.is_ignored(&f.path) && opts.in_scope(&f.path))
+        .collect();
+    if let Some(max_p_value) = opts.max_p_value {
+        raw_stats.retain(|f| {
+            risk::co_change_p_value(f.co_change_count, commit_count, f.total_commits, history.total_commits)
+                < max_p_value
+        });
+    }
+
+    let decay = match opts.half_life {
+        Some(half_life) => {
+            let half_life_secs = half_life.as_secs() as i64;
+            let target_weight = db
+                .commit_timestamps(file_path)?
+                .into_iter()
+                .map(|ts| Decay::weight(half_life_secs, newest_ts, ts))
+                .sum();
+            Some(Decay { half_life_secs, target_weight })
+        }
+        None => None,
+    };
+    let window = TimeWindow {
+        oldest_ts,
+        newest_ts,
+        decay,
+        metric: opts.coupling_metric,
+    };
+
+    // Score against every coupled file, so filters narrow the list without
+    // changing anyone's score, then fill the limit from what is left
+    let mut scored = risk::score_coupled_files(raw_stats, commit_count, history.total_commits, &window, None);
+    scored.retain(|f| opts.paths.allows(&f.path) && opts.meets_thresholds(f));
+    scored.truncate(opts.limit.unwrap_or(usize::MAX));
+    Ok(scored)
+}
+
+/// Files coupled with the target's top coupled files (`direct`) but not
+/// with the target itself: a change often ripples one hop further. Each is
+/// scored as a coupling of the file it is reached through (`via`), with
+/// its coupling and risk scaled down by that file's coupling to the
+/// target; its co-change count, confidence and lift are relative to `via`.
+fn transitive_coupled_files(
+    db: &Database,
+    opts: &IndexOptions,
+    file_path: &str,
+    direct: &[CoupledFile],
+    history: &History,
+) -> Result<Vec<CoupledFile>, rusqlite::Error> {
+    let mut best: HashMap<String, CoupledFile> = HashMap::new();
+    for via in direct.iter().take(MAX_HOP_SOURCES) {
+        let via_commits = db.commit_count(&via.path)?;
+        for mut hop in scored_coupled_files(db, opts, &via.path, via_commits, history)? {
+            if hop.path == file_path || direct.iter().any(|d| d.path == hop.path) {
+                continue;
+            }
+            hop.coupling_score *= via.coupling_score;
+            hop.risk_score *= via.coupling_score;
+            if hop.risk_score < MIN_TRANSITIVE_RISK || !opts.meets_thresholds(&hop) {
+                continue;
+            }
+            hop.via = Some(via.path.clone());
+            if best.get(&hop.path).is_none_or(|b| b.risk_score < hop.risk_score) {
+                best.insert(hop.path.clone(), hop);
+            }
+        }
+    }
+
+    let mut result: Vec<CoupledFile> = best.into_values().collect();
+    result.sort_by(|a, b| b.risk_score.total_cmp(&a.risk_score).then_with(|| a.path.cmp(&b.path)));
+    result.truncate(MAX_TRANSITIVE_FILES);
+    Ok(result)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::indexing::budgeted_global_index;
+    use crate::indexing::{budgeted_global_index, path_filtered_index};
     use git2::Signature;
     use std::fs;
     use tempfile::TempDir;
@@ -231,7 +452,7 @@ mod tests {
         let dir = create_test_repo(&commits);
         let db = Database::in_memory().unwrap();
 
-        let (response, _) = analyze(dir.path(), "src/A.ts", &db).unwrap();
+        let (response, _) = analyze(dir.path(), "src/A.ts", &db, &IndexOptions::default()).unwrap();
 
         // package-lock.json should NOT appear as a coupled file
         let lockfile = response.coupled_files.iter().find(|f| f.path == "package-lock.json");
@@ -242,6 +463,32 @@ mod tests {
         assert!(b_file.is_some(), "src/B.ts should still be coupled");
     }
 
+    #[test]
+    fn test_engramignore_filters_indexing_and_results() {
+        let commits: Vec<_> = (0..4)
+            .map(|i| {
+                f(&[
+                    ("src/A.ts", &format!("v{i}")),
+                    ("src/B.ts", &format!("v{i}")),
+                    ("src/generated/api.ts", &format!("v{i}")),
+                    ("fixtures/data.json", &format!("v{i}")),
+                ])
+            })
+            .collect();
+        let dir = create_test_repo(&commits);
+        let db = Database::in_memory().unwrap();
+        // Indexed before the ignore file existed
+        db.insert_commit("old", &["src/A.ts", "fixtures/data.json"], 1).unwrap();
+        fs::write(dir.path().join(".engramignore"), "generated/\nfixtures/\n").unwrap();
+
+        let opts = IndexOptions::load(dir.path(), &crate::config::Config::default());
+        let (response, _) = analyze(dir.path(), "src/A.ts", &db, &opts).unwrap();
+
+        let paths: Vec<&str> = response.coupled_files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec!["src/B.ts"]);
+        assert_eq!(db.commit_count("src/generated/api.ts").unwrap(), 0);
+    }
+
     #[test]
     fn test_smart_index_and_coupling() {
         let mut commits = Vec::new();
@@ -263,7 +510,7 @@ mod tests {
         let dir = create_test_repo(&commits);
         let db = Database::in_memory().unwrap();
 
-        let (response, _) = analyze(dir.path(), "src/A.ts", &db).unwrap();
+        let (response, _) = analyze(dir.path(), "src/A.ts", &db, &IndexOptions::default()).unwrap();
 
         assert_eq!(response.file_path, "src/A.ts");
         assert!(response.commit_count >= 10);
@@ -281,6 +528,7 @@ mod tests {
             "coupling score should be > 0.8, got {}",
             b_file.coupling_score
         );
+        assert_eq!(b_file.shared_commits.len(), SAMPLE_COMMITS);
 
         // C should have low coupling (only initial commit)
         if let Some(c_file) = response.coupled_files.iter().find(|f| f.path == "src/C.ts") {
@@ -292,6 +540,125 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_thresholds_apply_before_limit() {
+        let db = Database::in_memory().unwrap();
+        // s.rs changed with a.rs in two of its five commits, long ago
+        db.insert_commit("c1", &["a.rs", "s.rs"], 1000).unwrap();
+        db.insert_commit("c2", &["a.rs", "s.rs"], 2000).unwrap();
+        // Each w*.rs changed with a.rs once, but churns and changed lately,
+        // so it outranks s.rs on risk
+        for (i, weak) in ["w1.rs", "w2.rs", "w3.rs"].iter().enumerate() {
+            db.insert_commit(&format!("a{i}"), &["a.rs", weak], 3000 + i as i64).unwrap();
+            for j in 0..10 {
+                db.insert_commit(&format!("{weak}-{j}"), &[weak], 5000 + j).unwrap();
+            }
+        }
+        let history = History { total_commits: db.indexed_commit_count().unwrap(), time_range: (1000, 5009) };
+        let commit_count = db.commit_count("a.rs").unwrap();
+        let paths = |opts: &IndexOptions| -> Vec<String> {
+            let scored = scored_coupled_files(&db, opts, "a.rs", commit_count, &history).unwrap();
+            scored.into_iter().map(|f| f.path).collect()
+        };
+
+        let top = IndexOptions { limit: Some(2), ..Default::default() };
+        assert_eq!(paths(&top), vec!["w3.rs", "w2.rs"]);
+        // Filtering after the limit would leave nothing
+        let strong = IndexOptions { min_coupling: Some(0.4), ..top.clone() };
+        assert_eq!(paths(&strong), vec!["s.rs"]);
+        let weak = IndexOptions { min_risk: Some(0.45), limit: Some(3), ..Default::default() };
+        assert_eq!(paths(&weak), vec!["w3.rs", "w2.rs", "w1.rs"]);
+    }
+
+    #[test]
+    fn test_max_p_value_drops_chance_couplings() {
+        let mut commits: Vec<_> = (0..8).map(|i| f(&[("src/D.ts", &format!("v{i}"))])).collect();
+        for i in 0..4 {
+            commits.push(f(&[("src/A.ts", &format!("v{i}")), ("src/B.ts", &format!("v{i}"))]));
+        }
+        commits.push(f(&[("src/A.ts", "v4"), ("src/C.ts", "v0")]));
+        let dir = create_test_repo(&commits);
+        let db = Database::in_memory().unwrap();
+
+        let (response, _) = analyze(dir.path(), "src/A.ts", &db, &IndexOptions::default()).unwrap();
+        assert_eq!(response.coupled_files.len(), 2);
+
+        let opts = IndexOptions { max_p_value: Some(0.05), ..Default::default() };
+        let (response, _) = analyze(dir.path(), "src/A.ts", &db, &opts).unwrap();
+        let paths: Vec<&str> = response.coupled_files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec!["src/B.ts"]);
+    }
+
+    #[test]
+    fn test_risk_levels_calibrated_once_index_is_complete() {
+        let names = ["src/A.ts", "src/B.ts", "src/C.ts", "src/D.ts", "src/E.ts", "src/F.ts"];
+        // Later files change more often, so their couplings score apart
+        let commits: Vec<Vec<(String, String)>> = (0..6)
+            .map(|i| names[i % 3..].iter().map(|name| (name.to_string(), format!("v{i}"))).collect())
+            .collect();
+        let dir = create_test_repo(&commits);
+        let db = Database::in_memory().unwrap();
+
+        let (response, _) = analyze(dir.path(), "src/A.ts", &db, &IndexOptions::default()).unwrap();
+        assert_eq!(response.risk_thresholds, None);
+
+        let opts = IndexOptions { calibrate_risk: true, ..Default::default() };
+        let (response, _) = analyze(dir.path(), "src/A.ts", &db, &opts).unwrap();
+        let thresholds = response.risk_thresholds.unwrap();
+        assert!(thresholds.critical >= thresholds.high && thresholds.high >= thresholds.medium);
+        let head = Repository::open(dir.path()).unwrap().head().unwrap().target().unwrap().to_string();
+        assert_eq!(db.get_risk_calibration().unwrap(), Some((head, thresholds)));
+    }
+
+    #[test]
+    fn test_cross_package_filter_applies_before_limit() {
+        let mut commits = vec![f(&[("web/package.json", "{}"), ("api/go.mod", "module api")])];
+        for i in 0..4 {
+            commits.push(f(&[("web/a.ts", &format!("v{i}")), ("web/b.ts", &format!("v{i}"))]));
+        }
+        commits.push(f(&[("web/a.ts", "v4"), ("api/c.go", "v0")]));
+        let dir = create_test_repo(&commits);
+        let db = Database::in_memory().unwrap();
+
+        let (response, _) = analyze(dir.path(), "web/a.ts", &db, &IndexOptions::default()).unwrap();
+        let tagged: Vec<(&str, Option<bool>)> =
+            response.coupled_files.iter().map(|f| (f.path.as_str(), f.cross_package)).collect();
+        assert_eq!(tagged, vec![("web/b.ts", Some(false)), ("api/c.go", Some(true))]);
+
+        let opts = IndexOptions { cross_package_only: true, limit: Some(1), ..Default::default() };
+        let (response, _) = analyze(dir.path(), "web/a.ts", &db, &opts).unwrap();
+        let paths: Vec<&str> = response.coupled_files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec!["api/c.go"]);
+    }
+
+    #[test]
+    fn test_depth_two_reports_files_coupled_through_a_coupled_file() {
+        let mut commits = Vec::new();
+        for i in 0..4 {
+            commits.push(f(&[("src/A.ts", &format!("a{i}")), ("src/B.ts", &format!("a{i}"))]));
+            commits.push(f(&[("src/B.ts", &format!("b{i}")), ("src/C.ts", &format!("b{i}"))]));
+        }
+        let dir = create_test_repo(&commits);
+        let db = Database::in_memory().unwrap();
+
+        let (response, _) = analyze(dir.path(), "src/A.ts", &db, &IndexOptions::default()).unwrap();
+        let paths: Vec<&str> = response.coupled_files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec!["src/B.ts"]);
+
+        let opts = IndexOptions { depth: 2, ..Default::default() };
+        let (response, _) = analyze(dir.path(), "src/A.ts", &db, &opts).unwrap();
+        assert_eq!(response.coupled_files.len(), 2);
+        let direct = &response.coupled_files[0];
+        let transitive = &response.coupled_files[1];
+        assert_eq!(direct.path, "src/B.ts");
+        assert_eq!(direct.via, None);
+        assert_eq!(transitive.path, "src/C.ts");
+        assert_eq!(transitive.via.as_deref(), Some("src/B.ts"));
+        // Sampled from the hop's own co-changes, which never include A
+        assert_eq!(transitive.shared_commits.len(), 3);
+        assert!(transitive.coupling_score < direct.coupling_score);
+    }
+
     #[test]
     fn test_incremental_indexing() {
         let commits = vec![
@@ -303,19 +670,20 @@ mod tests {
         let db = Database::in_memory().unwrap();
 
         // First call indexes everything via smart_index
-        let (r1, _) = analyze(dir.path(), "a.txt", &db).unwrap();
+        let (r1, _) = analyze(dir.path(), "a.txt", &db, &IndexOptions::default()).unwrap();
         assert!(r1.indexing_status.as_ref().unwrap().is_complete);
 
         // Second call should do no additional indexing
-        let (r2, _) = analyze(dir.path(), "a.txt", &db).unwrap();
+        let (r2, _) = analyze(dir.path(), "a.txt", &db, &IndexOptions::default()).unwrap();
         assert!(r2.indexing_status.as_ref().unwrap().is_complete);
     }
 
     #[test]
     fn test_rename_detection() {
-        let mut commits = Vec::new();
-        commits.push(f(&[("src/A.ts", "v0"), ("src/B.ts", "v0")]));
-        commits.push(f(&[("src/A.ts", "v1"), ("src/B.ts", "v1")]));
+        let commits = vec![
+            f(&[("src/A.ts", "v0"), ("src/B.ts", "v0")]),
+            f(&[("src/A.ts", "v1"), ("src/B.ts", "v1")]),
+        ];
 
         let dir = create_test_repo(&commits);
 
@@ -340,16 +708,14 @@ mod tests {
         let db = Database::in_memory().unwrap();
         let repo = Repository::open(dir.path()).unwrap();
         let (indexed, _, _) = budgeted_global_index(
-            &repo, &db, Duration::from_secs(10), 1000, None, 100,
+            &repo, &db, &IndexOptions::default(), Duration::from_secs(10), 1000, None, 100,
         ).unwrap();
         assert!(indexed >= 3);
 
-        let count = db.commit_count("src/ARenamed.ts").unwrap();
-        assert!(count >= 1, "ARenamed.ts should be indexed, got count={count}");
-
-        let coupled = db.coupled_files("src/ARenamed.ts").unwrap();
-        let b_coupled = coupled.iter().find(|(p, _)| p == "src/B.ts");
-        assert!(b_coupled.is_some(), "B.ts should be coupled to ARenamed.ts after rename");
+        // History from before the rename is recorded under the new name
+        assert_eq!(db.commit_count("src/ARenamed.ts").unwrap(), 3);
+        assert_eq!(db.commit_count("src/A.ts").unwrap(), 0);
+        assert_eq!(db.co_change_count("src/ARenamed.ts", "src/B.ts").unwrap(), 3);
     }
 
     #[test]
@@ -370,10 +736,12 @@ mod tests {
     }
 
     #[test]
-    fn test_merge_commit_includes_branch_changes() {
+    fn test_merge_commit_attributed_to_branch_commits() {
         let dir = TempDir::new().unwrap();
         let repo = Repository::init(dir.path()).unwrap();
-        let sig = Signature::now("Test", "test@test.com").unwrap();
+        // Distinct timestamps keep the time-sorted walks deterministic
+        let sig_at = |t: i64| Signature::new("Test", "test@test.com", &git2::Time::new(t, 0)).unwrap();
+        let sig = sig_at(1000);
 
         fs::write(dir.path().join("A.ts"), "v0").unwrap();
         fs::write(dir.path().join("B.ts"), "v0").unwrap();
@@ -394,6 +762,7 @@ mod tests {
         index.write().unwrap();
         let tree_id = index.write_tree().unwrap();
         let tree = repo.find_tree(tree_id).unwrap();
+        let sig = sig_at(2000);
         let main_commit = repo.commit(Some("HEAD"), &sig, &sig, "main: change A", &tree, &[&commit0]).unwrap();
         let main_commit = repo.find_commit(main_commit).unwrap();
 
@@ -405,6 +774,7 @@ mod tests {
         index.write().unwrap();
         let tree_id = index.write_tree().unwrap();
         let tree = repo.find_tree(tree_id).unwrap();
+        let sig = sig_at(3000);
         let feature_commit = repo.commit(Some("refs/heads/feature"), &sig, &sig, "feature: change B", &tree, &[&commit0]).unwrap();
         let feature_commit = repo.find_commit(feature_commit).unwrap();
 
@@ -414,23 +784,53 @@ mod tests {
         let mut merge_index = repo.merge_commits(&main_commit, &feature_commit, None).unwrap();
         let merge_tree_id = merge_index.write_tree_to(&repo).unwrap();
         let merge_tree = repo.find_tree(merge_tree_id).unwrap();
+        let sig = sig_at(4000);
         repo.commit(
             Some("HEAD"), &sig, &sig, "merge feature into main",
             &merge_tree, &[&main_commit, &feature_commit],
         ).unwrap();
 
-        let db = Database::in_memory().unwrap();
         let repo = Repository::open(dir.path()).unwrap();
+
+        // Default: merges are skipped, so A and B (changed on different
+        // branches) only co-change in the initial commit.
+        let db = Database::in_memory().unwrap();
         let (indexed, _, _) = budgeted_global_index(
-            &repo, &db, Duration::from_secs(10), 1000, None, 100,
+            &repo, &db, &IndexOptions::default(), Duration::from_secs(10), 1000, None, 100,
         ).unwrap();
-        assert!(indexed >= 4, "should index at least 4 commits, got {indexed}");
+        assert_eq!(indexed, 3, "merge commit should not be indexed");
+        assert_eq!(db.commit_count("B.ts").unwrap(), 2);
+        assert_eq!(db.co_change_count("A.ts", "B.ts").unwrap(), 1);
 
-        let coupled = db.coupled_files("A.ts").unwrap();
-        let b_coupled = coupled.iter().find(|(p, _)| p == "B.ts");
-        assert!(
-            b_coupled.is_some(),
-            "B.ts should appear coupled to A.ts due to merge commit diffing against parent(0)"
+        // Path-filtered walks follow first parents only; B.ts reached main via
+        // the merge, and is credited to the feature commit that changed it.
+        let db = Database::in_memory().unwrap();
+        path_filtered_index(
+            &repo, &db, &IndexOptions::default(), "B.ts", Duration::from_secs(10), None, 100,
+        ).unwrap();
+        assert_eq!(db.commit_count("B.ts").unwrap(), 2);
+        assert_eq!(db.co_change_count("A.ts", "B.ts").unwrap(), 1);
+
+        // A branch over the cap leaves the merge unattributed
+        let db = Database::in_memory().unwrap();
+        let capped = IndexOptions { max_merged_branch: Some(0), ..Default::default() };
+        let (_, _, hit_end) = path_filtered_index(
+            &repo, &db, &capped, "B.ts", Duration::from_secs(10), None, 100,
+        ).unwrap();
+        assert!(hit_end);
+        assert_eq!(db.commit_count("B.ts").unwrap(), 1);
+
+        // Legacy mode diffs the merge against parent(0), inflating coupling
+        let db = Database::in_memory().unwrap();
+        let legacy = IndexOptions { diff_merges: true, ..Default::default() };
+        let (indexed, _, _) = budgeted_global_index(
+            &repo, &db, &legacy, Duration::from_secs(10), 1000, None, 100,
+        ).unwrap();
+        assert_eq!(indexed, 4);
+        assert_eq!(
+            db.commit_count("B.ts").unwrap(),
+            3,
+            "the merge re-attributes the feature branch's change to B.ts"
         );
     }
 
@@ -446,7 +846,7 @@ mod tests {
         let repo = Repository::open(dir.path()).unwrap();
 
         let (indexed, _, _) = budgeted_global_index(
-            &repo, &db, Duration::from_secs(10), 5, None, 100,
+            &repo, &db, &IndexOptions::default(), Duration::from_secs(10), 5, None, 100,
         ).unwrap();
         assert_eq!(indexed, 5, "should stop at the commit limit");
 
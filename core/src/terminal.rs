@@ -0,0 +1,342 @@
+use crate::markdown::{failing_marker, intent_line, stale_marker, upstream_line};
+use crate::types::{AnalysisResponse, CoupledFile, Memory, RiskThresholds, StaticLink, TestsToRunResponse};
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+
+/// Whether terminal output may use ANSI colors: not when `NO_COLOR` is set
+/// (https://no-color.org) or the terminal is `dumb`.
+pub fn color_enabled() -> bool {
+    std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+        && std::env::var("TERM").ok().as_deref() != Some("dumb")
+}
+
+/// Wraps text in ANSI styles when colors are on.
+#[derive(Clone, Copy)]
+struct Paint {
+    color: bool,
+}
+
+impl Paint {
+    fn style(self, code: &str, text: &str) -> String {
+        if self.color { format!("{code}{text}{RESET}") } else { text.to_string() }
+    }
+
+    fn bold(self, text: &str) -> String {
+        self.style(BOLD, text)
+    }
+
+    fn dim(self, text: &str) -> String {
+        self.style(DIM, text)
+    }
+
+    /// `text` colored by the risk level it stands for.
+    fn risk(self, level: &str, text: &str) -> String {
+        let code = match level {
+            "Critical" => "\x1b[1;31m",
+            "High" => "\x1b[33m",
+            "Medium" => "\x1b[36m",
+            _ => DIM,
+        };
+        self.style(code, text)
+    }
+}
+
+/// Render an analysis for a person at a terminal: a summary line, one row
+/// per coupled file colored by risk, with its notes, test intents and
+/// co-changing line regions as a tree beneath it, then the tests found for
+/// the analyzed file.
+pub fn render_analysis(response: &AnalysisResponse, color: bool) -> String {
+    let paint = Paint { color };
+    let files = &response.coupled_files;
+    let mut out = format!("{}\n", paint.bold(&response.file_path));
+    if files.is_empty() {
+        out.push_str(&format!("No coupled files in {}.\n", plural(response.commit_count, "commit")));
+        return out;
+    }
+
+    let thresholds = response.risk_thresholds.unwrap_or(RiskThresholds::FIXED);
+    let counts: Vec<String> = ["Critical", "High", "Medium", "Low"]
+        .iter()
+        .filter_map(|level| {
+            let n = files.iter().filter(|f| thresholds.level(f.risk_score) == *level).count();
+            (n > 0).then(|| paint.risk(level, &format!("{n} {}", level.to_lowercase())))
+        })
+        .collect();
+    out.push_str(&format!(
+        "{} coupled ({}) across {}.\n",
+        plural(files.len() as u32, "file"),
+        counts.join(", "),
+        plural(response.commit_count, "commit"),
+    ));
+    if response.indexing_status.as_ref().is_some_and(|s| !s.is_complete) {
+        out.push_str(&paint.dim("Indexing is still in progress; rerun for fuller results.\n"));
+    }
+    if let Some(drift) = &response.upstream_drift {
+        out.push_str(&format!("{}\n", paint.risk("High", &upstream_line(drift))));
+    }
+
+    out.push('\n');
+    out.push_str(&paint.dim(&format!("{:<8}  {:>5}  {:>10}  FILE", "RISK", "SCORE", "CO-CHANGES")));
+    out.push('\n');
+    for file in files {
+        let level = thresholds.level(file.risk_score);
+        let co_changes = format!("{}/{}", file.co_change_count, response.commit_count);
+        out.push_str(&format!(
+            "{}  {:>5.2}  {co_changes:>10}  {}{}\n",
+            paint.risk(level, &format!("{level:<8}")),
+            file.risk_score,
+            file.path,
+            paint.dim(&markers(file)),
+        ));
+        out.push_str(&tree(&details(file, &response.file_path), "          ", paint));
+    }
+
+    if let Some(info) = response.test_info.as_ref().filter(|i| !i.test_files.is_empty()) {
+        out.push_str(&format!("\n{}\n", paint.bold("Tests")));
+        for test_file in &info.test_files {
+            out.push_str(&format!(
+                "  {} {}{}{}\n",
+                test_file.path,
+                paint.dim(&format!("({})", plural(test_file.test_count, "test"))),
+                stale_marker(test_file),
+                failing_marker(test_file.recently_failing),
+            ));
+            let intents: Vec<String> = test_file.test_intents.iter().map(intent_line).collect();
+            out.push_str(&tree(&intents, "  ", paint));
+        }
+        if let Some(hint) = &info.coverage_hint {
+            out.push_str(&format!("  {}\n", paint.dim(hint)));
+        }
+    }
+
+    out
+}
+
+/// Render notes grouped by the file they are about, in the order listed.
+pub fn render_notes(memories: &[Memory], color: bool) -> String {
+    let paint = Paint { color };
+    if memories.is_empty() {
+        return "No notes.\n".to_string();
+    }
+
+    let mut out = String::new();
+    let mut current: Option<&str> = None;
+    let mut group: Vec<String> = Vec::new();
+    for memory in memories {
+        if current != Some(memory.file_path.as_str()) {
+            if let Some(path) = current {
+                out.push_str(&format!("{}\n{}", paint.bold(path), tree(&group, "", paint)));
+                group.clear();
+            }
+            current = Some(&memory.file_path);
+        }
+        let mut about = Vec::new();
+        if let Some(symbol) = &memory.symbol_name {
+            about.push(symbol.clone());
+        }
+        if let Some(author) = memory.author_name.as_ref().or(memory.author_email.as_ref()) {
+            about.push(author.clone());
+        }
+        if !memory.created_at.is_empty() {
+            about.push(memory.created_at.clone());
+        }
+        let about = if about.is_empty() { String::new() } else { format!(" ({})", about.join(", ")) };
+        group.push(format!("{}{}", memory.content, paint.dim(&about)));
+    }
+    if let Some(path) = current {
+        out.push_str(&format!("{}\n{}", paint.bold(path), tree(&group, "", paint)));
+    }
+    out
+}
+
+/// Render the tests to run: each test with why it was picked, then the
+/// commands that run them.
+pub fn render_tests_to_run(response: &TestsToRunResponse, color: bool) -> String {
+    let paint = Paint { color };
+    let changed = plural(response.files.len() as u32, "changed file");
+    if response.tests.is_empty() {
+        return format!("No tests found for {changed}.\n");
+    }
+
+    let mut out = format!("{} for {changed}\n", paint.bold(&plural(response.tests.len() as u32, "test")));
+    for test in &response.tests {
+        out.push_str(&format!("  {} {}\n", test.path, paint.dim(&format!("({})", test.reasons.join(", ")))));
+    }
+    if !response.commands.is_empty() {
+        out.push_str(&format!("\n{}\n", paint.bold("Run")));
+        for command in &response.commands {
+            out.push_str(&format!("  {}\n", command.command));
+        }
+    }
+    out
+}
+
+/// Short flags after a coupled file's path.
+fn markers(file: &CoupledFile) -> String {
+    let mut out = String::new();
+    if let Some(via) = &file.via {
+        out.push_str(&format!(" via {via}"));
+    }
+    if file.emerging {
+        out.push_str(" (emerging)");
+    }
+    if file.static_link == Some(StaticLink::None) {
+        out.push_str(" (no static link)");
+    }
+    if file.cross_package == Some(true) {
+        out.push_str(" (other package)");
+    }
+    if file.upstream_commits > 0 {
+        out.push_str(" (changed upstream)");
+    }
+    if file.has_tests == Some(false) {
+        out.push_str(" (no tests)");
+    }
+    out.push_str(failing_marker(file.recently_failing));
+    out
+}
+
+/// The tree lines under a coupled file: line regions, notes, test intents.
+fn details(file: &CoupledFile, analyzed: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    if !file.regions.is_empty() {
+        let spans: Vec<String> = file
+            .regions
+            .iter()
+            .map(|r| {
+                if r.start_line == r.end_line {
+                    format!("line {}", r.start_line)
+                } else {
+                    format!("lines {}-{}", r.start_line, r.end_line)
+                }
+            })
+            .collect();
+        lines.push(format!("co-changes with {} of {analyzed}", spans.join(", ")));
+    }
+    for memory in &file.memories {
+        let stale = match &memory.drift {
+            Some(drift) if drift.commits_since > 0 => {
+                format!(" ({} since)", plural(drift.commits_since, "commit"))
+            }
+            _ => String::new(),
+        };
+        lines.push(format!("note: {}{stale}", memory.content));
+    }
+    for intent in &file.test_intents {
+        lines.push(format!("test: {}", intent_line(intent)));
+    }
+    lines
+}
+
+/// Draw `lines` as tree branches under `indent`.
+fn tree(lines: &[String], indent: &str, paint: Paint) -> String {
+    let mut out = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        let branch = if i + 1 == lines.len() { "└─ " } else { "├─ " };
+        out.push_str(&format!("{indent}{}{line}\n", paint.dim(branch)));
+    }
+    out
+}
+
+/// `n` followed by `noun`, pluralized with a trailing "s".
+fn plural(n: u32, noun: &str) -> String {
+    format!("{n} {noun}{}", if n == 1 { "" } else { "s" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{TestCommand, TestIntent, TestToRun};
+
+    fn note(file_path: &str, content: &str) -> Memory {
+        Memory {
+            id: 1,
+            file_path: file_path.to_string(),
+            symbol_name: None,
+            content: content.to_string(),
+            created_at: String::new(),
+            author_name: None,
+            author_email: None,
+            head_commit: None,
+            blob_oid: None,
+            drift: None,
+            global: false,
+        }
+    }
+
+    #[test]
+    fn test_renders_analysis_rows_and_details() {
+        let response = AnalysisResponse {
+            file_path: "src/Auth.ts".to_string(),
+            repo_root: "/repo".to_string(),
+            coupled_files: vec![
+                CoupledFile {
+                    path: "src/Session.ts".to_string(),
+                    risk_score: 0.85,
+                    co_change_count: 8,
+                    memories: vec![note("src/Session.ts", "Token refresh is fragile")],
+                    test_intents: vec![TestIntent { title: "refreshes tokens".to_string(), ..Default::default() }],
+                    ..Default::default()
+                },
+                CoupledFile {
+                    path: "src/util.ts".to_string(),
+                    risk_score: 0.4,
+                    co_change_count: 3,
+                    emerging: true,
+                    ..Default::default()
+                },
+            ],
+            commit_count: 10,
+            analysis_time_ms: 5,
+            ..Default::default()
+        };
+
+        let plain = render_analysis(&response, false);
+        assert!(plain.starts_with("src/Auth.ts\n2 files coupled (1 critical, 1 medium) across 10 commits.\n"));
+        assert!(plain.contains("\nCritical   0.85        8/10  src/Session.ts\n"));
+        assert!(plain.contains("          ├─ note: Token refresh is fragile\n          └─ test: refreshes tokens\n"));
+        assert!(plain.contains("\nMedium     0.40        3/10  src/util.ts (emerging)\n"));
+        assert!(!plain.contains('\x1b'));
+
+        let colored = render_analysis(&response, true);
+        assert!(colored.contains("\x1b[1;31mCritical\x1b[0m"));
+    }
+
+    #[test]
+    fn test_renders_notes_grouped_by_file() {
+        let mut authored = note("src/a.rs", "Keep in sync with b");
+        authored.author_name = Some("Ada".to_string());
+        let notes = vec![authored, note("src/a.rs", "Hot path"), note("src/b.rs", "Generated")];
+
+        assert_eq!(
+            render_notes(&notes, false),
+            "src/a.rs\n├─ Keep in sync with b (Ada)\n└─ Hot path\nsrc/b.rs\n└─ Generated\n"
+        );
+        assert_eq!(render_notes(&[], false), "No notes.\n");
+    }
+
+    #[test]
+    fn test_renders_tests_to_run() {
+        let response = TestsToRunResponse {
+            repo_root: "/repo".to_string(),
+            files: vec!["src/auth.ts".to_string()],
+            tests: vec![TestToRun {
+                path: "src/auth.test.ts".to_string(),
+                reasons: vec!["convention".to_string(), "coupled".to_string()],
+                sources: vec!["src/auth.ts".to_string()],
+            }],
+            commands: vec![TestCommand {
+                framework: "jest".to_string(),
+                command: "npx jest src/auth.test.ts".to_string(),
+                tests: vec!["src/auth.test.ts".to_string()],
+            }],
+        };
+
+        assert_eq!(
+            render_tests_to_run(&response, false),
+            "1 test for 1 changed file\n  src/auth.test.ts (convention, coupled)\n\nRun\n  npx jest src/auth.test.ts\n"
+        );
+    }
+}
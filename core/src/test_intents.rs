@@ -1,17 +1,41 @@
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::sync::LazyLock;
+use std::time::{Duration, Instant};
 
-use regex::Regex;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use regex::{Captures, Regex};
 
+use crate::config::{IntentSelection, TestPatternsConfig};
+use crate::import_graph;
+use crate::sandbox;
+use crate::symbols;
+use crate::test_runners;
 use crate::types::{CoupledFile, DiscoveredTestFile, TestInfo, TestIntent};
 
 const MAX_INTENTS_PER_FILE: usize = 5;
 
+/// Most test files collected from the test runners' roots and globs.
+const MAX_RUNNER_TESTS: usize = 5000;
+
+/// Time allowed for discovering and reading the analyzed file's tests
+/// during `analyze`, on top of the indexing budget.
+pub const TEST_DISCOVERY_BUDGET: Duration = Duration::from_millis(300);
+
 // Compiled regexes for test title extraction
 static JS_TEST_RE: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r#"(?:^|\s)(?:it|test)\(\s*(?:'([^']*)'|"([^"]*)"|`([^`]*)`)"#).unwrap()
 });
 
+/// A `describe`/`context`/`suite` block (optionally `.only`/`.skip`) and
+/// its title.
+static JS_GROUP_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?:^|\s)(?:describe|context|suite)(?:\.(?:only|skip))?\(\s*(?:'([^']*)'|"([^"]*)"|`([^`]*)`)"#,
+    )
+    .unwrap()
+});
+
 static RUST_TEST_RE: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"#\[test\]\s*(?:\n\s*)*fn\s+(\w+)").unwrap()
 });
@@ -24,18 +48,118 @@ static GO_TEST_RE: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"func\s+(Test\w+)\s*\(").unwrap()
 });
 
+/// A Go subtest with a literal name: `t.Run("name", ...)`.
+static GO_SUBTEST_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"\b\w+\.Run\(\s*(?:"([^"]*)"|`([^`]*)`)"#).unwrap()
+});
+
+/// A Go subtest named by a variable, as in table-driven tests:
+/// `t.Run(tc.name, ...)`.
+static GO_DYNAMIC_SUBTEST_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"\b\w+\.Run\(\s*[^"`\s)]"#).unwrap()
+});
+
+/// The table a Go test ranges over: `for _, tc := range tests {`.
+static GO_RANGE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\bfor\s+[^{\n]*\brange\s+(\w+)\s*\{").unwrap()
+});
+
+/// Jest/Vitest `it.each`/`test.each` with an array table (`(`) or a
+/// tagged-template table (`` ` ``).
+static JS_EACH_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:^|\s)(?:it|test)(?:\.(?:only|skip|concurrent))?\.each\s*([(`])").unwrap()
+});
+
+/// The title call following an `.each` table: `('adds %i', ...)`.
+static JS_EACH_TITLE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"^\s*\(\s*(?:'([^']*)'|"([^"]*)"|`([^`]*)`)"#).unwrap()
+});
+
+static PYTHON_PARAMETRIZE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*@(?:pytest\.)?mark\.parametrize\s*\(").unwrap()
+});
+
+/// One line of a Python test file: group 1 is the indentation, then a
+/// class (2) or a test function (3).
+static PYTHON_LINE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^([ \t]*)(?:class\s+(\w+)|(?:async\s+)?def\s+(test_\w+)\s*\()").unwrap()
+});
+
+/// A `void` method with the annotations directly above it: group 1 is the
+/// annotation run (possibly empty), group 2 the method name.
 static JAVA_TEST_RE: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(r#"(?:@DisplayName\(\s*"([^"]*)"\s*\)|void\s+((?:test|should)\w+)\s*\()"#).unwrap()
+    Regex::new(
+        r#"((?:@\w+(?:\s*\((?:[^()"]|"[^"]*")*\))?\s+)*)(?:(?:public|protected|private|static|final)\s+)*void\s+(\w+)\s*\("#,
+    )
+    .unwrap()
+});
+
+static JAVA_ANNOTATION_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"@(\w+)").unwrap());
+
+static JAVA_DISPLAY_NAME_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"@DisplayName\(\s*"([^"]*)"\s*\)"#).unwrap()
+});
+
+/// JUnit 4/5 annotations that mark a method as a test.
+const JAVA_TEST_ANNOTATIONS: [&str; 5] =
+    ["Test", "ParameterizedTest", "RepeatedTest", "TestFactory", "TestTemplate"];
+
+/// An xUnit/NUnit test attribute followed by its method: group 1 is the
+/// attribute arguments, group 2 the method name.
+static CSHARP_TEST_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"\[\s*(?:Fact|Theory|Test|TestCase|TestCaseSource)\b(?:\s*\(((?:[^()"]|"[^"]*")*)\))?\s*\](?:\s*\[[^\]]*\])*\s*(?:(?:public|private|protected|internal|static|async|virtual|override)\s+)*[\w<>]+\s+(\w+)\s*\("#,
+    )
+    .unwrap()
+});
+
+static CSHARP_DISPLAY_NAME_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"DisplayName\s*=\s*"([^"]*)""#).unwrap()
 });
 
+/// Kotest string specs (`"title" {`, group 1) or JUnit test functions,
+/// named with backticks (group 2) or a plain identifier (group 3).
 static KOTLIN_TEST_RE: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(r#""([^"]*)"\s*\{"#).unwrap()
+    Regex::new(
+        r#""([^"]*)"\s*\{|@(?:Test|ParameterizedTest|RepeatedTest)\b(?:\s*@\w+(?:\([^)]*\))?)*\s+(?:(?:public|internal|open)\s+)?fun\s+(?:`([^`]+)`|(\w+))\s*\("#,
+    )
+    .unwrap()
 });
 
 static SCALA_TEST_RE: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r#""([^"]*)"\s*in\s*\{"#).unwrap()
 });
 
+/// One line of an RSpec/Minitest file: group 1 is the indentation; then
+/// either a `describe`/`context` block (title in 2-4), an example
+/// `it`/`specify`/`test` (title in 5-6), or a Minitest `def test_*` (7).
+static RUBY_TEST_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?m)^([ \t]*)(?:(?:RSpec\.)?(?:describe|context)\s*\(?\s*(?:"([^"]*)"|'([^']*)'|([A-Z][\w:]*))|(?:it|specify|scenario|test)\s*\(?\s*(?:"([^"]*)"|'([^']*)')|def\s+(test_\w+))"#,
+    )
+    .unwrap()
+});
+
+/// GoogleTest `TEST(Suite, Name)`-style macros (groups 1-2) or Catch2
+/// `TEST_CASE("title")` / `SCENARIO("title")` (group 3).
+static CPP_TEST_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"\b(?:TEST|TEST_F|TEST_P|TYPED_TEST|TYPED_TEST_P)\s*\(\s*(\w+)\s*,\s*(\w+)\s*\)|\b(?:TEST_CASE|SCENARIO)\s*\(\s*"([^"]*)""#,
+    )
+    .unwrap()
+});
+
+/// A PHP method with the attributes directly above it: group 1 is the
+/// attribute run (possibly empty), group 2 the modifiers, group 3 the name.
+static PHP_TEST_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"((?:#\[[^\]]*\]\s*)*)((?:(?:public|protected|private|static|final|abstract)\s+)*)function\s+(\w+)\s*\(",
+    )
+    .unwrap()
+});
+
+static PHP_ATTRIBUTE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"#\[\s*\\?([\w\\]+)").unwrap());
+
 /// Language classification for test regex selection.
 enum TestLang {
     JsTs,
@@ -43,10 +167,20 @@ enum TestLang {
     Python,
     Go,
     Java,
+    CSharp,
     Kotlin,
     Scala,
+    Ruby,
+    Cpp,
+    Php,
 }
 
+const CPP_EXTENSIONS: [&str; 3] = [".cc", ".cpp", ".cxx"];
+
+/// Joins a test title to the titles of its enclosing groups
+/// (`Auth > token refresh > should rotate keys`).
+pub const TITLE_SEPARATOR: &str = " > ";
+
 /// Select the appropriate test language and regex for a file path.
 fn detect_test_language(path: &str) -> Option<(TestLang, &'static Regex)> {
     let filename = Path::new(path)
@@ -60,18 +194,26 @@ fn detect_test_language(path: &str) -> Option<(TestLang, &'static Regex)> {
         || filename.ends_with(".jsx")
     {
         Some((TestLang::JsTs, &JS_TEST_RE))
-    } else if filename.ends_with(".rs") || path.contains("/tests/") {
-        Some((TestLang::Rust, &RUST_TEST_RE))
     } else if filename.ends_with(".py") {
         Some((TestLang::Python, &PYTHON_TEST_RE))
     } else if filename.ends_with(".go") {
         Some((TestLang::Go, &GO_TEST_RE))
     } else if filename.ends_with(".java") {
         Some((TestLang::Java, &JAVA_TEST_RE))
+    } else if filename.ends_with(".cs") {
+        Some((TestLang::CSharp, &CSHARP_TEST_RE))
     } else if filename.ends_with(".kt") {
         Some((TestLang::Kotlin, &KOTLIN_TEST_RE))
     } else if filename.ends_with(".scala") {
         Some((TestLang::Scala, &SCALA_TEST_RE))
+    } else if filename.ends_with(".rb") {
+        Some((TestLang::Ruby, &RUBY_TEST_RE))
+    } else if CPP_EXTENSIONS.iter().any(|ext| filename.ends_with(ext)) {
+        Some((TestLang::Cpp, &CPP_TEST_RE))
+    } else if filename.ends_with(".php") {
+        Some((TestLang::Php, &PHP_TEST_RE))
+    } else if filename.ends_with(".rs") || path.contains("/tests/") {
+        Some((TestLang::Rust, &RUST_TEST_RE))
     } else {
         None
     }
@@ -117,6 +259,36 @@ pub fn is_test_file(path: &str) -> bool {
         return true;
     }
 
+    // Kotlin: anything under a Gradle test source set
+    if filename.ends_with(".kt") && path.contains("src/test/kotlin/") {
+        return true;
+    }
+
+    // Ruby: RSpec *_spec.rb, Minitest *_test.rb / test_*.rb
+    if filename.ends_with("_spec.rb")
+        || filename.ends_with("_test.rb")
+        || (filename.starts_with("test_") && filename.ends_with(".rb"))
+    {
+        return true;
+    }
+
+    // C/C++: *_test.cc, *_unittest.cc, *_test.cpp, test_*.cpp (and .cxx)
+    if let Some(stem) = CPP_EXTENSIONS.iter().find_map(|ext| filename.strip_suffix(ext))
+        && (stem.ends_with("_test") || stem.ends_with("_unittest") || stem.starts_with("test_"))
+    {
+        return true;
+    }
+
+    // C#: *Tests.cs, *Test.cs
+    if filename.ends_with("Tests.cs") || filename.ends_with("Test.cs") {
+        return true;
+    }
+
+    // PHP: PHPUnit *Test.php
+    if filename.ends_with("Test.php") {
+        return true;
+    }
+
     // JS/TS: files inside a __tests__/ directory
     if path.contains("__tests__/")
         && (filename.ends_with(".ts")
@@ -137,7 +309,7 @@ pub fn is_test_file(path: &str) -> bool {
 
 /// Humanize a snake_case or camelCase test name by stripping the "test_"/"Test" prefix
 /// and replacing underscores with spaces.
-fn humanize(name: &str) -> String {
+pub(crate) fn humanize(name: &str) -> String {
     let stripped = name
         .strip_prefix("test_")
         .or_else(|| name.strip_prefix("test"))
@@ -160,57 +332,728 @@ fn humanize(name: &str) -> String {
     }
 }
 
-/// Extract test intent titles from file content using regex.
-/// Returns at most `MAX_INTENTS_PER_FILE` results.
-pub fn extract_test_intents(content: &str, path: &str) -> Vec<TestIntent> {
-    let Some((lang, re)) = detect_test_language(path) else {
-        return Vec::new();
+/// Title of a Java test method: its `@DisplayName` if present, otherwise
+/// the humanized method name. Methods are tests if they carry a JUnit test
+/// annotation, or follow the JUnit 3 `test*` naming convention.
+fn java_test_title(cap: &Captures) -> Option<String> {
+    let annotations = cap.get(1).map_or("", |m| m.as_str());
+    let name = cap.get(2)?.as_str();
+    let annotated = JAVA_ANNOTATION_RE
+        .captures_iter(annotations)
+        .any(|a| JAVA_TEST_ANNOTATIONS.contains(&&a[1]));
+    if !annotated && !name.starts_with("test") {
+        return None;
+    }
+    Some(match JAVA_DISPLAY_NAME_RE.captures(annotations) {
+        Some(display) => display[1].to_string(),
+        None => humanize(name),
+    })
+}
+
+/// Title of a C# test method: the `DisplayName` attribute argument if
+/// present, otherwise the method name with each `_`-separated segment
+/// humanized (`Login_WithBadPassword_Fails` -> "login with bad password fails").
+fn csharp_test_title(cap: &Captures) -> Option<String> {
+    if let Some(display) = cap.get(1).and_then(|args| CSHARP_DISPLAY_NAME_RE.captures(args.as_str())) {
+        return Some(display[1].to_string());
+    }
+    let name = cap.get(2)?.as_str();
+    let parts: Vec<String> = name.split('_').filter(|p| !p.is_empty()).map(humanize).collect();
+    Some(parts.join(" "))
+}
+
+/// Title of a PHPUnit test method: the humanized method name. Public methods
+/// are tests if they carry a `#[Test]` attribute or are named `test*`.
+fn php_test_title(cap: &Captures) -> Option<String> {
+    let modifiers = cap.get(2).map_or("", |m| m.as_str());
+    if modifiers.contains("private") || modifiers.contains("protected") {
+        return None;
+    }
+    let name = cap.get(3)?.as_str();
+    let annotated = PHP_ATTRIBUTE_RE
+        .captures_iter(cap.get(1).map_or("", |m| m.as_str()))
+        .any(|a| a[1].rsplit('\\').next() == Some("Test"));
+    (annotated || name.starts_with("test")).then(|| humanize(name))
+}
+
+/// Index of the bracket closing the `(`, `[` or `{` at `open`, skipping
+/// string literals and `//` comments. Unbalanced input runs to the end.
+fn matching_close(content: &str, open: usize) -> usize {
+    let bytes = content.as_bytes();
+    let (open_b, close_b) = match bytes.get(open) {
+        Some(b'(') => (b'(', b')'),
+        Some(b'[') => (b'[', b']'),
+        Some(b'{') => (b'{', b'}'),
+        _ => return open,
     };
+    let mut depth = 0usize;
+    let mut i = open;
+    while i < bytes.len() {
+        match bytes[i] {
+            quote @ (b'"' | b'\'' | b'`') => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    if bytes[i] == b'\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b if b == open_b => depth += 1,
+            b if b == close_b => {
+                depth -= 1;
+                if depth == 0 {
+                    return i;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    bytes.len()
+}
+
+/// Split `s` on commas outside brackets and string literals, dropping
+/// empty items (trailing commas).
+fn split_top_level(s: &str) -> Vec<&str> {
+    let bytes = s.as_bytes();
+    let mut items = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'(' | b'[' | b'{' | b'"' | b'\'' | b'`' => {
+                i = match bytes[i] {
+                    quote @ (b'"' | b'\'' | b'`') => {
+                        let mut j = i + 1;
+                        while j < bytes.len() && bytes[j] != quote {
+                            if bytes[j] == b'\\' {
+                                j += 1;
+                            }
+                            j += 1;
+                        }
+                        j
+                    }
+                    _ => matching_close(s, i),
+                };
+            }
+            b',' => {
+                items.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    items.push(&s[start.min(s.len())..]);
+    items.into_iter().map(str::trim).filter(|item| !item.is_empty()).collect()
+}
+
+/// Number of elements in the list, tuple or composite literal that `s`
+/// starts with, or `None` if `s` is not a literal (e.g. a variable).
+fn literal_len(s: &str) -> Option<u32> {
+    let s = s.trim_start();
+    if !s.starts_with(['[', '(', '{']) {
+        return None;
+    }
+    let close = matching_close(s, 0);
+    Some(split_top_level(s.get(1..close)?).len() as u32)
+}
 
-    let mut intents: Vec<TestIntent> = Vec::new();
+/// Case count of a Jest/Vitest `.each` table starting at `table` (just
+/// after `each(` or ``each` ``), and the offset just past the table.
+fn js_each_table(content: &str, table: usize, template: bool) -> (u32, usize) {
+    if template {
+        let end = content[table..].find('`').map_or(content.len(), |i| table + i);
+        // First row is the column header
+        let rows = content[table..end].lines().filter(|l| !l.trim().is_empty()).count();
+        return (rows.saturating_sub(1) as u32, end + 1);
+    }
+    let close = matching_close(content, table - 1);
+    let cases = literal_len(&content[table..close]).unwrap_or(0);
+    (cases, close + 1)
+}
+
+/// JS/TS `it`/`test` titles prefixed with their enclosing `describe`
+/// titles, with the case count of `.each` tests. A test belongs to a group
+/// if it sits inside the group's call parentheses.
+fn js_test_titles(content: &str) -> Vec<(String, u32)> {
+    let groups: Vec<(usize, usize, &str)> = JS_GROUP_RE
+        .captures_iter(content)
+        .filter_map(|cap| {
+            let whole = cap.get(0)?;
+            let open = whole.start() + whole.as_str().find('(')?;
+            let title = cap.get(1).or_else(|| cap.get(2)).or_else(|| cap.get(3))?.as_str();
+            Some((open, matching_close(content, open), title))
+        })
+        .collect();
+
+    let mut tests: Vec<(usize, &str, u32)> = JS_TEST_RE
+        .captures_iter(content)
+        .filter_map(|cap| {
+            let leaf = cap.get(1).or_else(|| cap.get(2)).or_else(|| cap.get(3))?.as_str();
+            Some((cap.get(0)?.start(), leaf, 0))
+        })
+        .collect();
+    for cap in JS_EACH_RE.captures_iter(content) {
+        let (Some(whole), Some(kind)) = (cap.get(0), cap.get(1)) else {
+            continue;
+        };
+        let (cases, end) = js_each_table(content, kind.end(), kind.as_str() == "`");
+        let Some(title) = content.get(end..).and_then(|rest| JS_EACH_TITLE_RE.captures(rest)) else {
+            continue;
+        };
+        if let Some(leaf) = title.get(1).or_else(|| title.get(2)).or_else(|| title.get(3)) {
+            tests.push((whole.start(), leaf.as_str(), cases));
+        }
+    }
+    tests.sort_by_key(|(pos, _, _)| *pos);
+
+    tests
+        .into_iter()
+        .map(|(pos, leaf, cases)| {
+            let mut parts: Vec<&str> = groups
+                .iter()
+                .filter(|(open, close, _)| *open < pos && pos < *close)
+                .map(|(_, _, title)| *title)
+                .collect();
+            parts.push(leaf);
+            (parts.join(TITLE_SEPARATOR), cases)
+        })
+        .collect()
+}
 
-    for cap in re.captures_iter(content) {
-        let title = match lang {
-            // JS/TS, Kotlin, Scala use string-based descriptions
-            TestLang::JsTs | TestLang::Kotlin | TestLang::Scala => {
+/// Case count of a `@pytest.mark.parametrize(...)` decorator whose
+/// argument list opens at `open`: the length of its literal `argvalues`.
+fn pytest_parametrize_cases(content: &str, open: usize) -> Option<u32> {
+    let close = matching_close(content, open);
+    let args = split_top_level(content.get(open + 1..close)?);
+    let values = args
+        .iter()
+        .find_map(|arg| arg.strip_prefix("argvalues").map(|v| v.trim_start().trim_start_matches('=')))
+        .or_else(|| args.get(1).copied())?;
+    literal_len(values)
+}
+
+/// Python test functions, prefixed with their enclosing test classes
+/// (`TestTokenRefresh.test_rotates_keys` -> "token refresh > rotates keys"),
+/// with the case count of `@pytest.mark.parametrize` tests (stacked
+/// decorators multiply). Nesting is inferred from indentation.
+fn python_test_titles(content: &str) -> Vec<(String, u32)> {
+    let mut classes: Vec<(usize, String)> = Vec::new();
+    let mut titles = Vec::new();
+    let mut parametrized: Option<u32> = None;
+    let mut offset = 0;
+
+    for line in content.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+        let line = line.trim_end_matches(['\n', '\r']);
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(m) = PYTHON_PARAMETRIZE_RE.find(line) {
+            let cases = pytest_parametrize_cases(content, line_start + m.end() - 1).unwrap_or(1);
+            parametrized = Some(parametrized.unwrap_or(1) * cases);
+            continue;
+        }
+        let indent = line.len() - line.trim_start().len();
+        while classes.last().is_some_and(|(i, _)| *i >= indent) {
+            classes.pop();
+        }
+        let Some(cap) = PYTHON_LINE_RE.captures(line) else {
+            // Decorators of a helper do not carry over to the next test
+            let code = line.trim_start();
+            if code.starts_with("def ") || code.starts_with("async def ") {
+                parametrized = None;
+            }
+            continue;
+        };
+        if let Some(class) = cap.get(2) {
+            classes.push((indent, humanize(class.as_str())));
+            parametrized = None;
+        } else if let Some(func) = cap.get(3) {
+            let mut parts: Vec<&str> = classes.iter().map(|(_, c)| c.as_str()).collect();
+            let leaf = humanize(func.as_str());
+            parts.push(&leaf);
+            titles.push((parts.join(TITLE_SEPARATOR), parametrized.take().unwrap_or(0)));
+        }
+    }
+
+    titles
+}
+
+/// Case count of a table-driven Go test `body`: the number of entries in
+/// the slice or map literal it ranges over while calling `t.Run` with a
+/// variable name. The table may be declared in the test or at package
+/// level in `content`.
+fn go_table_cases(content: &str, body: &str) -> u32 {
+    if !GO_DYNAMIC_SUBTEST_RE.is_match(body) {
+        return 0;
+    }
+    let Some(table) = GO_RANGE_RE.captures(body).map(|cap| cap[1].to_string()) else {
+        return 0;
+    };
+    let Ok(decl) = Regex::new(&format!(r"\b{table}\s*(?::=|=)\s*(?:\[\]|map\[)")) else {
+        return 0;
+    };
+    let found = decl
+        .find(body)
+        .map(|m| (body, m.end()))
+        .or_else(|| decl.find(content).map(|m| (content, m.end())));
+    let Some((src, type_start)) = found else {
+        return 0;
+    };
+
+    // Skip an inline `struct { ... }` element type to reach the literal
+    let Some(mut open) = src[type_start..].find('{').map(|i| type_start + i) else {
+        return 0;
+    };
+    if src[type_start..open].trim_end().ends_with("struct") {
+        let after = matching_close(src, open) + 1;
+        let Some(next) = src.get(after..).and_then(|rest| rest.find('{')) else {
+            return 0;
+        };
+        open = after + next;
+    }
+    literal_len(&src[open..]).unwrap_or(0)
+}
+
+/// Go test titles. A test function with literal-named `t.Run` subtests
+/// yields one title per innermost subtest (`auth > rotates keys`);
+/// otherwise the function itself is the test, with the case count of its
+/// table if it is table-driven.
+fn go_test_titles(content: &str) -> Vec<(String, u32)> {
+    let mut titles = Vec::new();
+    for cap in GO_TEST_RE.captures_iter(content) {
+        let name = humanize(&cap[1]);
+        let Some(body_open) = content[cap.get(0).map_or(0, |m| m.end())..]
+            .find('{')
+            .map(|i| i + cap.get(0).map_or(0, |m| m.end()))
+        else {
+            titles.push((name, 0));
+            continue;
+        };
+        let body_close = matching_close(content, body_open);
+        let body = &content[body_open..body_close];
+
+        let subtests: Vec<(usize, usize, &str)> = GO_SUBTEST_RE
+            .captures_iter(body)
+            .filter_map(|sub| {
+                let whole = sub.get(0)?;
+                let open = whole.start() + whole.as_str().find('(')?;
+                let title = sub.get(1).or_else(|| sub.get(2))?.as_str();
+                Some((open, matching_close(body, open), title))
+            })
+            .collect();
+        if subtests.is_empty() {
+            titles.push((name, go_table_cases(content, body)));
+            continue;
+        }
+
+        for (open, close, title) in &subtests {
+            let is_leaf = !subtests.iter().any(|(o, _, _)| open < o && o < close);
+            if !is_leaf {
+                continue;
+            }
+            let mut parts = vec![name.as_str()];
+            parts.extend(
+                subtests
+                    .iter()
+                    .filter(|(o, c, _)| o < open && open < c)
+                    .map(|(_, _, t)| *t),
+            );
+            parts.push(title);
+            titles.push((parts.join(TITLE_SEPARATOR), 0));
+        }
+    }
+    titles
+}
+
+/// Ruby example titles, prefixed with their enclosing `describe`/`context`
+/// titles (`Cart > #total > with discounts > applies percentage`). Nesting
+/// is inferred from indentation.
+fn ruby_test_titles(content: &str) -> Vec<(String, u32)> {
+    let mut groups: Vec<(usize, String)> = Vec::new();
+    let mut titles = Vec::new();
+
+    for cap in RUBY_TEST_RE.captures_iter(content) {
+        let indent = cap[1].len();
+        while groups.last().is_some_and(|(i, _)| *i >= indent) {
+            groups.pop();
+        }
+
+        if let Some(group) = cap.get(2).or_else(|| cap.get(3)).or_else(|| cap.get(4)) {
+            groups.push((indent, group.as_str().to_string()));
+        } else if let Some(example) = cap.get(5).or_else(|| cap.get(6)) {
+            let mut parts: Vec<&str> = groups.iter().map(|(_, g)| g.as_str()).collect();
+            parts.push(example.as_str());
+            titles.push((parts.join(TITLE_SEPARATOR), 0));
+        } else if let Some(method) = cap.get(7) {
+            titles.push((humanize(method.as_str()), 0));
+        }
+    }
+
+    titles
+}
+
+/// Every test title in `content`, in source order, with its parameterized
+/// case count (0 for a plain test).
+fn test_titles(content: &str, path: &str) -> Vec<(String, u32)> {
+    let Some((lang, re)) = detect_test_language(path) else {
+        return Vec::new();
+    };
+    match lang {
+        TestLang::Ruby => return ruby_test_titles(content),
+        TestLang::JsTs => return js_test_titles(content),
+        TestLang::Python => return python_test_titles(content),
+        TestLang::Go => return go_test_titles(content),
+        _ => {}
+    }
+
+    re.captures_iter(content)
+        .filter_map(|cap| match lang {
+            // Kotlin: spec strings and backtick names are used as-is,
+            // plain function names are humanized
+            TestLang::Kotlin => cap
+                .get(1)
+                .or_else(|| cap.get(2))
+                .map(|m| m.as_str().to_string())
+                .or_else(|| cap.get(3).map(|m| humanize(m.as_str()))),
+            // JS/TS, Scala use string-based descriptions
+            TestLang::JsTs | TestLang::Scala => {
                 cap.get(1).or_else(|| cap.get(2)).or_else(|| cap.get(3)).map(|m| m.as_str().to_string())
             },
             // Java uses @DisplayName (string) or method name (needs humanize)
-            TestLang::Java => {
-                cap.get(1).map(|m| m.as_str().to_string())
-                    .or_else(|| cap.get(2).map(|m| humanize(m.as_str())))
-            },
+            TestLang::Java => java_test_title(&cap),
+            TestLang::CSharp => csharp_test_title(&cap),
+            TestLang::Php => php_test_title(&cap),
+            // GoogleTest: "Suite: humanized name"; Catch2: the title string
+            TestLang::Cpp => cap
+                .get(3)
+                .map(|m| m.as_str().to_string())
+                .or_else(|| Some(format!("{}: {}", cap.get(1)?.as_str(), humanize(cap.get(2)?.as_str())))),
             // All other languages use group 1 with humanized names
             _ => cap.get(1).map(|m| humanize(m.as_str())),
+        })
+        .map(|title| (title, 0))
+        .collect()
+}
+
+/// Extract test intent titles from file content using regex.
+/// Returns the first `MAX_INTENTS_PER_FILE` results.
+pub fn extract_test_intents(content: &str, path: &str) -> Vec<TestIntent> {
+    TestPatterns::default().extract_test_intents(content, path)
+}
+
+/// Built-in test conventions plus the extra ones from the repo config
+/// (`test_patterns`), and how many intents to take from each test file.
+#[derive(Debug, Clone)]
+pub struct TestPatterns {
+    globs: Option<GlobSet>,
+    /// Extension (no leading dot) -> title regexes
+    titles: HashMap<String, Vec<Regex>>,
+    /// Also discover tests through their imports (`TestDiscovery::Imports`)
+    scan_imports: bool,
+    max_intents: usize,
+    selection: IntentSelection,
+    /// Symbols of the analyzed file, for `IntentSelection::Symbols`
+    target_symbols: Vec<String>,
+    /// Test file globs from the repo's test runner config
+    runner_globs: Option<GlobSet>,
+    /// Test files under the test runners' roots, with their directory
+    /// relative to the root they were found in
+    runner_tests: Vec<(String, String)>,
+}
+
+impl Default for TestPatterns {
+    fn default() -> Self {
+        Self {
+            globs: None,
+            titles: HashMap::new(),
+            scan_imports: false,
+            max_intents: MAX_INTENTS_PER_FILE,
+            selection: IntentSelection::First,
+            target_symbols: Vec::new(),
+            runner_globs: None,
+            runner_tests: Vec::new(),
+        }
+    }
+}
+
+impl TestPatterns {
+    /// Compile the configured patterns. Invalid globs and regexes are
+    /// reported on stderr and skipped.
+    pub fn from_config(config: &TestPatternsConfig) -> Self {
+        let mut builder = GlobSetBuilder::new();
+        for glob in &config.globs {
+            match GlobBuilder::new(glob).literal_separator(true).build() {
+                Ok(glob) => {
+                    builder.add(glob);
+                }
+                Err(e) => eprintln!("Warning: skipping test glob: {e}"),
+            }
+        }
+        let globs = match builder.build() {
+            Ok(set) if !set.is_empty() => Some(set),
+            Ok(_) => None,
+            Err(e) => {
+                eprintln!("Warning: ignoring test globs: {e}");
+                None
+            }
         };
-        if let Some(t) = title {
-            intents.push(TestIntent { title: t });
-            if intents.len() >= MAX_INTENTS_PER_FILE {
-                break;
+
+        let mut titles: HashMap<String, Vec<Regex>> = HashMap::new();
+        for (ext, patterns) in &config.titles {
+            for pattern in patterns {
+                match Regex::new(pattern) {
+                    Ok(re) => titles.entry(ext.trim_start_matches('.').to_string()).or_default().push(re),
+                    Err(e) => eprintln!("Warning: skipping test title pattern for .{ext}: {e}"),
+                }
             }
         }
+
+        Self { globs, titles, ..Self::default() }
+    }
+
+    /// Also find the analyzed file's tests by scanning test files' imports.
+    pub fn with_import_scan(mut self, scan_imports: bool) -> Self {
+        self.scan_imports = scan_imports;
+        self
     }
 
-    intents
+    /// Take at most `max_intents` intents per test file, chosen by
+    /// `selection`.
+    pub fn with_intent_selection(mut self, max_intents: usize, selection: IntentSelection) -> Self {
+        self.max_intents = max_intents;
+        self.selection = selection;
+        self
+    }
+
+    /// Symbols of the analyzed file, preferred by `IntentSelection::Symbols`.
+    pub fn with_target_symbols(mut self, target_symbols: Vec<String>) -> Self {
+        self.target_symbols = target_symbols;
+        self
+    }
+
+    /// Also use the test roots and globs declared in the repo's Jest,
+    /// Vitest and pytest config (`test_runners::load`). Collects the test
+    /// files under those roots, or matching those globs anywhere when no
+    /// roots are declared, so `find_test_files` can pair them with sources.
+    /// Roots resolving outside the repo are skipped, and the walk stops at
+    /// `deadline` or `MAX_RUNNER_TESTS` files.
+    pub fn with_test_runners(mut self, repo_root: &Path, deadline: Instant) -> Self {
+        let conventions = test_runners::load(repo_root);
+        let mut builder = GlobSetBuilder::new();
+        for glob in &conventions.globs {
+            if let Ok(glob) = GlobBuilder::new(glob).literal_separator(true).build() {
+                builder.add(glob);
+            }
+        }
+        self.runner_globs = builder.build().ok().filter(|set| !set.is_empty());
+        if conventions.roots.is_empty() && self.runner_globs.is_none() {
+            return self;
+        }
+        let Ok(canonical_root) = repo_root.canonicalize() else {
+            return self;
+        };
+
+        let mut seen: HashSet<String> = self.runner_tests.iter().map(|(path, _)| path.clone()).collect();
+        let roots = if conventions.roots.is_empty() { vec![String::new()] } else { conventions.roots };
+        for root in roots {
+            let Some(root_dir) = sandbox::repo_dir(repo_root, &root) else {
+                continue;
+            };
+            for entry in ignore::WalkBuilder::new(root_dir).build().flatten() {
+                if self.runner_tests.len() >= MAX_RUNNER_TESTS || Instant::now() >= deadline {
+                    return self;
+                }
+                if !entry.file_type().is_some_and(|t| t.is_file()) {
+                    continue;
+                }
+                let Some(rel) = entry
+                    .path()
+                    .strip_prefix(&canonical_root)
+                    .ok()
+                    .and_then(|p| p.to_str())
+                    .map(|p| p.replace('\\', "/"))
+                else {
+                    continue;
+                };
+                if !self.is_test_file(&rel) || !seen.insert(rel.clone()) {
+                    continue;
+                }
+                let dir = Path::new(&rel)
+                    .strip_prefix(&root)
+                    .ok()
+                    .and_then(|p| p.parent())
+                    .and_then(|p| p.to_str())
+                    .unwrap_or("")
+                    .to_string();
+                self.runner_tests.push((rel, dir));
+            }
+        }
+        self
+    }
+
+    /// `is_test_file`, or a match for one of the configured or test
+    /// runner globs.
+    pub fn is_test_file(&self, path: &str) -> bool {
+        is_test_file(path)
+            || self.globs.as_ref().is_some_and(|globs| globs.is_match(path))
+            || self.runner_globs.as_ref().is_some_and(|globs| globs.is_match(path))
+    }
+
+    /// `find_test_files`, plus the test runners' test files named after
+    /// `source_path` (`spec/models/cart.spec.ts` for `src/models/cart.ts`)
+    /// whose directory within their root mirrors the end of the source's.
+    pub fn find_test_files(&self, repo_root: &Path, source_path: &str) -> Vec<String> {
+        if self.is_test_file(source_path) {
+            return Vec::new();
+        }
+        let mut found = find_test_files(repo_root, source_path);
+        let source = Path::new(source_path);
+        let name = source.file_name().and_then(|f| f.to_str()).unwrap_or("");
+        let parent = source.parent().unwrap_or(Path::new(""));
+        for (test, dir) in &self.runner_tests {
+            let test_name = Path::new(test).file_name().and_then(|f| f.to_str()).unwrap_or("");
+            if names_subject(test_name, name) && parent.ends_with(dir) && !found.contains(test) {
+                found.push(test.clone());
+            }
+        }
+        found
+    }
+
+    /// Built-in titles followed by those of the configured regexes for the
+    /// file's extension, in source order and without duplicates.
+    fn test_titles(&self, content: &str, path: &str) -> Vec<(String, u32)> {
+        let mut titles = test_titles(content, path);
+        let ext = Path::new(path).extension().and_then(|e| e.to_str()).unwrap_or("");
+        let Some(regexes) = self.titles.get(ext) else {
+            return titles;
+        };
+
+        let mut custom: Vec<(usize, String)> = regexes
+            .iter()
+            .flat_map(|re| re.captures_iter(content))
+            .filter_map(|cap| {
+                let m = cap.get(1).or_else(|| cap.get(0))?;
+                Some((m.start(), m.as_str().trim().to_string()))
+            })
+            .collect();
+        custom.sort();
+        for (_, title) in custom {
+            if !title.is_empty() && !titles.iter().any(|(t, _)| *t == title) {
+                titles.push((title, 0));
+            }
+        }
+        titles
+    }
+
+    /// `extract_test_intents` with the configured title regexes, cap and
+    /// selection. Selected intents stay in source order.
+    pub fn extract_test_intents(&self, content: &str, path: &str) -> Vec<TestIntent> {
+        let mut titles = self.test_titles(content, path);
+        if self.selection == IntentSelection::Symbols && titles.len() > self.max_intents {
+            let names_symbol =
+                |title: &str| !symbols::covered_symbols(title, &self.target_symbols).is_empty();
+            let mut ranked: Vec<(bool, usize)> =
+                titles.iter().enumerate().map(|(i, (title, _))| (!names_symbol(title), i)).collect();
+            ranked.sort();
+            let mut keep: Vec<usize> = ranked.into_iter().take(self.max_intents).map(|(_, i)| i).collect();
+            keep.sort();
+            titles = keep.into_iter().map(|i| std::mem::take(&mut titles[i])).collect();
+        }
+        titles
+            .into_iter()
+            .take(self.max_intents)
+            .map(|(title, cases)| TestIntent { title, cases, ..Default::default() })
+            .collect()
+    }
+
+    /// Tests for `source_path`: by naming convention and the test runners'
+    /// config (`find_test_files`) and, with `scan_imports`, the test files
+    /// importing it. `false` when the import scan stopped at `deadline`, so
+    /// there may be more.
+    pub fn discover_test_files(&self, repo_root: &Path, source_path: &str, deadline: Instant) -> (Vec<String>, bool) {
+        let mut test_paths = self.find_test_files(repo_root, source_path);
+        let mut complete = true;
+        if self.scan_imports {
+            let (imported_by, scan_complete) = import_graph::tests_importing(repo_root, source_path, self, deadline);
+            complete = scan_complete;
+            for path in imported_by {
+                if !test_paths.contains(&path) {
+                    test_paths.push(path);
+                }
+            }
+        }
+        (test_paths, complete)
+    }
+
+    /// `count_test_cases` with the configured title regexes.
+    pub fn count_test_cases(&self, content: &str, path: &str) -> u32 {
+        self.test_titles(content, path).iter().map(|(_, cases)| (*cases).max(1)).sum()
+    }
 }
 
-/// Enrich coupled files with test intents by reading test files from disk.
-/// Silently ignores file read errors.
-pub fn enrich_with_test_intents(repo_root: &Path, coupled_files: &mut [CoupledFile]) {
+/// Enrich coupled files with test intents by reading test files from disk,
+/// and mark whether each other coupled file has tests (`has_tests`).
+/// Silently ignores file read errors and files resolving outside the repo.
+pub fn enrich_with_test_intents(repo_root: &Path, coupled_files: &mut [CoupledFile], patterns: &TestPatterns) {
+    let deadline = Instant::now() + TEST_DISCOVERY_BUDGET;
     for file in coupled_files.iter_mut() {
-        if !is_test_file(&file.path) {
+        if !patterns.is_test_file(&file.path) {
+            let (tests, complete) = patterns.discover_test_files(repo_root, &file.path, deadline);
+            // An import scan cut short cannot rule tests out
+            file.has_tests = (!tests.is_empty() || complete).then_some(!tests.is_empty());
             continue;
         }
 
-        let full_path = repo_root.join(&file.path);
-        let Ok(content) = std::fs::read_to_string(&full_path) else {
+        let Some(content) = sandbox::read_repo_file(repo_root, &file.path) else {
             continue;
         };
 
-        file.test_intents = extract_test_intents(&content, &file.path);
+        file.test_intents = patterns.extract_test_intents(&content, &file.path);
     }
 }
 
+/// Directories that may hold tests for a JVM source in `parent`: the same
+/// directory, plus the Maven/Gradle mirror `src/test/<lang>/<pkg>/` when the
+/// source lives under `src/main/<lang>/<pkg>/`.
+fn jvm_test_dirs(parent: &Path, lang: &str) -> Vec<std::path::PathBuf> {
+    let mut dirs = vec![parent.to_path_buf()];
+    let main_dir = format!("src/main/{lang}");
+    if let Some(p) = parent.to_str()
+        && let Some(idx) = p.find(&main_dir)
+    {
+        let mirrored = format!("{}src/test/{lang}{}", &p[..idx], &p[idx + main_dir.len()..]);
+        dirs.push(std::path::PathBuf::from(mirrored));
+    }
+    dirs
+}
+
+/// Whether a test file named `test_name` is about the source file named
+/// `source_name`: the same name up to the first dot, or that name with an
+/// affix such as the `test_` or `check_` of pytest's `python_files`
+/// (`check_cart.py`, `cart.spec.ts`, `CartTest.kt` for `cart`/`Cart`).
+fn names_subject(test_name: &str, source_name: &str) -> bool {
+    let test = test_name.split('.').next().unwrap_or(test_name);
+    let source = source_name.split('.').next().unwrap_or(source_name);
+    if source.is_empty() {
+        return false;
+    }
+    test == source
+        || test.strip_suffix(source).is_some_and(|affix| affix.ends_with('_'))
+        || test
+            .strip_prefix(source)
+            .is_some_and(|affix| affix.starts_with('_') || ["Test", "Tests", "Spec"].contains(&affix))
+}
+
 /// Find test files for a source file by naming convention, independent of git coupling.
 /// Checks candidate paths on disk and returns relative paths that exist.
 pub fn find_test_files(repo_root: &Path, source_path: &str) -> Vec<String> {
@@ -250,12 +1093,53 @@ pub fn find_test_files(repo_root: &Path, source_path: &str) -> Vec<String> {
     } else if let Some(stem) = filename.strip_suffix(".go") {
         candidates.push(parent.join(format!("{stem}_test.go")).display().to_string());
     } else if let Some(stem) = filename.strip_suffix(".java") {
-        candidates.push(parent.join(format!("{stem}Test.java")).display().to_string());
-        candidates.push(parent.join(format!("{stem}Tests.java")).display().to_string());
+        for dir in jvm_test_dirs(parent, "java") {
+            candidates.push(dir.join(format!("{stem}Test.java")).display().to_string());
+            candidates.push(dir.join(format!("{stem}Tests.java")).display().to_string());
+        }
+    } else if let Some(stem) = filename.strip_suffix(".rb") {
+        candidates.push(parent.join(format!("{stem}_spec.rb")).display().to_string());
+        candidates.push(parent.join(format!("{stem}_test.rb")).display().to_string());
+        // Root spec/ and test/ trees mirror the source tree, minus the Rails
+        // `app/` or gem `lib/` prefix (`app/models/cart.rb` -> `spec/models/cart_spec.rb`)
+        let mirrored = parent
+            .strip_prefix("app")
+            .or_else(|_| parent.strip_prefix("lib"))
+            .unwrap_or(parent);
+        for rel in [mirrored, parent] {
+            candidates.push(Path::new("spec").join(rel).join(format!("{stem}_spec.rb")).display().to_string());
+            candidates.push(Path::new("test").join(rel).join(format!("{stem}_test.rb")).display().to_string());
+        }
+    } else if let Some(stem) = [".cc", ".cpp", ".cxx", ".h", ".hpp"]
+        .iter()
+        .find_map(|ext| filename.strip_suffix(ext))
+    {
+        for ext in CPP_EXTENSIONS {
+            candidates.push(parent.join(format!("{stem}_test{ext}")).display().to_string());
+            candidates.push(parent.join(format!("{stem}_unittest{ext}")).display().to_string());
+            candidates.push(parent.join(format!("test_{stem}{ext}")).display().to_string());
+        }
+    } else if let Some(stem) = filename.strip_suffix(".cs") {
+        candidates.push(parent.join(format!("{stem}Tests.cs")).display().to_string());
+        candidates.push(parent.join(format!("{stem}Test.cs")).display().to_string());
+    } else if let Some(stem) = filename.strip_suffix(".php") {
+        candidates.push(parent.join(format!("{stem}Test.php")).display().to_string());
+        // PHPUnit's tests/ tree mirrors the PSR-4 source root, often split
+        // into Unit/ and Feature/ suites (`src/Shop/Cart.php` -> `tests/Unit/Shop/CartTest.php`)
+        let mirrored = parent
+            .strip_prefix("src")
+            .or_else(|_| parent.strip_prefix("app"))
+            .unwrap_or(parent);
+        for suite in ["", "Unit", "Feature"] {
+            let dir = Path::new("tests").join(suite).join(mirrored);
+            candidates.push(dir.join(format!("{stem}Test.php")).display().to_string());
+        }
     } else if let Some(stem) = filename.strip_suffix(".kt") {
-        candidates.push(parent.join(format!("{stem}Test.kt")).display().to_string());
-        candidates.push(parent.join(format!("{stem}Tests.kt")).display().to_string());
-        candidates.push(parent.join(format!("{stem}Spec.kt")).display().to_string());
+        for dir in jvm_test_dirs(parent, "kotlin") {
+            candidates.push(dir.join(format!("{stem}Test.kt")).display().to_string());
+            candidates.push(dir.join(format!("{stem}Tests.kt")).display().to_string());
+            candidates.push(dir.join(format!("{stem}Spec.kt")).display().to_string());
+        }
     } else if let Some(stem) = filename.strip_suffix(".scala") {
         candidates.push(parent.join(format!("{stem}Spec.scala")).display().to_string());
     } else if let Some(stem) = filename.strip_suffix(".rs") {
@@ -271,7 +1155,7 @@ pub fn find_test_files(repo_root: &Path, source_path: &str) -> Vec<String> {
         if !seen.insert(candidate.clone()) {
             continue;
         }
-        if repo_root.join(candidate).is_file() {
+        if sandbox::is_repo_file(repo_root, candidate) {
             found.push(candidate.clone());
         }
     }
@@ -280,15 +1164,35 @@ pub fn find_test_files(repo_root: &Path, source_path: &str) -> Vec<String> {
 }
 
 /// Count the total number of test cases in file content (no cap).
+/// Parameterized tests count once per case.
 pub fn count_test_cases(content: &str, path: &str) -> u32 {
-    detect_test_language(path)
-        .map(|(_, re)| re.captures_iter(content).count() as u32)
-        .unwrap_or(0)
+    TestPatterns::default().count_test_cases(content, path)
 }
 
 /// Discover test files for a source file and build a TestInfo with coverage hint.
 pub fn discover_test_info(repo_root: &Path, source_path: &str) -> Option<TestInfo> {
-    let test_paths = find_test_files(repo_root, source_path);
+    let deadline = Instant::now() + TEST_DISCOVERY_BUDGET;
+    discover_test_info_with_content(repo_root, source_path, None, &TestPatterns::default(), deadline)
+}
+
+/// Like `discover_test_info`, but uses `source_content` (e.g. an unsaved
+/// editor buffer) for the source file instead of reading it from disk, and
+/// the repo's configured test `patterns`.
+///
+/// Stops reading test files once `deadline` passes; the result then
+/// covers the files read so far and is marked `partial`.
+pub fn discover_test_info_with_content(
+    repo_root: &Path,
+    source_path: &str,
+    source_content: Option<&str>,
+    patterns: &TestPatterns,
+    deadline: Instant,
+) -> Option<TestInfo> {
+    if patterns.is_test_file(source_path) {
+        return None;
+    }
+    let (test_paths, complete) = patterns.discover_test_files(repo_root, source_path, deadline);
+    let mut partial = !complete;
     if test_paths.is_empty() {
         return None;
     }
@@ -297,19 +1201,25 @@ pub fn discover_test_info(repo_root: &Path, source_path: &str) -> Option<TestInf
     let mut total_tests: u32 = 0;
 
     for test_path in &test_paths {
-        let full_path = repo_root.join(test_path);
-        let Ok(content) = std::fs::read_to_string(&full_path) else {
+        if Instant::now() >= deadline {
+            partial = true;
+            break;
+        }
+        let Some(content) = sandbox::read_repo_file(repo_root, test_path) else {
             continue;
         };
 
-        let test_count = count_test_cases(&content, test_path);
-        let intents = extract_test_intents(&content, test_path);
+        let test_count = patterns.count_test_cases(&content, test_path);
+        let intents = patterns.extract_test_intents(&content, test_path);
         total_tests += test_count;
 
         test_files.push(DiscoveredTestFile {
             path: test_path.clone(),
             test_intents: intents,
             test_count,
+            recently_failing: false,
+            source_commits_since: None,
+            stale: false,
         });
     }
 
@@ -317,10 +1227,21 @@ pub fn discover_test_info(repo_root: &Path, source_path: &str) -> Option<TestInf
         return None;
     }
 
+    let source = match source_content {
+        Some(content) => Some(content.to_string()),
+        None => sandbox::read_repo_file(repo_root, source_path),
+    };
+
+    // Map each intent to the source symbols it names
+    if let Some(content) = &source {
+        let source_symbols = symbols::extract_symbols(content);
+        for intent in test_files.iter_mut().flat_map(|f| f.test_intents.iter_mut()) {
+            intent.covers_symbols = symbols::covered_symbols(&intent.title, &source_symbols);
+        }
+    }
+
     // Build coverage hint based on source file line count
-    let source_full = repo_root.join(source_path);
-    let coverage_hint = std::fs::read_to_string(&source_full)
-        .ok()
+    let coverage_hint = source
         .map(|content| {
             let line_count = content.lines().count();
             format!(
@@ -332,6 +1253,7 @@ pub fn discover_test_info(repo_root: &Path, source_path: &str) -> Option<TestInf
     Some(TestInfo {
         test_files,
         coverage_hint,
+        partial,
     })
 }
 
@@ -391,6 +1313,8 @@ mod tests {
         assert!(is_test_file("src/AuthTests.kt"));
         assert!(is_test_file("src/AuthSpec.kt"));
         assert!(is_test_file("src/AuthSpec.scala"));
+        assert!(is_test_file("app/src/test/kotlin/shop/CartShould.kt"));
+        assert!(!is_test_file("app/src/main/kotlin/shop/Cart.kt"));
     }
 
     // --- extract_test_intents tests ---
@@ -406,9 +1330,158 @@ describe("Auth", () => {
 "#;
         let intents = extract_test_intents(content, "src/Auth.test.ts");
         assert_eq!(intents.len(), 3);
-        assert_eq!(intents[0].title, "should login with valid credentials");
-        assert_eq!(intents[1].title, "should reject invalid password");
-        assert_eq!(intents[2].title, "should handle OAuth callback");
+        assert_eq!(intents[0].title, "Auth > should login with valid credentials");
+        assert_eq!(intents[1].title, "Auth > should reject invalid password");
+        assert_eq!(intents[2].title, "Auth > should handle OAuth callback");
+    }
+
+    #[test]
+    fn test_js_titles_include_nested_describes() {
+        let content = r#"
+describe('Auth', () => {
+  describe("token refresh", () => {
+    it('should rotate keys', () => {
+      expect(fn('(')).toBe(")");
+    });
+  });
+  it('should logout', () => {});
+});
+test('top level', () => {});
+"#;
+        let titles: Vec<String> =
+            extract_test_intents(content, "src/Auth.test.ts").into_iter().map(|i| i.title).collect();
+        assert_eq!(titles, vec!["Auth > token refresh > should rotate keys", "Auth > should logout", "top level"]);
+    }
+
+    #[test]
+    fn test_python_and_go_titles_include_enclosing_groups() {
+        let python = "class TestTokenRefresh:\n    def test_rotates_keys(self):\n        pass\n\ndef test_login():\n    pass\n";
+        let titles: Vec<String> =
+            extract_test_intents(python, "tests/test_auth.py").into_iter().map(|i| i.title).collect();
+        assert_eq!(titles, vec!["token refresh > rotates keys", "login"]);
+
+        let go = r#"
+func TestAuth(t *testing.T) {
+	t.Run("token refresh", func(t *testing.T) {
+		t.Run("rotates keys", func(t *testing.T) {})
+		t.Run("keeps session", func(t *testing.T) {})
+	})
+}
+
+func TestLogout(t *testing.T) {
+	if got := logout(); got != "}" {
+		t.Fatal("bad")
+	}
+}
+"#;
+        let titles: Vec<String> =
+            extract_test_intents(go, "auth/auth_test.go").into_iter().map(|i| i.title).collect();
+        assert_eq!(
+            titles,
+            vec!["auth > token refresh > rotates keys", "auth > token refresh > keeps session", "logout"]
+        );
+    }
+
+    #[test]
+    fn test_intent_cap_and_symbol_selection() {
+        let content = r#"
+it('renders header', () => {});
+it('renders footer', () => {});
+it('refreshes expired tokens', () => {});
+it('handles resize', () => {});
+it('logs out on refresh token failure', () => {});
+"#;
+        let titles = |patterns: &TestPatterns| -> Vec<String> {
+            patterns.extract_test_intents(content, "src/App.test.ts").into_iter().map(|i| i.title).collect()
+        };
+
+        let first = TestPatterns::default().with_intent_selection(2, IntentSelection::First);
+        assert_eq!(titles(&first), vec!["renders header", "renders footer"]);
+
+        let by_symbol = TestPatterns::default()
+            .with_intent_selection(3, IntentSelection::Symbols)
+            .with_target_symbols(vec!["refreshToken".to_string()]);
+        assert_eq!(
+            titles(&by_symbol),
+            vec!["renders header", "refreshes expired tokens", "logs out on refresh token failure"]
+        );
+        assert_eq!(titles(&TestPatterns::default()).len(), 5);
+    }
+
+    #[test]
+    fn test_counts_parameterized_cases() {
+        let js = r#"
+describe('math', () => {
+  test.each([
+    [1, 1, 2],
+    [1, 2, 3],
+    [2, [1], 4],
+  ])('add(%i, %i) -> %i', (a, b, expected) => {});
+  it.each`
+    a    | b    | expected
+    ${1} | ${1} | ${2}
+    ${2} | ${1} | ${3}
+  `('returns $expected', ({ a, b, expected }) => {});
+  it.each(cases)('from a variable', () => {});
+  it('plain', () => {});
+});
+"#;
+        let intents = extract_test_intents(js, "src/math.test.ts");
+        let titles: Vec<(&str, u32)> = intents.iter().map(|i| (i.title.as_str(), i.cases)).collect();
+        assert_eq!(
+            titles,
+            vec![
+                ("math > add(%i, %i) -> %i", 3),
+                ("math > returns $expected", 2),
+                ("math > from a variable", 0),
+                ("math > plain", 0),
+            ]
+        );
+        assert_eq!(count_test_cases(js, "src/math.test.ts"), 7);
+
+        let python = r#"
+@pytest.mark.parametrize("a,b", [(1, 2), (3, 4), (5, 6)])
+@pytest.mark.parametrize("sign", ["+", "-"])
+def test_add(a, b, sign):
+    pass
+
+@pytest.mark.parametrize(
+    argnames="x",
+    argvalues=[
+        1,
+        2,
+    ],
+)
+def test_square(x):
+    pass
+
+def test_plain():
+    pass
+"#;
+        let cases: Vec<u32> = extract_test_intents(python, "tests/test_math.py").iter().map(|i| i.cases).collect();
+        assert_eq!(cases, vec![6, 2, 0]);
+        assert_eq!(count_test_cases(python, "tests/test_math.py"), 9);
+
+        let go = r#"
+func TestAdd(t *testing.T) {
+	tests := []struct {
+		name string
+		a, b int
+	}{
+		{"zeros", 0, 0},
+		{"ones", 1, 1},
+		{name: "mixed", a: 1, b: 0},
+	}
+	for _, tc := range tests {
+		t.Run(tc.name, func(t *testing.T) {})
+	}
+}
+
+func TestPlain(t *testing.T) {}
+"#;
+        let cases: Vec<u32> = extract_test_intents(go, "math/add_test.go").iter().map(|i| i.cases).collect();
+        assert_eq!(cases, vec![3, 0]);
+        assert_eq!(count_test_cases(go, "math/add_test.go"), 4);
     }
 
     #[test]
@@ -506,6 +1579,205 @@ class AuthTest {
         assert_eq!(intents[2].title, "should handle o auth callback");
     }
 
+    #[test]
+    fn test_extracts_annotated_java_tests_and_skips_helpers() {
+        let content = r#"
+class CartTests {
+    @BeforeEach
+    void setUp() {}
+
+    @Test
+    public void addsItemToEmptyCart() throws Exception {}
+
+    @ParameterizedTest
+    @ValueSource(strings = {"a", "b (c)"})
+    void rejectsBlankSku(String sku) {}
+
+    @DisplayName("applies (stacked) discounts")
+    @Test
+    void discounts() {}
+
+    private void assertTotal(int expected) {}
+
+    public void testLegacyCheckout() {}
+}
+"#;
+        let path = "src/test/java/shop/CartTests.java";
+        let titles: Vec<String> =
+            extract_test_intents(content, path).into_iter().map(|i| i.title).collect();
+        assert_eq!(
+            titles,
+            vec![
+                "adds item to empty cart",
+                "rejects blank sku",
+                "applies (stacked) discounts",
+                "legacy checkout",
+            ]
+        );
+        assert_eq!(count_test_cases(content, path), 4);
+    }
+
+    #[test]
+    fn test_extracts_csharp_test_intents() {
+        let content = r#"
+public class CartTests
+{
+    [Fact]
+    public void AddsItem_ToEmptyCart() { }
+
+    [Theory]
+    [InlineData("")]
+    [InlineData(" ")]
+    public async Task RejectsBlankSku(string sku) { }
+
+    [Fact(DisplayName = "applies stacked discounts")]
+    public void Discounts() { }
+
+    [TestCase(1)]
+    [TestCase(2)]
+    public void Quantity_IsPositive(int q) { }
+
+    private void AssertTotal(int expected) { }
+}
+"#;
+        let titles: Vec<String> =
+            extract_test_intents(content, "tests/CartTests.cs").into_iter().map(|i| i.title).collect();
+        assert_eq!(
+            titles,
+            vec![
+                "adds item to empty cart",
+                "rejects blank sku",
+                "applies stacked discounts",
+                "quantity is positive",
+            ]
+        );
+        assert_eq!(count_test_cases(content, "tests/CartTests.cs"), 4);
+    }
+
+    #[test]
+    fn test_extracts_phpunit_test_intents() {
+        let content = r#"<?php
+use PHPUnit\Framework\Attributes\DataProvider;
+use PHPUnit\Framework\Attributes\Test;
+
+final class CartTest extends TestCase
+{
+    public function testAddsItemToEmptyCart(): void {}
+
+    #[Test]
+    public function rejects_blank_sku(): void {}
+
+    #[DataProvider('quantities')]
+    #[\PHPUnit\Framework\Attributes\Test]
+    public function quantityIsPositive(int $q): void {}
+
+    function testWithoutVisibility() {}
+
+    public static function quantities(): array { return []; }
+
+    private function testHelper(): void {}
+
+    protected function setUp(): void {}
+}
+"#;
+        let path = "tests/Unit/Shop/CartTest.php";
+        let titles: Vec<String> =
+            extract_test_intents(content, path).into_iter().map(|i| i.title).collect();
+        assert_eq!(
+            titles,
+            vec!["adds item to empty cart", "rejects blank sku", "quantity is positive", "without visibility"]
+        );
+        assert_eq!(count_test_cases(content, path), 4);
+    }
+
+    #[test]
+    fn test_extracts_rspec_titles_with_groups() {
+        let content = r##"
+require "rails_helper"
+
+RSpec.describe Cart, type: :model do
+  describe "#total" do
+    it "sums line items" do
+    end
+
+    context 'with discounts' do
+      it 'applies percentage' do
+      end
+    end
+  end
+
+  it { is_expected.to validate_presence_of(:user) }
+  specify "empty cart is free" do
+  end
+end
+"##;
+        let path = "spec/models/cart_spec.rb";
+        let titles: Vec<String> =
+            extract_test_intents(content, path).into_iter().map(|i| i.title).collect();
+        assert_eq!(
+            titles,
+            vec![
+                "Cart > #total > sums line items",
+                "Cart > #total > with discounts > applies percentage",
+                "Cart > empty cart is free",
+            ]
+        );
+        assert_eq!(count_test_cases(content, path), 3);
+    }
+
+    #[test]
+    fn test_extracts_minitest_titles() {
+        let content = r#"
+class CartTest < ActiveSupport::TestCase
+  test "adds an item" do
+  end
+
+  def test_total_is_zero_when_empty
+  end
+
+  def helper; end
+end
+"#;
+        let titles: Vec<String> = extract_test_intents(content, "test/models/cart_test.rb")
+            .into_iter()
+            .map(|i| i.title)
+            .collect();
+        assert_eq!(titles, vec!["adds an item", "total is zero when empty"]);
+    }
+
+    #[test]
+    fn test_extracts_googletest_and_catch2_intents() {
+        let gtest = r#"
+TEST(ParserTest, HandlesEmptyInput) {
+  EXPECT_TRUE(Parse("").ok());
+}
+
+TEST_F(ParserFixture, rejects_unterminated_string) {}
+TEST_P(ParamTest, RoundTrips) {}
+"#;
+        let titles: Vec<String> =
+            extract_test_intents(gtest, "src/parser_test.cc").into_iter().map(|i| i.title).collect();
+        assert_eq!(
+            titles,
+            vec![
+                "ParserTest: handles empty input",
+                "ParserFixture: rejects unterminated string",
+                "ParamTest: round trips",
+            ]
+        );
+
+        let catch2 = r#"
+TEST_CASE("vectors can be sized and resized", "[vector]") {
+    SECTION("resizing bigger changes size") {}
+}
+SCENARIO("cart totals include tax") {}
+"#;
+        let titles: Vec<String> =
+            extract_test_intents(catch2, "tests/test_vector.cpp").into_iter().map(|i| i.title).collect();
+        assert_eq!(titles, vec!["vectors can be sized and resized", "cart totals include tax"]);
+        assert_eq!(count_test_cases(catch2, "tests/test_vector.cpp"), 2);
+    }
+
     #[test]
     fn test_extracts_kotlin_test_intents() {
         let content = r#"
@@ -524,6 +1796,40 @@ class AuthSpec : StringSpec({
         assert_eq!(intents[1].title, "should logout");
     }
 
+    #[test]
+    fn test_extracts_kotlin_junit_test_intents() {
+        let content = r#"
+class CartTest {
+    @BeforeEach
+    fun setUp() {}
+
+    @Test
+    fun `adds item to an empty cart`() {
+        val cart = Cart()
+        assertEquals(1, cart.add("sku").size)
+    }
+
+    @Test
+    @DisplayName("ignored")
+    internal fun rejectsBlankSku() {}
+
+    @ParameterizedTest
+    @ValueSource(ints = [1, 2])
+    fun `quantity must be positive`(q: Int) {}
+
+    private fun helper() {}
+}
+"#;
+        let path = "src/test/kotlin/shop/CartTest.kt";
+        let titles: Vec<String> =
+            extract_test_intents(content, path).into_iter().map(|i| i.title).collect();
+        assert_eq!(
+            titles,
+            vec!["adds item to an empty cart", "rejects blank sku", "quantity must be positive"]
+        );
+        assert_eq!(count_test_cases(content, path), 3);
+    }
+
     #[test]
     fn test_extracts_scala_test_intents() {
         let content = r#"
@@ -549,6 +1855,48 @@ class AuthSpec extends AnyFlatSpec {
         assert!(intents.is_empty());
     }
 
+    #[test]
+    fn test_configured_test_patterns() {
+        let config: TestPatternsConfig = serde_json::from_str(
+            r#"{
+                "globs": ["spec/**/*_spec.lua", "t/*.t", "[invalid"],
+                "titles": {
+                    "lua": ["\\bit\\(\\s*\"([^\"]*)\""],
+                    ".t": ["ok\\(.*,\\s*'([^']*)'\\);", "is\\(.*,\\s*'([^']*)'\\);"],
+                    "ts": ["scenario\\(\\s*'([^']*)'", "(unclosed"]
+                }
+            }"#,
+        )
+        .unwrap();
+        let patterns = TestPatterns::from_config(&config);
+
+        assert!(patterns.is_test_file("spec/cart/cart_spec.lua"));
+        assert!(patterns.is_test_file("t/basic.t"));
+        assert!(!patterns.is_test_file("t/nested/basic.t"));
+        assert!(!patterns.is_test_file("src/cart.lua"));
+        assert!(patterns.is_test_file("src/cart.test.ts"));
+
+        let busted = "describe(\"cart\", function()\n  it(\"adds items\", function() end)\n  it(\"totals\", function() end)\nend)\n";
+        let titles: Vec<String> = patterns
+            .extract_test_intents(busted, "spec/cart/cart_spec.lua")
+            .into_iter()
+            .map(|i| i.title)
+            .collect();
+        assert_eq!(titles, vec!["adds items", "totals"]);
+
+        // Source order across several regexes
+        let tap = "ok(1, 'loads');\nis($x, 2, 'adds');\nok(1, 'saves');\n";
+        assert_eq!(patterns.count_test_cases(tap, "t/basic.t"), 3);
+        assert_eq!(patterns.extract_test_intents(tap, "t/basic.t")[1].title, "adds");
+
+        // Merged with the built-in extractor, without duplicates
+        let ts = "it('logs in', () => {});\nscenario('checkout', () => {});\nscenario('logs in', () => {});\n";
+        let titles: Vec<String> =
+            patterns.extract_test_intents(ts, "src/a.test.ts").into_iter().map(|i| i.title).collect();
+        assert_eq!(titles, vec!["logs in", "checkout"]);
+        assert_eq!(count_test_cases(ts, "src/a.test.ts"), 1);
+    }
+
     // --- enrich_with_test_intents tests ---
 
     #[test]
@@ -565,20 +1913,24 @@ describe("Auth", () => {
 "#;
         fs::write(test_dir.join("Auth.test.ts"), test_content).unwrap();
 
-        let mut files = vec![CoupledFile {
-            path: "src/Auth.test.ts".to_string(),
-            coupling_score: 0.8,
-            co_change_count: 20,
-            risk_score: 0.75,
-            memories: Vec::new(),
-            test_intents: Vec::new(),
-        }];
+        let mut files = vec![
+            CoupledFile {
+                path: "src/Auth.test.ts".to_string(),
+                coupling_score: 0.8,
+                co_change_count: 20,
+                risk_score: 0.75,
+                ..Default::default()
+            },
+            CoupledFile { path: "src/Auth.ts".to_string(), ..Default::default() },
+        ];
 
-        enrich_with_test_intents(tmp.path(), &mut files);
+        enrich_with_test_intents(tmp.path(), &mut files, &TestPatterns::default());
 
         assert_eq!(files[0].test_intents.len(), 2);
-        assert_eq!(files[0].test_intents[0].title, "should login");
-        assert_eq!(files[0].test_intents[1].title, "should logout");
+        assert_eq!(files[0].test_intents[0].title, "Auth > should login");
+        assert_eq!(files[0].test_intents[1].title, "Auth > should logout");
+        assert_eq!(files[0].has_tests, None);
+        assert_eq!(files[1].has_tests, Some(true));
     }
 
     #[test]
@@ -590,12 +1942,30 @@ describe("Auth", () => {
             coupling_score: 0.8,
             co_change_count: 20,
             risk_score: 0.75,
-            memories: Vec::new(),
-            test_intents: Vec::new(),
+            ..Default::default()
         }];
 
-        enrich_with_test_intents(tmp.path(), &mut files);
+        enrich_with_test_intents(tmp.path(), &mut files, &TestPatterns::default());
         assert!(files[0].test_intents.is_empty());
+        assert_eq!(files[0].has_tests, Some(false));
+    }
+
+    #[test]
+    fn test_enrich_finds_tests_through_imports() {
+        let tmp = TempDir::new().unwrap();
+        fs::create_dir_all(tmp.path().join("src")).unwrap();
+        fs::create_dir_all(tmp.path().join("test/flows")).unwrap();
+        fs::write(tmp.path().join("src/auth.ts"), "export function login() {}").unwrap();
+        fs::write(tmp.path().join("test/flows/login.spec.ts"), "import { login } from '../../src/auth';").unwrap();
+
+        let file = || vec![CoupledFile { path: "src/auth.ts".to_string(), ..Default::default() }];
+        let mut by_name = file();
+        enrich_with_test_intents(tmp.path(), &mut by_name, &TestPatterns::default());
+        assert_eq!(by_name[0].has_tests, Some(false));
+
+        let mut by_import = file();
+        enrich_with_test_intents(tmp.path(), &mut by_import, &TestPatterns::default().with_import_scan(true));
+        assert_eq!(by_import[0].has_tests, Some(true));
     }
 
     #[test]
@@ -607,11 +1977,10 @@ describe("Auth", () => {
             coupling_score: 0.8,
             co_change_count: 20,
             risk_score: 0.75,
-            memories: Vec::new(),
-            test_intents: Vec::new(),
+            ..Default::default()
         }];
 
-        enrich_with_test_intents(tmp.path(), &mut files);
+        enrich_with_test_intents(tmp.path(), &mut files, &TestPatterns::default());
         assert!(files[0].test_intents.is_empty());
     }
 
@@ -780,6 +2149,104 @@ describe("Auth", () => {
         assert_eq!(found, vec!["tests/auth.rs"]);
     }
 
+    #[test]
+    fn test_detects_and_finds_csharp_tests() {
+        assert!(is_test_file("Shop/CartTests.cs"));
+        assert!(is_test_file("Shop/CartTest.cs"));
+        assert!(!is_test_file("Shop/Cart.cs"));
+
+        let tmp = TempDir::new().unwrap();
+        let dir = tmp.path().join("Shop");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("Cart.cs"), "class Cart {}").unwrap();
+        fs::write(dir.join("CartTests.cs"), "class CartTests {}").unwrap();
+
+        assert_eq!(find_test_files(tmp.path(), "Shop/Cart.cs"), vec!["Shop/CartTests.cs"]);
+    }
+
+    #[test]
+    fn test_detects_and_finds_php_tests() {
+        assert!(is_test_file("tests/Unit/Shop/CartTest.php"));
+        assert!(!is_test_file("src/Shop/Cart.php"));
+
+        let tmp = TempDir::new().unwrap();
+        fs::create_dir_all(tmp.path().join("src/Shop")).unwrap();
+        fs::create_dir_all(tmp.path().join("tests/Unit/Shop")).unwrap();
+        fs::write(tmp.path().join("src/Shop/Cart.php"), "<?php class Cart {}").unwrap();
+        fs::write(tmp.path().join("tests/Unit/Shop/CartTest.php"), "").unwrap();
+
+        assert_eq!(find_test_files(tmp.path(), "src/Shop/Cart.php"), vec!["tests/Unit/Shop/CartTest.php"]);
+    }
+
+    #[test]
+    fn test_detects_and_finds_ruby_tests() {
+        assert!(is_test_file("spec/models/cart_spec.rb"));
+        assert!(is_test_file("test/models/cart_test.rb"));
+        assert!(is_test_file("test/test_cart.rb"));
+        assert!(!is_test_file("app/models/cart.rb"));
+
+        let tmp = TempDir::new().unwrap();
+        fs::create_dir_all(tmp.path().join("app/models")).unwrap();
+        fs::create_dir_all(tmp.path().join("spec/models")).unwrap();
+        fs::create_dir_all(tmp.path().join("test/models")).unwrap();
+        fs::write(tmp.path().join("app/models/cart.rb"), "class Cart; end").unwrap();
+        fs::write(tmp.path().join("spec/models/cart_spec.rb"), "").unwrap();
+        fs::write(tmp.path().join("test/models/cart_test.rb"), "").unwrap();
+
+        assert_eq!(
+            find_test_files(tmp.path(), "app/models/cart.rb"),
+            vec!["spec/models/cart_spec.rb", "test/models/cart_test.rb"]
+        );
+    }
+
+    #[test]
+    fn test_detects_and_finds_cpp_tests() {
+        assert!(is_test_file("src/parser_test.cc"));
+        assert!(is_test_file("src/parser_unittest.cc"));
+        assert!(is_test_file("src/parser_test.cpp"));
+        assert!(is_test_file("tests/test_parser.cpp"));
+        assert!(!is_test_file("src/parser.cc"));
+        assert!(!is_test_file("src/attest.cpp"));
+
+        let tmp = TempDir::new().unwrap();
+        let src = tmp.path().join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("parser.h"), "").unwrap();
+        fs::write(src.join("parser.cc"), "").unwrap();
+        fs::write(src.join("parser_test.cc"), "").unwrap();
+
+        assert_eq!(find_test_files(tmp.path(), "src/parser.cc"), vec!["src/parser_test.cc"]);
+        assert_eq!(find_test_files(tmp.path(), "src/parser.h"), vec!["src/parser_test.cc"]);
+    }
+
+    #[test]
+    fn test_find_java_tests_in_maven_layout() {
+        let tmp = TempDir::new().unwrap();
+        let main = tmp.path().join("src/main/java/com/shop");
+        let test = tmp.path().join("src/test/java/com/shop");
+        fs::create_dir_all(&main).unwrap();
+        fs::create_dir_all(&test).unwrap();
+        fs::write(main.join("Cart.java"), "class Cart {}").unwrap();
+        fs::write(test.join("CartTest.java"), "class CartTest {}").unwrap();
+
+        let found = find_test_files(tmp.path(), "src/main/java/com/shop/Cart.java");
+        assert_eq!(found, vec!["src/test/java/com/shop/CartTest.java"]);
+    }
+
+    #[test]
+    fn test_find_kotlin_tests_in_gradle_layout() {
+        let tmp = TempDir::new().unwrap();
+        let main = tmp.path().join("app/src/main/kotlin/shop");
+        let test = tmp.path().join("app/src/test/kotlin/shop");
+        fs::create_dir_all(&main).unwrap();
+        fs::create_dir_all(&test).unwrap();
+        fs::write(main.join("Cart.kt"), "class Cart").unwrap();
+        fs::write(test.join("CartTest.kt"), "class CartTest").unwrap();
+
+        let found = find_test_files(tmp.path(), "app/src/main/kotlin/shop/Cart.kt");
+        assert_eq!(found, vec!["app/src/test/kotlin/shop/CartTest.kt"]);
+    }
+
     #[test]
     fn test_find_java_tests() {
         let tmp = TempDir::new().unwrap();
@@ -838,6 +2305,53 @@ describe("Auth", () => {
         assert!(found.is_empty());
     }
 
+    #[test]
+    fn test_find_tests_under_test_runner_roots() {
+        let tmp = TempDir::new().unwrap();
+        fs::write(
+            tmp.path().join("jest.config.js"),
+            "module.exports = { roots: ['<rootDir>/spec'], testMatch: ['**/*.it.ts'] };\n",
+        )
+        .unwrap();
+        fs::write(tmp.path().join("pytest.ini"), "[pytest]\ntestpaths = checks\npython_files = check_*.py\n").unwrap();
+        for dir in ["src/models", "spec/models", "spec/other", "checks", "app"] {
+            fs::create_dir_all(tmp.path().join(dir)).unwrap();
+        }
+        fs::write(tmp.path().join("spec/models/cart.it.ts"), "it('adds items', () => {})").unwrap();
+        fs::write(tmp.path().join("spec/other/cart.it.ts"), "it('is elsewhere', () => {})").unwrap();
+        fs::write(tmp.path().join("checks/check_billing.py"), "def test_bills(): pass").unwrap();
+
+        let patterns = TestPatterns::default().with_test_runners(tmp.path(), Instant::now() + TEST_DISCOVERY_BUDGET);
+        assert!(patterns.is_test_file("spec/models/cart.it.ts"));
+        assert!(!TestPatterns::default().is_test_file("spec/models/cart.it.ts"));
+        assert_eq!(patterns.find_test_files(tmp.path(), "src/models/cart.ts"), vec!["spec/models/cart.it.ts"]);
+        assert_eq!(patterns.find_test_files(tmp.path(), "app/billing.py"), vec!["checks/check_billing.py"]);
+        assert!(find_test_files(tmp.path(), "app/billing.py").is_empty());
+    }
+
+    #[test]
+    fn test_test_runner_walk_stays_in_repo_and_on_time() {
+        let outside = TempDir::new().unwrap();
+        fs::write(outside.path().join("leak.it.ts"), "it('leaks', () => {})").unwrap();
+        let tmp = TempDir::new().unwrap();
+        fs::create_dir_all(tmp.path().join("spec")).unwrap();
+        fs::write(tmp.path().join("spec/cart.it.ts"), "it('adds items', () => {})").unwrap();
+        let config = "module.exports = { roots: ['linked', 'spec'], testMatch: ['**/*.it.ts'] };\n";
+        fs::write(tmp.path().join("jest.config.js"), config).unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(outside.path(), tmp.path().join("linked")).unwrap();
+
+        let patterns = TestPatterns::default().with_test_runners(tmp.path(), Instant::now() + TEST_DISCOVERY_BUDGET);
+        let tests: Vec<&str> = patterns.runner_tests.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(tests, ["spec/cart.it.ts"]);
+
+        // Globs without roots walk the whole repo, but not past the deadline
+        fs::write(tmp.path().join("jest.config.js"), "module.exports = { testMatch: ['**/*.it.ts'] };\n").unwrap();
+        let patterns = TestPatterns::default().with_test_runners(tmp.path(), Instant::now());
+        assert!(patterns.runner_tests.is_empty());
+        assert!(patterns.is_test_file("spec/cart.it.ts"));
+    }
+
     // --- count_test_cases tests ---
 
     #[test]
@@ -954,6 +2468,53 @@ describe("Auth", () => {
         assert!(hint.contains("1 test covering"));
     }
 
+    #[test]
+    fn test_discover_test_info_through_imports() {
+        let tmp = TempDir::new().unwrap();
+        fs::create_dir_all(tmp.path().join("src")).unwrap();
+        fs::create_dir_all(tmp.path().join("e2e")).unwrap();
+        fs::write(tmp.path().join("src/auth.ts"), "export function login() {}").unwrap();
+        fs::write(tmp.path().join("src/auth.test.ts"), "it('logs in', () => {});").unwrap();
+        fs::write(
+            tmp.path().join("e2e/auth_flow.spec.ts"),
+            "import { login } from '../src/auth';\nit('signs in end to end', () => {});",
+        )
+        .unwrap();
+
+        let naming = discover_test_info(tmp.path(), "src/auth.ts").unwrap();
+        assert_eq!(naming.test_files.len(), 1);
+
+        let patterns = TestPatterns::default().with_import_scan(true);
+        let deadline = Instant::now() + TEST_DISCOVERY_BUDGET;
+        let info = discover_test_info_with_content(tmp.path(), "src/auth.ts", None, &patterns, deadline).unwrap();
+        assert!(!info.partial);
+        let paths: Vec<&str> = info.test_files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec!["src/auth.test.ts", "e2e/auth_flow.spec.ts"]);
+        assert_eq!(info.test_files[1].test_intents[0].title, "signs in end to end");
+    }
+
+    #[test]
+    fn test_discover_test_info_prefers_buffer_content() {
+        let tmp = TempDir::new().unwrap();
+        let src = tmp.path().join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("Auth.tsx"), "export class Auth {}").unwrap();
+        fs::write(src.join("Auth.test.tsx"), "it('should login', () => {});").unwrap();
+
+        let buffer = "export class Auth {\n  login() {}\n  logout() {}\n}\n";
+        let info = discover_test_info_with_content(
+            tmp.path(),
+            "src/Auth.tsx",
+            Some(buffer),
+            &TestPatterns::default(),
+            Instant::now() + TEST_DISCOVERY_BUDGET,
+        )
+        .unwrap();
+        assert_eq!(info.test_files[0].test_intents[0].covers_symbols, vec!["login"]);
+        let hint = info.coverage_hint.unwrap();
+        assert!(hint.contains("4-line source file"), "{hint}");
+    }
+
     #[test]
     fn test_count_unknown_extension() {
         let count = count_test_cases("some content", "README.md");
@@ -986,8 +2547,8 @@ describe("Auth", () => {
 "#;
         let intents = extract_test_intents(content, "src/Auth.test.ts");
         assert_eq!(intents.len(), 2);
-        assert_eq!(intents[0].title, "should handle template literal name");
-        assert_eq!(intents[1].title, "should also work with test()");
+        assert_eq!(intents[0].title, "Auth > should handle template literal name");
+        assert_eq!(intents[1].title, "Auth > should also work with test()");
     }
 
     #[test]
@@ -1015,9 +2576,9 @@ describe("Suite", () => {
 "#;
         let intents = extract_test_intents(content, "src/Auth.test.ts");
         assert_eq!(intents.len(), 3);
-        assert_eq!(intents[0].title, "single");
-        assert_eq!(intents[1].title, "double");
-        assert_eq!(intents[2].title, "backtick");
+        assert_eq!(intents[0].title, "Suite > single");
+        assert_eq!(intents[1].title, "Suite > double");
+        assert_eq!(intents[2].title, "Suite > backtick");
     }
 
     #[test]
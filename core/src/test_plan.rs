@@ -0,0 +1,245 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::time::Instant;
+
+use crate::import_graph;
+use crate::indexing::IndexOptions;
+use crate::persistence::Database;
+use crate::sandbox;
+use crate::temporal;
+use crate::test_intents::{TestPatterns, TEST_DISCOVERY_BUDGET};
+use crate::types::{TestCommand, TestToRun, TestsToRunResponse};
+
+/// Coupled test files below this risk score are too weakly linked to run.
+const MIN_COUPLED_RISK: f64 = 0.3;
+
+/// The tests worth running for a change to `files`: test files among the
+/// changes themselves, test files coupled with them in history, tests found
+/// by naming convention, and tests that import them. Each test carries why
+/// it was picked; the tests are grouped into one runnable command per
+/// framework.
+///
+/// Indexes the same way `analyze` does. Only tests that exist in the
+/// working tree are listed.
+pub fn tests_to_run(
+    repo_root: &Path,
+    db: &Database,
+    opts: &IndexOptions,
+    patterns: &TestPatterns,
+    files: &[String],
+) -> Result<TestsToRunResponse, Box<dyn std::error::Error>> {
+    // test path -> (reasons, source files)
+    let mut picked: BTreeMap<String, (BTreeSet<&'static str>, BTreeSet<String>)> = BTreeMap::new();
+    let mut pick = |test: String, reason: &'static str, source: &str| {
+        let entry = picked.entry(test).or_default();
+        entry.0.insert(reason);
+        entry.1.insert(source.to_string());
+    };
+
+    for file in files {
+        if patterns.is_test_file(file) {
+            pick(file.clone(), "changed", file);
+            continue;
+        }
+        let (analysis, _) = temporal::analyze(repo_root, file, db, opts)?;
+        for coupled in analysis.coupled_files {
+            if patterns.is_test_file(&coupled.path) && coupled.risk_score >= MIN_COUPLED_RISK {
+                pick(coupled.path, "coupled", file);
+            }
+        }
+        for test in patterns.find_test_files(repo_root, file) {
+            pick(test, "convention", file);
+        }
+        let deadline = Instant::now() + TEST_DISCOVERY_BUDGET;
+        for test in import_graph::tests_importing(repo_root, file, patterns, deadline).0 {
+            pick(test, "imports", file);
+        }
+    }
+
+    let tests: Vec<TestToRun> = picked
+        .into_iter()
+        .filter(|(path, _)| sandbox::is_repo_file(repo_root, path))
+        .map(|(path, (reasons, sources))| TestToRun {
+            path,
+            reasons: reasons.into_iter().map(String::from).collect(),
+            sources: sources.into_iter().collect(),
+        })
+        .collect();
+    let commands = test_commands(repo_root, tests.iter().map(|t| t.path.as_str()));
+
+    Ok(TestsToRunResponse {
+        repo_root: repo_root.to_string_lossy().to_string(),
+        files: files.to_vec(),
+        tests,
+        commands,
+    })
+}
+
+/// Group test paths by framework and build one command per group. Tests
+/// whose framework is unknown are left out.
+fn test_commands<'a>(repo_root: &Path, tests: impl Iterator<Item = &'a str>) -> Vec<TestCommand> {
+    let mut groups: BTreeMap<&'static str, Vec<&str>> = BTreeMap::new();
+    for test in tests {
+        if let Some(framework) = framework(repo_root, test) {
+            groups.entry(framework).or_default().push(test);
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(framework, tests)| TestCommand {
+            framework: framework.to_string(),
+            command: command(repo_root, framework, &tests),
+            tests: tests.into_iter().map(String::from).collect(),
+        })
+        .collect()
+}
+
+/// The framework that runs the test file at `path`, from its extension and,
+/// for JavaScript, the repo's `package.json`.
+fn framework(repo_root: &Path, path: &str) -> Option<&'static str> {
+    let ext = path.rsplit_once('.').map_or("", |(_, ext)| ext);
+    Some(match ext {
+        "rs" => "cargo",
+        "go" => "go",
+        "py" => "pytest",
+        "rb" if path.ends_with("_spec.rb") => "rspec",
+        "rb" => "minitest",
+        "java" | "kt" if repo_root.join("pom.xml").is_file() => "maven",
+        "java" | "kt" => "gradle",
+        "ts" | "tsx" | "js" | "jsx" | "mjs" | "cjs" => {
+            let manifest = sandbox::read_repo_file(repo_root, "package.json").unwrap_or_default();
+            if manifest.contains("\"vitest\"") {
+                "vitest"
+            } else if manifest.contains("\"mocha\"") && !manifest.contains("\"jest\"") {
+                "mocha"
+            } else {
+                "jest"
+            }
+        }
+        _ => return None,
+    })
+}
+
+fn command(repo_root: &Path, framework: &str, tests: &[&str]) -> String {
+    let paths = || tests.iter().map(|t| quote(t)).collect::<Vec<_>>().join(" ");
+    let stems = || tests.iter().map(|t| stem(t)).collect::<Vec<_>>();
+    match framework {
+        "cargo" => {
+            // Integration tests are their own targets; anything else runs
+            // with the crate's unit tests
+            let targets: Vec<String> = tests
+                .iter()
+                .filter(|t| t.starts_with("tests/") || t.contains("/tests/"))
+                .map(|t| format!("--test {}", quote(stem(t))))
+                .collect();
+            if targets.len() == tests.len() {
+                format!("cargo test {}", targets.join(" "))
+            } else {
+                "cargo test".to_string()
+            }
+        }
+        "go" => {
+            let packages: BTreeSet<String> = tests
+                .iter()
+                .map(|t| t.rsplit_once('/').map_or(".".to_string(), |(dir, _)| format!("./{dir}")))
+                .collect();
+            format!("go test {}", packages.into_iter().map(|p| quote(&p)).collect::<Vec<_>>().join(" "))
+        }
+        "pytest" => format!("pytest {}", paths()),
+        "rspec" => format!("bundle exec rspec {}", paths()),
+        "minitest" if repo_root.join("bin/rails").is_file() => format!("bin/rails test {}", paths()),
+        "minitest" => {
+            format!("bundle exec ruby -Itest -e 'ARGV.each {{ |f| require File.expand_path(f) }}' {}", paths())
+        }
+        "maven" => format!("mvn test -Dtest={}", stems().join(",")),
+        "gradle" => {
+            let gradle = if repo_root.join("gradlew").is_file() { "./gradlew" } else { "gradle" };
+            let filters: Vec<String> = stems().iter().map(|s| format!("--tests {}", quote(s))).collect();
+            format!("{gradle} test {}", filters.join(" "))
+        }
+        "vitest" => format!("npx vitest run {}", paths()),
+        "mocha" => format!("npx mocha {}", paths()),
+        _ => format!("npx jest {}", paths()),
+    }
+}
+
+/// File name without directories or extension.
+fn stem(path: &str) -> &str {
+    let name = path.rsplit_once('/').map_or(path, |(_, name)| name);
+    name.split_once('.').map_or(name, |(stem, _)| stem)
+}
+
+/// Single-quote `arg` for a POSIX shell when it needs it.
+fn quote(arg: &str) -> String {
+    if arg.chars().all(|c| c.is_ascii_alphanumeric() || "/._-,=+".contains(c)) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::commit_files;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_commands_grouped_by_framework() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("package.json"), r#"{"devDependencies": {"vitest": "1"}}"#).unwrap();
+        let tests = [
+            "src/auth.test.ts",
+            "web/my app.spec.tsx",
+            "tests/flow.rs",
+            "pkg/auth/auth_test.go",
+            "tests/test_auth.py",
+            "src/test/java/AuthTest.java",
+            "README.md",
+        ];
+
+        let commands = test_commands(dir.path(), tests.into_iter());
+        let by_framework: BTreeMap<&str, &str> =
+            commands.iter().map(|c| (c.framework.as_str(), c.command.as_str())).collect();
+        assert_eq!(by_framework["vitest"], "npx vitest run src/auth.test.ts 'web/my app.spec.tsx'");
+        assert_eq!(by_framework["cargo"], "cargo test --test flow");
+        assert_eq!(by_framework["go"], "go test ./pkg/auth");
+        assert_eq!(by_framework["pytest"], "pytest tests/test_auth.py");
+        assert_eq!(by_framework["gradle"], "gradle test --tests AuthTest");
+        assert_eq!(commands.len(), 5);
+    }
+
+    #[test]
+    fn test_tests_to_run_combines_sources() {
+        let dir = TempDir::new().unwrap();
+        let repo = git2::Repository::init(dir.path()).unwrap();
+        let files = [
+            ("src/auth.ts", "export const login = 1;"),
+            ("src/auth.test.ts", "test('logs in', () => {});"),
+            ("test/flow.spec.ts", "import { login } from '../src/auth';"),
+        ];
+        commit_files(&repo, "alice", &files, "init");
+
+        let db = Database::in_memory().unwrap();
+        let patterns = TestPatterns::default();
+        let files = vec!["src/auth.ts".to_string(), "test/gone.spec.ts".to_string()];
+        let response = tests_to_run(dir.path(), &db, &IndexOptions::default(), &patterns, &files).unwrap();
+
+        let picked: Vec<(&str, Vec<&str>)> = response
+            .tests
+            .iter()
+            .map(|t| (t.path.as_str(), t.reasons.iter().map(String::as_str).collect()))
+            .collect();
+        assert_eq!(
+            picked,
+            vec![
+                ("src/auth.test.ts", vec!["convention", "coupled"]),
+                ("test/flow.spec.ts", vec!["coupled", "imports"]),
+            ]
+        );
+        assert_eq!(response.commands.len(), 1);
+        assert_eq!(response.commands[0].command, "npx jest src/auth.test.ts test/flow.spec.ts");
+    }
+}
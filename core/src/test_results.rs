@@ -0,0 +1,329 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use crate::persistence::Database;
+use crate::test_intents::{humanize, is_test_file, TITLE_SEPARATOR};
+use crate::types::{AnalysisResponse, ImportTestResultsResponse, TestIntent};
+
+/// Failures imported longer ago than this no longer mark a test as failing.
+const RECENT_FAILURE_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// One `<testcase>` from a JUnit XML report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestCaseResult {
+    /// `file` attribute of the case or its enclosing suite, if the runner
+    /// wrote one (pytest, jest-junit, vitest do)
+    pub file: Option<String>,
+    pub classname: Option<String>,
+    pub name: String,
+    pub passed: bool,
+}
+
+/// Parse a JUnit XML report. A case with a `<failure>` or `<error>` child
+/// failed; skipped cases are left out.
+pub fn parse_junit(xml: &str) -> Result<Vec<TestCaseResult>, roxmltree::Error> {
+    let doc = roxmltree::Document::parse(xml)?;
+    let cases = doc
+        .descendants()
+        .filter(|n| n.has_tag_name("testcase"))
+        .filter(|case| !case.children().any(|c| c.has_tag_name("skipped")))
+        .filter_map(|case| {
+            let file = case.attribute("file").or_else(|| {
+                case.ancestors()
+                    .filter(|a| a.has_tag_name("testsuite"))
+                    .find_map(|suite| suite.attribute("file"))
+            });
+            Some(TestCaseResult {
+                file: file.map(str::to_string),
+                classname: case.attribute("classname").map(str::to_string),
+                name: case.attribute("name")?.to_string(),
+                passed: !case
+                    .children()
+                    .any(|c| c.has_tag_name("failure") || c.has_tag_name("error")),
+            })
+        })
+        .collect();
+    Ok(cases)
+}
+
+/// Import JUnit XML reports, replacing the stored results of every test
+/// file they cover.
+///
+/// Cases are mapped to a repo-relative file by their `file` attribute, or
+/// else by matching the dotted `classname` (`com.shop.CartTest`,
+/// `tests.test_cart.TestCart`) against indexed test file paths.
+pub fn import_junit(
+    db: &Database,
+    repo_root: &Path,
+    reports: &[String],
+    now: i64,
+) -> Result<ImportTestResultsResponse, Box<dyn std::error::Error>> {
+    let mut modules: Vec<(String, String)> = db
+        .file_commit_counts_split(0)?
+        .into_keys()
+        .filter(|path| is_test_file(path))
+        .map(|path| (module_name(&path), path))
+        .collect();
+    modules.sort();
+
+    let root = repo_root.to_string_lossy().replace('\\', "/");
+    let mut by_file: BTreeMap<String, Vec<(String, bool)>> = BTreeMap::new();
+    let mut resolved: HashMap<String, Option<String>> = HashMap::new();
+    let mut unmapped_tests = 0;
+
+    for report in reports {
+        for case in parse_junit(report)? {
+            let file = match (&case.file, &case.classname) {
+                (Some(file), _) => Some(relative_path(file, &root)),
+                (None, Some(classname)) => resolved
+                    .entry(classname.clone())
+                    .or_insert_with(|| file_for_classname(classname, &modules))
+                    .clone(),
+                (None, None) => None,
+            };
+            match file {
+                Some(file) => by_file.entry(file).or_default().push((case.name, case.passed)),
+                None => unmapped_tests += 1,
+            }
+        }
+    }
+
+    let mut tests_imported = 0;
+    let mut tests_failed = 0;
+    for (file, results) in &by_file {
+        tests_imported += results.len() as u32;
+        tests_failed += results.iter().filter(|(_, passed)| !passed).count() as u32;
+        db.replace_test_results(file, results, now)?;
+    }
+
+    Ok(ImportTestResultsResponse {
+        repo_root: repo_root.to_string_lossy().to_string(),
+        files_imported: by_file.len() as u32,
+        tests_imported,
+        tests_failed,
+        unmapped_tests,
+    })
+}
+
+/// `src/test/java/com/shop/CartTest.java` -> `src.test.java.com.shop.CartTest`
+fn module_name(path: &str) -> String {
+    let stem = path.rsplit_once('.').map_or(path, |(stem, _)| stem);
+    stem.replace('/', ".")
+}
+
+/// Indexed test file whose module name ends with `classname`, trying again
+/// without the last segment for test classes nested in a module (pytest).
+fn file_for_classname(classname: &str, modules: &[(String, String)]) -> Option<String> {
+    let find = |name: &str| {
+        let suffix = format!(".{name}");
+        modules
+            .iter()
+            .find(|(module, _)| module == name || module.ends_with(&suffix))
+            .map(|(_, path)| path.clone())
+    };
+    find(classname).or_else(|| find(classname.rsplit_once('.')?.0))
+}
+
+/// Normalize a report's `file` attribute to a repo-relative path.
+fn relative_path(file: &str, repo_root: &str) -> String {
+    let file = file.replace('\\', "/");
+    let file = file
+        .strip_prefix(repo_root)
+        .map_or(file.as_str(), |rest| rest.trim_start_matches('/'));
+    file.strip_prefix("./").unwrap_or(file).to_string()
+}
+
+/// Mark coupled test files, discovered test files, and their intents that
+/// failed in a recently imported run.
+pub fn mark_recent_failures(
+    db: &Database,
+    response: &mut AnalysisResponse,
+    now: i64,
+) -> Result<(), rusqlite::Error> {
+    let since = now - RECENT_FAILURE_SECS;
+    for file in response.coupled_files.iter_mut().filter(|f| is_test_file(&f.path)) {
+        let failing = db.failing_tests(&file.path, since)?;
+        file.recently_failing = !failing.is_empty();
+        mark_intents(&mut file.test_intents, &failing);
+    }
+    if let Some(info) = response.test_info.as_mut() {
+        for test_file in info.test_files.iter_mut() {
+            let failing = db.failing_tests(&test_file.path, since)?;
+            test_file.recently_failing = !failing.is_empty();
+            mark_intents(&mut test_file.test_intents, &failing);
+        }
+    }
+    Ok(())
+}
+
+/// Mark the analyzed file's tests (`test_info`) that have not changed
+/// across the last `min_commits` indexed changes to the file. Test files
+/// with no indexed commits are left unmarked.
+pub fn mark_stale_tests(
+    db: &Database,
+    response: &mut AnalysisResponse,
+    min_commits: u32,
+) -> Result<(), rusqlite::Error> {
+    let Some(info) = response.test_info.as_mut() else {
+        return Ok(());
+    };
+    let source_commits = db.commit_count(&response.file_path)?;
+    for test_file in info.test_files.iter_mut() {
+        let Some((_, last_changed)) = db.file_time_range(&test_file.path)? else {
+            continue;
+        };
+        let up_to_test = db.commit_count_before(&response.file_path, last_changed + 1)?;
+        let since = source_commits.saturating_sub(up_to_test);
+        test_file.source_commits_since = Some(since);
+        test_file.stale = min_commits > 0 && since >= min_commits;
+    }
+    Ok(())
+}
+
+fn mark_intents(intents: &mut [TestIntent], failing: &[String]) {
+    for intent in intents.iter_mut() {
+        intent.failing = failing.iter().any(|name| intent_matches(&intent.title, name));
+    }
+}
+
+/// Whether an extracted intent title names the reported test. Reports use
+/// the raw name (`test_login`, `addsItem()`), the title string, or the
+/// title prefixed with its `describe` blocks joined by spaces rather than
+/// `" > "`.
+fn intent_matches(title: &str, name: &str) -> bool {
+    let name = name.split_once('(').map_or(name, |(method, _)| method).trim();
+    let humanized = humanize(name);
+    let flat = title.replace(TITLE_SEPARATOR, " ");
+    let leaf = title.rsplit(TITLE_SEPARATOR).next().unwrap_or(title);
+    name == title
+        || name == flat
+        || humanized == title
+        || humanized == leaf
+        || name.ends_with(&format!(" {flat}"))
+        || title.ends_with(&format!(": {humanized}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{CoupledFile, DiscoveredTestFile, TestInfo};
+
+    const REPORT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites>
+  <testsuite name="cart" file="./src/cart.test.ts">
+    <testcase classname="cart" name="Cart adds item to empty cart"/>
+    <testcase classname="cart" name="Cart rejects blank sku">
+      <failure message="expected error">stack</failure>
+    </testcase>
+    <testcase classname="cart" name="Cart is slow"><skipped/></testcase>
+  </testsuite>
+  <testsuite name="com.shop.CheckoutTest">
+    <testcase classname="com.shop.CheckoutTest" name="chargesCard()"><error/></testcase>
+  </testsuite>
+  <testsuite name="pytest">
+    <testcase classname="tests.test_tax.TestTax" name="test_rounds_half_up"/>
+    <testcase classname="vendor.Unknown" name="test_x"/>
+  </testsuite>
+</testsuites>
+"#;
+
+    fn response() -> AnalysisResponse {
+        AnalysisResponse {
+            file_path: "src/cart.ts".to_string(),
+            repo_root: "/repo".to_string(),
+            coupled_files: vec![CoupledFile {
+                path: "src/test/java/com/shop/CheckoutTest.java".to_string(),
+                test_intents: vec![
+                    TestIntent { title: "charges card".to_string(), ..Default::default() },
+                    TestIntent { title: "refunds".to_string(), ..Default::default() },
+                ],
+                ..Default::default()
+            }],
+            commit_count: 1,
+            analysis_time_ms: 1,
+            test_info: Some(TestInfo {
+                test_files: vec![DiscoveredTestFile {
+                    path: "src/cart.test.ts".to_string(),
+                    test_intents: vec![
+                        TestIntent { title: "Cart > adds item to empty cart".to_string(), ..Default::default() },
+                        TestIntent { title: "Cart > rejects blank sku".to_string(), ..Default::default() },
+                    ],
+                    test_count: 2,
+                    recently_failing: false,
+                    source_commits_since: None,
+                    stale: false,
+                }],
+                coverage_hint: None,
+                partial: false,
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_junit() {
+        let cases = parse_junit(REPORT).unwrap();
+        assert_eq!(cases.len(), 5);
+        assert_eq!(cases[0].file.as_deref(), Some("./src/cart.test.ts"));
+        assert!(cases[0].passed);
+        assert!(!cases[1].passed);
+        assert!(!cases[2].passed);
+        assert_eq!(cases[2].file, None);
+        assert!(parse_junit("<testsuite>").is_err());
+    }
+
+    #[test]
+    fn test_import_and_mark_recent_failures() {
+        let db = Database::in_memory().unwrap();
+        db.insert_commit("c1", &["src/test/java/com/shop/CheckoutTest.java", "tests/test_tax.py"], 1)
+            .unwrap();
+
+        let resp = import_junit(&db, Path::new("/repo"), &[REPORT.to_string()], 1_000_000).unwrap();
+        assert_eq!(resp.files_imported, 3);
+        assert_eq!(resp.tests_imported, 4);
+        assert_eq!(resp.tests_failed, 2);
+        assert_eq!(resp.unmapped_tests, 1);
+        assert_eq!(db.failing_tests("tests/test_tax.py", 0).unwrap(), Vec::<String>::new());
+
+        let mut analysis = response();
+        mark_recent_failures(&db, &mut analysis, 1_000_000).unwrap();
+        let checkout = &analysis.coupled_files[0];
+        assert!(checkout.recently_failing);
+        assert!(checkout.test_intents[0].failing);
+        assert!(!checkout.test_intents[1].failing);
+        let cart = &analysis.test_info.as_ref().unwrap().test_files[0];
+        assert!(cart.recently_failing);
+        assert!(!cart.test_intents[0].failing);
+        assert!(cart.test_intents[1].failing);
+
+        // Old failures no longer count
+        let mut analysis = response();
+        mark_recent_failures(&db, &mut analysis, 1_000_000 + RECENT_FAILURE_SECS + 1).unwrap();
+        assert!(!analysis.coupled_files[0].recently_failing);
+
+        // A later green run replaces the red one
+        let green = r#"<testsuite file="src/cart.test.ts"><testcase name="Cart rejects blank sku"/></testsuite>"#;
+        import_junit(&db, Path::new("/repo"), &[green.to_string()], 1_000_100).unwrap();
+        assert!(db.failing_tests("src/cart.test.ts", 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_mark_stale_tests() {
+        let db = Database::in_memory().unwrap();
+        db.insert_commit("c1", &["src/cart.ts", "src/cart.test.ts"], 100).unwrap();
+        for (i, ts) in [200, 300, 400].iter().enumerate() {
+            db.insert_commit(&format!("s{i}"), &["src/cart.ts"], *ts).unwrap();
+        }
+
+        let mut analysis = response();
+        mark_stale_tests(&db, &mut analysis, 3).unwrap();
+        let cart = &analysis.test_info.as_ref().unwrap().test_files[0];
+        assert_eq!((cart.source_commits_since, cart.stale), (Some(3), true));
+
+        db.insert_commit("c2", &["src/cart.test.ts"], 500).unwrap();
+        let mut analysis = response();
+        mark_stale_tests(&db, &mut analysis, 3).unwrap();
+        let cart = &analysis.test_info.as_ref().unwrap().test_files[0];
+        assert_eq!((cart.source_commits_since, cart.stale), (Some(0), false));
+    }
+}
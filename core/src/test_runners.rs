@@ -0,0 +1,258 @@
+use std::path::{Component, Path};
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::sandbox;
+
+/// Config files read for Jest's `roots` and `testMatch`.
+const JEST_CONFIGS: &[&str] =
+    &["jest.config.js", "jest.config.ts", "jest.config.mjs", "jest.config.cjs", "jest.config.json"];
+
+/// Config files read for Vitest's `test.dir` and `test.include`; Vitest
+/// also reads the Vite config when it has none of its own.
+const VITEST_CONFIGS: &[&str] = &[
+    "vitest.config.ts",
+    "vitest.config.js",
+    "vitest.config.mts",
+    "vitest.config.mjs",
+    "vite.config.ts",
+    "vite.config.js",
+    "vite.config.mts",
+    "vite.config.mjs",
+];
+
+/// pytest config files and the section holding its options in each, in
+/// the order pytest looks for them.
+const PYTEST_CONFIGS: &[(&str, &str)] = &[
+    ("pytest.ini", "[pytest]"),
+    ("pyproject.toml", "[tool.pytest.ini_options]"),
+    ("tox.ini", "[pytest]"),
+    ("setup.cfg", "[tool:pytest]"),
+];
+
+/// Where the repo's test runners look for tests, as declared in their
+/// config at the repo root. Go needs none: `go test` only runs the
+/// `_test.go` files of each package, which the built-in conventions know.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RunnerConventions {
+    /// Directories tests live under (`spec`, `integration`), repo-relative
+    pub roots: Vec<String>,
+    /// Globs of test files, repo-relative (`**/*.it.ts`)
+    pub globs: Vec<String>,
+}
+
+/// Read the Jest, Vitest and pytest config of the repo at `repo_root`.
+/// Settings that cannot be read statically (computed in JavaScript) and
+/// globs using micromatch extensions (`?(*.)+(spec|test)`) are skipped.
+pub fn load(repo_root: &Path) -> RunnerConventions {
+    let mut conventions = RunnerConventions::default();
+
+    let jest = JEST_CONFIGS
+        .iter()
+        .find_map(|name| sandbox::read_repo_file(repo_root, name))
+        .or_else(|| package_json_jest(repo_root));
+    if let Some(source) = jest {
+        conventions.add_roots(string_array(&source, "roots"));
+        conventions.add_globs(string_array(&source, "testMatch"));
+    }
+
+    if let Some(source) = VITEST_CONFIGS.iter().find_map(|name| sandbox::read_repo_file(repo_root, name)) {
+        conventions.add_roots(string_value(&source, "dir").into_iter().collect());
+        conventions.add_globs(string_array(&source, "include"));
+    }
+
+    for (name, section) in PYTEST_CONFIGS {
+        let Some(source) = sandbox::read_repo_file(repo_root, name) else {
+            continue;
+        };
+        let Some(options) = ini_section(&source, section) else {
+            continue;
+        };
+        conventions.add_roots(ini_values(options, "testpaths"));
+        // Matched against file names, in any directory
+        let files = ini_values(options, "python_files");
+        conventions.add_globs(files.into_iter().map(|pattern| format!("**/{pattern}")).collect());
+        break;
+    }
+
+    conventions
+}
+
+impl RunnerConventions {
+    /// Add the repo-relative `roots`. Absolute roots and roots climbing out
+    /// with `..` are dropped: a hostile config must not point the walk in
+    /// `with_test_runners` outside the repo.
+    fn add_roots(&mut self, roots: Vec<String>) {
+        for root in roots {
+            let root = strip_root_dir(&root).trim_start_matches("./").trim_end_matches('/').to_string();
+            let escapes = root.split(['/', '\\']).any(|part| part == "..")
+                || Path::new(&root).components().any(|c| !matches!(c, Component::Normal(_) | Component::CurDir));
+            if !root.is_empty() && root != "." && !escapes && !self.roots.contains(&root) {
+                self.roots.push(root);
+            }
+        }
+    }
+
+    fn add_globs(&mut self, globs: Vec<String>) {
+        for glob in globs {
+            let glob = strip_root_dir(&glob).trim_start_matches("./").to_string();
+            let extglob = ["?(", "*(", "+(", "@(", "!("].iter().any(|ext| glob.contains(ext));
+            if !glob.is_empty() && !extglob && !self.globs.contains(&glob) {
+                self.globs.push(glob);
+            }
+        }
+    }
+}
+
+/// Jest's `<rootDir>/` token, which is the repo root for a root config.
+fn strip_root_dir(path: &str) -> &str {
+    path.strip_prefix("<rootDir>/").unwrap_or(path)
+}
+
+/// The `"jest"` object of `package.json`, as text.
+fn package_json_jest(repo_root: &Path) -> Option<String> {
+    let manifest: serde_json::Value = serde_json::from_str(&sandbox::read_repo_file(repo_root, "package.json")?).ok()?;
+    manifest.get("jest").map(|jest| jest.to_string())
+}
+
+static QUOTED: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#""([^"]*)"|'([^']*)'|`([^`]*)`"#).unwrap());
+
+/// The string literals of the array literal assigned to `key` in a
+/// JavaScript or JSON config (`testMatch: ['**/spec/**/*.ts']`).
+fn string_array(source: &str, key: &str) -> Vec<String> {
+    let pattern = format!(r#"["']?\b{key}["']?\s*:\s*\[([^\]]*)\]"#);
+    let Some(items) = Regex::new(&pattern).ok().and_then(|re| re.captures(source)).and_then(|c| c.get(1)) else {
+        return Vec::new();
+    };
+    quoted_strings(items.as_str())
+}
+
+/// The string literal assigned to `key` (`dir: 'spec'`).
+fn string_value(source: &str, key: &str) -> Option<String> {
+    let pattern = format!(r#"["']?\b{key}["']?\s*:\s*("[^"]*"|'[^']*'|`[^`]*`)"#);
+    let literal = Regex::new(&pattern).ok()?.captures(source)?.get(1)?;
+    quoted_strings(literal.as_str()).into_iter().next()
+}
+
+fn quoted_strings(text: &str) -> Vec<String> {
+    QUOTED
+        .captures_iter(text)
+        .filter_map(|c| c.get(1).or_else(|| c.get(2)).or_else(|| c.get(3)))
+        .map(|m| m.as_str().to_string())
+        .collect()
+}
+
+/// The lines of the INI or TOML section headed `header`, up to the next
+/// section.
+fn ini_section<'a>(source: &'a str, header: &str) -> Option<&'a str> {
+    let start = source.lines().position(|line| line.trim() == header)?;
+    let mut lines = source.split_inclusive('\n');
+    let offset: usize = lines.by_ref().take(start + 1).map(str::len).sum();
+    let body = &source[offset..];
+    let end = body
+        .split_inclusive('\n')
+        .scan(0, |pos, line| {
+            let at = *pos;
+            *pos += line.len();
+            Some((at, line))
+        })
+        .find(|(_, line)| line.trim_start().starts_with('['))
+        .map_or(body.len(), |(at, _)| at);
+    Some(&body[..end])
+}
+
+/// The values of `key` in an INI section, where they are separated by
+/// whitespace and may continue on indented lines, or a TOML array or
+/// string.
+fn ini_values(section: &str, key: &str) -> Vec<String> {
+    let mut lines = section.lines();
+    let Some(first) = lines.by_ref().find_map(|line| {
+        let (name, value) = line.split_once('=')?;
+        (name.trim() == key).then(|| value.trim())
+    }) else {
+        return Vec::new();
+    };
+
+    if first.starts_with('[') {
+        // TOML array, possibly over several lines
+        let mut array = first.to_string();
+        for line in lines.by_ref() {
+            if array.contains(']') {
+                break;
+            }
+            array.push_str(line);
+        }
+        return quoted_strings(&array);
+    }
+    if first.starts_with(['"', '\'']) {
+        return quoted_strings(first);
+    }
+    let continued = lines.take_while(|line| line.starts_with([' ', '\t']) && !line.trim().is_empty());
+    std::iter::once(first).chain(continued).flat_map(str::split_whitespace).map(String::from).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_reads_jest_and_pytest_config() {
+        let dir = TempDir::new().unwrap();
+        fs::write(
+            dir.path().join("jest.config.js"),
+            concat!(
+                "module.exports = {\n  roots: ['<rootDir>/spec'],\n",
+                "  testMatch: [\"**/*.it.ts\", '**/?(*.)+(spec|test).ts'],\n};\n",
+            ),
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("setup.cfg"),
+            concat!(
+                "[metadata]\nname = shop\n\n",
+                "[tool:pytest]\ntestpaths =\n    checks\n    integration\npython_files = check_*.py\n",
+            ),
+        )
+        .unwrap();
+
+        let conventions = load(dir.path());
+        assert_eq!(conventions.roots, vec!["spec", "checks", "integration"]);
+        assert_eq!(conventions.globs, vec!["**/*.it.ts", "**/check_*.py"]);
+    }
+
+    #[test]
+    fn test_reads_vitest_and_pyproject_config() {
+        let dir = TempDir::new().unwrap();
+        fs::write(
+            dir.path().join("vite.config.ts"),
+            "export default defineConfig({\n  test: { dir: 'spec', include: ['**/*.unit.ts'] },\n});\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("pyproject.toml"),
+            "[tool.pytest.ini_options]\ntestpaths = [\n  \"qa\",\n]\n\n[tool.ruff]\nline-length = 100\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("package.json"), r#"{"jest": {"testMatch": ["**/*.jest.js"]}}"#).unwrap();
+
+        let conventions = load(dir.path());
+        assert_eq!(conventions.roots, vec!["spec", "qa"]);
+        assert_eq!(conventions.globs, vec!["**/*.jest.js", "**/*.unit.ts"]);
+    }
+
+    #[test]
+    fn test_drops_roots_outside_the_repo() {
+        let dir = TempDir::new().unwrap();
+        fs::write(
+            dir.path().join("jest.config.js"),
+            r#"module.exports = { roots: ['../..', '/', '/etc', '<rootDir>/../x', 'a/../../b', '..\\up', 'spec'] };"#,
+        )
+        .unwrap();
+        fs::write(dir.path().join("pytest.ini"), "[pytest]\ntestpaths = .. /home ./checks\n").unwrap();
+
+        assert_eq!(load(dir.path()).roots, vec!["spec", "checks"]);
+    }
+}
@@ -0,0 +1,48 @@
+//! Git fixtures shared by unit tests.
+
+use std::path::Path;
+
+use git2::{Oid, Repository, Signature};
+
+/// Write each `(path, content)` into the work tree and commit them on top
+/// of HEAD as `author` (`<author>@example.com`). Only the listed files are
+/// staged; files committed earlier stay as they were.
+pub fn commit_files(repo: &Repository, author: &str, files: &[(&str, &str)], message: &str) -> Oid {
+    let sig = Signature::now(author, &format!("{author}@example.com")).unwrap();
+    commit_as(repo, &sig, files, message)
+}
+
+/// `commit` dated `time` (seconds since the epoch), for tests that need a
+/// stable time order.
+pub fn commit_at(repo: &Repository, files: &[&str], message: &str, time: i64) -> Oid {
+    let sig = Signature::new("alice", "alice@example.com", &git2::Time::new(time, 0)).unwrap();
+    let files: Vec<(&str, &str)> = files.iter().map(|file| (*file, message)).collect();
+    commit_as(repo, &sig, &files, message)
+}
+
+fn commit_as(repo: &Repository, sig: &Signature, files: &[(&str, &str)], message: &str) -> Oid {
+    let workdir = repo.workdir().unwrap();
+    let mut index = repo.index().unwrap();
+    for (file, content) in files {
+        let path = workdir.join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, content).unwrap();
+        index.add_path(Path::new(file)).unwrap();
+    }
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+    repo.commit(Some("HEAD"), sig, sig, message, &tree, &parents).unwrap()
+}
+
+/// `commit_files` with `message` as the content of every file.
+pub fn commit_by(repo: &Repository, author: &str, files: &[&str], message: &str) -> Oid {
+    let files: Vec<(&str, &str)> = files.iter().map(|file| (*file, message)).collect();
+    commit_files(repo, author, &files, message)
+}
+
+/// `commit_by` alice.
+pub fn commit(repo: &Repository, files: &[&str], message: &str) -> Oid {
+    commit_by(repo, "alice", files, message)
+}
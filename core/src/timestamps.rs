@@ -0,0 +1,33 @@
+/// UTC calendar date of a Unix timestamp, as `YYYY-MM-DD`.
+pub(crate) fn date(timestamp: i64) -> String {
+    // Civil-from-days (Howard Hinnant's algorithm)
+    let z = timestamp.div_euclid(86_400) + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// UTC time of a Unix timestamp, as ISO 8601 `YYYY-MM-DDTHH:MM:SSZ`.
+pub(crate) fn iso_timestamp(timestamp: i64) -> String {
+    let secs = timestamp.rem_euclid(86_400);
+    format!("{}T{:02}:{:02}:{:02}Z", date(timestamp), secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_formats_utc_dates_and_times() {
+        assert_eq!(date(0), "1970-01-01");
+        assert_eq!(date(951_782_400), "2000-02-29");
+        assert_eq!(iso_timestamp(1_700_000_000), "2023-11-14T22:13:20Z");
+        assert_eq!(iso_timestamp(-1), "1969-12-31T23:59:59Z");
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::{BTreeMap, BTreeSet};
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,19 +13,122 @@ pub struct IndexingStatus {
     pub strategy: String,
     pub commits_indexed: u32,
     pub is_complete: bool,
+    /// The repo is a shallow clone: a complete index covers only the
+    /// fetched history, so coupling from older commits is missing.
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub complete_to_depth: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AnalysisResponse {
     pub file_path: String,
     pub repo_root: String,
     pub coupled_files: Vec<CoupledFile>,
     pub commit_count: u32,
+    /// Time spent indexing and scoring (`timings.index_ms + timings.query_ms`);
+    /// the whole analysis, enrichment included, took `timings.total_ms`.
     pub analysis_time_ms: u64,
+    /// Risk level lines calibrated to this repo's scores, when configured
+    /// and the index is complete; absent, the fixed lines apply.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub risk_thresholds: Option<RiskThresholds>,
+    /// Where the analysis time went, phase by phase.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub timings: Option<PhaseTimings>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub test_info: Option<TestInfo>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub indexing_status: Option<IndexingStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warmup_plan: Option<WarmupPlan>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enrichment_omitted: Option<EnrichmentOmitted>,
+    /// Output of external enrichers, keyed by enricher name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<BTreeMap<String, serde_json::Value>>,
+    /// Files changed under the same tickets as the analyzed file, often in
+    /// separate commits (a backend change and its UI follow-up).
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub ticket_coupled_files: Vec<TicketCoupledFile>,
+    /// Offset of the next page of coupled files, when the limit cut the
+    /// list short.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub next_offset: Option<usize>,
+    /// How far the checked-out branch's upstream moved on since the branch
+    /// point. Absent without an upstream or when the branch is not behind.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub upstream_drift: Option<UpstreamDrift>,
+}
+
+/// Lowest risk score at each level above Low.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RiskThresholds {
+    pub critical: f64,
+    pub high: f64,
+    pub medium: f64,
+}
+
+/// Time spent in each phase of an analysis. The phases add up to
+/// `total_ms`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PhaseTimings {
+    /// Bringing the index up to date within the foreground budget.
+    pub index_ms: u64,
+    /// Scoring coupled files from the index.
+    pub query_ms: u64,
+    /// Attaching notes, with their drift, to coupled files.
+    pub enrich_memories_ms: u64,
+    /// Finding tests, their intents, failures and staleness.
+    pub enrich_tests_ms: u64,
+    /// Everything else: loading config, static import links, upstream
+    /// drift, blame regions, external enrichers and budget trimming.
+    pub other_ms: u64,
+    /// The whole analysis.
+    pub total_ms: u64,
+}
+
+/// Commits on the upstream tracking ref since the branch point.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpstreamDrift {
+    /// The upstream ref compared against (`origin/main`).
+    pub upstream: String,
+    /// Merge base of HEAD and the upstream.
+    pub branch_point: String,
+    /// Upstream commits since the branch point.
+    pub commits: u32,
+    /// Of those, the commits that changed the analyzed file.
+    pub file_commits: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TicketCoupledFile {
+    pub path: String,
+    /// Tickets (`PAY-431`, `#567`) both files were changed under
+    pub shared_tickets: u32,
+}
+
+/// Enrichment dropped by the response-wide caps or a context budget.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EnrichmentOmitted {
+    pub memories: u32,
+    pub test_intents: u32,
+    /// Lowest-risk coupled files dropped to fit a context budget.
+    #[serde(skip_serializing_if = "is_zero", default)]
+    pub coupled_files: u32,
+}
+
+fn is_zero(n: &u32) -> bool {
+    *n == 0
+}
+
+/// Estimate of remaining background indexing, present when the index is
+/// still warming up.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WarmupPlan {
+    pub estimated_cycles_remaining: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimated_commits_remaining: Option<u32>,
+    pub suggested_requery_ms: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,6 +136,17 @@ pub struct DiscoveredTestFile {
     pub path: String,
     pub test_intents: Vec<TestIntent>,
     pub test_count: u32,
+    /// A recently imported test run had failures in this file.
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub recently_failing: bool,
+    /// Indexed commits to the analyzed file since this test file last
+    /// changed. Absent when the test file has no indexed commits.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub source_commits_since: Option<u32>,
+    /// The analyzed file changed at least `stale_test_commits` times
+    /// without this test changing: likely rotten coverage.
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub stale: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,16 +154,33 @@ pub struct TestInfo {
     pub test_files: Vec<DiscoveredTestFile>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub coverage_hint: Option<String>,
+    /// Discovery ran out of time before reading every candidate test file,
+    /// so `test_files` and the counts are incomplete.
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub partial: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TestIntent {
     pub title: String,
+    /// Number of cases of a parameterized test (`test.each`,
+    /// `@pytest.mark.parametrize`, table-driven `t.Run`); 0 for a plain test.
+    #[serde(skip_serializing_if = "is_zero", default)]
+    pub cases: u32,
+    /// Symbols of the analyzed file this test appears to exercise, matched
+    /// by name. Only set for the analyzed file's own tests (`test_info`).
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub covers_symbols: Vec<String>,
+    /// This test failed in a recently imported test run.
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub failing: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CoupledFile {
     pub path: String,
+    /// Under the configured `coupling_metric`; by default the same as
+    /// `confidence`.
     pub coupling_score: f64,
     pub co_change_count: u32,
     pub risk_score: f64,
@@ -55,6 +188,136 @@ pub struct CoupledFile {
     pub memories: Vec<Memory>,
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     pub test_intents: Vec<TestIntent>,
+    /// Coupling that only became significant within the recent window.
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub emerging: bool,
+    /// Whether the two files change together more or less often within
+    /// the recent window than before it. Absent when the indexed history
+    /// does not reach back past the window.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub trend: Option<CouplingTrend>,
+    /// The path in git is not valid UTF-8; `path` replaces the bad bytes
+    /// with U+FFFD, so it cannot be opened as-is.
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub lossy_path: bool,
+    /// Commits on the branch's upstream since the branch point that changed
+    /// this file: expect conflicts (see `AnalysisResponse::upstream_drift`).
+    #[serde(skip_serializing_if = "is_zero", default)]
+    pub upstream_commits: u32,
+    /// This is a test file with failures in a recently imported test run.
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub recently_failing: bool,
+    /// Co-changes by conventional-commit type (`feat`, `fix`, `chore`, ...;
+    /// `other` for untyped messages). Empty when none of them were typed.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
+    pub co_change_types: BTreeMap<String, u32>,
+    /// Share of the analyzed file's commits that changed this file too
+    /// (`coupling_score` under the default metric).
+    #[serde(default)]
+    pub confidence: f64,
+    /// Share of this file's commits that changed the analyzed file too.
+    #[serde(default)]
+    pub reverse_confidence: f64,
+    /// How many times likelier the two change together than if they
+    /// changed independently; near 1 for a file that changes constantly.
+    #[serde(default)]
+    pub lift: f64,
+    /// For a file coupled two hops away (`--depth 2`), the directly
+    /// coupled file it was reached through.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub via: Option<String>,
+    /// The most recent commits that changed this file together with the
+    /// analyzed file (or with `via`), newest first.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub shared_commits: Vec<SharedCommit>,
+    /// When this file last changed together with the analyzed file (or with
+    /// `via`), as an ISO 8601 UTC timestamp.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub last_co_change_at: Option<String>,
+    /// Line regions of the analyzed file last changed by commits shared
+    /// with this file, in file order (`--regions`).
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub regions: Vec<LineRegion>,
+    /// Whether either file imports the other. `none` is hidden coupling:
+    /// the files change together with nothing in the code linking them.
+    /// Absent when the working tree was not read.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub static_link: Option<StaticLink>,
+    /// Whether tests for this file were found, the same way as for the
+    /// analyzed file (naming convention, plus imports under `test_discovery
+    /// = "imports"`); `false` flags a file with no test coverage. Absent for
+    /// test files, when the working tree was not read, and when the import
+    /// scan ran out of time before finding any.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub has_tests: Option<bool>,
+    /// Whether this file lies in another package (nearest directory with a
+    /// `Cargo.toml`, `package.json`, `go.mod`, ...) than the analyzed file:
+    /// the part of the blast radius other teams and releases see. Absent
+    /// when neither file is in a package.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub cross_package: Option<bool>,
+    /// What `risk_score` is weighed from, for consumers that rank by their
+    /// own policy. For a two-hop coupling, relative to `via`.
+    #[serde(default)]
+    pub risk_components: RiskComponents,
+    /// `risk_score` was capped below Critical because the weighted coupling
+    /// is under 0.5.
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub gated: bool,
+}
+
+/// The inputs of a coupled file's risk score, each in `[0, 1]`:
+/// `risk = 0.5 * coupling + 0.3 * churn + 0.2 * recency`, before the gate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct RiskComponents {
+    /// Share of the analyzed file's commits shared with this file, with
+    /// bug-fix co-changes counting extra and, under a half-life, old ones
+    /// counting less.
+    pub coupling: f64,
+    /// This file's commits relative to the busiest coupled file's.
+    pub churn: f64,
+    /// When the file last changed, from the oldest indexed commit (0) to
+    /// the newest (1).
+    pub recency: f64,
+}
+
+/// Direction of a coupling over time (`alerts::mark_trends`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CouplingTrend {
+    /// An active problem: co-changing more often lately.
+    Increasing,
+    Stable,
+    /// Historical residue: co-changing less often lately.
+    Decreasing,
+}
+
+/// Static dependency between the analyzed file and a coupled file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StaticLink {
+    /// The analyzed file imports the coupled file.
+    Imports,
+    /// The coupled file imports the analyzed file.
+    ImportedBy,
+    /// Each imports the other.
+    Mutual,
+    None,
+}
+
+/// A span of lines (1-based, inclusive) and the shared commits that last
+/// changed them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LineRegion {
+    pub start_line: u32,
+    pub end_line: u32,
+    pub commits: BTreeSet<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SharedCommit {
+    pub hash: String,
+    pub timestamp: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -64,6 +327,33 @@ pub struct Memory {
     pub symbol_name: Option<String>,
     pub content: String,
     pub created_at: String,
+    /// Git identity of whoever added the note, when it was configured.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub author_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub author_email: Option<String>,
+    /// HEAD when the note was added.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub head_commit: Option<String>,
+    /// The file's blob at `head_commit`; absent if it was not committed.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub blob_oid: Option<String>,
+    /// How far the file has moved on since the note was added; set when
+    /// notes are served with an analysis.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub drift: Option<NoteDrift>,
+    /// From the user-level notes database; `file_path` is then the glob
+    /// pattern the note applies to.
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub global: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NoteDrift {
+    /// Indexed commits to the file since the note was added.
+    pub commits_since: u32,
+    /// The file's content at HEAD differs from when the note was added.
+    pub file_changed: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -77,12 +367,18 @@ pub struct AddNoteResponse {
 pub struct SearchNotesResponse {
     pub query: String,
     pub memories: Vec<Memory>,
+    /// Offset of the next page, when the limit cut the list short.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub next_offset: Option<usize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ListNotesResponse {
     pub file_path: Option<String>,
     pub memories: Vec<Memory>,
+    /// Offset of the next page, when the limit cut the list short.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub next_offset: Option<usize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -99,10 +395,545 @@ pub struct MetricsSummary {
     pub test_files_found: u32,
     pub test_intents_extracted: u32,
     pub avg_analysis_time_ms: u64,
+    /// Serialized analysis response size, in bytes.
+    pub avg_response_bytes: u64,
+    pub max_response_bytes: u64,
+    /// Per-section share of the response, in bytes.
+    pub avg_coupled_files_bytes: u64,
+    pub avg_memories_bytes: u64,
+    pub avg_test_intents_bytes: u64,
+    /// Foreground and background indexing runs.
+    pub indexing_runs: u32,
+    pub background_indexing_runs: u32,
+    pub avg_indexing_time_ms: u64,
+    /// Mean walk throughput over the runs that indexed commits.
+    pub avg_commits_per_sec: f64,
+}
+
+/// Length of the periods a metrics series is grouped into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetricsPeriod {
+    Day,
+    /// ISO weeks, starting on Monday
+    Week,
+}
+
+/// Metrics for the events of one period.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsPeriodSummary {
+    /// First day of the period (UTC), `YYYY-MM-DD`
+    pub period_start: String,
+    #[serde(flatten)]
+    pub summary: MetricsSummary,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MetricsResponse {
     pub repo_root: String,
     pub summary: MetricsSummary,
+    /// Set when the metrics were requested per period (`--group-by`).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub group_by: Option<MetricsPeriod>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub series: Vec<MetricsPeriodSummary>,
+    /// Analyses of one file, when requested with `--file`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub file: Option<FileMetrics>,
+}
+
+/// How often one file was analyzed and what its analyses typically found.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileMetrics {
+    pub file_path: String,
+    pub times_analyzed: u32,
+    pub avg_coupled_files: f64,
+    /// Mean number of coupled files per analysis at each risk level.
+    pub avg_critical_count: f64,
+    pub avg_high_count: f64,
+    pub avg_medium_count: f64,
+    pub avg_low_count: f64,
+    pub avg_analysis_time_ms: u64,
+    /// UTC timestamp of the latest analysis
+    pub last_analyzed_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OwnershipEntry {
+    pub path: String,
+    pub bus_factor: u32,
+    pub dominant_author: String,
+    pub dominant_author_email: String,
+    pub dominant_share: f64,
+    pub author_count: u32,
+    pub commit_count: u32,
+}
+
+/// A suggested reviewer for a change, with the history behind it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Expert {
+    pub name: String,
+    pub email: String,
+    /// Relative expertise, for ranking; higher is better.
+    pub score: f64,
+    /// Their indexed commits to the files below, counted per file.
+    pub commits: u32,
+    /// Their last change to any of the files, ISO 8601 UTC.
+    pub last_commit_at: String,
+    /// The analyzed file and coupled files they changed, most commits first.
+    pub files: Vec<ExpertFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpertFile {
+    pub path: String,
+    pub commits: u32,
+    pub last_commit_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpertsResponse {
+    pub file_path: String,
+    pub repo_root: String,
+    pub experts: Vec<Expert>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BusFactorResponse {
+    pub repo_root: String,
+    pub path_prefix: Option<String>,
+    pub files: Vec<OwnershipEntry>,
+    pub directories: Vec<OwnershipEntry>,
+}
+
+/// A coupling between two files that crossed the significance threshold
+/// within the recent window.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CouplingAlert {
+    pub file_a: String,
+    pub file_b: String,
+    pub co_change_count: u32,
+    pub recent_co_change_count: u32,
+    pub score: f64,
+    pub previous_score: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertsResponse {
+    pub repo_root: String,
+    pub window_days: u32,
+    pub threshold: f64,
+    pub alerts: Vec<CouplingAlert>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphNode {
+    pub path: String,
+    /// Hops from the root file.
+    pub distance: u32,
+    pub commit_count: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphEdge {
+    pub source: String,
+    pub target: String,
+    pub co_change_count: u32,
+    pub coupling_score: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CouplingGraph {
+    pub root: String,
+    pub depth: u32,
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+/// Health of a repo's engram database, from `engram-core doctor`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DoctorReport {
+    pub repo_root: String,
+    /// No issues found
+    pub healthy: bool,
+    /// `PRAGMA integrity_check` output; `["ok"]` when intact
+    pub integrity: Vec<String>,
+    /// Database size on disk, including its WAL
+    pub db_bytes: u64,
+    /// Space `compact` would reclaim
+    pub free_bytes: u64,
+    pub temporal_index_rows: u64,
+    pub indexed_commits: u64,
+    pub indexed_files: u64,
+    pub indexing_status: Option<IndexingStatus>,
+    /// Commit the index was built at
+    pub indexed_head: Option<String>,
+    pub background_queue_len: u32,
+    pub quarantined_commits: u32,
+    pub issues: Vec<DoctorIssue>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DoctorIssue {
+    pub problem: String,
+    /// Suggested fix, usually an engram-core command
+    pub fix: String,
+}
+
+/// Blast-radius check of a pending commit, from `engram-core check`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckResponse {
+    pub repo_root: String,
+    /// Checked the index (`--staged`) rather than the whole working tree
+    pub staged: bool,
+    pub changed_files: Vec<String>,
+    /// Strongly coupled files missing from the change, highest risk first
+    pub findings: Vec<CheckFinding>,
+    pub passed: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckFinding {
+    pub path: String,
+    pub risk_level: String,
+    pub risk_score: f64,
+    pub co_change_count: u32,
+    /// Changed files this one usually changes with
+    pub coupled_to: Vec<String>,
+    /// At or above the `--fail-on` level, so it fails the check
+    pub blocking: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstallHooksResponse {
+    pub repo_root: String,
+    pub hooks_dir: String,
+    pub hooks: Vec<HookInstall>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookInstall {
+    pub name: String,
+    pub path: String,
+    /// `created`, `appended` (to an existing hook), `inserted` (before the
+    /// hook's final `exit` or `exec`), `updated`, `unchanged` or `skipped`
+    pub action: String,
+    /// Why a hook was skipped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// One catch-up by `engram-core watch` or `update`. Watch prints one per
+/// JSON line.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchUpdate {
+    pub head_commit: String,
+    pub strategy: String,
+    pub commits_indexed: u32,
+    pub is_complete: bool,
+    pub elapsed_ms: u64,
+}
+
+/// Indexing progress and database stats, from `engram-core status`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusResponse {
+    pub repo_root: String,
+    /// `None` until the first analysis or reindex
+    pub indexing: Option<IndexStateInfo>,
+    /// An explicit `reindex` was interrupted and can be resumed
+    pub reindex_in_progress: bool,
+    pub background_queue_len: u32,
+    /// Database size on disk, including its WAL
+    pub db_bytes: u64,
+    pub temporal_index_rows: u64,
+    pub indexed_commits: u64,
+    pub indexed_files: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexStateInfo {
+    pub strategy: String,
+    pub commits_indexed: u32,
+    pub is_complete: bool,
+    /// Unix seconds
+    pub last_updated: i64,
+    /// File a path-filtered walk is indexing for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_path: Option<String>,
+    /// Commit the index was built at
+    pub head_commit: String,
+    /// Commits in the history of `head_commit`; `None` if it no longer exists
+    pub total_commits: Option<u32>,
+    /// Shallow clone: `total_commits` counts only the fetched history
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub shallow: bool,
+    /// Share of that history walked so far, 0-100
+    pub percent_complete: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompactResponse {
+    pub repo_root: String,
+    /// Database size on disk, including its WAL
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+    pub elapsed_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReindexResponse {
+    pub repo_root: String,
+    /// True if this run continued an interrupted reindex.
+    pub resumed: bool,
+    /// Commits indexed by this run.
+    pub commits_indexed: u32,
+    /// Commits indexed since the reindex started, across runs.
+    pub total_commits_indexed: u32,
+    pub is_complete: bool,
+    pub elapsed_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecentCommit {
+    pub hash: String,
+    pub summary: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    pub timestamp: i64,
+}
+
+/// The commits behind a coupling claim, so a surprising score can be
+/// checked against history.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CouplingEvidence {
+    pub file_path: String,
+    pub coupled_path: String,
+    pub repo_root: String,
+    /// Indexed commits that changed `file_path`.
+    pub commit_count: u32,
+    pub co_change_count: u32,
+    /// Under the configured `coupling_metric`, as in `CoupledFile`.
+    pub coupling_score: f64,
+    /// Present when `coupled_path` is among the coupled files `analyze`
+    /// reports for `file_path`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub risk_score: Option<f64>,
+    /// Commits that changed both files, newest first.
+    pub commits: Vec<RecentCommit>,
+}
+
+/// How a coupling changed between two points in history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CouplingChange {
+    /// Never co-changed before; does now.
+    Appeared,
+    Strengthened,
+    Weakened,
+    /// Co-changed before; no longer does.
+    Vanished,
+}
+
+/// One coupling of the compared file whose strength changed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CouplingDelta {
+    pub path: String,
+    pub change: CouplingChange,
+    /// Coupling score up to `from`.
+    pub before: f64,
+    /// Coupling score after `from`, up to `to`.
+    pub after: f64,
+    pub before_co_changes: u32,
+    pub after_co_changes: u32,
+}
+
+/// Couplings of a file that appeared, strengthened, weakened or vanished
+/// between two refs (`engram compare`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompareResponse {
+    pub file_path: String,
+    pub repo_root: String,
+    pub from: String,
+    pub to: String,
+    /// Indexed commits of the file up to `from`.
+    pub before_commits: u32,
+    /// Indexed commits of the file after `from`, up to `to`.
+    pub after_commits: u32,
+    /// Strongest change first.
+    pub changes: Vec<CouplingDelta>,
+}
+
+/// A test file picked for a change, and why.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestToRun {
+    pub path: String,
+    /// `changed` (part of the change itself), `coupled` (co-changes in
+    /// history), `convention` (named after a changed file), `imports`
+    /// (imports a changed file).
+    pub reasons: Vec<String>,
+    /// The changed files that led to this test.
+    pub sources: Vec<String>,
+}
+
+/// One runnable command for the picked tests of a framework.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestCommand {
+    /// `cargo`, `jest`, `vitest`, `mocha`, `pytest`, `go`, `rspec`,
+    /// `minitest`, `maven` or `gradle`.
+    pub framework: String,
+    pub command: String,
+    pub tests: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestsToRunResponse {
+    pub repo_root: String,
+    /// The changed files the tests were picked for.
+    pub files: Vec<String>,
+    pub tests: Vec<TestToRun>,
+    pub commands: Vec<TestCommand>,
+}
+
+/// Orientation context for a file an agent is opening for the first time:
+/// who owns it, how old it is, what it moves with, and what changed lately.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrientationBrief {
+    pub file_path: String,
+    pub repo_root: String,
+    pub commit_count: u32,
+    /// Oldest and newest indexed commits touching the file (unix seconds).
+    /// While the index is warming up, `first_seen_at` may be later than the
+    /// file's real creation.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_seen_at: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_changed_at: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ownership: Option<OwnershipEntry>,
+    pub top_coupled_files: Vec<CoupledFile>,
+    pub notes: Vec<Memory>,
+    pub recent_commits: Vec<RecentCommit>,
+}
+
+/// A file whose strongest couplings live in another directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MoveSuggestion {
+    pub path: String,
+    pub current_dir: String,
+    pub suggested_dir: String,
+    /// Share of the file's strong coupling that points into `suggested_dir`.
+    pub misplacement_score: f64,
+    /// Strongly coupled files in `suggested_dir`, strongest first.
+    pub coupled_files: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MoveSuggestionsResponse {
+    pub repo_root: String,
+    pub suggestions: Vec<MoveSuggestion>,
+}
+
+/// What `export-matrix` wrote.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatrixExportResponse {
+    pub repo_root: String,
+    pub format: String,
+    /// File pairs written, one record each
+    pub pairs: usize,
+}
+
+/// Files that change together more than with the rest of the repository:
+/// an implicit module.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CouplingCluster {
+    pub files: Vec<String>,
+    /// Share of the members' coupling weight that stays inside the cluster.
+    pub cohesion: f64,
+    /// Declared packages (manifest directories, `.` for the root) the files
+    /// belong to.
+    pub packages: Vec<String>,
+    /// The files belong to more than one declared package.
+    pub spans_packages: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClustersResponse {
+    pub repo_root: String,
+    /// Modularity of the partition; above ~0.3 the co-change graph has
+    /// clear module structure.
+    pub modularity: f64,
+    /// Largest first.
+    pub clusters: Vec<CouplingCluster>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hotspot {
+    pub path: String,
+    pub commit_count: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecentAnalysis {
+    pub file_path: String,
+    /// UTC, as recorded by SQLite (`YYYY-MM-DD HH:MM:SS`).
+    pub timestamp: String,
+    pub coupled_files_count: u32,
+    pub critical_count: u32,
+    pub high_count: u32,
+    pub analysis_time_ms: u64,
+}
+
+/// Read-only snapshot of a repo's engram state for the dashboard.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DashboardSnapshot {
+    pub repo_root: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub indexing_status: Option<IndexingStatus>,
+    pub hotspots: Vec<Hotspot>,
+    pub recent_analyses: Vec<RecentAnalysis>,
+    pub recent_notes: Vec<Memory>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthorInfo {
+    pub name: String,
+    pub email: String,
+}
+
+/// Edit-weighted commits by one author to one module.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModuleContribution {
+    pub author_email: String,
+    pub module: String,
+    pub commits: u32,
+}
+
+/// Two authors who edited the same files.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoEditEdge {
+    pub author_a: String,
+    pub author_b: String,
+    pub shared_files: u32,
+    /// Sum over shared files of the smaller of the two authors' commit counts.
+    pub weight: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollabGraphResponse {
+    pub repo_root: String,
+    pub module_depth: u32,
+    pub authors: Vec<AuthorInfo>,
+    pub contributions: Vec<ModuleContribution>,
+    pub co_editing: Vec<CoEditEdge>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportTestResultsResponse {
+    pub repo_root: String,
+    /// Test files whose stored results were replaced
+    pub files_imported: u32,
+    pub tests_imported: u32,
+    pub tests_failed: u32,
+    /// Test cases that could not be mapped to a file in the repo
+    pub unmapped_tests: u32,
 }
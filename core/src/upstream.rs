@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+
+use git2::{Branch, DiffOptions, Oid, Repository};
+
+use crate::types::{AnalysisResponse, UpstreamDrift};
+
+/// Upstream commits past the branch point examined at most; any older
+/// ones are not counted.
+const MAX_UPSTREAM_COMMITS: usize = 1000;
+
+/// The checked-out branch's upstream tracking ref, as (name, tip). None on
+/// a detached HEAD or a branch that tracks nothing.
+fn upstream_of_head(repo: &Repository) -> Option<(String, Oid)> {
+    let head = repo.head().ok()?;
+    if !head.is_branch() {
+        return None;
+    }
+    let upstream = Branch::wrap(head).upstream().ok()?;
+    let name = upstream.name().ok()??.to_string();
+    Some((name, upstream.get().target()?))
+}
+
+/// Flag the analyzed file and coupled files that changed on the checked-out
+/// branch's upstream since the branch point: someone else touched what
+/// this change is about to conflict with. Sets `upstream_drift` on the
+/// response and `upstream_commits` on each coupled file; leaves both unset
+/// without an upstream or when the branch is not behind it.
+pub fn mark_upstream_drift(repo: &Repository, response: &mut AnalysisResponse) -> Result<(), git2::Error> {
+    let Some((upstream, tip)) = upstream_of_head(repo) else {
+        return Ok(());
+    };
+    let head = repo.head()?.peel_to_commit()?.id();
+    let branch_point = repo.merge_base(head, tip)?;
+
+    let mut walk = repo.revwalk()?;
+    walk.push(tip)?;
+    walk.hide(branch_point)?;
+
+    // Only the paths in the response are diffed, so each commit is cheap
+    let mut diff_opts = DiffOptions::new();
+    diff_opts.disable_pathspec_match(true);
+    diff_opts.pathspec(&response.file_path);
+    for file in &response.coupled_files {
+        diff_opts.pathspec(&file.path);
+    }
+
+    let mut commits = 0u32;
+    let mut changes: HashMap<String, u32> = HashMap::new();
+    for oid in walk.take(MAX_UPSTREAM_COMMITS) {
+        let commit = repo.find_commit(oid?)?;
+        commits += 1;
+        // A merge's changes are counted on the commits it merges
+        if commit.parent_count() > 1 {
+            continue;
+        }
+        let parent_tree = commit.parents().next().map(|p| p.tree()).transpose()?;
+        let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), Some(&mut diff_opts))?;
+        for delta in diff.deltas() {
+            if let Some(path) = delta.new_file().path().and_then(|p| p.to_str()) {
+                *changes.entry(path.to_string()).or_default() += 1;
+            }
+        }
+    }
+    if commits == 0 {
+        return Ok(());
+    }
+
+    for file in response.coupled_files.iter_mut() {
+        file.upstream_commits = changes.get(&file.path).copied().unwrap_or(0);
+    }
+    response.upstream_drift = Some(UpstreamDrift {
+        upstream,
+        branch_point: branch_point.to_string(),
+        commits,
+        file_commits: changes.get(&response.file_path).copied().unwrap_or(0),
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::commit;
+    use crate::types::CoupledFile;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_flags_files_changed_upstream() {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let base = commit(&repo, &["auth.rs", "session.rs", "util.rs"], "base");
+
+        // main moves on under feature: session.rs twice, auth.rs once
+        repo.branch("main-tip", &repo.find_commit(base).unwrap(), true).unwrap();
+        repo.set_head("refs/heads/main-tip").unwrap();
+        commit(&repo, &["session.rs"], "upstream 1");
+        commit(&repo, &["session.rs", "auth.rs"], "upstream 2");
+
+        let feature = repo.branch("feature", &repo.find_commit(base).unwrap(), true).unwrap();
+        repo.set_head("refs/heads/feature").unwrap();
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force())).unwrap();
+        commit(&repo, &["auth.rs"], "local");
+
+        let file = |path: &str| CoupledFile { path: path.to_string(), ..Default::default() };
+        let mut response = AnalysisResponse {
+            file_path: "auth.rs".to_string(),
+            coupled_files: vec![file("session.rs"), file("util.rs")],
+            ..Default::default()
+        };
+
+        // No upstream configured yet
+        mark_upstream_drift(&repo, &mut response).unwrap();
+        assert!(response.upstream_drift.is_none());
+
+        let mut feature = feature;
+        feature.set_upstream(Some("main-tip")).unwrap();
+        mark_upstream_drift(&repo, &mut response).unwrap();
+        let drift = response.upstream_drift.as_ref().unwrap();
+        assert_eq!(drift.upstream, "main-tip");
+        assert_eq!(drift.branch_point, base.to_string());
+        assert_eq!((drift.commits, drift.file_commits), (2, 1));
+        assert_eq!(response.coupled_files[0].upstream_commits, 2);
+        assert_eq!(response.coupled_files[1].upstream_commits, 0);
+    }
+}
@@ -0,0 +1,191 @@
+use std::net::{SocketAddr, TcpListener};
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use git2::Repository;
+use notify::{RecursiveMode, Watcher};
+
+use crate::config::Config;
+use crate::dashboard;
+use crate::indexing::{self, IndexOptions};
+use crate::persistence::Database;
+use crate::types::WatchUpdate;
+
+#[derive(Debug, Clone)]
+pub struct WatchOptions {
+    /// Quiet period after a ref change before indexing, so a rebase or
+    /// pull is picked up once rather than per ref update
+    pub debounce: Duration,
+    /// Time per indexing pass; passes repeat until the index is complete
+    pub budget: Duration,
+    /// Also serve the read-only dashboard (see `dashboard::serve`) here
+    pub dashboard: Option<SocketAddr>,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        Self { debounce: Duration::from_millis(500), budget: indexing::BACKGROUND_BUDGET, dashboard: None }
+    }
+}
+
+/// Keep the index of `repo_root` current: watch the git directory for ref
+/// changes (commits, checkouts, pulls) and index new history as it lands,
+/// calling `on_update` after each catch-up. Runs until the watcher fails.
+/// With `options.dashboard`, the dashboard is served on a thread alongside.
+///
+/// Only an existing index is maintained; until the first analysis picks an
+/// indexing strategy (and, on huge repos, the file to follow) there is
+/// nothing to keep current.
+pub fn watch(
+    repo_root: &Path,
+    db_path: &Path,
+    options: &WatchOptions,
+    mut on_update: impl FnMut(&WatchUpdate),
+) -> Result<(), Box<dyn std::error::Error>> {
+    let git_dir = Repository::open(repo_root)?.path().to_path_buf();
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(&git_dir, RecursiveMode::Recursive)?;
+
+    if let Some(addr) = options.dashboard {
+        let listener = TcpListener::bind(addr)?;
+        eprintln!("Serving the dashboard on http://{}", listener.local_addr()?);
+        let (repo_root, db_path) = (repo_root.to_path_buf(), db_path.to_path_buf());
+        std::thread::spawn(move || {
+            if let Err(e) = dashboard::serve(&listener, &repo_root, &db_path) {
+                eprintln!("Warning: dashboard stopped: {e}");
+            }
+        });
+    }
+
+    let mut last_head = None;
+    loop {
+        if let Some(update) = catch_up(repo_root, db_path, options.budget, &mut last_head)? {
+            on_update(&update);
+        }
+
+        // Wait for a ref to move, then for the burst of writes to settle
+        loop {
+            match rx.recv()? {
+                Ok(event) if event.paths.iter().any(|p| is_ref_path(&git_dir, p)) => break,
+                Ok(_) => {}
+                Err(e) => eprintln!("Warning: watch error: {e}"),
+            }
+        }
+        while rx.recv_timeout(options.debounce).is_ok() {}
+    }
+}
+
+/// Index the commits that landed since the last run, once. What the git
+/// hooks from `install-hooks` run; `None` when there is no index yet.
+pub fn update(
+    repo_root: &Path,
+    db_path: &Path,
+    budget: Duration,
+) -> Result<Option<WatchUpdate>, Box<dyn std::error::Error>> {
+    catch_up(repo_root, db_path, budget, &mut None)
+}
+
+/// Files whose change can move HEAD or a branch: `HEAD`, `packed-refs`,
+/// and anything under `refs/`. Lock files are written first and renamed.
+fn is_ref_path(git_dir: &Path, path: &Path) -> bool {
+    let Ok(relative) = path.strip_prefix(git_dir) else {
+        return false;
+    };
+    if relative.extension().is_some_and(|ext| ext == "lock") {
+        return false;
+    }
+    relative == Path::new("HEAD") || relative == Path::new("packed-refs") || relative.starts_with("refs")
+}
+
+/// Index up to the current HEAD if it differs from `last_head` or the index
+/// is incomplete, running passes of `budget` until it is complete or stops
+/// making progress. Returns `None` when there was nothing to do.
+fn catch_up(
+    repo_root: &Path,
+    db_path: &Path,
+    budget: Duration,
+    last_head: &mut Option<String>,
+) -> Result<Option<WatchUpdate>, Box<dyn std::error::Error>> {
+    let start = Instant::now();
+    let repo = Repository::open(repo_root)?;
+    let db = Database::open(db_path)?;
+    let Some(state) = db.get_indexing_state()? else {
+        return Ok(None);
+    };
+    // Follow the revision the index was built for (HEAD, or e.g. a
+    // remote-tracking branch that moves on fetch)
+    let opts = IndexOptions::load(repo_root, &Config::load(repo_root)?).with_target(db.get_index_target()?);
+    let Ok(head) = opts.tip(&repo) else {
+        return Ok(None); // Unborn branch, or the revision is gone
+    };
+    let head = head.to_string();
+    if state.is_complete && state.head_commit == head && last_head.as_deref() == Some(head.as_str()) {
+        return Ok(None);
+    }
+
+    let target = state.target_path.unwrap_or_default();
+    let mut result = indexing::smart_index(&repo, &db, &opts, &target, budget)?;
+    while !result.is_complete {
+        let record_metrics = opts.metrics_repo_root.is_some();
+        indexing::background_index(repo_root, budget, Some(&target), record_metrics)?;
+        let Some(state) = db.get_indexing_state()? else { break };
+        if state.commits_indexed == result.commits_indexed && !state.is_complete {
+            break; // No progress; another process may hold the work
+        }
+        result.commits_indexed = state.commits_indexed;
+        result.is_complete = state.is_complete;
+    }
+
+    *last_head = Some(head.clone());
+    Ok(Some(WatchUpdate {
+        head_commit: head,
+        strategy: result.strategy.as_str().to_string(),
+        commits_indexed: result.commits_indexed,
+        is_complete: result.is_complete,
+        elapsed_ms: start.elapsed().as_millis() as u64,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::commit;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_is_ref_path() {
+        let git_dir = Path::new("/repo/.git");
+        assert!(is_ref_path(git_dir, Path::new("/repo/.git/HEAD")));
+        assert!(is_ref_path(git_dir, Path::new("/repo/.git/refs/heads/main")));
+        assert!(is_ref_path(git_dir, Path::new("/repo/.git/packed-refs")));
+        assert!(!is_ref_path(git_dir, Path::new("/repo/.git/refs/heads/main.lock")));
+        assert!(!is_ref_path(git_dir, Path::new("/repo/.git/objects/ab/cdef")));
+        assert!(!is_ref_path(git_dir, Path::new("/repo/src/refs/x.rs")));
+    }
+
+    #[test]
+    fn test_catch_up_indexes_new_commits_once() {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        commit(&repo, &["a.rs", "b.rs"], "c0");
+        let db_path = dir.path().join("engram.db");
+        let budget = Duration::from_secs(5);
+        let mut last_head = None;
+
+        // Nothing to maintain before the first analysis
+        assert!(catch_up(dir.path(), &db_path, budget, &mut last_head).unwrap().is_none());
+
+        let db = Database::open(&db_path).unwrap();
+        indexing::smart_index(&repo, &db, &IndexOptions::default(), "a.rs", budget).unwrap();
+        commit(&repo, &["a.rs", "b.rs"], "c1");
+
+        let update = catch_up(dir.path(), &db_path, budget, &mut last_head).unwrap().unwrap();
+        assert!(update.is_complete);
+        assert_eq!(update.head_commit, repo.head().unwrap().target().unwrap().to_string());
+        assert_eq!(db.co_change_count("a.rs", "b.rs").unwrap(), 2);
+
+        assert!(catch_up(dir.path(), &db_path, budget, &mut last_head).unwrap().is_none());
+    }
+}
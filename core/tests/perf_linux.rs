@@ -36,7 +36,7 @@ fn clear_engram_state() {
 /// Simulate the background indexing that main.rs runs after flushing stdout.
 /// `file_path` is passed from the foreground caller, just like in production.
 fn run_background(repo_root: &Path, file_path: Option<&str>) {
-    let _ = engram_core::indexing::background_index(repo_root, Duration::from_secs(5), file_path);
+    let _ = engram_core::indexing::background_index(repo_root, Duration::from_secs(5), file_path, true);
 }
 
 #[test]